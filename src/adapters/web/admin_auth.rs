@@ -0,0 +1,185 @@
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{ResponseError, web};
+use log::warn;
+
+use crate::adapters::web::errors::ApiError;
+
+/// Header carrying the admin token, as on the payment processors' admin API.
+const TOKEN_HEADER: &str = "X-Rinha-Token";
+
+/// Token the admin routes that change state require. Without one they are
+/// refused altogether.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdminToken(Option<String>);
+
+impl AdminToken {
+	pub fn new(token: Option<String>) -> Self {
+		Self(token.filter(|token| !token.is_empty()))
+	}
+
+	/// Compares every byte, so how long the check takes does not tell how
+	/// much of the token was right.
+	fn accepts(&self, presented: &[u8]) -> bool {
+		let Some(token) = &self.0 else {
+			return false;
+		};
+
+		token.len() == presented.len() &&
+			token
+				.bytes()
+				.zip(presented)
+				.fold(0, |diff, (expected, actual)| diff | (expected ^ actual)) ==
+				0
+	}
+}
+
+/// Whether the request reaches an admin route that changes state: any admin
+/// request but a read, and the admin WebSocket, as it takes commands. Checks
+/// the path the router matches, with percent-encoded characters decoded, so
+/// `/%61dmin/...` is an admin route as well.
+fn changes_state(req: &ServiceRequest) -> bool {
+	let path = req.match_info().as_str();
+	path.starts_with("/admin/") &&
+		(path == "/admin/ws" ||
+			!matches!(
+				*req.method(),
+				Method::GET | Method::HEAD | Method::OPTIONS
+			))
+}
+
+/// Answers with a 401 when a request to an admin route that changes state
+/// does not carry the [`AdminToken`] in the app data, or when there is none.
+/// Reads of the admin routes and every other endpoint are passed through.
+pub async fn require_admin_token(
+	req: ServiceRequest,
+	next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+	if !changes_state(&req) {
+		return next
+			.call(req)
+			.await
+			.map(ServiceResponse::map_into_boxed_body);
+	}
+
+	let authorized = req
+		.app_data::<web::Data<AdminToken>>()
+		.zip(req.headers().get(TOKEN_HEADER))
+		.is_some_and(|(token, presented)| token.accepts(presented.as_bytes()));
+	if authorized {
+		return next
+			.call(req)
+			.await
+			.map(ServiceResponse::map_into_boxed_body);
+	}
+
+	warn!(
+		"Refused {} {} without a valid admin token",
+		req.method(),
+		req.path()
+	);
+	let response = ApiError::UnauthorizedError.error_response();
+	Ok(req.into_response(response))
+}
+
+#[cfg(test)]
+mod tests {
+	use actix_web::http::StatusCode;
+	use actix_web::{App, HttpResponse, middleware, test as actix_test};
+
+	use super::*;
+
+	async fn ok() -> HttpResponse {
+		HttpResponse::Ok().finish()
+	}
+
+	#[actix_web::test]
+	async fn test_requires_token_on_admin_routes_that_change_state() {
+		let app = actix_test::init_service(
+			App::new()
+				.wrap(middleware::from_fn(require_admin_token))
+				.app_data(web::Data::new(AdminToken::new(Some("s3cret".into()))))
+				.route("/admin/processors/{name}", web::put().to(ok))
+				.route("/admin/processors", web::get().to(ok))
+				.route("/admin/ws", web::get().to(ok))
+				.route("/purge-payments", web::post().to(ok)),
+		)
+		.await;
+		let status = |req: actix_test::TestRequest| {
+			let app = &app;
+			async move {
+				actix_test::call_service(app, req.to_request())
+					.await
+					.status()
+			}
+		};
+
+		assert_eq!(
+			status(actix_test::TestRequest::put().uri("/admin/processors/default"))
+				.await,
+			StatusCode::UNAUTHORIZED
+		);
+		assert_eq!(
+			status(
+				actix_test::TestRequest::put()
+					.uri("/admin/processors/default")
+					.insert_header((TOKEN_HEADER, "wrong"))
+			)
+			.await,
+			StatusCode::UNAUTHORIZED
+		);
+		assert_eq!(
+			status(
+				actix_test::TestRequest::put()
+					.uri("/admin/processors/default")
+					.insert_header((TOKEN_HEADER, "s3cret"))
+			)
+			.await,
+			StatusCode::OK
+		);
+		assert_eq!(
+			status(actix_test::TestRequest::get().uri("/admin/ws")).await,
+			StatusCode::UNAUTHORIZED
+		);
+		assert_eq!(
+			status(
+				actix_test::TestRequest::put().uri("/%61dmin/processors/default")
+			)
+			.await,
+			StatusCode::UNAUTHORIZED
+		);
+		assert_eq!(
+			status(actix_test::TestRequest::get().uri("/admin/w%73")).await,
+			StatusCode::UNAUTHORIZED
+		);
+		assert_eq!(
+			status(actix_test::TestRequest::get().uri("/admin/processors")).await,
+			StatusCode::OK
+		);
+		assert_eq!(
+			status(actix_test::TestRequest::post().uri("/purge-payments")).await,
+			StatusCode::OK
+		);
+	}
+
+	#[actix_web::test]
+	async fn test_refuses_admin_routes_that_change_state_without_token() {
+		let app = actix_test::init_service(
+			App::new()
+				.wrap(middleware::from_fn(require_admin_token))
+				.app_data(web::Data::new(AdminToken::new(Some(String::new()))))
+				.route("/admin/snapshot", web::post().to(ok)),
+		)
+		.await;
+
+		let req = actix_test::TestRequest::post()
+			.uri("/admin/snapshot")
+			.insert_header((TOKEN_HEADER, ""))
+			.to_request();
+		let resp = actix_test::call_service(&app, req).await;
+
+		assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+	}
+}
@@ -0,0 +1,204 @@
+use circuitbreaker_rs::{CircuitBreaker, DefaultPolicy};
+use serde::{Deserialize, Serialize};
+
+use crate::adapters::web::schema::WorkersStatus;
+use crate::domain::breaker_transition::BreakerTransition;
+use crate::infrastructure::observability::metrics::metrics;
+use crate::infrastructure::routing::in_memory_payment_router::InMemoryPaymentRouter;
+use crate::infrastructure::workers::worker_control::worker_control;
+use crate::use_cases::process_payment::PaymentProcessingError;
+
+/// Commands an operator can send over the admin channel.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum AdminCommand {
+	/// Streams a stats frame every `interval_ms` milliseconds.
+	Subscribe {
+		#[serde(rename = "intervalMs", default = "default_stats_interval_ms")]
+		interval_ms: u64,
+	},
+	Unsubscribe,
+	PauseWorkers,
+	ResumeWorkers,
+	OpenBreaker {
+		processor: String,
+	},
+	CloseBreaker {
+		processor: String,
+	},
+	SetConcurrency {
+		concurrency: usize,
+	},
+}
+
+impl AdminCommand {
+	pub fn name(&self) -> &'static str {
+		match self {
+			AdminCommand::Subscribe { .. } => "subscribe",
+			AdminCommand::Unsubscribe => "unsubscribe",
+			AdminCommand::PauseWorkers => "pause_workers",
+			AdminCommand::ResumeWorkers => "resume_workers",
+			AdminCommand::OpenBreaker { .. } => "open_breaker",
+			AdminCommand::CloseBreaker { .. } => "close_breaker",
+			AdminCommand::SetConcurrency { .. } => "set_concurrency",
+		}
+	}
+}
+
+fn default_stats_interval_ms() -> u64 {
+	1000
+}
+
+/// Frames sent back to the operator.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AdminReply {
+	Ack { command: String },
+	Error { message: String },
+	Stats(AdminStats),
+	BreakerTransition(BreakerTransition),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AdminStats {
+	pub metrics: serde_json::Map<String, serde_json::Value>,
+	pub workers: WorkersStatus,
+}
+
+impl AdminStats {
+	pub fn current() -> Self {
+		let metrics = metrics()
+			.snapshot()
+			.into_iter()
+			.map(|sample| {
+				let name = sample
+					.tags
+					.iter()
+					.fold(sample.name.to_string(), |name, (_, value)| {
+						format!("{name}.{value}")
+					});
+				(name, serde_json::Value::from(sample.value))
+			})
+			.collect();
+
+		Self {
+			metrics,
+			workers: WorkersStatus::current(),
+		}
+	}
+}
+
+/// Applies the control commands of the admin channel. Subscriptions are
+/// handled by the connection itself.
+#[derive(Clone)]
+pub struct AdminCommandDispatcher {
+	router: InMemoryPaymentRouter,
+}
+
+impl AdminCommandDispatcher {
+	pub fn new(router: InMemoryPaymentRouter) -> Self {
+		Self { router }
+	}
+
+	pub fn dispatch(&self, command: &AdminCommand) -> AdminReply {
+		let result = match command {
+			AdminCommand::Subscribe { .. } | AdminCommand::Unsubscribe => Ok(()),
+			AdminCommand::PauseWorkers => {
+				worker_control().pause();
+				Ok(())
+			}
+			AdminCommand::ResumeWorkers => {
+				worker_control().resume();
+				Ok(())
+			}
+			AdminCommand::OpenBreaker { processor } => {
+				self.breaker(processor).map(|breaker| {
+					breaker.force_open();
+				})
+			}
+			AdminCommand::CloseBreaker { processor } => {
+				self.breaker(processor).map(|breaker| {
+					breaker.force_closed();
+				})
+			}
+			AdminCommand::SetConcurrency { concurrency } => {
+				worker_control().set_concurrency(*concurrency);
+				Ok(())
+			}
+		};
+
+		match result {
+			Ok(()) => AdminReply::Ack {
+				command: command.name().to_string(),
+			},
+			Err(message) => AdminReply::Error { message },
+		}
+	}
+
+	fn breaker(
+		&self,
+		processor: &str,
+	) -> Result<&CircuitBreaker<DefaultPolicy, PaymentProcessingError>, String> {
+		match processor {
+			"default" => Ok(&self.router.default_breaker),
+			"fallback" => Ok(&self.router.fallback_breaker),
+			_ => Err(format!("unknown processor '{processor}'")),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use circuitbreaker_rs::State;
+
+	use super::*;
+
+	#[test]
+	fn test_parses_commands() {
+		let parse = |json| serde_json::from_str::<AdminCommand>(json).unwrap();
+
+		assert_eq!(
+			parse(r#"{"command":"subscribe"}"#),
+			AdminCommand::Subscribe { interval_ms: 1000 }
+		);
+		assert_eq!(
+			parse(r#"{"command":"open_breaker","processor":"fallback"}"#),
+			AdminCommand::OpenBreaker {
+				processor: "fallback".to_string(),
+			}
+		);
+		assert_eq!(
+			parse(r#"{"command":"set_concurrency","concurrency":4}"#),
+			AdminCommand::SetConcurrency { concurrency: 4 }
+		);
+		assert!(serde_json::from_str::<AdminCommand>(r#"{"command":"x"}"#).is_err());
+	}
+
+	#[test]
+	fn test_dispatches_breaker_commands() {
+		let router = InMemoryPaymentRouter::new();
+		let dispatcher = AdminCommandDispatcher::new(router.clone());
+
+		assert_eq!(
+			dispatcher.dispatch(&AdminCommand::OpenBreaker {
+				processor: "default".to_string(),
+			}),
+			AdminReply::Ack {
+				command: "open_breaker".to_string(),
+			}
+		);
+		assert_eq!(router.default_breaker.current_state(), State::Open);
+
+		dispatcher.dispatch(&AdminCommand::CloseBreaker {
+			processor: "default".to_string(),
+		});
+		assert_eq!(router.default_breaker.current_state(), State::Closed);
+
+		assert!(matches!(
+			dispatcher.dispatch(&AdminCommand::OpenBreaker {
+				processor: "unknown".to_string(),
+			}),
+			AdminReply::Error { .. }
+		));
+	}
+}
@@ -0,0 +1,44 @@
+use actix_web::{HttpResponse, Responder, get, web};
+
+use crate::adapters::web::schema::{EffectiveConfig, WorkersStatus};
+use crate::adapters::web::state::DebugVarsState;
+use crate::domain::payment_processor::{PROCESSOR_GROUPS, replica_urls};
+use crate::domain::processor_settings::ProcessorSettings;
+use crate::infrastructure::config::feature_flags::feature_flags;
+use crate::infrastructure::config::settings::redact_credentials;
+use crate::infrastructure::routing::in_memory_payment_router::InMemoryPaymentRouter;
+
+/// Configuration the instance is running with, secrets masked, to compare
+/// instances when they behave differently.
+#[get("/admin/config")]
+pub async fn effective_config(
+	state: web::Data<DebugVarsState>,
+	router: web::Data<InMemoryPaymentRouter>,
+) -> impl Responder {
+	let processors = PROCESSOR_GROUPS
+		.iter()
+		.filter_map(|name| {
+			router.processor_settings(name).map(|settings| {
+				let settings = ProcessorSettings {
+					url: replica_urls(&settings.url)
+						.map(redact_credentials)
+						.collect::<Vec<_>>()
+						.join(","),
+					..settings
+				};
+				(name.to_string(), settings)
+			})
+		})
+		.collect();
+
+	HttpResponse::Ok().json(EffectiveConfig {
+		config: state.config.clone(),
+		processors,
+		workers: WorkersStatus::current(),
+		feature_flags: feature_flags()
+			.snapshot()
+			.into_iter()
+			.map(|(name, enabled)| (name.to_string(), enabled))
+			.collect(),
+	})
+}
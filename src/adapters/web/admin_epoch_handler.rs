@@ -0,0 +1,30 @@
+use actix_web::{HttpResponse, Responder, ResponseError, get, post, web};
+use log::{error, info};
+
+use crate::adapters::web::errors::ApiError;
+use crate::adapters::web::schema::RunEpochStatus;
+use crate::infrastructure::persistence::redis_run_epoch::RedisRunEpoch;
+use crate::use_cases::run_epoch;
+
+#[get("/admin/epoch")]
+pub async fn current_run_epoch() -> impl Responder {
+	HttpResponse::Ok().json(RunEpochStatus {
+		epoch: run_epoch::current_epoch(),
+	})
+}
+
+/// Starts a new run epoch, so the payments accepted from now on can be summed
+/// and purged apart from the ones of earlier runs.
+#[post("/admin/epoch")]
+pub async fn start_run_epoch(run_epoch: web::Data<RedisRunEpoch>) -> impl Responder {
+	match run_epoch.advance().await {
+		Ok(epoch) => {
+			info!("Started run epoch {epoch}");
+			HttpResponse::Ok().json(RunEpochStatus { epoch })
+		}
+		Err(e) => {
+			error!("Failed to start a new run epoch: {e}");
+			ApiError::DatabaseConnectionError.error_response()
+		}
+	}
+}
@@ -0,0 +1,34 @@
+use actix_web::{HttpResponse, Responder, ResponseError, get, put, web};
+use log::info;
+
+use crate::adapters::web::errors::ApiError;
+use crate::adapters::web::schema::FeatureFlagRequest;
+use crate::infrastructure::config::feature_flags::{FeatureFlag, feature_flags};
+
+#[get("/admin/flags")]
+pub async fn list_feature_flags() -> impl Responder {
+	HttpResponse::Ok().json(feature_flags().snapshot())
+}
+
+/// Turns an experimental behavior on or off for the rest of the run.
+#[put("/admin/flags/{name}")]
+pub async fn set_feature_flag(
+	name: web::Path<String>,
+	payload: web::Json<FeatureFlagRequest>,
+) -> impl Responder {
+	let Ok(flag) = name.parse::<FeatureFlag>() else {
+		return ApiError::NotFoundError.error_response();
+	};
+
+	feature_flags().set(flag, payload.enabled);
+	info!(
+		"Feature flag '{}' {}",
+		flag.as_str(),
+		if payload.enabled {
+			"enabled"
+		} else {
+			"disabled"
+		}
+	);
+	HttpResponse::Ok().json(feature_flags().snapshot())
+}
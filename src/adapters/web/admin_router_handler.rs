@@ -0,0 +1,88 @@
+use actix_web::{
+	HttpResponse, Responder, ResponseError, delete, get, post, put, web,
+};
+use log::info;
+
+use crate::adapters::web::errors::ApiError;
+use crate::adapters::web::schema::ProcessorStatus;
+use crate::domain::processor_settings::ProcessorSettings;
+use crate::infrastructure::routing::in_memory_payment_router::InMemoryPaymentRouter;
+
+/// Lists the processors as the router sees them, to check its view against
+/// what the processors themselves report.
+#[get("/admin/processors")]
+pub async fn list_processors(
+	router: web::Data<InMemoryPaymentRouter>,
+) -> impl Responder {
+	let processors = router
+		.processors_snapshot()
+		.iter()
+		.map(|processor| {
+			ProcessorStatus::new(processor, router.breaker_state(&processor.name))
+		})
+		.collect::<Vec<_>>();
+
+	HttpResponse::Ok().json(processors)
+}
+
+/// Drops everything the router learnt about the processors, including the
+/// breaker counters, and has them health checked again right away. Useful
+/// after the processor URLs change or a chaos experiment ends.
+#[post("/admin/router/reset")]
+pub async fn reset_router(
+	router: web::Data<InMemoryPaymentRouter>,
+) -> impl Responder {
+	router.reset();
+	info!("Payment router reset");
+	HttpResponse::NoContent().finish()
+}
+
+/// Points a processor at a new URL, and optionally a new fee rate and
+/// response time threshold, without restarting the service. The processor is
+/// health checked at its new URL right away.
+#[put("/admin/processors/{name}")]
+pub async fn configure_processor(
+	router: web::Data<InMemoryPaymentRouter>,
+	name: web::Path<String>,
+	payload: web::Json<ProcessorSettings>,
+) -> impl Responder {
+	let settings = payload.into_inner();
+	if !settings.is_valid() {
+		return ApiError::ValidationError.error_response();
+	}
+
+	if !router.configure_processor(&name, settings.clone()) {
+		return ApiError::NotFoundError.error_response();
+	}
+
+	info!("Payment processor '{name}' now at {}", settings.url);
+	HttpResponse::Ok().json(settings)
+}
+
+/// Health checks of a processor, oldest first, to tell flapping apart from a
+/// processor that stayed down.
+#[get("/admin/processors/{name}/history")]
+pub async fn processor_health_history(
+	router: web::Data<InMemoryPaymentRouter>,
+	name: web::Path<String>,
+) -> impl Responder {
+	match router.health_history(&name) {
+		Some(history) => HttpResponse::Ok().json(history),
+		None => ApiError::NotFoundError.error_response(),
+	}
+}
+
+/// Forgets the health checks of one processor, for instance before the next
+/// chaos experiment, without touching the other's.
+#[delete("/admin/processors/{name}/history")]
+pub async fn purge_processor_health_history(
+	router: web::Data<InMemoryPaymentRouter>,
+	name: web::Path<String>,
+) -> impl Responder {
+	if !router.purge_health_history(&name) {
+		return ApiError::NotFoundError.error_response();
+	}
+
+	info!("Health history of payment processor '{name}' purged");
+	HttpResponse::NoContent().finish()
+}
@@ -0,0 +1,37 @@
+use actix_web::{HttpResponse, Responder, ResponseError, post, put, web};
+use log::info;
+
+use crate::adapters::web::errors::ApiError;
+use crate::adapters::web::schema::{WorkerConcurrencyRequest, WorkersStatus};
+use crate::infrastructure::workers::worker_control::worker_control;
+
+/// Stops the payment workers from taking new payments. Queued payments stay
+/// in the queue until the workers are resumed.
+#[post("/admin/workers/pause")]
+pub async fn pause_workers() -> impl Responder {
+	worker_control().pause();
+	info!("Payment workers paused");
+	HttpResponse::Ok().json(WorkersStatus::current())
+}
+
+#[post("/admin/workers/resume")]
+pub async fn resume_workers() -> impl Responder {
+	worker_control().resume();
+	info!("Payment workers resumed");
+	HttpResponse::Ok().json(WorkersStatus::current())
+}
+
+/// Caps how many payments the workers process at the same time, without
+/// restarting them.
+#[put("/admin/workers/concurrency")]
+pub async fn set_workers_concurrency(
+	payload: web::Json<WorkerConcurrencyRequest>,
+) -> impl Responder {
+	if payload.concurrency == 0 {
+		return ApiError::ValidationError.error_response();
+	}
+
+	worker_control().set_concurrency(payload.concurrency);
+	info!("Payment workers concurrency set to {}", payload.concurrency);
+	HttpResponse::Ok().json(WorkersStatus::current())
+}
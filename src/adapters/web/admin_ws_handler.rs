@@ -0,0 +1,138 @@
+use std::time::Duration;
+
+use actix_web::{HttpRequest, HttpResponse, get, web};
+use actix_ws::{Message, MessageStream, Session};
+use futures::StreamExt;
+use log::{error, info};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::{Interval, MissedTickBehavior, interval};
+
+use crate::adapters::web::admin_command::{
+	AdminCommand, AdminCommandDispatcher, AdminReply, AdminStats,
+};
+use crate::infrastructure::observability::breaker_events::breaker_events;
+
+const MIN_STATS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Admin channel over a single WebSocket: JSON commands in, acknowledgements
+/// and subscribed stats frames out. Subscribers also receive circuit breaker
+/// transitions as they happen.
+#[get("/admin/ws")]
+pub async fn admin_ws(
+	req: HttpRequest,
+	body: web::Payload,
+	dispatcher: web::Data<AdminCommandDispatcher>,
+) -> Result<HttpResponse, actix_web::Error> {
+	let (response, session, stream) = actix_ws::handle(&req, body)?;
+
+	info!("Admin channel opened");
+	actix_web::rt::spawn(admin_session(
+		session,
+		stream,
+		dispatcher.get_ref().clone(),
+	));
+
+	Ok(response)
+}
+
+async fn admin_session(
+	mut session: Session,
+	mut stream: MessageStream,
+	dispatcher: AdminCommandDispatcher,
+) {
+	let mut stats_interval: Option<Interval> = None;
+	let mut transitions = breaker_events().subscribe();
+
+	loop {
+		let message = tokio::select! {
+			message = stream.next() => message,
+			_ = next_tick(&mut stats_interval) => {
+				if send(&mut session, &AdminReply::Stats(AdminStats::current()))
+					.await
+					.is_err()
+				{
+					return;
+				}
+				continue;
+			}
+			transition = transitions.recv() => {
+				match transition {
+					Ok(transition) if stats_interval.is_some() => {
+						let reply = AdminReply::BreakerTransition(transition);
+						if send(&mut session, &reply).await.is_err() {
+							return;
+						}
+					}
+					Ok(_) | Err(RecvError::Lagged(_)) => {}
+					Err(RecvError::Closed) => break,
+				}
+				continue;
+			}
+		};
+
+		match message {
+			Some(Ok(Message::Text(text))) => {
+				let reply = match serde_json::from_str::<AdminCommand>(&text) {
+					Ok(command) => {
+						match &command {
+							AdminCommand::Subscribe { interval_ms } => {
+								let mut stats = interval(
+									Duration::from_millis(*interval_ms)
+										.max(MIN_STATS_INTERVAL),
+								);
+								stats.set_missed_tick_behavior(
+									MissedTickBehavior::Skip,
+								);
+								stats_interval = Some(stats);
+							}
+							AdminCommand::Unsubscribe => stats_interval = None,
+							_ => info!("Admin command received: {command:?}"),
+						}
+						dispatcher.dispatch(&command)
+					}
+					Err(e) => AdminReply::Error {
+						message: e.to_string(),
+					},
+				};
+
+				if send(&mut session, &reply).await.is_err() {
+					return;
+				}
+			}
+			Some(Ok(Message::Ping(bytes))) => {
+				if session.pong(&bytes).await.is_err() {
+					return;
+				}
+			}
+			Some(Ok(Message::Close(reason))) => {
+				let _ = session.close(reason).await;
+				return;
+			}
+			Some(Ok(_)) => {}
+			Some(Err(e)) => {
+				error!("Admin channel failed: {e}");
+				break;
+			}
+			None => break,
+		}
+	}
+
+	let _ = session.close(None).await;
+}
+
+async fn next_tick(stats_interval: &mut Option<Interval>) {
+	match stats_interval {
+		Some(stats_interval) => {
+			stats_interval.tick().await;
+		}
+		None => std::future::pending().await,
+	}
+}
+
+async fn send(
+	session: &mut Session,
+	reply: &AdminReply,
+) -> Result<(), actix_ws::Closed> {
+	let frame = serde_json::to_string(reply).expect("Admin replies serialize");
+	session.text(frame).await
+}
@@ -0,0 +1,90 @@
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawAmount {
+	Number(f64),
+	Text(String),
+}
+
+/// Deserializes a monetary amount given either as a JSON number or as a
+/// decimal string such as `"100.50"`. Numbers are taken as they are. Strings
+/// must be plain decimals, without sign, exponent or surrounding whitespace,
+/// of a finite, positive value.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+	D: Deserializer<'de>,
+{
+	match RawAmount::deserialize(deserializer)? {
+		RawAmount::Number(amount) => Ok(amount),
+		RawAmount::Text(text) => parse_decimal(&text).map_err(D::Error::custom),
+	}
+}
+
+fn parse_decimal(text: &str) -> Result<f64, String> {
+	let invalid = || format!("invalid amount '{text}'");
+
+	let (whole, fraction) = text.split_once('.').unwrap_or((text, "0"));
+	let is_digits =
+		|part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+	if !is_digits(whole) || !is_digits(fraction) {
+		return Err(invalid());
+	}
+
+	let amount: f64 = text.parse().map_err(|_| invalid())?;
+	if !amount.is_finite() || amount <= 0.0 {
+		return Err(format!("amount must be a positive number, got '{text}'"));
+	}
+
+	Ok(amount)
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use crate::adapters::web::schema::PaymentRequest;
+
+	fn parse(amount: serde_json::Value) -> Result<f64, serde_json::Error> {
+		serde_json::from_value::<PaymentRequest>(json!({
+			"correlationId": "4a7901b8-7d26-4d9d-aa19-4dc1c7cf60b3",
+			"amount": amount,
+		}))
+		.map(|request| request.amount)
+	}
+
+	#[test]
+	fn test_accepts_numbers_and_decimal_strings() {
+		assert_eq!(parse(json!(19.9)).unwrap(), 19.9);
+		assert_eq!(parse(json!(100)).unwrap(), 100.0);
+		assert_eq!(parse(json!("100.50")).unwrap(), 100.5);
+		assert_eq!(parse(json!("42")).unwrap(), 42.0);
+	}
+
+	#[test]
+	fn test_accepts_any_number_as_before() {
+		assert_eq!(parse(json!(0)).unwrap(), 0.0);
+		assert_eq!(parse(json!(-5.5)).unwrap(), -5.5);
+	}
+
+	#[test]
+	fn test_rejects_invalid_amounts() {
+		for amount in [
+			json!("abc"),
+			json!(""),
+			json!("1e3"),
+			json!("-10.00"),
+			json!(" 10.00"),
+			json!("10."),
+			json!(".5"),
+			json!("NaN"),
+			json!("0"),
+			json!("0.00"),
+			json!("9".repeat(400)),
+			json!(null),
+		] {
+			assert!(parse(amount.clone()).is_err(), "accepted {amount}");
+		}
+	}
+}
@@ -0,0 +1,46 @@
+use actix_web::{HttpResponse, Responder, get, web};
+
+use crate::adapters::web::schema::{
+	BuildInfo, DebugVars, QueueStatus, WorkersStatus,
+};
+use crate::adapters::web::state::DebugVarsState;
+use crate::infrastructure::observability::metrics::metrics;
+
+/// Machine-readable snapshot of the instance: uptime, build, redacted config,
+/// queue and worker state, and every metric sample.
+#[get("/debug/vars")]
+pub async fn debug_vars(state: web::Data<DebugVarsState>) -> impl Responder {
+	let metrics = metrics();
+
+	let counters = metrics
+		.snapshot()
+		.into_iter()
+		.map(|sample| {
+			let key = sample
+				.tags
+				.iter()
+				.fold(sample.name.to_string(), |key, (_, value)| {
+					format!("{key}.{value}")
+				});
+			(key, sample.value)
+		})
+		.collect();
+
+	HttpResponse::Ok().json(DebugVars {
+		uptime_secs: state.started_at.elapsed().as_secs(),
+		build: BuildInfo {
+			name:    env!("CARGO_PKG_NAME").to_string(),
+			version: env!("CARGO_PKG_VERSION").to_string(),
+		},
+		config: state.config.clone(),
+		queue: QueueStatus {
+			depth:              metrics.queue_depth(),
+			throughput:         metrics.throughput(),
+			estimated_delay_ms: metrics
+				.estimated_processing_delay()
+				.map(|delay| delay.as_millis() as u64),
+		},
+		workers: WorkersStatus::current(),
+		counters,
+	})
+}
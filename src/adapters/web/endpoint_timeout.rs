@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::web;
+use log::warn;
+
+use crate::adapters::web::errors::ApiError;
+
+/// Server-side time limits of the endpoints that need one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EndpointTimeouts {
+	pub payments: Duration,
+	pub summary:  Duration,
+}
+
+impl EndpointTimeouts {
+	fn for_path(&self, path: &str) -> Option<Duration> {
+		match path {
+			"/payments" => Some(self.payments),
+			"/payments-summary" => Some(self.summary),
+			_ => None,
+		}
+	}
+}
+
+/// Answers with a 504 when a request outlives the limit of its endpoint,
+/// taken from the [`EndpointTimeouts`] in the app data. The handler is
+/// dropped at that point, so a slow summary scan stops holding the worker
+/// thread. Requests to other endpoints are not limited.
+pub async fn enforce_endpoint_timeouts(
+	req: ServiceRequest,
+	next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+	let timeout = req
+		.app_data::<web::Data<EndpointTimeouts>>()
+		.and_then(|timeouts| timeouts.for_path(req.path()));
+	let Some(timeout) = timeout else {
+		return next.call(req).await;
+	};
+
+	let endpoint = format!("{} {}", req.method(), req.path());
+	match tokio::time::timeout(timeout, next.call(req)).await {
+		Ok(response) => response,
+		Err(_) => {
+			warn!("{endpoint} timed out after {}ms", timeout.as_millis());
+			Err(ApiError::TimeoutError.into())
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use actix_web::body::to_bytes;
+	use actix_web::http::StatusCode;
+	use actix_web::{App, HttpResponse, middleware, test as actix_test};
+
+	use super::*;
+
+	async fn slow() -> HttpResponse {
+		tokio::time::sleep(Duration::from_millis(200)).await;
+		HttpResponse::Ok().finish()
+	}
+
+	#[actix_web::test]
+	async fn test_times_out_only_limited_endpoints() {
+		let app = actix_test::init_service(
+			App::new()
+				.wrap(middleware::from_fn(enforce_endpoint_timeouts))
+				.app_data(web::Data::new(EndpointTimeouts {
+					payments: Duration::from_millis(20),
+					summary:  Duration::from_secs(1),
+				}))
+				.route("/payments", web::post().to(slow))
+				.route("/payments-summary", web::get().to(slow))
+				.route("/debug/vars", web::get().to(slow)),
+		)
+		.await;
+
+		let result = actix_test::try_call_service(
+			&app,
+			actix_test::TestRequest::post()
+				.uri("/payments")
+				.to_request(),
+		)
+		.await;
+		let Err(error) = result else {
+			panic!("Slow payments request should time out");
+		};
+		let resp = error.error_response();
+		assert_eq!(resp.status(), StatusCode::GATEWAY_TIMEOUT);
+		let body = to_bytes(resp.into_body()).await.unwrap();
+		let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+		assert_eq!(body["statusCode"], 504);
+
+		for uri in ["/payments-summary", "/debug/vars"] {
+			let resp = actix_test::call_service(
+				&app,
+				actix_test::TestRequest::get().uri(uri).to_request(),
+			)
+			.await;
+			assert_eq!(resp.status(), StatusCode::OK);
+		}
+	}
+}
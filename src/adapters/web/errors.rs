@@ -1,9 +1,13 @@
+use actix_web::error::JsonPayloadError;
 use actix_web::http::StatusCode;
 use actix_web::http::header::ContentType;
-use actix_web::{HttpResponse, error};
+use actix_web::{HttpRequest, HttpResponse, error};
 use derive_more::derive::{Display, Error};
 use serde::Serialize;
 
+use crate::domain::queue::QueueError;
+use crate::domain::repository::RepositoryError;
+
 #[derive(Serialize)]
 struct ErrorResponse {
 	#[serde(rename = "statusCode")]
@@ -16,21 +20,43 @@ struct ErrorResponse {
 pub enum ApiError {
 	#[display("Could not connect to the database.")]
 	DatabaseConnectionError,
+	#[display("Could not reach the payments queue.")]
+	QueueUnavailableError,
+	#[display("The request conflicts with existing payments.")]
+	ConflictError,
+	#[display("The requested resource was not found.")]
+	NotFoundError,
+	#[display("A valid admin token is required.")]
+	UnauthorizedError,
+	#[display("The method is not allowed on this resource.")]
+	MethodNotAllowedError,
+	#[display("Request data failed validation.")]
+	ValidationError,
 	#[display("Could not perform this operation.")]
 	TransactionError,
 	#[display("Request data is invalid.")]
 	BadClientDataError,
 	#[display("Internal server error.")]
 	InternalServerError,
+	#[display("The request took too long to complete.")]
+	TimeoutError,
 }
 
 impl ApiError {
 	pub fn name(&self) -> String {
 		match self {
-			ApiError::DatabaseConnectionError => "Insufficient Storage".to_string(),
+			ApiError::DatabaseConnectionError | ApiError::QueueUnavailableError => {
+				"Service Unavailable".to_string()
+			}
+			ApiError::ConflictError => "Conflict".to_string(),
+			ApiError::NotFoundError => "Not Found".to_string(),
+			ApiError::UnauthorizedError => "Unauthorized".to_string(),
+			ApiError::MethodNotAllowedError => "Method Not Allowed".to_string(),
+			ApiError::ValidationError => "Unprocessable Entity".to_string(),
 			ApiError::TransactionError => "Unprocessable Entity".to_string(),
 			ApiError::BadClientDataError => "Bad request".to_string(),
 			ApiError::InternalServerError => "Internal Server Error".to_string(),
+			ApiError::TimeoutError => "Gateway Timeout".to_string(),
 		}
 	}
 }
@@ -48,10 +74,18 @@ impl error::ResponseError for ApiError {
 
 	fn status_code(&self) -> StatusCode {
 		match self {
-			ApiError::DatabaseConnectionError => StatusCode::INSUFFICIENT_STORAGE,
+			ApiError::DatabaseConnectionError | ApiError::QueueUnavailableError => {
+				StatusCode::SERVICE_UNAVAILABLE
+			}
+			ApiError::ConflictError => StatusCode::CONFLICT,
+			ApiError::NotFoundError => StatusCode::NOT_FOUND,
+			ApiError::UnauthorizedError => StatusCode::UNAUTHORIZED,
+			ApiError::MethodNotAllowedError => StatusCode::METHOD_NOT_ALLOWED,
+			ApiError::ValidationError => StatusCode::UNPROCESSABLE_ENTITY,
 			ApiError::TransactionError => StatusCode::UNPROCESSABLE_ENTITY,
 			ApiError::BadClientDataError => StatusCode::BAD_REQUEST,
 			ApiError::InternalServerError => StatusCode::INTERNAL_SERVER_ERROR,
+			ApiError::TimeoutError => StatusCode::GATEWAY_TIMEOUT,
 		}
 	}
 }
@@ -62,20 +96,112 @@ impl From<Box<dyn std::error::Error>> for ApiError {
 	}
 }
 
+impl From<QueueError> for ApiError {
+	fn from(error: QueueError) -> Self {
+		match error {
+			QueueError::Unavailable(_) => ApiError::QueueUnavailableError,
+		}
+	}
+}
+
+impl From<RepositoryError> for ApiError {
+	fn from(error: RepositoryError) -> Self {
+		match error {
+			RepositoryError::Unavailable(_) => ApiError::DatabaseConnectionError,
+			RepositoryError::NotFound(_) => ApiError::NotFoundError,
+			RepositoryError::Conflict(_) => ApiError::ConflictError,
+		}
+	}
+}
+
+/// Rejects request bodies that are not JSON as bad requests, and JSON bodies
+/// with invalid values, such as a negative amount, as failed validation.
+pub fn json_error(error: JsonPayloadError, _: &HttpRequest) -> error::Error {
+	match error {
+		JsonPayloadError::Deserialize(e) if e.is_data() => {
+			ApiError::ValidationError.into()
+		}
+		_ => ApiError::BadClientDataError.into(),
+	}
+}
+
+/// Maps the errors returned by the use cases, which carry a [`QueueError`]
+/// or [`RepositoryError`] when the failure has a more precise status than a
+/// plain internal error.
+impl From<Box<dyn std::error::Error + Send>> for ApiError {
+	fn from(error: Box<dyn std::error::Error + Send>) -> Self {
+		if let Some(error) = error.downcast_ref::<QueueError>() {
+			return error.clone().into();
+		}
+		if let Some(error) = error.downcast_ref::<RepositoryError>() {
+			return error.clone().into();
+		}
+		ApiError::InternalServerError
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use actix_web::error::ResponseError;
 
 	use super::*;
+	use crate::adapters::web::schema::PaymentRequest;
 
 	#[test]
 	fn test_database_connection_error() {
 		let error = ApiError::DatabaseConnectionError;
-		assert_eq!(error.name(), "Insufficient Storage");
-		assert_eq!(error.status_code(), StatusCode::INSUFFICIENT_STORAGE);
+		assert_eq!(error.name(), "Service Unavailable");
+		assert_eq!(error.status_code(), StatusCode::SERVICE_UNAVAILABLE);
 
 		let resp = error.error_response();
-		assert_eq!(resp.status(), StatusCode::INSUFFICIENT_STORAGE);
+		assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+	}
+
+	#[test]
+	fn test_json_errors_tell_malformed_from_invalid_bodies() {
+		let req = actix_web::test::TestRequest::default().to_http_request();
+		let status = |body: &str| {
+			let error = serde_json::from_str::<PaymentRequest>(body).unwrap_err();
+			json_error(JsonPayloadError::Deserialize(error), &req)
+				.as_response_error()
+				.status_code()
+		};
+
+		assert_eq!(status(r#"{"correlationId": "#), StatusCode::BAD_REQUEST);
+		assert_eq!(
+			status(
+				r#"{"correlationId":"4a7901b8-7d26-4d9d-aa19-4dc1c7cf60b3","amount":"-1"}"#
+			),
+			StatusCode::UNPROCESSABLE_ENTITY
+		);
+	}
+
+	#[test]
+	fn test_maps_queue_and_repository_errors() {
+		let status = |error: Box<dyn std::error::Error + Send>| {
+			ApiError::from(error).status_code()
+		};
+
+		assert_eq!(
+			status(Box::new(QueueError::Unavailable("down".into()))),
+			StatusCode::SERVICE_UNAVAILABLE
+		);
+		assert_eq!(
+			status(Box::new(RepositoryError::Unavailable("down".into()))),
+			StatusCode::SERVICE_UNAVAILABLE
+		);
+		assert_eq!(
+			status(Box::new(RepositoryError::NotFound("payment".into()))),
+			StatusCode::NOT_FOUND
+		);
+		assert_eq!(
+			status(Box::new(RepositoryError::Conflict("payment".into()))),
+			StatusCode::CONFLICT
+		);
+		assert_eq!(
+			status(Box::new(std::io::Error::other("boom"))),
+			StatusCode::INTERNAL_SERVER_ERROR
+		);
 	}
 
 	#[test]
@@ -97,4 +223,14 @@ mod tests {
 		let resp = error.error_response();
 		assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
 	}
+
+	#[test]
+	fn test_timeout_error() {
+		let error = ApiError::TimeoutError;
+		assert_eq!(error.name(), "Gateway Timeout");
+		assert_eq!(error.status_code(), StatusCode::GATEWAY_TIMEOUT);
+
+		let resp = error.error_response();
+		assert_eq!(resp.status(), StatusCode::GATEWAY_TIMEOUT);
+	}
 }
@@ -1,3 +1,13 @@
+pub use crate::adapters::web::admin_config_handler::*;
+pub use crate::adapters::web::admin_epoch_handler::*;
+pub use crate::adapters::web::admin_flags_handler::*;
+pub use crate::adapters::web::admin_router_handler::*;
+pub use crate::adapters::web::admin_workers_handler::*;
+pub use crate::adapters::web::admin_ws_handler::*;
+pub use crate::adapters::web::debug_vars_handler::*;
+pub use crate::adapters::web::metrics_handler::*;
+pub use crate::adapters::web::payments_duplicates_handler::*;
 pub use crate::adapters::web::payments_handler::*;
 pub use crate::adapters::web::payments_purge_handler::*;
+pub use crate::adapters::web::payments_snapshot_handler::*;
 pub use crate::adapters::web::payments_summary_handler::*;
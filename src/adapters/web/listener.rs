@@ -0,0 +1,54 @@
+//! Listening sockets bound with `SO_REUSEPORT`, so the kernel spreads
+//! incoming connections over several accept queues instead of one.
+
+use std::io;
+use std::net::{SocketAddr, TcpListener};
+
+use socket2::{Domain, Socket, Type};
+
+/// Backlog of each listener when none is configured, the actix default.
+pub const DEFAULT_BACKLOG: i32 = 2048;
+
+/// Binds a listener on `addr` that other sockets, of this process or of
+/// others, can bind to as well.
+pub fn reuse_port_listener(
+	addr: SocketAddr,
+	backlog: i32,
+) -> io::Result<TcpListener> {
+	let socket = Socket::new(Domain::for_address(addr), Type::STREAM, None)?;
+	socket.set_reuse_address(true)?;
+	socket.set_reuse_port(true)?;
+	socket.set_nonblocking(true)?;
+	socket.bind(&addr.into())?;
+	socket.listen(backlog)?;
+	Ok(socket.into())
+}
+
+/// Binds `count` listeners sharing `addr`.
+pub fn reuse_port_listeners(
+	addr: SocketAddr,
+	count: usize,
+	backlog: i32,
+) -> io::Result<Vec<TcpListener>> {
+	(0..count.max(1))
+		.map(|_| reuse_port_listener(addr, backlog))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_listeners_share_the_port() {
+		let first = reuse_port_listener("127.0.0.1:0".parse().unwrap(), 16).unwrap();
+		let addr = first.local_addr().unwrap();
+
+		let others = reuse_port_listeners(addr, 2, 16).unwrap();
+
+		assert_eq!(others.len(), 2);
+		for listener in others {
+			assert_eq!(listener.local_addr().unwrap(), addr);
+		}
+	}
+}
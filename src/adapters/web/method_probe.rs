@@ -0,0 +1,135 @@
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::http::header::{ALLOW, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{HttpResponse, ResponseError};
+
+use crate::adapters::web::errors::ApiError;
+
+/// Methods served by each public endpoint.
+const PUBLIC_ENDPOINTS: [(&str, &[Method]); 4] = [
+	("/payments", &[Method::POST]),
+	("/payments-summary", &[Method::GET]),
+	("/payments-duplicates", &[Method::GET]),
+	("/purge-payments", &[Method::POST]),
+];
+
+fn allowed_methods(path: &str) -> Option<&'static [Method]> {
+	PUBLIC_ENDPOINTS
+		.iter()
+		.find(|(endpoint, _)| *endpoint == path)
+		.map(|(_, methods)| *methods)
+}
+
+/// Value of the `Allow` header of an endpoint: its own methods, `HEAD` when it
+/// can be read with `GET`, and `OPTIONS`.
+fn allow_header(methods: &[Method]) -> HeaderValue {
+	let mut allowed: Vec<&str> = methods.iter().map(Method::as_str).collect();
+	if methods.contains(&Method::GET) {
+		allowed.push(Method::HEAD.as_str());
+	}
+	allowed.push(Method::OPTIONS.as_str());
+
+	HeaderValue::from_str(&allowed.join(", ")).expect("Methods are valid headers")
+}
+
+/// Answers the `HEAD` and `OPTIONS` probes of benchmark tooling and load
+/// balancers on the public endpoints. `OPTIONS` gets a 204 listing the
+/// allowed methods, `HEAD` is served by the `GET` handler, whose body is
+/// dropped by the HTTP layer, and any other method not served by the
+/// endpoint gets a 405 instead of falling through to the 404 of unknown
+/// routes. Requests to other endpoints are passed through untouched.
+pub async fn answer_method_probes(
+	mut req: ServiceRequest,
+	next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+	let Some(methods) = allowed_methods(req.path()) else {
+		return next
+			.call(req)
+			.await
+			.map(ServiceResponse::map_into_boxed_body);
+	};
+
+	if req.method() == Method::HEAD && methods.contains(&Method::GET) {
+		req.head_mut().method = Method::GET;
+	}
+	if methods.contains(req.method()) {
+		return next
+			.call(req)
+			.await
+			.map(ServiceResponse::map_into_boxed_body);
+	}
+
+	let response = if req.method() == Method::OPTIONS {
+		HttpResponse::NoContent()
+			.insert_header((ALLOW, allow_header(methods)))
+			.finish()
+	} else {
+		let mut response = ApiError::MethodNotAllowedError.error_response();
+		response.headers_mut().insert(ALLOW, allow_header(methods));
+		response
+	};
+	Ok(req.into_response(response))
+}
+
+#[cfg(test)]
+mod tests {
+	use actix_web::http::StatusCode;
+	use actix_web::{App, middleware, test as actix_test, web};
+
+	use super::*;
+
+	#[actix_web::test]
+	async fn test_answers_probes_on_public_endpoints_only() {
+		let app = actix_test::init_service(
+			App::new()
+				.wrap(middleware::from_fn(answer_method_probes))
+				.route("/payments", web::post().to(HttpResponse::Created))
+				.route("/payments-summary", web::get().to(HttpResponse::Ok))
+				.route("/debug/vars", web::get().to(HttpResponse::Ok)),
+		)
+		.await;
+
+		let resp = actix_test::call_service(
+			&app,
+			actix_test::TestRequest::default()
+				.method(Method::OPTIONS)
+				.uri("/payments")
+				.to_request(),
+		)
+		.await;
+		assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+		assert_eq!(resp.headers().get(ALLOW).unwrap(), "POST, OPTIONS");
+
+		let resp = actix_test::call_service(
+			&app,
+			actix_test::TestRequest::default()
+				.method(Method::HEAD)
+				.uri("/payments-summary")
+				.to_request(),
+		)
+		.await;
+		assert_eq!(resp.status(), StatusCode::OK);
+
+		let resp = actix_test::call_service(
+			&app,
+			actix_test::TestRequest::delete()
+				.uri("/payments-summary")
+				.to_request(),
+		)
+		.await;
+		assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+		assert_eq!(resp.headers().get(ALLOW).unwrap(), "GET, HEAD, OPTIONS");
+
+		let resp = actix_test::call_service(
+			&app,
+			actix_test::TestRequest::default()
+				.method(Method::HEAD)
+				.uri("/debug/vars")
+				.to_request(),
+		)
+		.await;
+		assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+	}
+}
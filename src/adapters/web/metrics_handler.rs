@@ -0,0 +1,12 @@
+use actix_web::{HttpResponse, Responder, get};
+
+use crate::infrastructure::observability::metrics::metrics;
+use crate::infrastructure::observability::prometheus_exporter;
+
+/// Every metric sample, in the Prometheus text format, for scrapers.
+#[get("/metrics")]
+pub async fn prometheus_metrics() -> impl Responder {
+	HttpResponse::Ok()
+		.content_type("text/plain; version=0.0.4")
+		.body(prometheus_exporter::render(&metrics().snapshot()))
+}
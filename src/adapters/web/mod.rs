@@ -1,6 +1,24 @@
+pub mod admin_auth;
+pub mod admin_command;
+pub mod admin_config_handler;
+pub mod admin_epoch_handler;
+pub mod admin_flags_handler;
+pub mod admin_router_handler;
+pub mod admin_workers_handler;
+pub mod admin_ws_handler;
+pub mod amount;
+pub mod debug_vars_handler;
+pub mod endpoint_timeout;
 pub mod errors;
 pub mod handlers;
+pub mod listener;
+pub mod method_probe;
+pub mod metrics_handler;
+pub mod payments_duplicates_handler;
 pub mod payments_handler;
 pub mod payments_purge_handler;
+pub mod payments_snapshot_handler;
 pub mod payments_summary_handler;
+pub mod request_id;
 pub mod schema;
+pub mod state;
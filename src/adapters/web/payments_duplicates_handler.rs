@@ -0,0 +1,30 @@
+use actix_web::{HttpResponse, Responder, ResponseError, get, web};
+use log::error;
+
+use crate::adapters::web::errors::ApiError;
+use crate::adapters::web::schema::DuplicatesFilter;
+use crate::adapters::web::state::AppState;
+use crate::infrastructure::observability::error_reporting;
+use crate::use_cases::dto::GetDuplicatesReportQuery;
+
+/// How many submissions were caught as duplicates within the range, at
+/// ingest and by the workers.
+#[get("/payments-duplicates")]
+pub async fn payments_duplicates(
+	filter: web::Query<DuplicatesFilter>,
+	state: web::Data<AppState>,
+) -> impl Responder {
+	let query = GetDuplicatesReportQuery {
+		from: filter.from,
+		to:   filter.to,
+	};
+
+	match state.get_duplicates_report.execute(query).await {
+		Ok(report) => HttpResponse::Ok().json(report),
+		Err(e) => {
+			error!("Error getting the duplicates report: {e:?}");
+			error_reporting::report_error(e.as_ref());
+			ApiError::from(e).error_response()
+		}
+	}
+}
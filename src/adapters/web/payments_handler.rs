@@ -1,33 +1,108 @@
-use actix_web::{HttpResponse, Responder, ResponseError, post, web};
+use std::time::Duration;
+
+use actix_web::{HttpRequest, HttpResponse, Responder, ResponseError, post, web};
 use log::{info, warn};
 
 use crate::adapters::web::errors::ApiError;
+use crate::adapters::web::request_id::RequestId;
 use crate::adapters::web::schema::{PaymentRequest, PaymentResponse};
-use crate::infrastructure::queue::redis_payment_queue::PaymentQueue;
-use crate::use_cases::create_payment::CreatePaymentUseCase;
+use crate::adapters::web::state::AppState;
+use crate::infrastructure::config::feature_flags::{FeatureFlag, feature_flags};
+use crate::infrastructure::observability::metrics::metrics;
+use crate::infrastructure::observability::{error_reporting, log_redaction};
 use crate::use_cases::dto::CreatePaymentCommand;
 
+/// Header tagging a payment with the environment it was submitted from.
+pub const PAYMENT_TAG_HEADER: &str = "x-payment-tag";
+
+const MAX_PAYMENT_TAG_LEN: usize = 64;
+
 #[post("/payments")]
 pub async fn payments(
+	req: HttpRequest,
 	payload: web::Json<PaymentRequest>,
-	create_payment_use_case: web::Data<CreatePaymentUseCase<PaymentQueue>>,
+	state: web::Data<AppState>,
+	request_id: RequestId,
 ) -> impl Responder {
+	let Ok(tag) = payment_tag(&req) else {
+		return ApiError::BadClientDataError.error_response();
+	};
+
 	let command = CreatePaymentCommand {
 		correlation_id: payload.correlation_id,
-		amount:         payload.amount,
+		amount: payload.amount,
+		request_id: Some(request_id.0.clone()),
+		tag,
 	};
 
-	match create_payment_use_case.execute(command).await {
-		Ok(_) => {
-			info!("Payment received and queued: {}", payload.correlation_id);
-			HttpResponse::Ok().json(PaymentResponse {
-				payment: payload.0,
-				status:  "queued".to_string(),
-			})
+	if feature_flags().is_enabled(FeatureFlag::FireAndForgetIngest) {
+		let create_payment = state.create_payment.clone();
+		let correlation_id = payload.correlation_id;
+		actix_web::rt::spawn(async move {
+			if let Err(e) = create_payment.execute(command).await {
+				warn!("Error queueing payment (request {}): {e:?}", request_id.0);
+				error_reporting::report_payment_error(
+					correlation_id,
+					None,
+					e.as_ref(),
+				);
+			}
+		});
+		return payment_response(payload.0, "accepted", Duration::ZERO);
+	}
+
+	match state.create_payment.execute(command).await {
+		Ok(result) => {
+			info!(
+				"Payment received and queued: {} (request {})",
+				log_redaction::correlation_id(payload.correlation_id),
+				request_id.0
+			);
+			payment_response(payload.0, "queued", result.queue_latency)
 		}
 		Err(e) => {
-			warn!("Error processing payment: {e:?}");
-			ApiError::InternalServerError.error_response()
+			warn!("Error processing payment (request {}): {e:?}", request_id.0);
+			error_reporting::report_payment_error(
+				payload.correlation_id,
+				None,
+				e.as_ref(),
+			);
+			ApiError::from(e).error_response()
+		}
+	}
+}
+
+fn payment_response(
+	payment: PaymentRequest,
+	status: &str,
+	queue_latency: Duration,
+) -> HttpResponse {
+	HttpResponse::Ok().json(PaymentResponse {
+		payment,
+		status: status.to_string(),
+		queue_depth: metrics().queue_depth(),
+		estimated_delay_ms: metrics()
+			.estimated_processing_delay()
+			.map(|delay| delay.as_millis() as u64),
+		queue_latency_us: queue_latency.as_micros() as u64,
+	})
+}
+
+/// Reads the optional payment tag, rejecting tags that are not short
+/// printable tokens.
+fn payment_tag(req: &HttpRequest) -> Result<Option<String>, ApiError> {
+	let Some(value) = req.headers().get(PAYMENT_TAG_HEADER) else {
+		return Ok(None);
+	};
+
+	match value.to_str() {
+		Ok(tag)
+			if !tag.is_empty() &&
+				tag.len() <= MAX_PAYMENT_TAG_LEN &&
+				tag.bytes().all(|byte| byte.is_ascii_graphic()) =>
+		{
+			Ok(Some(tag.to_string()))
 		}
+		_ => Err(ApiError::BadClientDataError),
 	}
 }
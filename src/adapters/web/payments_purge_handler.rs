@@ -1,23 +1,75 @@
-use actix_web::{HttpResponse, Responder, post, web};
-use log::info;
+use actix_web::{HttpResponse, Responder, ResponseError, post, web};
+use log::{info, warn};
 
-use crate::infrastructure::persistence::redis_payment_repository::RedisPaymentRepository;
-use crate::use_cases::purge_payments::PurgePaymentsUseCase;
+use crate::adapters::web::errors::ApiError;
+use crate::adapters::web::schema::PurgePaymentsFilter;
+use crate::adapters::web::state::AppState;
+use crate::infrastructure::observability::error_reporting;
+use crate::infrastructure::workers::in_flight_registry::in_flight_payments;
+use crate::infrastructure::workers::worker_control::worker_control;
 
+/// Drains the payment workers of this instance first, so a payment they were
+/// processing is not saved after the purge. Payments in flight on the other
+/// instances are only fenced off, and discarded when they are saved, while
+/// the in-flight registry is shared through `APP_IN_FLIGHT_REGISTRY`. Without
+/// it they may still be saved after the purge, which is logged as a warning.
 #[post("/purge-payments")]
 pub async fn payments_purge(
-	purge_use_case: web::Data<PurgePaymentsUseCase<RedisPaymentRepository>>,
+	filter: web::Query<PurgePaymentsFilter>,
+	state: web::Data<AppState>,
 ) -> impl Responder {
-	info!("Received request to purge payments");
-	match purge_use_case.execute().await {
-		Ok(_) => {
-			info!("Payments purged successfully");
-			HttpResponse::Ok().body("Payments purged successfully")
+	if filter.tag.is_some() && filter.epoch.is_some() {
+		return ApiError::BadClientDataError.error_response();
+	}
+
+	if !in_flight_payments().is_shared() {
+		warn!(
+			"Purging without the shared in-flight registry, payments in flight on \
+			 other instances may be saved after the purge. Set \
+			 APP_IN_FLIGHT_REGISTRY to fence them off"
+		);
+	}
+
+	let _drained = worker_control().drain().await;
+	let result = match (&filter.tag, filter.epoch) {
+		(None, Some(epoch)) => {
+			let epoch = epoch.resolve();
+			info!("Received request to purge payments of run epoch {epoch}");
+			state
+				.purge_payments
+				.execute_for_epoch(epoch)
+				.await
+				.map(|purged| {
+					format!("Purged {purged} payments of run epoch {epoch}")
+				})
+		}
+		(Some(tag), _) => {
+			info!("Received request to purge payments tagged '{tag}'");
+			state
+				.purge_payments
+				.execute_for_tag(tag)
+				.await
+				.map(|purged| format!("Purged {purged} payments tagged '{tag}'"))
+		}
+		(None, None) => {
+			info!("Received request to purge payments");
+			state
+				.purge_payments
+				.execute()
+				.await
+				.map(|_| "Payments purged successfully".to_string())
+		}
+	};
+
+	match result {
+		Ok(message) => {
+			info!("{message}");
+			HttpResponse::Ok().body(message)
 		}
 		Err(e) => {
 			log::error!("Failed to purge payments: {e}");
-			HttpResponse::InternalServerError()
-				.body(format!("Failed to purge payments: {e}"))
+			error_reporting::report_error(e.as_ref());
+			ApiError::from(e).error_response()
 		}
 	}
 }
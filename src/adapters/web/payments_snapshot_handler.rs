@@ -0,0 +1,104 @@
+use actix_web::web::Bytes;
+use actix_web::{HttpResponse, Responder, ResponseError, get, post, web};
+use futures::StreamExt;
+use log::{error, info};
+
+use crate::adapters::web::errors::ApiError;
+use crate::adapters::web::state::AppState;
+use crate::use_cases::dto::{SnapshotImportReport, SnapshotRecord};
+
+/// Longest snapshot record accepted, well above any record the export writes,
+/// so a record without its newline is not buffered without bounds.
+pub const MAX_SNAPSHOT_RECORD_LEN: usize = 64 * 1024;
+
+#[get("/admin/snapshot")]
+pub async fn export_snapshot(state: web::Data<AppState>) -> impl Responder {
+	match state.payments_snapshot.export().await {
+		Ok(records) => HttpResponse::Ok()
+			.content_type("application/x-ndjson")
+			.streaming(records.map(|record| {
+				record
+					.and_then(|record| {
+						serde_json::to_vec(&record).map_err(|e| {
+							Box::new(e) as Box<dyn std::error::Error + Send>
+						})
+					})
+					.map(|mut line| {
+						line.push(b'\n');
+						Bytes::from(line)
+					})
+					.map_err(|e| std::io::Error::other(e.to_string()))
+			})),
+		Err(e) => {
+			error!("Failed to export payments snapshot: {e}");
+			ApiError::from(e).error_response()
+		}
+	}
+}
+
+#[post("/admin/snapshot")]
+pub async fn import_snapshot(
+	mut payload: web::Payload,
+	state: web::Data<AppState>,
+) -> impl Responder {
+	let mut report = SnapshotImportReport::default();
+	let mut buffer: Vec<u8> = Vec::new();
+
+	loop {
+		let chunk = payload.next().await;
+		let finished = chunk.is_none();
+
+		match chunk {
+			Some(Ok(bytes)) => buffer.extend_from_slice(&bytes),
+			Some(Err(e)) => {
+				error!("Failed to read payments snapshot: {e}");
+				return ApiError::BadClientDataError.error_response();
+			}
+			None => buffer.push(b'\n'),
+		}
+
+		while let Some(position) = buffer.iter().position(|byte| *byte == b'\n') {
+			let line: Vec<u8> = buffer.drain(..=position).collect();
+			if line.iter().all(u8::is_ascii_whitespace) {
+				continue;
+			}
+
+			let record: SnapshotRecord = match serde_json::from_slice(&line) {
+				Ok(record) => record,
+				Err(e) => {
+					error!("Invalid payments snapshot record: {e}");
+					return ApiError::BadClientDataError.error_response();
+				}
+			};
+
+			if let Err(e) = state.payments_snapshot.import(record, &mut report).await
+			{
+				error!("Failed to import payments snapshot record: {e}");
+				return ApiError::from(e).error_response();
+			}
+		}
+
+		if buffer.len() > MAX_SNAPSHOT_RECORD_LEN {
+			error!(
+				"Payments snapshot record longer than {MAX_SNAPSHOT_RECORD_LEN} \
+				 bytes"
+			);
+			return ApiError::BadClientDataError.error_response();
+		}
+
+		if finished {
+			break;
+		}
+	}
+
+	info!(
+		"Payments snapshot imported: {} queued, {} processed, {} skipped, {} \
+		 summary buckets, {} failures",
+		report.queued,
+		report.processed,
+		report.skipped,
+		report.buckets,
+		report.failures
+	);
+	HttpResponse::Ok().json(report)
+}
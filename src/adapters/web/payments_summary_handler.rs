@@ -1,28 +1,43 @@
 use actix_web::{HttpResponse, Responder, ResponseError, get, web};
 
 use crate::adapters::web::errors::ApiError;
-use crate::adapters::web::schema::PaymentsSummaryFilter;
-use crate::infrastructure::persistence::redis_payment_repository::RedisPaymentRepository;
+use crate::adapters::web::schema::{
+	EpochFilter, PaymentsSummaryFilter, SummaryBreakdown,
+};
+use crate::adapters::web::state::AppState;
+use crate::infrastructure::observability::error_reporting;
 use crate::use_cases::dto::GetPaymentSummaryQuery;
-use crate::use_cases::get_payment_summary::GetPaymentSummaryUseCase;
 
 #[get("/payments-summary")]
 pub async fn payments_summary(
 	filter: web::Query<PaymentsSummaryFilter>,
-	get_payment_summary_use_case: web::Data<
-		GetPaymentSummaryUseCase<RedisPaymentRepository>,
-	>,
+	state: web::Data<AppState>,
 ) -> impl Responder {
 	let query = GetPaymentSummaryQuery {
-		from: filter.from,
-		to:   filter.to,
+		from:           filter.from,
+		to:             filter.to,
+		failures:       filter.failures,
+		amount_buckets: filter.breakdown == Some(SummaryBreakdown::AmountBuckets),
+		latency:        filter.latency,
+		epoch:          filter.epoch.map(EpochFilter::resolve),
 	};
 
-	match get_payment_summary_use_case.execute(query).await {
+	let mut result = state.get_payment_summary.execute(query).await;
+	if filter.pending &&
+		let Ok(summary) = &mut result
+	{
+		match state.get_pending_payments.execute().await {
+			Ok(pending) => summary.pending = Some(pending),
+			Err(e) => result = Err(e),
+		}
+	}
+
+	match result {
 		Ok(summary) => HttpResponse::Ok().json(summary),
 		Err(e) => {
 			eprintln!("Error getting payment summary: {e:?}");
-			ApiError::InternalServerError.error_response()
+			error_reporting::report_error(e.as_ref());
+			ApiError::from(e).error_response()
 		}
 	}
 }
@@ -0,0 +1,135 @@
+use std::convert::Infallible;
+use std::future::{Ready, ready};
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::error::InternalError;
+use actix_web::http::header::{HeaderMap, HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{FromRequest, HttpMessage, HttpRequest};
+use uuid::Uuid;
+
+use crate::use_cases::process_payment::REQUEST_ID_HEADER;
+
+/// Longest client supplied id that is accepted as is.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Identifies an HTTP request across this service's logs and the processor
+/// calls made on its behalf.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+	/// Reuses the id sent by the client, unless it is missing or could mangle
+	/// log lines, in which case a new one is generated.
+	fn from_headers(headers: &HeaderMap) -> Self {
+		headers
+			.get(REQUEST_ID_HEADER)
+			.and_then(|value| value.to_str().ok())
+			.filter(|id| {
+				!id.is_empty() &&
+					id.len() <= MAX_REQUEST_ID_LEN &&
+					id.bytes().all(|byte| byte.is_ascii_graphic())
+			})
+			.map_or_else(|| Self(Uuid::new_v4().to_string()), |id| Self(id.into()))
+	}
+}
+
+impl FromRequest for RequestId {
+	type Error = Infallible;
+	type Future = Ready<Result<Self, Self::Error>>;
+
+	fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+		let request_id = req
+			.extensions()
+			.get::<RequestId>()
+			.cloned()
+			.unwrap_or_else(|| RequestId::from_headers(req.headers()));
+
+		ready(Ok(request_id))
+	}
+}
+
+/// Assigns every request an id, available to handlers through the
+/// [`RequestId`] extractor, and echoes it back in the response headers, error
+/// responses included.
+pub async fn propagate_request_id(
+	req: ServiceRequest,
+	next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+	let request_id = RequestId::from_headers(req.headers());
+	let header_value =
+		HeaderValue::from_str(&request_id.0).expect("Request ids are visible ASCII");
+	req.extensions_mut().insert(request_id);
+
+	let header_name = HeaderName::from_static(REQUEST_ID_HEADER);
+	match next.call(req).await {
+		Ok(mut response) => {
+			response.headers_mut().insert(header_name, header_value);
+			Ok(response)
+		}
+		Err(e) => {
+			let mut response = e.error_response();
+			response.headers_mut().insert(header_name, header_value);
+			Err(InternalError::from_response(e, response).into())
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use actix_web::{App, HttpResponse, middleware, test as actix_test, web};
+
+	use super::*;
+
+	fn headers(request_id: &str) -> HeaderMap {
+		let mut headers = HeaderMap::new();
+		headers.insert(
+			HeaderName::from_static(REQUEST_ID_HEADER),
+			HeaderValue::from_str(request_id).unwrap(),
+		);
+		headers
+	}
+
+	#[test]
+	fn test_reuses_client_request_id() {
+		assert_eq!(
+			RequestId::from_headers(&headers("abc-123")),
+			RequestId("abc-123".to_string())
+		);
+	}
+
+	#[test]
+	fn test_replaces_unusable_request_id() {
+		let request_id = RequestId::from_headers(&headers("two words"));
+
+		assert!(Uuid::parse_str(&request_id.0).is_ok());
+		assert!(
+			Uuid::parse_str(&RequestId::from_headers(&HeaderMap::new()).0).is_ok()
+		);
+	}
+
+	#[actix_web::test]
+	async fn test_echoes_request_id_seen_by_handler() {
+		let app = actix_test::init_service(
+			App::new()
+				.wrap(middleware::from_fn(propagate_request_id))
+				.route(
+					"/",
+					web::get().to(|request_id: RequestId| async move {
+						HttpResponse::Ok().body(request_id.0)
+					}),
+				),
+		)
+		.await;
+
+		let req = actix_test::TestRequest::get()
+			.uri("/")
+			.insert_header((REQUEST_ID_HEADER, "abc-123"))
+			.to_request();
+		let resp = actix_test::call_service(&app, req).await;
+
+		assert_eq!(resp.headers().get(REQUEST_ID_HEADER).unwrap(), "abc-123");
+		assert_eq!(actix_test::read_body(resp).await, "abc-123");
+	}
+}
@@ -1,24 +1,345 @@
+use std::collections::BTreeMap;
+
 use actix_web::cookie::time::OffsetDateTime;
+use circuitbreaker_rs::State;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::adapters::web::amount;
+use crate::domain::health_status::HealthStatus;
+use crate::domain::payment::Payment;
+use crate::domain::payment_processor::PaymentProcessor;
+use crate::domain::processor_settings::ProcessorSettings;
+use crate::domain::queue::Message;
+use crate::infrastructure::config::settings::Config;
+use crate::infrastructure::workers::worker_control::worker_control;
+use crate::use_cases::dto::{
+	AmountBucketCount, PaymentFailures, PaymentSummaryResult,
+	PaymentsSummaryResponse, PendingPayments, ProcessingLatency,
+};
+use crate::use_cases::{run_epoch, time_bound};
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct PaymentRequest {
 	#[serde(rename = "correlationId")]
 	pub correlation_id: Uuid,
+	#[serde(deserialize_with = "amount::deserialize")]
 	pub amount:         f64,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct PaymentResponse {
-	pub payment: PaymentRequest,
-	pub status:  String,
+	pub payment:            PaymentRequest,
+	pub status:             String,
+	/// Payments waiting in the queue when this one was accepted.
+	#[serde(rename = "queueDepth", default)]
+	pub queue_depth:        u64,
+	/// Estimated time until the queue drains at the current throughput.
+	#[serde(
+		rename = "estimatedDelayMs",
+		default,
+		skip_serializing_if = "Option::is_none"
+	)]
+	pub estimated_delay_ms: Option<u64>,
+	/// Time spent pushing this payment to the queue, in microseconds.
+	#[serde(rename = "queueLatencyUs", default)]
+	pub queue_latency_us:   u64,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// Range of the payments summary. Either bound may be omitted to leave that
+/// side of the range open, and both accept relative expressions such as `now`
+/// or `-5m`.
+#[derive(Debug, Deserialize, Serialize, Default)]
 pub struct PaymentsSummaryFilter {
-	#[serde(with = "time::serde::rfc3339::option", default)]
+	#[serde(
+		serialize_with = "time::serde::rfc3339::option::serialize",
+		deserialize_with = "time_bound::deserialize",
+		default
+	)]
+	pub from:      Option<OffsetDateTime>,
+	#[serde(
+		serialize_with = "time::serde::rfc3339::option::serialize",
+		deserialize_with = "time_bound::deserialize",
+		default
+	)]
+	pub to:        Option<OffsetDateTime>,
+	/// Adds the queued and in-processing payment counts to the summary.
+	#[serde(default)]
+	pub pending:   bool,
+	/// Adds the failed and rejected processor calls of each processor.
+	#[serde(default)]
+	pub failures:  bool,
+	#[serde(default)]
+	pub breakdown: Option<SummaryBreakdown>,
+	/// Adds the average and p95 processing latency of each processor.
+	#[serde(default)]
+	pub latency:   bool,
+	/// Sums only the payments of a run epoch. The failures, amount buckets
+	/// and latency still cover every epoch.
+	#[serde(default)]
+	pub epoch:     Option<EpochFilter>,
+}
+
+/// Run epoch a summary or purge is scoped to: its number, or `current` for
+/// the epoch payments are being stamped with.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub enum EpochFilter {
+	Current,
+	Epoch(u64),
+}
+
+impl EpochFilter {
+	pub fn resolve(self) -> u64 {
+		match self {
+			EpochFilter::Current => run_epoch::current_epoch(),
+			EpochFilter::Epoch(epoch) => epoch,
+		}
+	}
+}
+
+impl TryFrom<String> for EpochFilter {
+	type Error = String;
+
+	fn try_from(value: String) -> Result<Self, Self::Error> {
+		if value == "current" {
+			return Ok(EpochFilter::Current);
+		}
+		value
+			.parse()
+			.map(EpochFilter::Epoch)
+			.map_err(|_| format!("invalid run epoch '{value}'"))
+	}
+}
+
+impl From<EpochFilter> for String {
+	fn from(filter: EpochFilter) -> Self {
+		match filter {
+			EpochFilter::Current => "current".to_string(),
+			EpochFilter::Epoch(epoch) => epoch.to_string(),
+		}
+	}
+}
+
+/// Extra detail the summary can be broken down by.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryBreakdown {
+	/// Payment counts per amount range, for each processor.
+	AmountBuckets,
+}
+
+/// Range of the duplicates report, with the same bounds as
+/// [`PaymentsSummaryFilter`].
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DuplicatesFilter {
+	#[serde(
+		serialize_with = "time::serde::rfc3339::option::serialize",
+		deserialize_with = "time_bound::deserialize",
+		default
+	)]
 	pub from: Option<OffsetDateTime>,
-	#[serde(with = "time::serde::rfc3339::option", default)]
+	#[serde(
+		serialize_with = "time::serde::rfc3339::option::serialize",
+		deserialize_with = "time_bound::deserialize",
+		default
+	)]
 	pub to:   Option<OffsetDateTime>,
 }
+
+/// Restricts a purge to the payments submitted under `tag`, or to the
+/// payments of a run epoch.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct PurgePaymentsFilter {
+	pub tag:   Option<String>,
+	pub epoch: Option<EpochFilter>,
+}
+
+/// Run epoch new payments are stamped with.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct RunEpochStatus {
+	pub epoch: u64,
+}
+
+/// Settings the instance runs with right now: the redacted startup config
+/// plus what was changed through the admin endpoints since.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct EffectiveConfig {
+	pub config:        Config,
+	pub processors:    BTreeMap<String, ProcessorSettings>,
+	pub workers:       WorkersStatus,
+	#[serde(rename = "featureFlags")]
+	pub feature_flags: BTreeMap<String, bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct FeatureFlagRequest {
+	pub enabled: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct WorkerConcurrencyRequest {
+	pub concurrency: usize,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct WorkersStatus {
+	pub paused:      bool,
+	pub concurrency: Option<usize>,
+	#[serde(rename = "inFlight")]
+	pub in_flight:   usize,
+}
+
+impl WorkersStatus {
+	pub fn current() -> Self {
+		let control = worker_control();
+		let concurrency = control.concurrency();
+
+		Self {
+			paused:      control.is_paused(),
+			concurrency: (concurrency != usize::MAX).then_some(concurrency),
+			in_flight:   control.in_flight(),
+		}
+	}
+}
+
+/// What the router currently knows about a processor.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessorStatus {
+	pub name:              String,
+	pub url:               String,
+	pub health:            String,
+	pub min_response_time: u64,
+	#[serde(with = "time::serde::rfc3339::option", default)]
+	pub last_updated:      Option<OffsetDateTime>,
+	/// State of the processor's circuit breaker.
+	pub breaker:           Option<String>,
+}
+
+impl ProcessorStatus {
+	pub fn new(processor: &PaymentProcessor, breaker: Option<State>) -> Self {
+		let health = match processor.health {
+			HealthStatus::Healthy => "healthy",
+			HealthStatus::Failing => "failing",
+			HealthStatus::Slow => "slow",
+		};
+		let breaker = breaker.map(|state| {
+			match state {
+				State::Closed => "closed",
+				State::Open => "open",
+				State::HalfOpen => "half_open",
+			}
+			.to_string()
+		});
+
+		Self {
+			name: processor.name.to_string(),
+			url: processor.url.to_string(),
+			health: health.to_string(),
+			min_response_time: processor.min_response_time,
+			last_updated: processor.last_updated,
+			breaker,
+		}
+	}
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct BuildInfo {
+	pub name:    String,
+	pub version: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct QueueStatus {
+	pub depth:              u64,
+	/// Payments processed per second by this instance.
+	pub throughput:         f64,
+	#[serde(rename = "estimatedDelayMs")]
+	pub estimated_delay_ms: Option<u64>,
+}
+
+/// Internal state in the spirit of Go's expvar, for scripts that cannot
+/// scrape StatsD. Counters are keyed by metric name followed by its tag
+/// values, e.g. `payments_processed.default`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DebugVars {
+	#[serde(rename = "uptimeSecs")]
+	pub uptime_secs: u64,
+	pub build:       BuildInfo,
+	pub config:      Config,
+	pub queue:       QueueStatus,
+	pub workers:     WorkersStatus,
+	pub counters:    BTreeMap<String, u64>,
+}
+
+/// JSON shapes others depend on: the payment request and summary of the
+/// rinha spec, and the queue message instances of other versions read. Every
+/// optional field is set, so a renamed or dropped field shows up against the
+/// golden files in `tests/golden`.
+pub fn canonical_shapes() -> Vec<(&'static str, serde_json::Value)> {
+	let correlation_id = Uuid::from_u128(0x4a7901b8_7d26_4d9d_aa19_4dc1c7cf60b3);
+	let requested_at = OffsetDateTime::from_unix_timestamp(1_752_451_200)
+		.expect("Sample timestamp is valid");
+	let summary = |total_requests, total_amount| PaymentSummaryResult {
+		total_requests,
+		total_amount,
+		failures: Some(PaymentFailures {
+			failed:   1,
+			rejected: 0,
+		}),
+		amount_buckets: Some(vec![AmountBucketCount {
+			range: "10-100".to_string(),
+			count: total_requests,
+		}]),
+		latency: Some(ProcessingLatency {
+			count:  total_requests,
+			avg_ms: 12.5,
+			p95_ms: 40.0,
+		}),
+	};
+
+	let payment_request = PaymentRequest {
+		correlation_id,
+		amount: 19.9,
+	};
+	let payments_summary = PaymentsSummaryResponse {
+		default:  summary(43236, 415542345.98),
+		fallback: summary(423545, 329347.34),
+		pending:  Some(PendingPayments {
+			queued:        12,
+			in_processing: 3,
+		}),
+	};
+	let queue_message = Message {
+		id:          correlation_id,
+		body:        Payment {
+			correlation_id,
+			amount: 19.9,
+			requested_at: Some(requested_at),
+			processed_at: Some(requested_at),
+			processed_by: Some("default".to_string()),
+			tag: Some("staging".to_string()),
+			epoch: Some(3),
+		},
+		attempts:    2,
+		request_id:  Some("req-1".to_string()),
+		sequence:    Some(42),
+		enqueued_at: Some(requested_at),
+	};
+
+	let shape = |value: Result<serde_json::Value, serde_json::Error>| {
+		value.expect("Canonical shapes serialize")
+	};
+	vec![
+		(
+			"payment_request",
+			shape(serde_json::to_value(payment_request)),
+		),
+		(
+			"payments_summary",
+			shape(serde_json::to_value(payments_summary)),
+		),
+		("queue_message", shape(serde_json::to_value(queue_message))),
+	]
+}
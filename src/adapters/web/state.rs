@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::domain::payment::Payment;
+use crate::domain::payment_archive::PaymentArchive;
+use crate::domain::queue::DynQueue;
+use crate::domain::repository::DynPaymentRepository;
+use crate::infrastructure::config::settings::Config;
+use crate::use_cases::create_payment::CreatePaymentUseCase;
+use crate::use_cases::get_duplicates_report::GetDuplicatesReportUseCase;
+use crate::use_cases::get_payment_summary::GetPaymentSummaryUseCase;
+use crate::use_cases::get_pending_payments::GetPendingPaymentsUseCase;
+use crate::use_cases::payments_snapshot::PaymentsSnapshotUseCase;
+use crate::use_cases::purge_payments::PurgePaymentsUseCase;
+
+pub type SharedPaymentQueue = Arc<dyn DynQueue<Payment>>;
+pub type SharedPaymentRepository = Arc<dyn DynPaymentRepository>;
+
+/// Use cases served by the HTTP handlers, registered once as app data. The
+/// queue and repository are trait objects so alternative implementations can
+/// be injected without touching the handlers.
+#[derive(Clone)]
+pub struct AppState {
+	pub create_payment:        CreatePaymentUseCase<SharedPaymentQueue>,
+	pub get_payment_summary:   GetPaymentSummaryUseCase<SharedPaymentRepository>,
+	pub get_pending_payments:  GetPendingPaymentsUseCase<SharedPaymentQueue>,
+	pub get_duplicates_report: GetDuplicatesReportUseCase<SharedPaymentRepository>,
+	pub purge_payments:        PurgePaymentsUseCase<SharedPaymentRepository>,
+	pub payments_snapshot:
+		PaymentsSnapshotUseCase<SharedPaymentQueue, SharedPaymentRepository>,
+}
+
+impl AppState {
+	pub fn new(
+		payment_queue: SharedPaymentQueue,
+		payment_repo: SharedPaymentRepository,
+	) -> Self {
+		Self {
+			create_payment:        CreatePaymentUseCase::new(payment_queue.clone()),
+			get_payment_summary:   GetPaymentSummaryUseCase::new(
+				payment_repo.clone(),
+			),
+			get_pending_payments:  GetPendingPaymentsUseCase::new(
+				payment_queue.clone(),
+			),
+			get_duplicates_report: GetDuplicatesReportUseCase::new(
+				payment_repo.clone(),
+			),
+			purge_payments:        PurgePaymentsUseCase::new(payment_repo.clone()),
+			payments_snapshot:     PaymentsSnapshotUseCase::new(
+				payment_queue,
+				payment_repo,
+			),
+		}
+	}
+
+	/// Includes the queue of each processor in payment snapshots.
+	pub fn with_processor_queues(
+		mut self,
+		processor_queues: HashMap<String, SharedPaymentQueue>,
+	) -> Self {
+		self.payments_snapshot = self
+			.payments_snapshot
+			.with_processor_queues(processor_queues);
+		self
+	}
+
+	/// Merges archived payments older than `hot_window` into summaries and
+	/// clears the archive on purge.
+	pub fn with_archive(
+		mut self,
+		archive: Arc<dyn PaymentArchive>,
+		hot_window: time::Duration,
+	) -> Self {
+		self.get_payment_summary = self
+			.get_payment_summary
+			.with_archive(archive.clone(), hot_window);
+		self.purge_payments = self.purge_payments.with_archive(archive);
+		self
+	}
+}
+
+/// What `/debug/vars` reports besides the process-wide metrics. The config is
+/// redacted once, when the app is built.
+pub struct DebugVarsState {
+	pub started_at: Instant,
+	pub config:     Config,
+}
+
+impl DebugVarsState {
+	pub fn new(started_at: Instant, config: &Config) -> Self {
+		Self {
+			started_at,
+			config: config.redacted(),
+		}
+	}
+}
@@ -0,0 +1,102 @@
+//! Client of this service's own API, for load generators and other Rust
+//! services submitting payments to it. Requests and responses use the same
+//! types the handlers do, so the client cannot drift from the API.
+
+use reqwest::{Client, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+
+use crate::adapters::web::payments_handler::PAYMENT_TAG_HEADER;
+pub use crate::adapters::web::schema::{
+	EpochFilter, PaymentRequest, PaymentResponse, PaymentsSummaryFilter,
+	PurgePaymentsFilter, SummaryBreakdown,
+};
+pub use crate::use_cases::dto::{PaymentSummaryResult, PaymentsSummaryResponse};
+
+#[derive(Clone)]
+pub struct PaymentsClient {
+	http_client: Client,
+	base_url:    String,
+	tag:         Option<String>,
+}
+
+impl PaymentsClient {
+	pub fn new(http_client: Client, base_url: impl Into<String>) -> Self {
+		Self {
+			http_client,
+			base_url: base_url.into().trim_end_matches('/').to_string(),
+			tag: None,
+		}
+	}
+
+	/// Submits every payment under `tag`, so they can be purged on their own.
+	pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+		self.tag = Some(tag.into());
+		self
+	}
+
+	/// Queues a payment, returning how the service accepted it.
+	pub async fn submit_payment(
+		&self,
+		payment: &PaymentRequest,
+	) -> Result<PaymentResponse, Box<dyn std::error::Error + Send>> {
+		let mut request = self.http_client.post(self.url("/payments")).json(payment);
+		if let Some(tag) = &self.tag {
+			request = request.header(PAYMENT_TAG_HEADER, tag);
+		}
+
+		json(send(request).await?).await
+	}
+
+	pub async fn get_summary(
+		&self,
+		filter: &PaymentsSummaryFilter,
+	) -> Result<PaymentsSummaryResponse, Box<dyn std::error::Error + Send>> {
+		let request = self
+			.http_client
+			.get(self.url("/payments-summary"))
+			.query(filter);
+
+		json(send(request).await?).await
+	}
+
+	/// Purges the processed payments matching `filter`, returning the
+	/// service's description of what was purged.
+	pub async fn purge_payments(
+		&self,
+		filter: &PurgePaymentsFilter,
+	) -> Result<String, Box<dyn std::error::Error + Send>> {
+		let request = self
+			.http_client
+			.post(self.url("/purge-payments"))
+			.query(filter);
+
+		send(request)
+			.await?
+			.text()
+			.await
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)
+	}
+
+	fn url(&self, path: &str) -> String {
+		format!("{}{path}", self.base_url)
+	}
+}
+
+async fn send(
+	request: RequestBuilder,
+) -> Result<Response, Box<dyn std::error::Error + Send>> {
+	request
+		.send()
+		.await
+		.and_then(|response| response.error_for_status())
+		.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)
+}
+
+async fn json<T: DeserializeOwned>(
+	response: Response,
+) -> Result<T, Box<dyn std::error::Error + Send>> {
+	response
+		.json()
+		.await
+		.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)
+}
@@ -0,0 +1,248 @@
+//! Contract checks of the payment processor API: the payment, health and
+//! admin endpoints are exercised the way this service uses them, and every
+//! response is checked against the wire format the service relies on, so a
+//! new processor image that changes it is caught before it reaches a run.
+
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::domain::payment::Payment;
+use crate::infrastructure::config::settings::RequestedAtFormat;
+use crate::infrastructure::gateway::processor_admin_client::ProcessorAdminClient;
+use crate::use_cases::dto::PaymentProcessorRequest;
+
+/// Tolerance used when comparing amounts and fees.
+const AMOUNT_TOLERANCE: f64 = 0.01;
+
+#[derive(Debug, Clone)]
+pub struct ContractCheck {
+	pub name:    &'static str,
+	/// Why the check failed, if it did.
+	pub failure: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ContractReport {
+	pub checks: Vec<ContractCheck>,
+}
+
+impl ContractReport {
+	pub fn is_satisfied(&self) -> bool {
+		self.checks.iter().all(|check| check.failure.is_none())
+	}
+
+	pub fn failures(&self) -> impl Iterator<Item = &ContractCheck> {
+		self.checks.iter().filter(|check| check.failure.is_some())
+	}
+
+	fn record(&mut self, name: &'static str, outcome: Result<(), String>) {
+		self.checks.push(ContractCheck {
+			name,
+			failure: outcome.err(),
+		});
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ServiceHealth {
+	failing:           bool,
+	min_response_time: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProcessedPayment {
+	correlation_id: Uuid,
+	amount:         f64,
+}
+
+/// Runs the contract checks against the processor at `url`. The processor
+/// is purged and its failure flag toggled along the way, so it must not be
+/// shared with anything else while the checks run.
+pub struct ProcessorContract {
+	http_client: Client,
+	url:         String,
+	admin:       ProcessorAdminClient,
+}
+
+impl ProcessorContract {
+	pub fn new(
+		http_client: Client,
+		url: String,
+		admin: ProcessorAdminClient,
+	) -> Self {
+		Self {
+			http_client,
+			url,
+			admin,
+		}
+	}
+
+	/// Runs every check, even after one fails, and reports all of them.
+	pub async fn run(&self) -> ContractReport {
+		let mut report = ContractReport::default();
+
+		// The health endpoint is rate limited, so it is checked once, first.
+		report.record("service_health", self.check_service_health().await);
+		report.record("admin_purge", self.check_admin_purge().await);
+
+		let payment = Self::payment(19.9);
+		report.record(
+			"payment_accepted",
+			self.check_payment_accepted(&payment).await,
+		);
+		report.record("payment_lookup", self.check_payment_lookup(&payment).await);
+		report.record(
+			"duplicate_payment_rejected",
+			self.check_duplicate_payment_rejected(&payment).await,
+		);
+		report.record(
+			"admin_summary",
+			self.check_admin_summary(1, payment.amount).await,
+		);
+		report.record("admin_failure", self.check_admin_failure().await);
+
+		report
+	}
+
+	fn payment(amount: f64) -> Payment {
+		Payment {
+			correlation_id: Uuid::new_v4(),
+			amount,
+			requested_at: Some(OffsetDateTime::now_utc()),
+			processed_at: None,
+			processed_by: None,
+			tag: None,
+			epoch: None,
+		}
+	}
+
+	async fn post_payment(&self, payment: &Payment) -> Result<StatusCode, String> {
+		self.http_client
+			.post(format!("{}/payments", self.url))
+			.json(&PaymentProcessorRequest::from_payment(
+				payment,
+				RequestedAtFormat::Millis,
+			))
+			.send()
+			.await
+			.map(|response| response.status())
+			.map_err(|e| e.to_string())
+	}
+
+	async fn check_service_health(&self) -> Result<(), String> {
+		let health: ServiceHealth = self
+			.http_client
+			.get(format!("{}/payments/service-health", self.url))
+			.send()
+			.await
+			.and_then(|response| response.error_for_status())
+			.map_err(|e| e.to_string())?
+			.json()
+			.await
+			.map_err(|e| format!("Unexpected health body: {e}"))?;
+
+		if health.failing {
+			return Err(format!(
+				"Processor reported failing, min response time {}ms",
+				health.min_response_time
+			));
+		}
+		Ok(())
+	}
+
+	async fn check_admin_purge(&self) -> Result<(), String> {
+		self.admin
+			.purge_payments()
+			.await
+			.map_err(|e| e.to_string())?;
+		self.check_admin_summary(0, 0.0).await
+	}
+
+	async fn check_payment_accepted(&self, payment: &Payment) -> Result<(), String> {
+		match self.post_payment(payment).await? {
+			status if status.is_success() => Ok(()),
+			status => Err(format!("Payment answered with {status}")),
+		}
+	}
+
+	async fn check_payment_lookup(&self, payment: &Payment) -> Result<(), String> {
+		let processed: ProcessedPayment = self
+			.http_client
+			.get(format!("{}/payments/{}", self.url, payment.correlation_id))
+			.send()
+			.await
+			.and_then(|response| response.error_for_status())
+			.map_err(|e| e.to_string())?
+			.json()
+			.await
+			.map_err(|e| format!("Unexpected payment body: {e}"))?;
+
+		if processed.correlation_id != payment.correlation_id ||
+			(processed.amount - payment.amount).abs() > AMOUNT_TOLERANCE
+		{
+			return Err(format!(
+				"Looked up {} of {}, sent {} of {}",
+				processed.correlation_id,
+				processed.amount,
+				payment.correlation_id,
+				payment.amount
+			));
+		}
+		Ok(())
+	}
+
+	async fn check_duplicate_payment_rejected(
+		&self,
+		payment: &Payment,
+	) -> Result<(), String> {
+		match self.post_payment(payment).await? {
+			status if status.is_client_error() => Ok(()),
+			status => Err(format!("Duplicate payment answered with {status}")),
+		}
+	}
+
+	async fn check_admin_summary(
+		&self,
+		total_requests: u64,
+		total_amount: f64,
+	) -> Result<(), String> {
+		let summary = self
+			.admin
+			.get_payments_summary(None, None)
+			.await
+			.map_err(|e| e.to_string())?;
+
+		let expected_fee = total_amount * summary.fee_per_transaction;
+		if summary.total_requests != total_requests ||
+			(summary.total_amount - total_amount).abs() > AMOUNT_TOLERANCE ||
+			(summary.total_fee - expected_fee).abs() > AMOUNT_TOLERANCE
+		{
+			return Err(format!(
+				"Expected {total_requests} payments of {total_amount}, got \
+				 {summary:?}"
+			));
+		}
+		Ok(())
+	}
+
+	async fn check_admin_failure(&self) -> Result<(), String> {
+		self.admin
+			.set_failure(true)
+			.await
+			.map_err(|e| e.to_string())?;
+		let status = self.post_payment(&Self::payment(1.0)).await;
+		self.admin
+			.set_failure(false)
+			.await
+			.map_err(|e| e.to_string())?;
+
+		match status? {
+			status if status.is_server_error() => Ok(()),
+			status => Err(format!("Failing processor answered with {status}")),
+		}
+	}
+}
@@ -0,0 +1,51 @@
+use std::fmt;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Alert {
+	AllProcessorsDown { down_for: Duration },
+	ProcessorsRecovered { down_for: Duration },
+}
+
+impl Alert {
+	pub fn event(&self) -> &'static str {
+		match self {
+			Alert::AllProcessorsDown { .. } => "all_processors_down",
+			Alert::ProcessorsRecovered { .. } => "processors_recovered",
+		}
+	}
+
+	pub fn down_for(&self) -> Duration {
+		match self {
+			Alert::AllProcessorsDown { down_for } |
+			Alert::ProcessorsRecovered { down_for } => *down_for,
+		}
+	}
+}
+
+impl fmt::Display for Alert {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Alert::AllProcessorsDown { down_for } => write!(
+				f,
+				"All payment processors have been failing for {}s",
+				down_for.as_secs()
+			),
+			Alert::ProcessorsRecovered { down_for } => write!(
+				f,
+				"Payment processors recovered after {}s of downtime",
+				down_for.as_secs()
+			),
+		}
+	}
+}
+
+#[async_trait]
+pub trait Alerter: Send + Sync + 'static {
+	async fn send(
+		&self,
+		alert: &Alert,
+	) -> Result<(), Box<dyn std::error::Error + Send>>;
+}
@@ -0,0 +1,28 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// A payment processor's circuit breaker moved from one state to another.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BreakerTransition {
+	pub processor:    String,
+	pub from:         String,
+	pub to:           String,
+	/// Share of the calls made since the previous transition that failed.
+	#[serde(rename = "failureRate")]
+	pub failure_rate: f64,
+}
+
+impl fmt::Display for BreakerTransition {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"Circuit breaker of processor '{}' went from {} to {} (failure rate \
+			 {:.1}%)",
+			self.processor,
+			self.from,
+			self.to,
+			self.failure_rate * 100.0
+		)
+	}
+}
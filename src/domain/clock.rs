@@ -0,0 +1,18 @@
+use time::OffsetDateTime;
+
+/// Source of the timestamps stamped on payments. Instances sharing a clock
+/// agree on the order of payments regardless of the skew between their
+/// system clocks.
+pub trait Clock: Send + Sync {
+	fn now(&self) -> OffsetDateTime;
+}
+
+/// The system clock of this instance.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+	fn now(&self) -> OffsetDateTime {
+		OffsetDateTime::now_utc()
+	}
+}
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// Outcome of one health check of a processor, kept to tell a flapping
+/// processor from one that stayed down.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthSample {
+	#[serde(with = "time::serde::rfc3339")]
+	pub checked_at:        OffsetDateTime,
+	/// What the processor itself reported.
+	pub reported_healthy:  bool,
+	/// Whether the router considered it healthy afterwards, once flap damping
+	/// was applied.
+	pub healthy:           bool,
+	pub min_response_time: u64,
+}
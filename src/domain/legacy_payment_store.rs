@@ -0,0 +1,22 @@
+use std::future::Future;
+
+use crate::domain::payment::Payment;
+
+/// Payments read from the legacy layout for one processor.
+#[derive(Debug, Default, Clone)]
+pub struct LegacyPayments {
+	pub payments:   Vec<Payment>,
+	/// Entries that could not be read as payments.
+	pub unreadable: usize,
+}
+
+pub trait LegacyPaymentStore: Send + Sync + 'static {
+	fn load_payments(
+		&self,
+		group: &str,
+	) -> impl Future<Output = Result<LegacyPayments, Box<dyn std::error::Error + Send>>>
+	+ Send;
+	fn remove(
+		&self,
+	) -> impl Future<Output = Result<(), Box<dyn std::error::Error + Send>>> + Send;
+}
@@ -1,6 +1,16 @@
+pub mod alerter;
+pub mod breaker_transition;
+pub mod clock;
+pub mod health_sample;
 pub mod health_status;
+pub mod legacy_payment_store;
 pub mod payment;
+pub mod payment_archive;
+pub mod payment_metrics;
 pub mod payment_processor;
 pub mod payment_router;
+pub mod processor_health_reporter;
+pub mod processor_selection;
+pub mod processor_settings;
 pub mod queue;
 pub mod repository;
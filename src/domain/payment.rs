@@ -23,6 +23,15 @@ pub struct Payment {
 	pub processed_at:   Option<OffsetDateTime>,
 	#[serde(skip_serializing_if = "Option::is_none", default)]
 	pub processed_by:   Option<String>,
+	/// Environment or label the payment was submitted under, such as
+	/// `staging`, so the payments of a tag can be purged on their own.
+	#[serde(skip_serializing_if = "Option::is_none", default)]
+	pub tag:            Option<String>,
+	/// Benchmark run the payment was accepted in, when a run epoch was
+	/// started, so the payments of one run can be summed or purged on their
+	/// own.
+	#[serde(skip_serializing_if = "Option::is_none", default)]
+	pub epoch:          Option<u64>,
 }
 
 #[cfg(test)]
@@ -48,6 +57,8 @@ mod tests {
 			requested_at: Some(requested_at),
 			processed_at: None,
 			processed_by: None,
+			tag: None,
+			epoch: None,
 		};
 
 		let expected_json = serde_json::json!({
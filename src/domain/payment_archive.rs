@@ -0,0 +1,25 @@
+use async_trait::async_trait;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::domain::payment::Payment;
+
+/// Cold storage for processed payments that have aged out of the hot
+/// repository.
+#[async_trait]
+pub trait PaymentArchive: Send + Sync + 'static {
+	async fn archive(
+		&self,
+		payments: &[Payment],
+	) -> Result<(), Box<dyn std::error::Error + Send>>;
+	/// Totals of the archived payments of `group` requested within the range,
+	/// leaving out the `excluded` ones.
+	async fn get_summary_by_group(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+		excluded: &[Uuid],
+	) -> Result<(usize, f64), Box<dyn std::error::Error + Send>>;
+	async fn clear(&self) -> Result<(), Box<dyn std::error::Error + Send>>;
+}
@@ -0,0 +1,13 @@
+/// Receives what happens to payments as the use cases handle them, so the
+/// events can be exported without the use cases knowing about the exporters.
+pub trait PaymentMetrics: Send + Sync {
+	/// A payment was accepted by `processor` and saved.
+	fn record_processed(&self, processor: &str);
+}
+
+/// Drops every event, for use cases built without metrics.
+pub struct NoPaymentMetrics;
+
+impl PaymentMetrics for NoPaymentMetrics {
+	fn record_processed(&self, _processor: &str) {}
+}
@@ -1,9 +1,90 @@
+use std::sync::Arc;
+
+use time::OffsetDateTime;
+
 use crate::domain::health_status::HealthStatus;
 
+pub const PROCESSOR_GROUPS: [&str; 2] = ["default", "fallback"];
+
+/// Splits a processor URL setting into the base URLs of its replicas.
+pub fn replica_urls(url: &str) -> impl Iterator<Item = &str> {
+	url.split(',').map(str::trim).filter(|url| !url.is_empty())
+}
+
 #[derive(Clone)]
 pub struct PaymentProcessor {
-	pub name:              String,
-	pub url:               String,
-	pub health:            HealthStatus,
-	pub min_response_time: u64,
+	pub name:                  Arc<str>,
+	/// Base URL of the processor, or of each of its replicas separated by
+	/// commas.
+	pub url:                   Arc<str>,
+	/// Endpoints payments are posted to, one per replica in the order they
+	/// should be tried, derived from `url` once instead of on every payment.
+	pub payments_urls:         Arc<[Arc<str>]>,
+	pub health:                HealthStatus,
+	pub min_response_time:     u64,
+	pub consecutive_failures:  u32,
+	pub consecutive_successes: u32,
+	/// When the processor was last health checked.
+	pub last_updated:          Option<OffsetDateTime>,
+}
+
+impl PaymentProcessor {
+	pub fn new(
+		name: impl Into<Arc<str>>,
+		url: &str,
+		health: HealthStatus,
+		min_response_time: u64,
+	) -> Self {
+		Self {
+			name: name.into(),
+			url: url.into(),
+			payments_urls: Self::payments_urls_for(url),
+			health,
+			min_response_time,
+			consecutive_failures: 0,
+			consecutive_successes: 0,
+			last_updated: None,
+		}
+	}
+
+	/// Points the processor at a new base URL, leaving it untouched when the
+	/// URL did not change.
+	pub fn set_url(&mut self, url: &str) {
+		if *self.url != *url {
+			self.url = url.into();
+			self.payments_urls = Self::payments_urls_for(url);
+		}
+	}
+
+	fn payments_urls_for(url: &str) -> Arc<[Arc<str>]> {
+		replica_urls(url)
+			.map(|url| format!("{url}/payments").into())
+			.collect()
+	}
+
+	/// Records the outcome of a health probe. The processor only flips to
+	/// `Failing` after `failure_threshold` failed probes in a row, and back
+	/// to `Healthy` after `success_threshold` successful probes in a row, so
+	/// a single bad probe does not make routing flap between processors.
+	pub fn record_probe(
+		&mut self,
+		healthy: bool,
+		failure_threshold: u32,
+		success_threshold: u32,
+	) {
+		if healthy {
+			self.consecutive_successes =
+				self.consecutive_successes.saturating_add(1);
+			self.consecutive_failures = 0;
+			if self.consecutive_successes >= success_threshold {
+				self.health = HealthStatus::Healthy;
+			}
+		} else {
+			self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+			self.consecutive_successes = 0;
+			if self.consecutive_failures >= failure_threshold {
+				self.health = HealthStatus::Failing;
+			}
+		}
+	}
 }
@@ -1,15 +1,14 @@
-use async_trait::async_trait;
-use circuitbreaker_rs::{CircuitBreaker, DefaultPolicy};
+use std::future::Future;
 
-use crate::use_cases::process_payment::PaymentProcessingError;
+use crate::domain::processor_selection::ProcessorSelection;
 
-#[async_trait]
 pub trait PaymentRouter: Send + Sync + 'static {
-	async fn get_processor_for_payment(
+	fn get_processor_for_payment(
 		&self,
-	) -> Option<(
-		String,
-		String,
-		CircuitBreaker<DefaultPolicy, PaymentProcessingError>,
-	)>;
+	) -> impl Future<Output = Option<ProcessorSelection>> + Send;
+	/// Returns the named processor if it can currently take payments.
+	fn get_processor(
+		&self,
+		name: &str,
+	) -> impl Future<Output = Option<ProcessorSelection>> + Send;
 }
@@ -0,0 +1,6 @@
+/// Receives the outcome of live payment calls, so the routing health model
+/// can react to a failing processor before its next health check.
+pub trait ProcessorHealthReporter: Send + Sync {
+	fn report_success(&self, processor: &str);
+	fn report_failure(&self, processor: &str);
+}
@@ -0,0 +1,21 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use circuitbreaker_rs::{CircuitBreaker, DefaultPolicy};
+
+use crate::use_cases::process_payment::PaymentProcessingError;
+
+/// The processor a router picked for a payment, along with what it knows
+/// about the choice.
+#[derive(Clone)]
+pub struct ProcessorSelection {
+	pub name:             Arc<str>,
+	/// Endpoints of the processor's replicas, in the order they should be
+	/// tried.
+	pub payments_urls:    Arc<[Arc<str>]>,
+	pub breaker:          CircuitBreaker<DefaultPolicy, PaymentProcessingError>,
+	/// Fee rate charged per transaction, when the router knows it.
+	pub fee:              Option<f64>,
+	/// Minimum response time last reported by the processor.
+	pub expected_latency: Duration,
+}
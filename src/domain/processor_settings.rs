@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::payment_processor::replica_urls;
+
+/// Processors whose last reported minimum response time reaches this many
+/// milliseconds are not routed to.
+pub const DEFAULT_MAX_RESPONSE_TIME_MS: u64 = 100;
+
+/// What can be changed about a payment processor while the service runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessorSettings {
+	/// Base URL of the processor, or of each of its replicas separated by
+	/// commas.
+	pub url:                  String,
+	/// Fee rate charged per transaction, when known.
+	#[serde(default)]
+	pub fee:                  Option<f64>,
+	#[serde(default = "default_max_response_time_ms")]
+	pub max_response_time_ms: u64,
+}
+
+impl ProcessorSettings {
+	pub fn new(url: impl Into<String>) -> Self {
+		Self {
+			url:                  url.into(),
+			fee:                  None,
+			max_response_time_ms: DEFAULT_MAX_RESPONSE_TIME_MS,
+		}
+	}
+
+	/// Whether the settings can be applied: the URL must name at least one
+	/// HTTP endpoint and the fee must be a rate between 0 and 1.
+	pub fn is_valid(&self) -> bool {
+		let mut replicas = replica_urls(&self.url).peekable();
+		let urls_valid = replicas.peek().is_some() &&
+			replicas.all(|url| {
+				url.starts_with("http://") || url.starts_with("https://")
+			});
+
+		urls_valid && self.fee.is_none_or(|fee| (0.0..=1.0).contains(&fee))
+	}
+}
+
+fn default_max_response_time_ms() -> u64 {
+	DEFAULT_MAX_RESPONSE_TIME_MS
+}
@@ -1,26 +1,184 @@
-use async_trait::async_trait;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
 use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
 use uuid::Uuid;
 
+type DynFuture<'a, T> = BoxFuture<'a, Result<T, Box<dyn std::error::Error + Send>>>;
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Message<B> {
-	pub id:   Uuid,
-	pub body: B,
+	pub id:          Uuid,
+	pub body:        B,
+	/// How many times the message was re-queued after a failed attempt.
+	#[serde(default)]
+	pub attempts:    u32,
+	/// Id of the HTTP request that submitted the message, forwarded to the
+	/// processors.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub request_id:  Option<String>,
+	/// Position of the message in the order messages were first queued, when
+	/// the queue numbers them. Kept across re-queues.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub sequence:    Option<u64>,
+	/// When the message was last pushed, stamped by the queue. Messages
+	/// pushed by older versions have none.
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none",
+		with = "time::serde::rfc3339::option"
+	)]
+	pub enqueued_at: Option<OffsetDateTime>,
 }
 
 impl<B> Message<B> {
 	pub fn with(id: Uuid, body: B) -> Message<B> {
-		Message { id, body }
+		Message {
+			id,
+			body,
+			attempts: 0,
+			request_id: None,
+			sequence: None,
+			enqueued_at: None,
+		}
+	}
+
+	pub fn with_request_id(mut self, request_id: Option<String>) -> Message<B> {
+		self.request_id = request_id;
+		self
+	}
+
+	pub fn retried(mut self) -> Message<B> {
+		self.attempts += 1;
+		self
 	}
+
+	pub fn is_retry(&self) -> bool {
+		self.attempts > 0
+	}
+}
+
+/// Queue failures callers can tell apart, carried inside the boxed errors
+/// returned by [`Queue`] implementations. Any other error is an unexpected
+/// failure.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueueError {
+	/// The backing store could not be reached.
+	Unavailable(String),
 }
 
-#[async_trait]
+impl fmt::Display for QueueError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			QueueError::Unavailable(reason) => {
+				write!(f, "Queue unavailable: {reason}")
+			}
+		}
+	}
+}
+
+impl std::error::Error for QueueError {}
+
 pub trait Queue<B>: Send + Sync + 'static {
+	/// Waits for the next message, returning `None` when none arrived within
+	/// the queue's blocking timeout, so callers can poll it in a loop without
+	/// sleeping in between.
+	fn pop(
+		&self,
+	) -> impl Future<
+		Output = Result<Option<Message<B>>, Box<dyn std::error::Error + Send>>,
+	> + Send;
+	/// Returns up to `limit` queued messages, in the order they would be
+	/// popped, skipping the first `offset`, without removing them.
+	fn peek(
+		&self,
+		offset: usize,
+		limit: usize,
+	) -> impl Future<Output = Result<Vec<Message<B>>, Box<dyn std::error::Error + Send>>>
+	+ Send;
+	/// Returns the number of queued messages as reported by the backing
+	/// store.
+	fn depth(
+		&self,
+	) -> impl Future<Output = Result<usize, Box<dyn std::error::Error + Send>>> + Send;
+	/// Returns when the next message to be popped was pushed, or `None` when
+	/// the queue is empty or that message carries no timestamp.
+	fn oldest_enqueued_at(
+		&self,
+	) -> impl Future<
+		Output = Result<Option<OffsetDateTime>, Box<dyn std::error::Error + Send>>,
+	> + Send;
+	fn push(
+		&self,
+		message: Message<B>,
+	) -> impl Future<Output = Result<(), Box<dyn std::error::Error + Send>>> + Send;
+}
+
+/// Object-safe counterpart of [`Queue`], implemented for every queue. Only
+/// used where a queue has to be a trait object, as it boxes every returned
+/// future.
+pub trait DynQueue<B>: Send + Sync + 'static {
+	fn pop(&self) -> DynFuture<'_, Option<Message<B>>>;
+	fn peek(&self, offset: usize, limit: usize) -> DynFuture<'_, Vec<Message<B>>>;
+	fn depth(&self) -> DynFuture<'_, usize>;
+	fn oldest_enqueued_at(&self) -> DynFuture<'_, Option<OffsetDateTime>>;
+	fn push(&self, message: Message<B>) -> DynFuture<'_, ()>;
+}
+
+impl<B: Send + 'static, Q: Queue<B>> DynQueue<B> for Q {
+	fn pop(&self) -> DynFuture<'_, Option<Message<B>>> {
+		Box::pin(Queue::pop(self))
+	}
+
+	fn peek(&self, offset: usize, limit: usize) -> DynFuture<'_, Vec<Message<B>>> {
+		Box::pin(Queue::peek(self, offset, limit))
+	}
+
+	fn depth(&self) -> DynFuture<'_, usize> {
+		Box::pin(Queue::depth(self))
+	}
+
+	fn oldest_enqueued_at(&self) -> DynFuture<'_, Option<OffsetDateTime>> {
+		Box::pin(Queue::oldest_enqueued_at(self))
+	}
+
+	fn push(&self, message: Message<B>) -> DynFuture<'_, ()> {
+		Box::pin(Queue::push(self, message))
+	}
+}
+
+impl<B: Send + 'static> Queue<B> for Arc<dyn DynQueue<B>> {
 	async fn pop(
 		&self,
-	) -> Result<Option<Message<B>>, Box<dyn std::error::Error + Send>>;
+	) -> Result<Option<Message<B>>, Box<dyn std::error::Error + Send>> {
+		DynQueue::pop(&**self).await
+	}
+
+	async fn peek(
+		&self,
+		offset: usize,
+		limit: usize,
+	) -> Result<Vec<Message<B>>, Box<dyn std::error::Error + Send>> {
+		DynQueue::peek(&**self, offset, limit).await
+	}
+
+	async fn depth(&self) -> Result<usize, Box<dyn std::error::Error + Send>> {
+		DynQueue::depth(&**self).await
+	}
+
+	async fn oldest_enqueued_at(
+		&self,
+	) -> Result<Option<OffsetDateTime>, Box<dyn std::error::Error + Send>> {
+		DynQueue::oldest_enqueued_at(&**self).await
+	}
+
 	async fn push(
 		&self,
 		message: Message<B>,
-	) -> Result<(), Box<dyn std::error::Error + Send>>;
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		DynQueue::push(&**self, message).await
+	}
 }
@@ -1,28 +1,674 @@
-use async_trait::async_trait;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
+use uuid::Uuid;
 
 use crate::domain::payment::Payment;
 
-#[async_trait]
+type DynFuture<'a, T> = BoxFuture<'a, Result<T, Box<dyn std::error::Error + Send>>>;
+
+pub type PaymentStream =
+	BoxStream<'static, Result<Payment, Box<dyn std::error::Error + Send>>>;
+
+pub type SummaryBucketStream =
+	BoxStream<'static, Result<SummaryBucket, Box<dyn std::error::Error + Send>>>;
+
+pub type FailureStream =
+	BoxStream<'static, Result<RecordedFailure, Box<dyn std::error::Error + Send>>>;
+
+/// Repository failures callers can tell apart, carried inside the boxed
+/// errors returned by [`PaymentRepository`] implementations. Any other error
+/// is an unexpected failure.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RepositoryError {
+	/// The backing store could not be reached.
+	Unavailable(String),
+	/// The requested payments do not exist.
+	NotFound(String),
+	/// The operation clashes with payments already stored.
+	Conflict(String),
+}
+
+impl fmt::Display for RepositoryError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			RepositoryError::Unavailable(reason) => {
+				write!(f, "Payment repository unavailable: {reason}")
+			}
+			RepositoryError::NotFound(reason) => write!(f, "Not found: {reason}"),
+			RepositoryError::Conflict(reason) => write!(f, "Conflict: {reason}"),
+		}
+	}
+}
+
+impl std::error::Error for RepositoryError {}
+
+/// How a call to a processor failed to process a payment.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentFailure {
+	/// The call errored or timed out, or the processor answered with a server
+	/// error.
+	Failed,
+	/// The processor refused the payment with a client error.
+	Rejected,
+}
+
+impl PaymentFailure {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			PaymentFailure::Failed => "failed",
+			PaymentFailure::Rejected => "rejected",
+		}
+	}
+}
+
+/// A call to the processor of `group` that did not process a payment, as
+/// recorded by [`PaymentRepository::record_failure`].
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RecordedFailure {
+	pub correlation_id: Uuid,
+	pub group:          String,
+	pub failure:        PaymentFailure,
+	#[serde(
+		with = "time::serde::rfc3339::option",
+		skip_serializing_if = "Option::is_none",
+		default
+	)]
+	pub requested_at:   Option<OffsetDateTime>,
+	/// When the call failed. Missing for failures recorded before it was
+	/// kept.
+	#[serde(
+		with = "time::serde::rfc3339::option",
+		skip_serializing_if = "Option::is_none",
+		default
+	)]
+	pub failed_at:      Option<OffsetDateTime>,
+}
+
+/// Totals of the payments of `group` requested within the minute starting at
+/// `starts_at`, folded together once the payments were trimmed.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SummaryBucket {
+	pub group:          String,
+	#[serde(with = "time::serde::rfc3339")]
+	pub starts_at:      OffsetDateTime,
+	pub requests:       usize,
+	pub amount:         f64,
+	/// Payments per amount bucket, see [`AMOUNT_BUCKET_BOUNDS`]. Empty for
+	/// buckets folded before the counts were kept.
+	#[serde(skip_serializing_if = "Vec::is_empty", default)]
+	pub amount_buckets: Vec<usize>,
+}
+
+/// Where a payment submitted more than once was caught.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DuplicateStage {
+	/// Dropped when queued, as the same payment was already waiting.
+	Ingest,
+	/// Skipped by a worker, as the payment was already processed.
+	Worker,
+}
+
+impl DuplicateStage {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			DuplicateStage::Ingest => "ingest",
+			DuplicateStage::Worker => "worker",
+		}
+	}
+}
+
+/// Bounds between the amount buckets counted by
+/// [`PaymentRepository::get_amount_buckets_by_group`]: below 10, from 10 to
+/// below 100, and from 100 up.
+pub const AMOUNT_BUCKET_BOUNDS: [f64; 2] = [10.0, 100.0];
+
 pub trait PaymentRepository: Send + Sync + 'static {
+	fn save(
+		&self,
+		payment: Payment,
+	) -> impl Future<Output = Result<(), Box<dyn std::error::Error + Send>>> + Send;
+	fn get_summary_by_group(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> impl Future<Output = Result<(usize, f64), Box<dyn std::error::Error + Send>>>
+	+ Send;
+	/// Totals of `group` like [`PaymentRepository::get_summary_by_group`],
+	/// along with the ids of the payments they count that were requested
+	/// before `before`, read at once so another tier holding some of those
+	/// payments can leave them out.
+	fn get_summary_with_ids_before(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+		before: OffsetDateTime,
+	) -> impl Future<
+		Output = Result<(usize, f64, Vec<Uuid>), Box<dyn std::error::Error + Send>>,
+	> + Send;
+	/// Lazily yields every payment of `group` requested within the range,
+	/// fetching them from storage in bounded batches.
+	fn get_payments_stream(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> PaymentStream;
+	fn get_payment_summary(
+		&self,
+		group: &str,
+		payment_id: &str,
+	) -> impl Future<Output = Result<Payment, Box<dyn std::error::Error + Send>>> + Send;
+	fn is_already_processed(
+		&self,
+		payment_id: &str,
+	) -> impl Future<Output = Result<bool, Box<dyn std::error::Error + Send>>> + Send;
+	/// Records a call to the processor of `group` that did not process
+	/// `payment`, at the time the payment was requested. Every call is
+	/// counted, so a payment failing twice is recorded twice.
+	fn record_failure(
+		&self,
+		payment: &Payment,
+		group: &str,
+		failure: PaymentFailure,
+	) -> impl Future<Output = Result<(), Box<dyn std::error::Error + Send>>> + Send;
+	/// Returns the failed and rejected calls to the processor of `group` for
+	/// payments requested within the range.
+	fn get_failures_by_group(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> impl Future<Output = Result<(usize, usize), Box<dyn std::error::Error + Send>>>
+	+ Send;
+	/// Lazily yields every recorded call to the processor of `group` that
+	/// ended in `failure`, fetching them from storage in bounded batches.
+	fn get_failures_stream(
+		&self,
+		group: &str,
+		failure: PaymentFailure,
+	) -> FailureStream;
+	/// Stores `failure` as it was recorded, so restoring it twice does not
+	/// count it twice.
+	fn restore_failure(
+		&self,
+		failure: &RecordedFailure,
+	) -> impl Future<Output = Result<(), Box<dyn std::error::Error + Send>>> + Send;
+	/// Records that `payment_id` was caught as a duplicate at `stage`, now.
+	fn record_duplicate(
+		&self,
+		payment_id: &str,
+		stage: DuplicateStage,
+	) -> impl Future<Output = Result<(), Box<dyn std::error::Error + Send>>> + Send;
+	/// Returns the duplicates caught at ingest and by the workers within the
+	/// range.
+	fn get_duplicates(
+		&self,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> impl Future<Output = Result<(usize, usize), Box<dyn std::error::Error + Send>>>
+	+ Send;
+	/// Counts the payments of `group` requested within the range per amount
+	/// bucket, see [`AMOUNT_BUCKET_BOUNDS`].
+	fn get_amount_buckets_by_group(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> impl Future<Output = Result<Vec<usize>, Box<dyn std::error::Error + Send>>> + Send;
+	/// Returns the time from being requested to being processed, in
+	/// microseconds, of each payment of `group` requested within the range.
+	/// Payments already folded into summary buckets are not included.
+	fn get_latencies_by_group(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> impl Future<Output = Result<Vec<u64>, Box<dyn std::error::Error + Send>>> + Send;
+	/// Removes the given processed payments without aggregating them.
+	fn delete(
+		&self,
+		payments: &[Payment],
+	) -> impl Future<Output = Result<(), Box<dyn std::error::Error + Send>>> + Send;
+	/// Removes the given processed payments once they are moved to the
+	/// archive. Unlike [`PaymentRepository::delete`], they still count in the
+	/// totals kept since the last clear.
+	fn remove_archived(
+		&self,
+		payments: &[Payment],
+	) -> impl Future<Output = Result<(), Box<dyn std::error::Error + Send>>> + Send;
+	/// Lazily yields every summary bucket of `group`, oldest first, fetching
+	/// them from storage in bounded batches.
+	fn get_summary_buckets_stream(&self, group: &str) -> SummaryBucketStream;
+	/// Stores `bucket`, replacing the bucket of its group starting at the same
+	/// time, so restoring it twice does not count its payments twice.
+	fn restore_summary_bucket(
+		&self,
+		bucket: &SummaryBucket,
+	) -> impl Future<Output = Result<(), Box<dyn std::error::Error + Send>>> + Send;
+	/// Removes processed payments requested before `cutoff`, folding them into
+	/// pre-aggregated summary buckets. Returns how many payments were trimmed.
+	fn trim_older_than(
+		&self,
+		cutoff: OffsetDateTime,
+	) -> impl Future<Output = Result<usize, Box<dyn std::error::Error + Send>>> + Send;
+	fn clear(
+		&self,
+	) -> impl Future<Output = Result<(), Box<dyn std::error::Error + Send>>> + Send;
+}
+
+/// Object-safe counterpart of [`PaymentRepository`], implemented for every
+/// repository. Only used where a repository has to be a trait object, as it
+/// boxes every returned future.
+pub trait DynPaymentRepository: Send + Sync + 'static {
+	fn save(&self, payment: Payment) -> DynFuture<'_, ()>;
+	fn get_summary_by_group<'a>(
+		&'a self,
+		group: &'a str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> DynFuture<'a, (usize, f64)>;
+	fn get_summary_with_ids_before<'a>(
+		&'a self,
+		group: &'a str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+		before: OffsetDateTime,
+	) -> DynFuture<'a, (usize, f64, Vec<Uuid>)>;
+	fn get_payments_stream(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> PaymentStream;
+	fn get_payment_summary<'a>(
+		&'a self,
+		group: &'a str,
+		payment_id: &'a str,
+	) -> DynFuture<'a, Payment>;
+	fn is_already_processed<'a>(
+		&'a self,
+		payment_id: &'a str,
+	) -> DynFuture<'a, bool>;
+	fn record_failure<'a>(
+		&'a self,
+		payment: &'a Payment,
+		group: &'a str,
+		failure: PaymentFailure,
+	) -> DynFuture<'a, ()>;
+	fn get_failures_by_group<'a>(
+		&'a self,
+		group: &'a str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> DynFuture<'a, (usize, usize)>;
+	fn get_failures_stream(
+		&self,
+		group: &str,
+		failure: PaymentFailure,
+	) -> FailureStream;
+	fn restore_failure<'a>(
+		&'a self,
+		failure: &'a RecordedFailure,
+	) -> DynFuture<'a, ()>;
+	fn record_duplicate<'a>(
+		&'a self,
+		payment_id: &'a str,
+		stage: DuplicateStage,
+	) -> DynFuture<'a, ()>;
+	fn get_duplicates(
+		&self,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> DynFuture<'_, (usize, usize)>;
+	fn get_amount_buckets_by_group<'a>(
+		&'a self,
+		group: &'a str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> DynFuture<'a, Vec<usize>>;
+	fn get_latencies_by_group<'a>(
+		&'a self,
+		group: &'a str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> DynFuture<'a, Vec<u64>>;
+	fn delete<'a>(&'a self, payments: &'a [Payment]) -> DynFuture<'a, ()>;
+	fn remove_archived<'a>(&'a self, payments: &'a [Payment]) -> DynFuture<'a, ()>;
+	fn get_summary_buckets_stream(&self, group: &str) -> SummaryBucketStream;
+	fn restore_summary_bucket<'a>(
+		&'a self,
+		bucket: &'a SummaryBucket,
+	) -> DynFuture<'a, ()>;
+	fn trim_older_than(&self, cutoff: OffsetDateTime) -> DynFuture<'_, usize>;
+	fn clear(&self) -> DynFuture<'_, ()>;
+}
+
+impl<R: PaymentRepository> DynPaymentRepository for R {
+	fn save(&self, payment: Payment) -> DynFuture<'_, ()> {
+		Box::pin(PaymentRepository::save(self, payment))
+	}
+
+	fn get_summary_by_group<'a>(
+		&'a self,
+		group: &'a str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> DynFuture<'a, (usize, f64)> {
+		Box::pin(PaymentRepository::get_summary_by_group(
+			self, group, from_ts, to_ts,
+		))
+	}
+
+	fn get_summary_with_ids_before<'a>(
+		&'a self,
+		group: &'a str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+		before: OffsetDateTime,
+	) -> DynFuture<'a, (usize, f64, Vec<Uuid>)> {
+		Box::pin(PaymentRepository::get_summary_with_ids_before(
+			self, group, from_ts, to_ts, before,
+		))
+	}
+
+	fn get_payments_stream(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> PaymentStream {
+		PaymentRepository::get_payments_stream(self, group, from_ts, to_ts)
+	}
+
+	fn get_payment_summary<'a>(
+		&'a self,
+		group: &'a str,
+		payment_id: &'a str,
+	) -> DynFuture<'a, Payment> {
+		Box::pin(PaymentRepository::get_payment_summary(
+			self, group, payment_id,
+		))
+	}
+
+	fn is_already_processed<'a>(
+		&'a self,
+		payment_id: &'a str,
+	) -> DynFuture<'a, bool> {
+		Box::pin(PaymentRepository::is_already_processed(self, payment_id))
+	}
+
+	fn record_failure<'a>(
+		&'a self,
+		payment: &'a Payment,
+		group: &'a str,
+		failure: PaymentFailure,
+	) -> DynFuture<'a, ()> {
+		Box::pin(PaymentRepository::record_failure(
+			self, payment, group, failure,
+		))
+	}
+
+	fn get_failures_by_group<'a>(
+		&'a self,
+		group: &'a str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> DynFuture<'a, (usize, usize)> {
+		Box::pin(PaymentRepository::get_failures_by_group(
+			self, group, from_ts, to_ts,
+		))
+	}
+
+	fn get_failures_stream(
+		&self,
+		group: &str,
+		failure: PaymentFailure,
+	) -> FailureStream {
+		PaymentRepository::get_failures_stream(self, group, failure)
+	}
+
+	fn restore_failure<'a>(
+		&'a self,
+		failure: &'a RecordedFailure,
+	) -> DynFuture<'a, ()> {
+		Box::pin(PaymentRepository::restore_failure(self, failure))
+	}
+
+	fn record_duplicate<'a>(
+		&'a self,
+		payment_id: &'a str,
+		stage: DuplicateStage,
+	) -> DynFuture<'a, ()> {
+		Box::pin(PaymentRepository::record_duplicate(self, payment_id, stage))
+	}
+
+	fn get_duplicates(
+		&self,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> DynFuture<'_, (usize, usize)> {
+		Box::pin(PaymentRepository::get_duplicates(self, from_ts, to_ts))
+	}
+
+	fn get_amount_buckets_by_group<'a>(
+		&'a self,
+		group: &'a str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> DynFuture<'a, Vec<usize>> {
+		Box::pin(PaymentRepository::get_amount_buckets_by_group(
+			self, group, from_ts, to_ts,
+		))
+	}
+
+	fn get_latencies_by_group<'a>(
+		&'a self,
+		group: &'a str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> DynFuture<'a, Vec<u64>> {
+		Box::pin(PaymentRepository::get_latencies_by_group(
+			self, group, from_ts, to_ts,
+		))
+	}
+
+	fn delete<'a>(&'a self, payments: &'a [Payment]) -> DynFuture<'a, ()> {
+		Box::pin(PaymentRepository::delete(self, payments))
+	}
+
+	fn remove_archived<'a>(&'a self, payments: &'a [Payment]) -> DynFuture<'a, ()> {
+		Box::pin(PaymentRepository::remove_archived(self, payments))
+	}
+
+	fn get_summary_buckets_stream(&self, group: &str) -> SummaryBucketStream {
+		PaymentRepository::get_summary_buckets_stream(self, group)
+	}
+
+	fn restore_summary_bucket<'a>(
+		&'a self,
+		bucket: &'a SummaryBucket,
+	) -> DynFuture<'a, ()> {
+		Box::pin(PaymentRepository::restore_summary_bucket(self, bucket))
+	}
+
+	fn trim_older_than(&self, cutoff: OffsetDateTime) -> DynFuture<'_, usize> {
+		Box::pin(PaymentRepository::trim_older_than(self, cutoff))
+	}
+
+	fn clear(&self) -> DynFuture<'_, ()> {
+		Box::pin(PaymentRepository::clear(self))
+	}
+}
+
+impl PaymentRepository for Arc<dyn DynPaymentRepository> {
 	async fn save(
 		&self,
 		payment: Payment,
-	) -> Result<(), Box<dyn std::error::Error + Send>>;
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		DynPaymentRepository::save(&**self, payment).await
+	}
+
 	async fn get_summary_by_group(
 		&self,
 		group: &str,
 		from_ts: OffsetDateTime,
 		to_ts: OffsetDateTime,
-	) -> Result<(usize, f64), Box<dyn std::error::Error + Send>>;
+	) -> Result<(usize, f64), Box<dyn std::error::Error + Send>> {
+		DynPaymentRepository::get_summary_by_group(&**self, group, from_ts, to_ts)
+			.await
+	}
+
+	async fn get_summary_with_ids_before(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+		before: OffsetDateTime,
+	) -> Result<(usize, f64, Vec<Uuid>), Box<dyn std::error::Error + Send>> {
+		DynPaymentRepository::get_summary_with_ids_before(
+			&**self, group, from_ts, to_ts, before,
+		)
+		.await
+	}
+
+	fn get_payments_stream(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> PaymentStream {
+		DynPaymentRepository::get_payments_stream(&**self, group, from_ts, to_ts)
+	}
+
 	async fn get_payment_summary(
 		&self,
 		group: &str,
 		payment_id: &str,
-	) -> Result<Payment, Box<dyn std::error::Error + Send>>;
+	) -> Result<Payment, Box<dyn std::error::Error + Send>> {
+		DynPaymentRepository::get_payment_summary(&**self, group, payment_id).await
+	}
+
 	async fn is_already_processed(
 		&self,
 		payment_id: &str,
-	) -> Result<bool, Box<dyn std::error::Error + Send>>;
-	async fn clear(&self) -> Result<(), Box<dyn std::error::Error + Send>>;
+	) -> Result<bool, Box<dyn std::error::Error + Send>> {
+		DynPaymentRepository::is_already_processed(&**self, payment_id).await
+	}
+
+	async fn record_failure(
+		&self,
+		payment: &Payment,
+		group: &str,
+		failure: PaymentFailure,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		DynPaymentRepository::record_failure(&**self, payment, group, failure).await
+	}
+
+	async fn get_failures_by_group(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> Result<(usize, usize), Box<dyn std::error::Error + Send>> {
+		DynPaymentRepository::get_failures_by_group(&**self, group, from_ts, to_ts)
+			.await
+	}
+
+	fn get_failures_stream(
+		&self,
+		group: &str,
+		failure: PaymentFailure,
+	) -> FailureStream {
+		DynPaymentRepository::get_failures_stream(&**self, group, failure)
+	}
+
+	async fn restore_failure(
+		&self,
+		failure: &RecordedFailure,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		DynPaymentRepository::restore_failure(&**self, failure).await
+	}
+
+	async fn record_duplicate(
+		&self,
+		payment_id: &str,
+		stage: DuplicateStage,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		DynPaymentRepository::record_duplicate(&**self, payment_id, stage).await
+	}
+
+	async fn get_duplicates(
+		&self,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> Result<(usize, usize), Box<dyn std::error::Error + Send>> {
+		DynPaymentRepository::get_duplicates(&**self, from_ts, to_ts).await
+	}
+
+	async fn get_amount_buckets_by_group(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> Result<Vec<usize>, Box<dyn std::error::Error + Send>> {
+		DynPaymentRepository::get_amount_buckets_by_group(
+			&**self, group, from_ts, to_ts,
+		)
+		.await
+	}
+
+	async fn get_latencies_by_group(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> Result<Vec<u64>, Box<dyn std::error::Error + Send>> {
+		DynPaymentRepository::get_latencies_by_group(&**self, group, from_ts, to_ts)
+			.await
+	}
+
+	async fn delete(
+		&self,
+		payments: &[Payment],
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		DynPaymentRepository::delete(&**self, payments).await
+	}
+
+	async fn remove_archived(
+		&self,
+		payments: &[Payment],
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		DynPaymentRepository::remove_archived(&**self, payments).await
+	}
+
+	fn get_summary_buckets_stream(&self, group: &str) -> SummaryBucketStream {
+		DynPaymentRepository::get_summary_buckets_stream(&**self, group)
+	}
+
+	async fn restore_summary_bucket(
+		&self,
+		bucket: &SummaryBucket,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		DynPaymentRepository::restore_summary_bucket(&**self, bucket).await
+	}
+
+	async fn trim_older_than(
+		&self,
+		cutoff: OffsetDateTime,
+	) -> Result<usize, Box<dyn std::error::Error + Send>> {
+		DynPaymentRepository::trim_older_than(&**self, cutoff).await
+	}
+
+	async fn clear(&self) -> Result<(), Box<dyn std::error::Error + Send>> {
+		DynPaymentRepository::clear(&**self).await
+	}
 }
@@ -0,0 +1,174 @@
+//! End-to-end smoke test of the full pipeline: the HTTP app and the
+//! background workers run against the Redis and payment processors of an
+//! [`AppContext`], a batch of payments is pumped through `/payments`, and the
+//! resulting summary is checked against what the processors received.
+
+use std::time::Duration;
+
+use actix_web::test;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::adapters::web::schema::PaymentRequest;
+use crate::domain::queue::Queue;
+use crate::infrastructure::gateway::processor_admin_client::{
+	ProcessorAdminClient, ProcessorPaymentsSummary,
+};
+use crate::use_cases::dto::{GetPaymentSummaryQuery, PaymentsSummaryResponse};
+use crate::use_cases::get_payment_summary::GetPaymentSummaryUseCase;
+use crate::{AppContext, build_app, start_workers};
+
+/// Tolerance used when comparing summed amounts.
+const AMOUNT_TOLERANCE: f64 = 0.01;
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+pub struct SmokeTest {
+	pub payments: usize,
+	pub amount:   f64,
+	/// How long to wait for the workers to drain the queue.
+	pub timeout:  Duration,
+}
+
+impl Default for SmokeTest {
+	fn default() -> Self {
+		Self {
+			payments: 100,
+			amount:   19.9,
+			timeout:  Duration::from_secs(30),
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct SmokeTestReport {
+	pub sent:               usize,
+	pub accepted:           usize,
+	pub drained:            bool,
+	pub summary:            PaymentsSummaryResponse,
+	pub default_processor:  ProcessorPaymentsSummary,
+	pub fallback_processor: ProcessorPaymentsSummary,
+}
+
+impl SmokeTestReport {
+	/// Every accepted payment was processed exactly once, and the summary
+	/// served by the app matches what each processor received.
+	pub fn is_consistent(&self) -> bool {
+		let matches = |summary_requests: usize,
+		               summary_amount: f64,
+		               processor: &ProcessorPaymentsSummary| {
+			summary_requests as u64 == processor.total_requests &&
+				(summary_amount - processor.total_amount).abs() < AMOUNT_TOLERANCE
+		};
+
+		self.drained &&
+			self.summary.default.total_requests +
+				self.summary.fallback.total_requests ==
+				self.accepted &&
+			matches(
+				self.summary.default.total_requests,
+				self.summary.default.total_amount,
+				&self.default_processor,
+			) && matches(
+			self.summary.fallback.total_requests,
+			self.summary.fallback.total_amount,
+			&self.fallback_processor,
+		)
+	}
+}
+
+impl SmokeTest {
+	/// Purges the app and both processors, starts the workers, pumps the
+	/// payments and waits for them to be processed before collecting the
+	/// summaries.
+	pub async fn run(
+		&self,
+		context: &AppContext,
+		default_admin: &ProcessorAdminClient,
+		fallback_admin: &ProcessorAdminClient,
+	) -> Result<SmokeTestReport, Box<dyn std::error::Error + Send>> {
+		let app = test::init_service(build_app(context)).await;
+
+		let req = test::TestRequest::post()
+			.uri("/purge-payments")
+			.to_request();
+		test::call_service(&app, req).await;
+		default_admin.purge_payments().await?;
+		fallback_admin.purge_payments().await?;
+
+		let workers = start_workers(context).await;
+		let started_at = OffsetDateTime::now_utc();
+
+		let mut accepted = 0;
+		for _ in 0..self.payments {
+			let req = test::TestRequest::post()
+				.uri("/payments")
+				.set_json(PaymentRequest {
+					correlation_id: Uuid::new_v4(),
+					amount:         self.amount,
+				})
+				.to_request();
+			if test::call_service(&app, req).await.status().is_success() {
+				accepted += 1;
+			}
+		}
+
+		let drained = self.wait_until_drained(context, accepted).await;
+		workers.abort();
+
+		let req = test::TestRequest::get()
+			.uri("/payments-summary")
+			.to_request();
+		let summary: PaymentsSummaryResponse =
+			test::call_and_read_body_json(&app, req).await;
+
+		Ok(SmokeTestReport {
+			sent: self.payments,
+			accepted,
+			drained,
+			summary,
+			default_processor: default_admin
+				.get_payments_summary(Some(started_at), None)
+				.await?,
+			fallback_processor: fallback_admin
+				.get_payments_summary(Some(started_at), None)
+				.await?,
+		})
+	}
+
+	async fn wait_until_drained(
+		&self,
+		context: &AppContext,
+		accepted: usize,
+	) -> bool {
+		let summary_use_case =
+			GetPaymentSummaryUseCase::new(context.payment_repo.clone());
+		let deadline = tokio::time::Instant::now() + self.timeout;
+
+		while tokio::time::Instant::now() < deadline {
+			let query = GetPaymentSummaryQuery {
+				from:           None,
+				to:             None,
+				failures:       false,
+				amount_buckets: false,
+				latency:        false,
+				epoch:          None,
+			};
+			let processed = summary_use_case
+				.execute(query)
+				.await
+				.map(|summary| {
+					summary.default.total_requests + summary.fallback.total_requests
+				})
+				.unwrap_or_default();
+			let queued = context.payment_queue.depth().await.unwrap_or(usize::MAX);
+
+			if processed >= accepted && queued == 0 {
+				return true;
+			}
+
+			tokio::time::sleep(POLL_INTERVAL).await;
+		}
+
+		false
+	}
+}
@@ -0,0 +1,3 @@
+pub mod processor_downtime_monitor;
+pub mod slack_alerter;
+pub mod webhook_alerter;
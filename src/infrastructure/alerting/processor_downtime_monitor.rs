@@ -0,0 +1,215 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::{error, warn};
+use reqwest::Client;
+
+use crate::domain::alerter::{Alert, Alerter};
+use crate::infrastructure::alerting::slack_alerter::SlackAlerter;
+use crate::infrastructure::alerting::webhook_alerter::WebhookAlerter;
+use crate::infrastructure::config::settings::Config;
+
+/// How long delivering an alert may take before it is given up on, so an
+/// unresponsive webhook does not pile up pending alerts.
+const ALERT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Tracks how long every processor has been failing and notifies the
+/// configured alerters once the outage outlasts `window`, and again when the
+/// processors recover from an outage that was alerted on.
+pub struct ProcessorDowntimeMonitor {
+	alerters:   Vec<Arc<dyn Alerter>>,
+	window:     Duration,
+	down_since: Option<Instant>,
+	alerted:    bool,
+}
+
+impl ProcessorDowntimeMonitor {
+	pub fn new(alerters: Vec<Arc<dyn Alerter>>, window: Duration) -> Self {
+		Self {
+			alerters,
+			window,
+			down_since: None,
+			alerted: false,
+		}
+	}
+
+	pub fn from_config(config: &Config) -> Self {
+		let http_client = Client::builder()
+			.timeout(ALERT_TIMEOUT)
+			.build()
+			.expect("Failed to build the alerting HTTP client");
+		let mut alerters: Vec<Arc<dyn Alerter>> = Vec::new();
+
+		if let Some(url) = &config.alert_webhook_url {
+			alerters.push(Arc::new(WebhookAlerter::new(
+				http_client.clone(),
+				url.clone(),
+			)));
+		}
+
+		if let Some(url) = &config.alert_slack_webhook_url {
+			alerters.push(Arc::new(SlackAlerter::new(http_client, url.clone())));
+		}
+
+		Self::new(alerters, Duration::from_secs(config.alert_downtime_window))
+	}
+
+	pub fn disabled() -> Self {
+		Self::new(Vec::new(), Duration::ZERO)
+	}
+
+	/// Records whether every processor is failing, sending any alert due in
+	/// the background so the health checks are not held up by delivery.
+	pub fn observe(&mut self, all_processors_failing: bool) {
+		if let Some(alert) = self.next_alert(all_processors_failing, Instant::now())
+		{
+			warn!("{alert}");
+			if !self.alerters.is_empty() {
+				tokio::spawn(dispatch(self.alerters.clone(), alert));
+			}
+		}
+	}
+
+	fn next_alert(
+		&mut self,
+		all_processors_failing: bool,
+		now: Instant,
+	) -> Option<Alert> {
+		match (all_processors_failing, self.down_since) {
+			(true, None) => {
+				self.down_since = Some(now);
+				None
+			}
+			(true, Some(since)) => {
+				let down_for = now.duration_since(since);
+				if !self.alerted && down_for >= self.window {
+					self.alerted = true;
+					return Some(Alert::AllProcessorsDown { down_for });
+				}
+				None
+			}
+			(false, Some(since)) => {
+				self.down_since = None;
+				if std::mem::take(&mut self.alerted) {
+					return Some(Alert::ProcessorsRecovered {
+						down_for: now.duration_since(since),
+					});
+				}
+				None
+			}
+			(false, None) => None,
+		}
+	}
+}
+
+async fn dispatch(alerters: Vec<Arc<dyn Alerter>>, alert: Alert) {
+	for alerter in &alerters {
+		if let Err(e) = alerter.send(&alert).await {
+			error!("Failed to send '{}' alert: {e}", alert.event());
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use async_trait::async_trait;
+	use tokio::sync::Notify;
+
+	use super::*;
+
+	/// Takes alerts but never finishes delivering them.
+	struct StalledAlerter {
+		sent: Arc<Notify>,
+	}
+
+	#[async_trait]
+	impl Alerter for StalledAlerter {
+		async fn send(
+			&self,
+			_alert: &Alert,
+		) -> Result<(), Box<dyn std::error::Error + Send>> {
+			self.sent.notify_one();
+			std::future::pending().await
+		}
+	}
+
+	fn monitor(window: Duration) -> ProcessorDowntimeMonitor {
+		ProcessorDowntimeMonitor::new(Vec::new(), window)
+	}
+
+	#[test]
+	fn test_does_not_alert_before_window_elapses() {
+		let mut monitor = monitor(Duration::from_secs(10));
+		let start = Instant::now();
+
+		assert_eq!(monitor.next_alert(true, start), None);
+		assert_eq!(
+			monitor.next_alert(true, start + Duration::from_secs(5)),
+			None
+		);
+	}
+
+	#[test]
+	fn test_alerts_once_when_window_elapses() {
+		let mut monitor = monitor(Duration::from_secs(10));
+		let start = Instant::now();
+
+		monitor.next_alert(true, start);
+
+		assert_eq!(
+			monitor.next_alert(true, start + Duration::from_secs(10)),
+			Some(Alert::AllProcessorsDown {
+				down_for: Duration::from_secs(10),
+			})
+		);
+		assert_eq!(
+			monitor.next_alert(true, start + Duration::from_secs(20)),
+			None
+		);
+	}
+
+	#[test]
+	fn test_alerts_on_recovery_after_alerted_outage() {
+		let mut monitor = monitor(Duration::from_secs(10));
+		let start = Instant::now();
+
+		monitor.next_alert(true, start);
+		monitor.next_alert(true, start + Duration::from_secs(15));
+
+		assert_eq!(
+			monitor.next_alert(false, start + Duration::from_secs(30)),
+			Some(Alert::ProcessorsRecovered {
+				down_for: Duration::from_secs(30),
+			})
+		);
+	}
+
+	#[test]
+	fn test_does_not_alert_on_recovery_from_short_outage() {
+		let mut monitor = monitor(Duration::from_secs(10));
+		let start = Instant::now();
+
+		monitor.next_alert(true, start);
+
+		assert_eq!(
+			monitor.next_alert(false, start + Duration::from_secs(5)),
+			None
+		);
+	}
+
+	#[tokio::test]
+	async fn test_observe_sends_alerts_without_waiting_for_delivery() {
+		let sent = Arc::new(Notify::new());
+		let mut monitor = ProcessorDowntimeMonitor::new(
+			vec![Arc::new(StalledAlerter { sent: sent.clone() })],
+			Duration::ZERO,
+		);
+
+		monitor.observe(true);
+		monitor.observe(true);
+
+		tokio::time::timeout(Duration::from_secs(1), sent.notified())
+			.await
+			.unwrap();
+	}
+}
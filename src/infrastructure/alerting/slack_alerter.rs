@@ -0,0 +1,50 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Serialize;
+
+use crate::domain::alerter::{Alert, Alerter};
+
+#[derive(Serialize)]
+struct SlackMessage {
+	text: String,
+}
+
+#[derive(Clone)]
+pub struct SlackAlerter {
+	http_client: Client,
+	webhook_url: String,
+}
+
+impl SlackAlerter {
+	pub fn new(http_client: Client, webhook_url: String) -> Self {
+		Self {
+			http_client,
+			webhook_url,
+		}
+	}
+}
+
+#[async_trait]
+impl Alerter for SlackAlerter {
+	async fn send(
+		&self,
+		alert: &Alert,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		let icon = match alert {
+			Alert::AllProcessorsDown { .. } => ":rotating_light:",
+			Alert::ProcessorsRecovered { .. } => ":white_check_mark:",
+		};
+
+		self.http_client
+			.post(&self.webhook_url)
+			.json(&SlackMessage {
+				text: format!("{icon} {alert}"),
+			})
+			.send()
+			.await
+			.and_then(|response| response.error_for_status())
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,47 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Serialize;
+
+use crate::domain::alerter::{Alert, Alerter};
+
+#[derive(Serialize)]
+struct WebhookPayload {
+	event:            &'static str,
+	message:          String,
+	#[serde(rename = "downForSeconds")]
+	down_for_seconds: u64,
+}
+
+#[derive(Clone)]
+pub struct WebhookAlerter {
+	http_client: Client,
+	url:         String,
+}
+
+impl WebhookAlerter {
+	pub fn new(http_client: Client, url: String) -> Self {
+		Self { http_client, url }
+	}
+}
+
+#[async_trait]
+impl Alerter for WebhookAlerter {
+	async fn send(
+		&self,
+		alert: &Alert,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		self.http_client
+			.post(&self.url)
+			.json(&WebhookPayload {
+				event:            alert.event(),
+				message:          alert.to_string(),
+				down_for_seconds: alert.down_for().as_secs(),
+			})
+			.send()
+			.await
+			.and_then(|response| response.error_for_status())
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+		Ok(())
+	}
+}
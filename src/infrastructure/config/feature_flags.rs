@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static FEATURE_FLAGS: LazyLock<FeatureFlags> = LazyLock::new(FeatureFlags::new);
+
+/// Process-wide switches for experimental behaviors. They are checked every
+/// time the behavior could kick in, so an operator can toggle them during a
+/// run without a redeploy.
+pub fn feature_flags() -> &'static FeatureFlags {
+	&FEATURE_FLAGS
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeatureFlag {
+	/// Answers `POST /payments` before the payment is pushed to the queue.
+	/// Failed pushes are only logged, so payments can be lost.
+	FireAndForgetIngest,
+}
+
+impl FeatureFlag {
+	pub const ALL: [FeatureFlag; 1] = [FeatureFlag::FireAndForgetIngest];
+
+	pub fn as_str(self) -> &'static str {
+		match self {
+			FeatureFlag::FireAndForgetIngest => "fire_and_forget_ingest",
+		}
+	}
+}
+
+impl FromStr for FeatureFlag {
+	type Err = String;
+
+	fn from_str(name: &str) -> Result<Self, Self::Err> {
+		Self::ALL
+			.into_iter()
+			.find(|flag| flag.as_str() == name)
+			.ok_or_else(|| format!("Unknown feature flag '{name}'"))
+	}
+}
+
+#[derive(Debug)]
+pub struct FeatureFlags {
+	enabled: [AtomicBool; FeatureFlag::ALL.len()],
+}
+
+impl FeatureFlags {
+	pub fn new() -> Self {
+		Self {
+			enabled: std::array::from_fn(|_| AtomicBool::new(false)),
+		}
+	}
+
+	pub fn is_enabled(&self, flag: FeatureFlag) -> bool {
+		self.enabled[flag as usize].load(Ordering::Relaxed)
+	}
+
+	pub fn set(&self, flag: FeatureFlag, enabled: bool) {
+		self.enabled[flag as usize].store(enabled, Ordering::Relaxed);
+	}
+
+	/// Enables the comma separated flags of `names`, returning the names that
+	/// are not known flags.
+	pub fn enable_all(&self, names: &str) -> Vec<String> {
+		names
+			.split(',')
+			.map(str::trim)
+			.filter(|name| !name.is_empty())
+			.filter_map(|name| match name.parse() {
+				Ok(flag) => {
+					self.set(flag, true);
+					None
+				}
+				Err(_) => Some(name.to_string()),
+			})
+			.collect()
+	}
+
+	pub fn snapshot(&self) -> BTreeMap<&'static str, bool> {
+		FeatureFlag::ALL
+			.into_iter()
+			.map(|flag| (flag.as_str(), self.is_enabled(flag)))
+			.collect()
+	}
+}
+
+impl Default for FeatureFlags {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_enable_all_skips_unknown_flags() {
+		let flags = FeatureFlags::new();
+
+		let unknown = flags.enable_all(" fire_and_forget_ingest, hedging,");
+
+		assert_eq!(unknown, vec!["hedging".to_string()]);
+		assert!(flags.is_enabled(FeatureFlag::FireAndForgetIngest));
+		assert_eq!(flags.snapshot().get("fire_and_forget_ingest"), Some(&true));
+
+		flags.set(FeatureFlag::FireAndForgetIngest, false);
+		assert!(!flags.is_enabled(FeatureFlag::FireAndForgetIngest));
+	}
+}
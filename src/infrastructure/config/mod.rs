@@ -1,2 +1,4 @@
+pub mod feature_flags;
 pub mod redis;
 pub mod settings;
+pub mod worker_budget;
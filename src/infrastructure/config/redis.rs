@@ -1,4 +1,30 @@
 pub const PAYMENTS_QUEUE_KEY: &str = "payments_queue";
+pub const PROCESSOR_PAYMENTS_QUEUE_KEY_PREFIX: &str = "payments_queue";
+pub const PAYMENTS_SEQUENCE_KEY: &str = "payments_queue:sequence";
+pub const DELIVERED_SEQUENCES_KEY: &str = "payments_queue:sequence:delivered";
+pub const QUEUED_PAYMENT_KEY_PREFIX: &str = "queued_payments";
+pub const QUARANTINED_MESSAGES_KEY_PREFIX: &str = "quarantined_messages";
+pub const DEAD_LETTERED_PAYMENTS_KEY_PREFIX: &str = "dead_lettered_payments";
+pub const IN_FLIGHT_PAYMENTS_KEY: &str = "payments_in_flight";
+pub const PURGED_IN_FLIGHT_PAYMENTS_KEY: &str = "payments_in_flight:purged";
+pub const PAYMENTS_INGEST_STREAM_KEY: &str = "payments_ingest";
+pub const PAYMENTS_INGEST_GROUP: &str = "payments_ingest_workers";
 pub const PROCESSED_PAYMENTS_SET_KEY: &str = "processed_payments";
+pub const PROCESSED_PAYMENTS_BLOOM_KEY: &str = "processed_payments:bloom";
+pub const PROCESSED_PAYMENT_KEY_PREFIX: &str = "processed_payments";
+pub const ARCHIVED_PAYMENTS_SET_KEY: &str = "processed_payments:archived";
+pub const RUN_EPOCH_KEY: &str = "payments_run_epoch";
 pub const DEFAULT_PAYMENT_SUMMARY_KEY: &str = "payment_summary:default";
 pub const FALLBACK_PAYMENT_SUMMARY_KEY: &str = "payment_summary:fallback";
+pub const PAYMENT_SUMMARY_BUCKET_KEY_PREFIX: &str = "payment_summary:bucket";
+pub const PAYMENT_SUMMARY_BUCKETS_KEY_PREFIX: &str = "payment_summary:buckets";
+pub const PAYMENT_FAILURES_KEY_PREFIX: &str = "payment_summary:failures";
+pub const PAYMENT_DUPLICATES_KEY_PREFIX: &str = "payment_summary:duplicates";
+
+// Key layout written by the pre-hexagonal `api`/`workers` implementation.
+pub const LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY: &str = "payments_summary_default";
+pub const LEGACY_FALLBACK_PAYMENT_SUMMARY_KEY: &str = "payments_summary_fallback";
+pub const LEGACY_PROCESSED_CORRELATION_IDS_KEY: &str = "processed_correlation_ids";
+// Aggregate fields kept in the legacy summary hashes for external dashboards.
+pub const LEGACY_TOTAL_REQUESTS_FIELD: &str = "total_requests";
+pub const LEGACY_TOTAL_AMOUNT_FIELD: &str = "total_amount";
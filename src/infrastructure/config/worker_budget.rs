@@ -0,0 +1,100 @@
+use std::num::NonZeroUsize;
+
+use crate::infrastructure::config::settings::Config;
+
+/// Payments the background workers process at the same time per CPU left to
+/// them, each in its own task. Processing mostly waits on Redis and the
+/// processors.
+const WORKER_CONCURRENCY_PER_CPU: usize = 16;
+
+/// How the CPUs of the container are shared between the HTTP workers and the
+/// background workers. Explicit settings always win; the others are derived
+/// from the available CPUs when `autotune_workers` is set, and left to the
+/// actix and worker defaults otherwise.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorkerBudget {
+	pub server_workers:     Option<usize>,
+	pub worker_concurrency: Option<usize>,
+}
+
+impl WorkerBudget {
+	/// Uses the CPUs available to the process, which honours the cgroup CPU
+	/// quota of the container.
+	pub fn from_config(config: &Config) -> Self {
+		let cpus = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+		Self::for_cpus(config, cpus)
+	}
+
+	fn for_cpus(config: &Config, cpus: usize) -> Self {
+		// One CPU is kept for the background workers whenever there is more
+		// than one, the rest serve HTTP.
+		let worker_cpus = if cpus > 1 { 1 } else { cpus };
+		let autotuned = config.autotune_workers.then(|| {
+			(
+				(cpus - worker_cpus).max(1),
+				worker_cpus.max(1) * WORKER_CONCURRENCY_PER_CPU,
+			)
+		});
+
+		Self {
+			server_workers:     config
+				.server_workers
+				.or(autotuned.map(|(server_workers, _)| server_workers)),
+			worker_concurrency: config
+				.worker_concurrency
+				.or(autotuned.map(|(_, worker_concurrency)| worker_concurrency)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use super::*;
+
+	fn config(autotune_workers: bool, server_workers: Option<usize>) -> Config {
+		serde_json::from_value(json!({
+			"redis_url": "redis://redis:6379",
+			"default_payment_processor_url": "http://default:8080",
+			"fallback_payment_processor_url": "http://fallback:8080",
+			"server_keepalive": 120,
+			"server_workers": server_workers,
+			"autotune_workers": autotune_workers,
+		}))
+		.unwrap()
+	}
+
+	#[test]
+	fn test_autotune_keeps_a_cpu_for_background_workers() {
+		assert_eq!(
+			WorkerBudget::for_cpus(&config(true, None), 4),
+			WorkerBudget {
+				server_workers:     Some(3),
+				worker_concurrency: Some(WORKER_CONCURRENCY_PER_CPU),
+			}
+		);
+		assert_eq!(
+			WorkerBudget::for_cpus(&config(true, None), 1),
+			WorkerBudget {
+				server_workers:     Some(1),
+				worker_concurrency: Some(WORKER_CONCURRENCY_PER_CPU),
+			}
+		);
+	}
+
+	#[test]
+	fn test_explicit_settings_override_autotune() {
+		assert_eq!(
+			WorkerBudget::for_cpus(&config(true, Some(2)), 4).server_workers,
+			Some(2)
+		);
+		assert_eq!(
+			WorkerBudget::for_cpus(&config(false, None), 4),
+			WorkerBudget {
+				server_workers:     None,
+				worker_concurrency: None,
+			}
+		);
+	}
+}
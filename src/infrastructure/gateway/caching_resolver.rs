@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+
+struct CachedAddrs {
+	addrs:       Vec<SocketAddr>,
+	resolved_at: Instant,
+}
+
+/// Resolves processor hostnames at most once per `ttl`, so a service whose
+/// address changes is picked up again without resolving on every new
+/// connection. Entries can be dropped early when connecting to them fails.
+pub struct CachingResolver {
+	ttl:   Duration,
+	cache: Arc<Mutex<HashMap<String, CachedAddrs>>>,
+}
+
+impl CachingResolver {
+	pub fn new(ttl: Duration) -> Self {
+		Self {
+			ttl,
+			cache: Arc::new(Mutex::new(HashMap::new())),
+		}
+	}
+
+	/// Forgets the addresses of `host`, so the next connection resolves it
+	/// again.
+	pub fn invalidate(&self, host: &str) {
+		self.cache.lock().unwrap().remove(host);
+	}
+
+	fn cached(&self, host: &str, now: Instant) -> Option<Vec<SocketAddr>> {
+		self.cache
+			.lock()
+			.unwrap()
+			.get(host)
+			.filter(|cached| {
+				now.saturating_duration_since(cached.resolved_at) < self.ttl
+			})
+			.map(|cached| cached.addrs.clone())
+	}
+}
+
+impl Resolve for CachingResolver {
+	fn resolve(&self, name: Name) -> Resolving {
+		let host = name.as_str().to_string();
+		let cached = self.cached(&host, Instant::now());
+		let cache = self.cache.clone();
+
+		Box::pin(async move {
+			let addrs = match cached {
+				Some(addrs) => addrs,
+				None => {
+					let addrs: Vec<SocketAddr> =
+						tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+					cache.lock().unwrap().insert(host, CachedAddrs {
+						addrs:       addrs.clone(),
+						resolved_at: Instant::now(),
+					});
+					addrs
+				}
+			};
+
+			Ok(Box::new(addrs.into_iter()) as Addrs)
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn test_caches_addresses_until_ttl_or_invalidation() {
+		let resolver = CachingResolver::new(Duration::from_secs(60));
+
+		let addrs: Vec<_> = resolver
+			.resolve("localhost".parse().unwrap())
+			.await
+			.unwrap()
+			.collect();
+		assert!(!addrs.is_empty());
+
+		let now = Instant::now();
+		assert_eq!(resolver.cached("localhost", now), Some(addrs));
+		assert_eq!(
+			resolver.cached("localhost", now + Duration::from_secs(60)),
+			None
+		);
+
+		resolver.invalidate("localhost");
+		assert_eq!(resolver.cached("localhost", now), None);
+	}
+}
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use futures::future::join_all;
+use log::{info, warn};
+use reqwest::Client;
+
+use crate::domain::payment_processor::replica_urls;
+
+/// How long a single warm-up request may take before it is given up on.
+const WARMUP_REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+
+struct WarmupTarget {
+	processor:   String,
+	urls:        Vec<String>,
+	http_client: Client,
+}
+
+/// Pre-establishes keep-alive connections to the processors, so the first
+/// payments sent to them do not pay for the connection handshakes.
+pub struct ConnectionWarmer {
+	connections: usize,
+	targets:     Vec<WarmupTarget>,
+}
+
+impl ConnectionWarmer {
+	pub fn new(connections: usize) -> Self {
+		Self {
+			connections,
+			targets: Vec::new(),
+		}
+	}
+
+	/// Warms up the connection pool of `http_client` against every replica
+	/// listed in `url`. The client must be the one payments to `processor` are
+	/// sent with.
+	pub fn with_target(
+		mut self,
+		processor: &str,
+		url: &str,
+		http_client: Client,
+	) -> Self {
+		self.targets.push(WarmupTarget {
+			processor: processor.to_string(),
+			urls: replica_urls(url).map(str::to_string).collect(),
+			http_client,
+		});
+		self
+	}
+
+	pub async fn warm_up_all(&self) {
+		join_all(
+			self.targets
+				.iter()
+				.map(|target| self.warm_up(&target.processor)),
+		)
+		.await;
+	}
+
+	/// Opens the configured number of connections to each replica of
+	/// `processor` at once and returns how many of them were established. Any
+	/// response, whatever its status, leaves a connection in the pool.
+	pub async fn warm_up(&self, processor: &str) -> usize {
+		let Some(target) = self
+			.targets
+			.iter()
+			.find(|target| target.processor == processor)
+		else {
+			return 0;
+		};
+
+		let results = join_all(target.urls.iter().flat_map(|url| {
+			(0..self.connections).map(move |_| {
+				target
+					.http_client
+					.head(url)
+					.timeout(WARMUP_REQUEST_TIMEOUT)
+					.send()
+			})
+		}))
+		.await;
+
+		let established = results.iter().filter(|result| result.is_ok()).count();
+		if let Some(Err(e)) = results.iter().find(|result| result.is_err()) {
+			warn!(
+				"Only {established} of {} connections to {processor} were warmed \
+				 up: {e}",
+				results.len()
+			);
+		} else {
+			info!("Warmed up {established} connections to {processor}");
+		}
+
+		established
+	}
+}
@@ -0,0 +1,4 @@
+pub mod caching_resolver;
+pub mod connection_warmer;
+pub mod processor_admin_client;
+pub mod processor_discovery;
@@ -0,0 +1,129 @@
+use std::time::Duration;
+
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+const TOKEN_HEADER: &str = "X-Rinha-Token";
+
+/// Totals reported by a payment processor's admin API.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessorPaymentsSummary {
+	pub total_requests:      u64,
+	pub total_amount:        f64,
+	pub total_fee:           f64,
+	pub fee_per_transaction: f64,
+}
+
+#[derive(Serialize)]
+struct FailureConfiguration {
+	failure: bool,
+}
+
+#[derive(Serialize)]
+struct DelayConfiguration {
+	delay: u128,
+}
+
+/// Client for the `/admin` endpoints of a payment processor, used to inject
+/// failures and delays and to read back what the processor received.
+#[derive(Clone)]
+pub struct ProcessorAdminClient {
+	http_client: Client,
+	base_url:    String,
+	token:       String,
+}
+
+impl ProcessorAdminClient {
+	pub fn new(http_client: Client, base_url: String, token: String) -> Self {
+		Self {
+			http_client,
+			base_url,
+			token,
+		}
+	}
+
+	/// Makes the processor fail every payment while `failure` is set.
+	pub async fn set_failure(
+		&self,
+		failure: bool,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		self.send(
+			self.http_client
+				.put(self.url("/admin/configurations/failure"))
+				.json(&FailureConfiguration { failure }),
+		)
+		.await
+	}
+
+	/// Delays every payment handled by the processor by `delay`.
+	pub async fn set_delay(
+		&self,
+		delay: Duration,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		self.send(
+			self.http_client
+				.put(self.url("/admin/configurations/delay"))
+				.json(&DelayConfiguration {
+					delay: delay.as_millis(),
+				}),
+		)
+		.await
+	}
+
+	pub async fn get_payments_summary(
+		&self,
+		from: Option<OffsetDateTime>,
+		to: Option<OffsetDateTime>,
+	) -> Result<ProcessorPaymentsSummary, Box<dyn std::error::Error + Send>> {
+		let mut query = Vec::new();
+		for (name, value) in [("from", from), ("to", to)] {
+			if let Some(value) = value {
+				let value = value
+					.format(&Rfc3339)
+					.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+				query.push((name, value));
+			}
+		}
+
+		self.http_client
+			.get(self.url("/admin/payments-summary"))
+			.header(TOKEN_HEADER, &self.token)
+			.query(&query)
+			.send()
+			.await
+			.and_then(|response| response.error_for_status())
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?
+			.json()
+			.await
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)
+	}
+
+	/// Deletes every payment the processor has received.
+	pub async fn purge_payments(
+		&self,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		self.send(self.http_client.post(self.url("/admin/purge-payments")))
+			.await
+	}
+
+	fn url(&self, path: &str) -> String {
+		format!("{}{path}", self.base_url)
+	}
+
+	async fn send(
+		&self,
+		request: RequestBuilder,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		request
+			.header(TOKEN_HEADER, &self.token)
+			.send()
+			.await
+			.and_then(|response| response.error_for_status())
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,261 @@
+use std::str::FromStr;
+
+use reqwest::Client;
+use serde::Deserialize;
+
+/// Where the replicas of a processor are discovered.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiscoverySource {
+	/// DNS SRV records of the name, each target served over plain HTTP.
+	Srv(String),
+	/// A Consul health or Kubernetes Endpoints API URL listing the
+	/// instances.
+	Endpoints(String),
+}
+
+impl FromStr for DiscoverySource {
+	type Err = String;
+
+	/// Parses `srv://_payments._tcp.processor.internal` or an `http(s)://`
+	/// URL.
+	fn from_str(source: &str) -> Result<Self, Self::Err> {
+		let source = source.trim();
+		if let Some(name) = source.strip_prefix("srv://") {
+			return match name.trim_end_matches('/') {
+				"" => Err(format!("Missing SRV name in '{source}'")),
+				name => Ok(DiscoverySource::Srv(name.to_string())),
+			};
+		}
+		if source.starts_with("http://") || source.starts_with("https://") {
+			return Ok(DiscoverySource::Endpoints(source.to_string()));
+		}
+
+		Err(format!("Unknown processor discovery source '{source}'"))
+	}
+}
+
+/// Instances listed by a discovery endpoint, in the shape of either the
+/// Consul health API or a Kubernetes Endpoints object.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum EndpointList {
+	Consul(Vec<ConsulServiceEntry>),
+	Kubernetes(KubernetesEndpoints),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ConsulServiceEntry {
+	node:    ConsulNode,
+	service: ConsulService,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ConsulNode {
+	address: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ConsulService {
+	#[serde(default)]
+	address: String,
+	port:    u16,
+}
+
+#[derive(Debug, Deserialize)]
+struct KubernetesEndpoints {
+	#[serde(default)]
+	subsets: Vec<KubernetesSubset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KubernetesSubset {
+	#[serde(default)]
+	addresses: Vec<KubernetesAddress>,
+	#[serde(default)]
+	ports:     Vec<KubernetesPort>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KubernetesAddress {
+	ip: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct KubernetesPort {
+	port: u16,
+}
+
+impl EndpointList {
+	/// Base URLs of the listed instances, sorted so the same instances always
+	/// give the same URLs.
+	fn urls(self) -> Vec<String> {
+		let mut urls: Vec<String> = match self {
+			EndpointList::Consul(entries) => entries
+				.into_iter()
+				.map(|entry| {
+					// Consul leaves the service address empty when the
+					// service listens on the address of its node.
+					let address = if entry.service.address.is_empty() {
+						entry.node.address
+					} else {
+						entry.service.address
+					};
+					base_url(&address, entry.service.port)
+				})
+				.collect(),
+			EndpointList::Kubernetes(endpoints) => endpoints
+				.subsets
+				.into_iter()
+				.filter_map(|subset| {
+					let port = subset.ports.first()?.port;
+					Some(
+						subset
+							.addresses
+							.into_iter()
+							.map(move |address| base_url(&address.ip, port)),
+					)
+				})
+				.flatten()
+				.collect(),
+		};
+		urls.sort();
+		urls.dedup();
+		urls
+	}
+}
+
+fn base_url(host: &str, port: u16) -> String {
+	let host = host.trim_end_matches('.');
+	if host.contains(':') {
+		format!("http://[{host}]:{port}")
+	} else {
+		format!("http://{host}:{port}")
+	}
+}
+
+/// Looks up the replicas of the processors, so instances can be added or
+/// removed without restarting the service with new URLs.
+#[derive(Clone)]
+pub struct ProcessorDiscovery {
+	http_client: Client,
+}
+
+impl ProcessorDiscovery {
+	pub fn new(http_client: Client) -> Self {
+		Self { http_client }
+	}
+
+	/// Base URLs of the instances currently behind `source`.
+	pub async fn discover(
+		&self,
+		source: &DiscoverySource,
+	) -> Result<Vec<String>, Box<dyn std::error::Error + Send>> {
+		match source {
+			DiscoverySource::Srv(name) => lookup_srv(name).await,
+			DiscoverySource::Endpoints(url) => {
+				let endpoints: EndpointList = self
+					.http_client
+					.get(url)
+					.send()
+					.await
+					.and_then(|response| response.error_for_status())
+					.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?
+					.json()
+					.await
+					.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+				Ok(endpoints.urls())
+			}
+		}
+	}
+}
+
+#[cfg(feature = "dns-srv")]
+async fn lookup_srv(
+	name: &str,
+) -> Result<Vec<String>, Box<dyn std::error::Error + Send>> {
+	use hickory_resolver::TokioAsyncResolver;
+
+	let resolver = TokioAsyncResolver::tokio_from_system_conf()
+		.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+	let records = resolver
+		.srv_lookup(name)
+		.await
+		.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+	let mut urls: Vec<String> = records
+		.iter()
+		.map(|record| base_url(&record.target().to_utf8(), record.port()))
+		.collect();
+	urls.sort();
+	urls.dedup();
+	Ok(urls)
+}
+
+#[cfg(not(feature = "dns-srv"))]
+async fn lookup_srv(
+	name: &str,
+) -> Result<Vec<String>, Box<dyn std::error::Error + Send>> {
+	Err(Box::new(std::io::Error::new(
+		std::io::ErrorKind::Unsupported,
+		format!(
+			"Cannot look up the SRV records of '{name}': the dns-srv feature is \
+			 disabled"
+		),
+	)))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parses_discovery_sources() {
+		assert_eq!(
+			"srv://_payments._tcp.default.internal".parse(),
+			Ok(DiscoverySource::Srv(
+				"_payments._tcp.default.internal".into()
+			))
+		);
+		assert_eq!(
+			"http://consul:8500/v1/health/service/default?passing".parse(),
+			Ok(DiscoverySource::Endpoints(
+				"http://consul:8500/v1/health/service/default?passing".into()
+			))
+		);
+		assert!("srv://".parse::<DiscoverySource>().is_err());
+		assert!("default.internal".parse::<DiscoverySource>().is_err());
+	}
+
+	#[test]
+	fn test_reads_consul_and_kubernetes_endpoint_lists() {
+		let consul: EndpointList = serde_json::from_str(
+			r#"[
+				{"Node": {"Address": "10.0.0.2"}, "Service": {"Address": "", "Port": 8080}},
+				{"Node": {"Address": "10.0.0.3"}, "Service": {"Address": "10.0.1.3", "Port": 8080}}
+			]"#,
+		)
+		.unwrap();
+		assert_eq!(consul.urls(), vec![
+			"http://10.0.0.2:8080".to_string(),
+			"http://10.0.1.3:8080".to_string(),
+		]);
+
+		let kubernetes: EndpointList = serde_json::from_str(
+			r#"{
+				"kind": "Endpoints",
+				"subsets": [{
+					"addresses": [{"ip": "10.1.0.5"}, {"ip": "10.1.0.4"}],
+					"ports": [{"port": 8080, "protocol": "TCP"}]
+				}]
+			}"#,
+		)
+		.unwrap();
+		assert_eq!(kubernetes.urls(), vec![
+			"http://10.1.0.4:8080".to_string(),
+			"http://10.1.0.5:8080".to_string(),
+		]);
+	}
+}
@@ -1,4 +1,7 @@
+pub mod alerting;
 pub mod config;
+pub mod gateway;
+pub mod observability;
 pub mod payment_processor;
 pub mod persistence;
 pub mod queue;
@@ -0,0 +1,149 @@
+//! Report of a benchmark run, written next to the flamegraph when the
+//! service shuts down, so runs can be compared without digging through logs.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::domain::payment_processor::PROCESSOR_GROUPS;
+use crate::infrastructure::observability::metrics::Metrics;
+use crate::use_cases::dto::{PaymentSummaryResult, PaymentsSummaryResponse};
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessorReport {
+	pub name:           &'static str,
+	pub processed:      usize,
+	pub amount:         f64,
+	/// Only known when the fee of the processor was configured.
+	pub estimated_fees: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkReport {
+	pub total_processed:       usize,
+	pub processors:            Vec<ProcessorReport>,
+	/// Time to push a payment to the queue, as seen by this instance.
+	pub ingest_latency_p99_us: Option<u64>,
+	/// Queued messages lost or delivered twice, as seen by this instance.
+	pub inconsistencies:       u64,
+}
+
+impl BenchmarkReport {
+	/// Builds the report from the summary of the run and the metrics of this
+	/// instance. `fees` holds the fee rate of each processor group, if known.
+	pub fn new(
+		summary: &PaymentsSummaryResponse,
+		fees: [Option<f64>; 2],
+		metrics: &Metrics,
+	) -> Self {
+		let processor = |name, result: &PaymentSummaryResult, fee: Option<f64>| {
+			ProcessorReport {
+				name,
+				processed: result.total_requests,
+				amount: result.total_amount,
+				estimated_fees: fee.map(|fee| result.total_amount * fee),
+			}
+		};
+		let processors = vec![
+			processor(PROCESSOR_GROUPS[0], &summary.default, fees[0]),
+			processor(PROCESSOR_GROUPS[1], &summary.fallback, fees[1]),
+		];
+
+		Self {
+			total_processed: processors.iter().map(|p| p.processed).sum(),
+			processors,
+			ingest_latency_p99_us: metrics
+				.queue_push_latency_percentile(0.99)
+				.map(|latency| latency.as_micros() as u64),
+			inconsistencies: metrics.message_inconsistencies(),
+		}
+	}
+
+	pub fn to_markdown(&self) -> String {
+		let mut markdown = String::from("# Benchmark report\n\n");
+		let _ = writeln!(markdown, "- Total processed: {}", self.total_processed);
+		let _ = writeln!(
+			markdown,
+			"- Ingest latency p99: {}",
+			self.ingest_latency_p99_us
+				.map_or("n/a".to_string(), |latency| format!("{latency}us"))
+		);
+		let _ = writeln!(markdown, "- Inconsistencies: {}", self.inconsistencies);
+
+		markdown.push_str(
+			"\n| Processor | Processed | Amount | Estimated fees |\n| --- | ---: | \
+			 ---: | ---: |\n",
+		);
+		for processor in &self.processors {
+			let _ = writeln!(
+				markdown,
+				"| {} | {} | {:.2} | {} |",
+				processor.name,
+				processor.processed,
+				processor.amount,
+				processor
+					.estimated_fees
+					.map_or("n/a".to_string(), |fees| format!("{fees:.2}"))
+			);
+		}
+
+		markdown
+	}
+
+	/// Writes `report.json` and `report.md` into `dir`.
+	pub fn write_to(&self, dir: &Path) -> std::io::Result<()> {
+		let json = serde_json::to_string_pretty(self)?;
+		std::fs::write(dir.join("report.json"), json)?;
+		std::fs::write(dir.join("report.md"), self.to_markdown())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use super::*;
+
+	fn summary(
+		default: (usize, f64),
+		fallback: (usize, f64),
+	) -> PaymentsSummaryResponse {
+		let result = |(total_requests, total_amount)| PaymentSummaryResult {
+			total_requests,
+			total_amount,
+			failures: None,
+			amount_buckets: None,
+			latency: None,
+		};
+		PaymentsSummaryResponse {
+			default:  result(default),
+			fallback: result(fallback),
+			pending:  None,
+		}
+	}
+
+	#[test]
+	fn test_report_splits_totals_and_estimates_known_fees() {
+		let metrics = Metrics::default();
+		metrics.record_queue_push(Duration::from_micros(200));
+
+		let report = BenchmarkReport::new(
+			&summary((3, 100.0), (1, 20.0)),
+			[Some(0.05), None],
+			&metrics,
+		);
+
+		assert_eq!(report.total_processed, 4);
+		assert_eq!(report.processors[0].estimated_fees, Some(5.0));
+		assert_eq!(report.processors[1].estimated_fees, None);
+		assert_eq!(report.ingest_latency_p99_us, Some(255));
+		assert_eq!(report.inconsistencies, 0);
+
+		let markdown = report.to_markdown();
+		assert!(markdown.contains("| default | 3 | 100.00 | 5.00 |"));
+		assert!(markdown.contains("| fallback | 1 | 20.00 | n/a |"));
+	}
+}
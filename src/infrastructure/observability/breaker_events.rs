@@ -0,0 +1,116 @@
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use circuitbreaker_rs::MetricSink;
+use log::{info, warn};
+use tokio::sync::broadcast;
+
+use crate::domain::breaker_transition::BreakerTransition;
+use crate::infrastructure::observability::metrics::metrics;
+
+/// Transitions kept for subscribers that fall behind.
+const BREAKER_EVENTS_CAPACITY: usize = 64;
+
+static BREAKER_EVENTS: LazyLock<broadcast::Sender<BreakerTransition>> =
+	LazyLock::new(|| broadcast::channel(BREAKER_EVENTS_CAPACITY).0);
+
+/// Process-wide channel on which every circuit breaker transition is
+/// published. Transitions made while nobody is subscribed are dropped.
+pub fn breaker_events() -> &'static broadcast::Sender<BreakerTransition> {
+	&BREAKER_EVENTS
+}
+
+/// Receives the calls and transitions of one processor's circuit breaker and
+/// turns every transition into a log line, a metric and a
+/// [`BreakerTransition`] event.
+pub struct BreakerEventSink {
+	processor: &'static str,
+	successes: AtomicU64,
+	failures:  AtomicU64,
+}
+
+impl BreakerEventSink {
+	pub fn new(processor: &'static str) -> Self {
+		Self {
+			processor,
+			successes: AtomicU64::new(0),
+			failures: AtomicU64::new(0),
+		}
+	}
+}
+
+impl MetricSink for BreakerEventSink {
+	fn record_state_transition(&self, from: &str, to: &str) {
+		let successes = self.successes.swap(0, Ordering::Relaxed);
+		let failures = self.failures.swap(0, Ordering::Relaxed);
+		let calls = successes + failures;
+
+		let transition = BreakerTransition {
+			processor:    self.processor.to_string(),
+			from:         from.to_string(),
+			to:           to.to_string(),
+			failure_rate: if calls == 0 {
+				0.0
+			} else {
+				failures as f64 / calls as f64
+			},
+		};
+
+		if to == "open" {
+			warn!("{transition}");
+		} else {
+			info!("{transition}");
+		}
+		metrics().record_breaker_transition(self.processor, to);
+		let _ = breaker_events().send(transition);
+	}
+
+	fn record_error_rate(&self, _rate: f64) {}
+
+	fn record_probe_attempt(&self, _success: bool) {}
+
+	fn record_call(&self, success: bool, _duration: Duration) {
+		if success {
+			&self.successes
+		} else {
+			&self.failures
+		}
+		.fetch_add(1, Ordering::Relaxed);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_publishes_transitions_with_failure_rate() {
+		let mut events = breaker_events().subscribe();
+		let sink = BreakerEventSink::new("sink-test");
+
+		sink.record_call(true, Duration::ZERO);
+		sink.record_call(false, Duration::ZERO);
+		sink.record_call(false, Duration::ZERO);
+		sink.record_call(false, Duration::ZERO);
+		sink.record_state_transition("closed", "open");
+		sink.record_state_transition("open", "half-open");
+
+		let mut transitions = std::iter::from_fn(|| events.try_recv().ok())
+			.filter(|transition| transition.processor == "sink-test");
+
+		assert_eq!(
+			transitions.next(),
+			Some(BreakerTransition {
+				processor:    "sink-test".to_string(),
+				from:         "closed".to_string(),
+				to:           "open".to_string(),
+				failure_rate: 0.75,
+			})
+		);
+		assert_eq!(
+			transitions.next().map(|transition| transition.failure_rate),
+			Some(0.0)
+		);
+	}
+}
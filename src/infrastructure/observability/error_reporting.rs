@@ -0,0 +1,76 @@
+use std::error::Error;
+
+use uuid::Uuid;
+
+use crate::infrastructure::config::settings::Config;
+
+/// Number of consecutive failures against the same processor before they are
+/// reported as a single incident.
+pub const REPEATED_FAILURES_THRESHOLD: u32 = 10;
+
+#[cfg(feature = "sentry")]
+pub type ErrorReportingGuard = sentry::ClientInitGuard;
+
+#[cfg(not(feature = "sentry"))]
+pub struct ErrorReportingGuard;
+
+/// Initializes the error reporter when a DSN is configured. The returned
+/// guard must be kept alive for as long as events should be delivered.
+#[cfg(feature = "sentry")]
+pub fn init(config: &Config) -> Option<ErrorReportingGuard> {
+	let dsn = config.sentry_dsn.as_deref()?;
+
+	let mut options = sentry::ClientOptions::default();
+	options.release = sentry::release_name!();
+
+	Some(sentry::init((dsn, options)))
+}
+
+#[cfg(not(feature = "sentry"))]
+pub fn init(_config: &Config) -> Option<ErrorReportingGuard> {
+	None
+}
+
+pub fn report_error(error: &(dyn Error + Send)) {
+	#[cfg(feature = "sentry")]
+	sentry::capture_message(&error.to_string(), sentry::Level::Error);
+
+	#[cfg(not(feature = "sentry"))]
+	let _ = error;
+}
+
+pub fn report_payment_error(
+	correlation_id: Uuid,
+	processor: Option<&str>,
+	error: &(dyn Error + Send),
+) {
+	#[cfg(feature = "sentry")]
+	sentry::with_scope(
+		|scope| {
+			scope.set_tag("correlation_id", correlation_id);
+			if let Some(processor) = processor {
+				scope.set_tag("processor", processor);
+			}
+		},
+		|| sentry::capture_message(&error.to_string(), sentry::Level::Error),
+	);
+
+	#[cfg(not(feature = "sentry"))]
+	let _ = (correlation_id, processor, error);
+}
+
+pub fn report_repeated_processor_failures(processor: &str, failures: u32) {
+	#[cfg(feature = "sentry")]
+	sentry::with_scope(
+		|scope| scope.set_tag("processor", processor),
+		|| {
+			sentry::capture_message(
+				&format!("Processor {processor} failed {failures} times in a row"),
+				sentry::Level::Warning,
+			)
+		},
+	);
+
+	#[cfg(not(feature = "sentry"))]
+	let _ = (processor, failures);
+}
@@ -0,0 +1,74 @@
+//! Flamegraphs of `perf` builds, written at exit and, optionally, as
+//! periodic snapshots so a run that gets killed still leaves a profile.
+
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use pprof::ProfilerGuard;
+use pprof::flamegraph::Options;
+
+const SNAPSHOT_PREFIX: &str = "flamegraph-";
+
+/// Writes the samples collected so far by `guard` to `path`.
+pub fn write_flamegraph(guard: &ProfilerGuard<'_>, path: &Path) -> io::Result<()> {
+	let report = guard.report().build().map_err(io::Error::other)?;
+	let mut file = File::create(path)?;
+	let mut options = Options::default();
+	options.title = "rinha-de-backend".to_string();
+	options.count_name = "samples".to_string();
+	report
+		.flamegraph_with_options(&mut file, &mut options)
+		.map_err(io::Error::other)
+}
+
+/// Path of a snapshot taken at `unix_ts`. Snapshot names sort by age.
+pub fn snapshot_path(dir: &Path, unix_ts: i64) -> PathBuf {
+	dir.join(format!("{SNAPSHOT_PREFIX}{unix_ts:012}.svg"))
+}
+
+/// Deletes the oldest snapshots in `dir` until at most `keep` are left,
+/// including those left by previous runs.
+pub fn rotate_snapshots(dir: &Path, keep: usize) -> io::Result<()> {
+	let mut snapshots: Vec<PathBuf> = std::fs::read_dir(dir)?
+		.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+		.filter(|path| {
+			path.file_name()
+				.and_then(|name| name.to_str())
+				.is_some_and(|name| {
+					name.starts_with(SNAPSHOT_PREFIX) && name.ends_with(".svg")
+				})
+		})
+		.collect();
+	snapshots.sort();
+
+	let excess = snapshots.len().saturating_sub(keep);
+	for snapshot in &snapshots[..excess] {
+		std::fs::remove_file(snapshot)?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_rotate_snapshots_keeps_newest() {
+		let dir = std::env::temp_dir()
+			.join(format!("flamegraphs-{}", uuid::Uuid::new_v4()));
+		std::fs::create_dir_all(&dir).unwrap();
+		for unix_ts in [300, 100, 200] {
+			std::fs::write(snapshot_path(&dir, unix_ts), "").unwrap();
+		}
+		std::fs::write(dir.join("flamegraph.svg"), "").unwrap();
+
+		rotate_snapshots(&dir, 2).unwrap();
+
+		assert!(!snapshot_path(&dir, 100).exists());
+		assert!(snapshot_path(&dir, 200).exists());
+		assert!(snapshot_path(&dir, 300).exists());
+		assert!(dir.join("flamegraph.svg").exists());
+		std::fs::remove_dir_all(dir).unwrap();
+	}
+}
@@ -0,0 +1,100 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use uuid::Uuid;
+
+use crate::infrastructure::config::settings::{Config, LogRedaction};
+
+static LOG_REDACTION: AtomicU8 = AtomicU8::new(LogRedaction::Off as u8);
+
+/// Applies the configured redaction policy to every log line written from
+/// now on.
+pub fn init(config: &Config) {
+	set_log_redaction(config.log_redaction);
+}
+
+pub fn set_log_redaction(policy: LogRedaction) {
+	LOG_REDACTION.store(policy as u8, Ordering::Relaxed);
+}
+
+pub fn log_redaction() -> LogRedaction {
+	match LOG_REDACTION.load(Ordering::Relaxed) {
+		policy if policy == LogRedaction::Amounts as u8 => LogRedaction::Amounts,
+		policy if policy == LogRedaction::All as u8 => LogRedaction::All,
+		_ => LogRedaction::Off,
+	}
+}
+
+/// Displays a payment amount, masked unless redaction is off.
+pub fn amount(amount: f64) -> Redacted<f64> {
+	Redacted {
+		value:  amount,
+		masked: log_redaction() != LogRedaction::Off,
+	}
+}
+
+/// Displays a correlation id, cut down to its first group when everything
+/// is redacted, which is still enough to tell payments apart in a log.
+pub fn correlation_id(correlation_id: Uuid) -> Redacted<Uuid> {
+	Redacted {
+		value:  correlation_id,
+		masked: log_redaction() == LogRedaction::All,
+	}
+}
+
+/// A value written to the logs as is, or masked by the redaction policy.
+#[derive(Debug, Clone, Copy)]
+pub struct Redacted<T> {
+	value:  T,
+	masked: bool,
+}
+
+impl fmt::Display for Redacted<f64> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if self.masked {
+			return write!(f, "***");
+		}
+		write!(f, "{:.2}", self.value)
+	}
+}
+
+impl fmt::Display for Redacted<Uuid> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if self.masked {
+			let mut buffer = Uuid::encode_buffer();
+			let hyphenated = self.value.hyphenated().encode_lower(&mut buffer);
+			return write!(f, "{}-***", &hyphenated[..8]);
+		}
+		write!(f, "{}", self.value)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_masks_values_by_policy() {
+		let id = Uuid::parse_str("4a7901b8-7d26-4d9d-aa19-4dc1c7cf60b3").unwrap();
+		let shown = |masked| Redacted { value: id, masked }.to_string();
+
+		assert_eq!(shown(false), "4a7901b8-7d26-4d9d-aa19-4dc1c7cf60b3");
+		assert_eq!(shown(true), "4a7901b8-***");
+		assert_eq!(
+			Redacted {
+				value:  19.9,
+				masked: false,
+			}
+			.to_string(),
+			"19.90"
+		);
+		assert_eq!(
+			Redacted {
+				value:  19.9,
+				masked: true,
+			}
+			.to_string(),
+			"***"
+		);
+	}
+}
@@ -0,0 +1,584 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::domain::payment_metrics::PaymentMetrics;
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::default);
+
+/// Process-wide metrics registry shared by the use cases, workers and
+/// exporters.
+pub fn metrics() -> &'static Metrics {
+	&METRICS
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetricKind {
+	Counter,
+	Gauge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricSample {
+	pub name:  &'static str,
+	pub tags:  Vec<(&'static str, &'static str)>,
+	pub kind:  MetricKind,
+	pub value: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+	payments_received:           AtomicU64,
+	payments_processed_default:  AtomicU64,
+	payments_processed_fallback: AtomicU64,
+	payments_requeued:           AtomicU64,
+	payments_failed:             AtomicU64,
+	payments_duplicated:         AtomicU64,
+	/// Messages popped for the first time more than once.
+	messages_redelivered:        AtomicU64,
+	/// Sequenced messages neither delivered nor still queued.
+	message_sequence_gaps:       AtomicU64,
+	messages_quarantined:        AtomicU64,
+	/// Payments dropped for waiting in the queue past its max age.
+	payments_dead_lettered:      AtomicU64,
+	/// Broken invariants found by the payment verification worker.
+	invariant_violations:        AtomicU64,
+	queue_depth:                 AtomicU64,
+	/// Age of the next message to be popped, in milliseconds.
+	queue_lag_millis:            AtomicU64,
+	/// Time the last payment took to be pushed to the queue.
+	queue_push_latency_micros:   AtomicU64,
+	queue_push_latencies:        LatencyHistogram,
+	/// Payments processed per second by this instance, in thousandths.
+	throughput_millis:           AtomicU64,
+	last_throughput_sample:      Mutex<Option<(Instant, u64)>>,
+	workers:                     Mutex<Vec<(&'static str, Arc<WorkerMetrics>)>>,
+	/// Circuit breaker transitions by processor and state entered.
+	breaker_transitions:         Mutex<BTreeMap<(&'static str, &'static str), u64>>,
+	/// Entries of the Redis keys tracked by the maintenance worker.
+	key_space_sizes:             Mutex<BTreeMap<&'static str, u64>>,
+}
+
+/// Latencies counted in power-of-two buckets of microseconds, precise enough
+/// for percentiles in reports without keeping every sample.
+#[derive(Debug, Default)]
+pub struct LatencyHistogram {
+	buckets: [AtomicU64; LATENCY_BUCKETS],
+}
+
+const LATENCY_BUCKETS: usize = 32;
+
+impl LatencyHistogram {
+	pub fn record(&self, latency: Duration) {
+		let micros = latency.as_micros().max(1) as u64;
+		let bucket = (u64::BITS - micros.leading_zeros()) as usize - 1;
+		self.buckets[bucket.min(LATENCY_BUCKETS - 1)]
+			.fetch_add(1, Ordering::Relaxed);
+	}
+
+	/// Upper bound of the bucket holding the `quantile` of the recorded
+	/// latencies, or `None` if nothing was recorded.
+	pub fn percentile(&self, quantile: f64) -> Option<Duration> {
+		let counts: Vec<u64> = self
+			.buckets
+			.iter()
+			.map(|bucket| bucket.load(Ordering::Relaxed))
+			.collect();
+		let total: u64 = counts.iter().sum();
+		if total == 0 {
+			return None;
+		}
+
+		let rank = ((total as f64 * quantile).ceil() as u64).max(1);
+		let mut seen = 0;
+		counts
+			.iter()
+			.position(|&count| {
+				seen += count;
+				seen >= rank
+			})
+			.map(|bucket| Duration::from_micros((2 << bucket) - 1))
+	}
+}
+
+/// Counters of a single worker task, so uneven work distribution and stuck
+/// workers show up.
+#[derive(Debug, Default)]
+pub struct WorkerMetrics {
+	processed:           AtomicU64,
+	failed:              AtomicU64,
+	requeued:            AtomicU64,
+	idle_millis:         AtomicU64,
+	loop_latency_micros: AtomicU64,
+}
+
+impl WorkerMetrics {
+	pub fn record_processed(&self) {
+		self.processed.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub fn record_failed(&self) {
+		self.failed.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub fn record_requeued(&self) {
+		self.requeued.fetch_add(1, Ordering::Relaxed);
+	}
+
+	/// Adds time spent waiting on an empty queue.
+	pub fn record_idle(&self, idle: Duration) {
+		self.idle_millis
+			.fetch_add(idle.as_millis() as u64, Ordering::Relaxed);
+	}
+
+	/// Records how long handling the last message took.
+	pub fn record_loop(&self, latency: Duration) {
+		self.loop_latency_micros
+			.store(latency.as_micros() as u64, Ordering::Relaxed);
+	}
+}
+
+impl Metrics {
+	pub fn record_received(&self) {
+		self.payments_received.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub fn record_queue_push(&self, latency: Duration) {
+		self.queue_push_latency_micros
+			.store(latency.as_micros() as u64, Ordering::Relaxed);
+		self.queue_push_latencies.record(latency);
+	}
+
+	pub fn queue_push_latency_percentile(&self, quantile: f64) -> Option<Duration> {
+		self.queue_push_latencies.percentile(quantile)
+	}
+
+	/// Sequenced messages found missing plus those delivered more than once.
+	pub fn message_inconsistencies(&self) -> u64 {
+		self.message_sequence_gaps.load(Ordering::Relaxed) +
+			self.messages_redelivered.load(Ordering::Relaxed)
+	}
+
+	pub fn record_processed(&self, processor: &str) {
+		match processor {
+			"default" => &self.payments_processed_default,
+			_ => &self.payments_processed_fallback,
+		}
+		.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub fn record_requeued(&self) {
+		self.payments_requeued.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub fn record_failed(&self) {
+		self.payments_failed.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub fn record_duplicated(&self) {
+		self.payments_duplicated.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub fn record_redelivered(&self) {
+		self.messages_redelivered.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub fn set_sequence_gaps(&self, gaps: u64) {
+		self.message_sequence_gaps.store(gaps, Ordering::Relaxed);
+	}
+
+	pub fn record_quarantined(&self) {
+		self.messages_quarantined.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub fn record_dead_lettered(&self) {
+		self.payments_dead_lettered.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub fn record_invariant_violations(&self, violations: u64) {
+		self.invariant_violations
+			.fetch_add(violations, Ordering::Relaxed);
+	}
+
+	pub fn record_breaker_transition(&self, processor: &'static str, to: &str) {
+		let state = match to {
+			"open" => "open",
+			"half-open" => "half_open",
+			"closed" => "closed",
+			_ => "unknown",
+		};
+		*self
+			.breaker_transitions
+			.lock()
+			.unwrap()
+			.entry((processor, state))
+			.or_default() += 1;
+	}
+
+	pub fn set_key_space_size(&self, key: &'static str, size: u64) {
+		self.key_space_sizes.lock().unwrap().insert(key, size);
+	}
+
+	pub fn record_enqueued(&self) {
+		self.queue_depth.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub fn record_dequeued(&self) {
+		let _ = self.queue_depth.fetch_update(
+			Ordering::Relaxed,
+			Ordering::Relaxed,
+			|depth| Some(depth.saturating_sub(1)),
+		);
+	}
+
+	/// Replaces the incremental queue depth estimate with the depth reported
+	/// by the queue itself, discarding any drift accumulated from pushes and
+	/// pops made by other instances.
+	pub fn set_queue_depth(&self, depth: u64) {
+		self.queue_depth.store(depth, Ordering::Relaxed);
+	}
+
+	pub fn queue_depth(&self) -> u64 {
+		self.queue_depth.load(Ordering::Relaxed)
+	}
+
+	/// Records how long the next message to be popped has been waiting, zero
+	/// when the queue is empty.
+	pub fn set_queue_lag(&self, lag: Duration) {
+		self.queue_lag_millis
+			.store(lag.as_millis() as u64, Ordering::Relaxed);
+	}
+
+	pub fn queue_lag(&self) -> Duration {
+		Duration::from_millis(self.queue_lag_millis.load(Ordering::Relaxed))
+	}
+
+	/// Updates the throughput estimate from the payments processed since the
+	/// previous sample.
+	pub fn sample_throughput(&self, now: Instant) {
+		let processed = self.payments_processed_default.load(Ordering::Relaxed) +
+			self.payments_processed_fallback.load(Ordering::Relaxed);
+
+		let mut last_sample = self.last_throughput_sample.lock().unwrap();
+		if let Some((sampled_at, sampled)) = *last_sample {
+			let elapsed = now.saturating_duration_since(sampled_at).as_secs_f64();
+			if elapsed > 0.0 {
+				let throughput = processed.saturating_sub(sampled) as f64 / elapsed;
+				self.throughput_millis
+					.store((throughput * 1000.0) as u64, Ordering::Relaxed);
+			}
+		}
+		*last_sample = Some((now, processed));
+	}
+
+	pub fn throughput(&self) -> f64 {
+		self.throughput_millis.load(Ordering::Relaxed) as f64 / 1000.0
+	}
+
+	/// How long the queued payments should take to drain at the current
+	/// throughput, or `None` while nothing is being processed.
+	pub fn estimated_processing_delay(&self) -> Option<Duration> {
+		let depth = self.queue_depth();
+		if depth == 0 {
+			return Some(Duration::ZERO);
+		}
+
+		let throughput = self.throughput();
+		(throughput > 0.0)
+			.then(|| Duration::from_secs_f64(depth as f64 / throughput))
+	}
+
+	/// Registers a worker task and returns its counters. Workers are
+	/// registered once when they start, so their ids are leaked to be usable
+	/// as sample tags.
+	pub fn register_worker(&self, kind: &str) -> Arc<WorkerMetrics> {
+		let mut workers = self.workers.lock().unwrap();
+		let id: &'static str =
+			Box::leak(format!("{kind}-{}", workers.len()).into_boxed_str());
+		let worker = Arc::new(WorkerMetrics::default());
+		workers.push((id, worker.clone()));
+		worker
+	}
+
+	pub fn snapshot(&self) -> Vec<MetricSample> {
+		let counter = |name, tags, value: &AtomicU64| MetricSample {
+			name,
+			tags,
+			kind: MetricKind::Counter,
+			value: value.load(Ordering::Relaxed),
+		};
+
+		let mut samples = vec![
+			counter("payments_received", vec![], &self.payments_received),
+			counter(
+				"payments_processed",
+				vec![("processor", "default")],
+				&self.payments_processed_default,
+			),
+			counter(
+				"payments_processed",
+				vec![("processor", "fallback")],
+				&self.payments_processed_fallback,
+			),
+			counter("payments_requeued", vec![], &self.payments_requeued),
+			counter("payments_failed", vec![], &self.payments_failed),
+			counter("payments_duplicated", vec![], &self.payments_duplicated),
+			counter("messages_quarantined", vec![], &self.messages_quarantined),
+			counter(
+				"payments_dead_lettered",
+				vec![],
+				&self.payments_dead_lettered,
+			),
+			counter("invariant_violations", vec![], &self.invariant_violations),
+			counter("messages_redelivered", vec![], &self.messages_redelivered),
+			MetricSample {
+				name:  "message_sequence_gaps",
+				tags:  vec![],
+				kind:  MetricKind::Gauge,
+				value: self.message_sequence_gaps.load(Ordering::Relaxed),
+			},
+			MetricSample {
+				name:  "payments_queue_depth",
+				tags:  vec![],
+				kind:  MetricKind::Gauge,
+				value: self.queue_depth(),
+			},
+			MetricSample {
+				name:  "payments_queue_lag_ms",
+				tags:  vec![],
+				kind:  MetricKind::Gauge,
+				value: self.queue_lag_millis.load(Ordering::Relaxed),
+			},
+			MetricSample {
+				name:  "queue_push_latency_us",
+				tags:  vec![],
+				kind:  MetricKind::Gauge,
+				value: self.queue_push_latency_micros.load(Ordering::Relaxed),
+			},
+		];
+
+		for (&(processor, state), &value) in
+			self.breaker_transitions.lock().unwrap().iter()
+		{
+			samples.push(MetricSample {
+				name: "breaker_transitions",
+				tags: vec![("processor", processor), ("state", state)],
+				kind: MetricKind::Counter,
+				value,
+			});
+		}
+
+		for (&key, &value) in self.key_space_sizes.lock().unwrap().iter() {
+			samples.push(MetricSample {
+				name: "redis_key_space_size",
+				tags: vec![("key", key)],
+				kind: MetricKind::Gauge,
+				value,
+			});
+		}
+
+		for (id, worker) in self.workers.lock().unwrap().iter() {
+			let tags = vec![("worker", *id)];
+			samples.extend([
+				counter("worker_processed", tags.clone(), &worker.processed),
+				counter("worker_failed", tags.clone(), &worker.failed),
+				counter("worker_requeued", tags.clone(), &worker.requeued),
+				counter("worker_idle_ms", tags.clone(), &worker.idle_millis),
+				MetricSample {
+					name: "worker_loop_latency_us",
+					tags,
+					kind: MetricKind::Gauge,
+					value: worker.loop_latency_micros.load(Ordering::Relaxed),
+				},
+			]);
+		}
+
+		samples
+	}
+}
+
+impl PaymentMetrics for Metrics {
+	fn record_processed(&self, processor: &str) {
+		Metrics::record_processed(self, processor);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_snapshot_reflects_recorded_events() {
+		let metrics = Metrics::default();
+
+		metrics.record_received();
+		metrics.record_received();
+		metrics.record_processed("default");
+		metrics.record_processed("fallback");
+		metrics.record_requeued();
+
+		let snapshot = metrics.snapshot();
+		let value = |name, tags: Vec<(&str, &str)>| {
+			snapshot
+				.iter()
+				.find(|sample| sample.name == name && sample.tags == tags)
+				.map(|sample| sample.value)
+				.unwrap()
+		};
+
+		assert_eq!(value("payments_received", vec![]), 2);
+		assert_eq!(
+			value("payments_processed", vec![("processor", "default")]),
+			1
+		);
+		assert_eq!(
+			value("payments_processed", vec![("processor", "fallback")]),
+			1
+		);
+		assert_eq!(value("payments_requeued", vec![]), 1);
+		assert_eq!(value("payments_failed", vec![]), 0);
+	}
+
+	#[test]
+	fn test_snapshot_reports_last_queue_push_latency() {
+		let metrics = Metrics::default();
+
+		metrics.record_queue_push(Duration::from_micros(900));
+		metrics.record_queue_push(Duration::from_micros(350));
+
+		let latency = metrics
+			.snapshot()
+			.into_iter()
+			.find(|sample| sample.name == "queue_push_latency_us")
+			.unwrap();
+		assert_eq!(latency.kind, MetricKind::Gauge);
+		assert_eq!(latency.value, 350);
+	}
+
+	#[test]
+	fn test_latency_histogram_percentiles() {
+		let histogram = LatencyHistogram::default();
+		assert_eq!(histogram.percentile(0.99), None);
+
+		for _ in 0..98 {
+			histogram.record(Duration::from_micros(100));
+		}
+		histogram.record(Duration::from_micros(3000));
+		histogram.record(Duration::from_secs(1));
+
+		assert_eq!(histogram.percentile(0.5), Some(Duration::from_micros(127)));
+		assert_eq!(
+			histogram.percentile(0.99),
+			Some(Duration::from_micros(4095))
+		);
+		assert_eq!(
+			histogram.percentile(1.0),
+			Some(Duration::from_micros(1_048_575))
+		);
+	}
+
+	#[test]
+	fn test_snapshot_counts_breaker_transitions_by_state() {
+		let metrics = Metrics::default();
+
+		metrics.record_breaker_transition("default", "open");
+		metrics.record_breaker_transition("default", "half-open");
+		metrics.record_breaker_transition("default", "open");
+
+		let snapshot = metrics.snapshot();
+		let value = |state| {
+			snapshot
+				.iter()
+				.find(|sample| {
+					sample.name == "breaker_transitions" &&
+						sample.tags ==
+							vec![("processor", "default"), ("state", state)]
+				})
+				.map(|sample| sample.value)
+		};
+
+		assert_eq!(value("open"), Some(2));
+		assert_eq!(value("half_open"), Some(1));
+		assert_eq!(value("closed"), None);
+	}
+
+	#[test]
+	fn test_snapshot_tags_worker_samples_with_worker_id() {
+		let metrics = Metrics::default();
+
+		let first = metrics.register_worker("payment");
+		let second = metrics.register_worker("payment");
+		first.record_processed();
+		first.record_processed();
+		second.record_requeued();
+		second.record_loop(Duration::from_millis(3));
+
+		let snapshot = metrics.snapshot();
+		let value = |name, worker| {
+			snapshot
+				.iter()
+				.find(|sample| {
+					sample.name == name && sample.tags == vec![("worker", worker)]
+				})
+				.map(|sample| sample.value)
+				.unwrap()
+		};
+
+		assert_eq!(value("worker_processed", "payment-0"), 2);
+		assert_eq!(value("worker_processed", "payment-1"), 0);
+		assert_eq!(value("worker_requeued", "payment-1"), 1);
+		assert_eq!(value("worker_loop_latency_us", "payment-1"), 3000);
+	}
+
+	#[test]
+	fn test_queue_depth_tracks_pushes_and_pops() {
+		let metrics = Metrics::default();
+
+		metrics.record_enqueued();
+		metrics.record_enqueued();
+		metrics.record_dequeued();
+
+		assert_eq!(metrics.queue_depth(), 1);
+
+		metrics.record_dequeued();
+		metrics.record_dequeued();
+
+		assert_eq!(metrics.queue_depth(), 0);
+
+		metrics.set_queue_depth(42);
+		metrics.set_queue_lag(Duration::from_millis(1500));
+
+		assert_eq!(metrics.queue_depth(), 42);
+		assert_eq!(metrics.queue_lag(), Duration::from_millis(1500));
+		assert!(metrics.snapshot().contains(&MetricSample {
+			name:  "payments_queue_lag_ms",
+			tags:  vec![],
+			kind:  MetricKind::Gauge,
+			value: 1500,
+		}));
+	}
+
+	#[test]
+	fn test_estimates_processing_delay_from_sampled_throughput() {
+		let metrics = Metrics::default();
+		let start = Instant::now();
+
+		metrics.sample_throughput(start);
+		assert_eq!(metrics.estimated_processing_delay(), Some(Duration::ZERO));
+
+		metrics.set_queue_depth(20);
+		assert_eq!(metrics.estimated_processing_delay(), None);
+
+		for _ in 0..10 {
+			metrics.record_processed("default");
+		}
+		metrics.sample_throughput(start + Duration::from_secs(2));
+
+		assert_eq!(metrics.throughput(), 5.0);
+		assert_eq!(
+			metrics.estimated_processing_delay(),
+			Some(Duration::from_secs(4))
+		);
+	}
+}
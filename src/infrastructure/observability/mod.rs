@@ -0,0 +1,9 @@
+pub mod benchmark_report;
+pub mod breaker_events;
+pub mod error_reporting;
+#[cfg(feature = "perf")]
+pub mod flamegraph;
+pub mod log_redaction;
+pub mod metrics;
+pub mod prometheus_exporter;
+pub mod statsd_exporter;
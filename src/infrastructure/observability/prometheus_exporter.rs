@@ -0,0 +1,87 @@
+use std::fmt::Write;
+
+use crate::infrastructure::observability::metrics::{MetricKind, MetricSample};
+
+/// Prefix of every metric name, as Prometheus metrics share one namespace.
+const METRIC_PREFIX: &str = "rinha";
+
+/// Renders `samples` in the Prometheus text exposition format. Samples
+/// sharing a name are grouped under a single `# TYPE` line, with their tags
+/// as labels.
+pub fn render(samples: &[MetricSample]) -> String {
+	let mut names: Vec<&str> = Vec::new();
+	for sample in samples {
+		if !names.contains(&sample.name) {
+			names.push(sample.name);
+		}
+	}
+
+	let mut output = String::new();
+	for name in names {
+		let mut family = samples.iter().filter(|sample| sample.name == name);
+		let kind = match family.clone().next().map(|sample| sample.kind) {
+			Some(MetricKind::Counter) => "counter",
+			_ => "gauge",
+		};
+
+		let _ = writeln!(output, "# TYPE {METRIC_PREFIX}_{name} {kind}");
+		for sample in family.by_ref() {
+			let _ = write!(output, "{METRIC_PREFIX}_{name}");
+			if !sample.tags.is_empty() {
+				let labels: Vec<String> = sample
+					.tags
+					.iter()
+					.map(|(key, value)| format!("{key}=\"{}\"", escape(value)))
+					.collect();
+				let _ = write!(output, "{{{}}}", labels.join(","));
+			}
+			let _ = writeln!(output, " {}", sample.value);
+		}
+	}
+
+	output
+}
+
+fn escape(value: &str) -> String {
+	value
+		.replace('\\', "\\\\")
+		.replace('"', "\\\"")
+		.replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_render_groups_samples_by_name_with_tags_as_labels() {
+		let samples = vec![
+			MetricSample {
+				name:  "payments_processed",
+				tags:  vec![("processor", "default")],
+				kind:  MetricKind::Counter,
+				value: 3,
+			},
+			MetricSample {
+				name:  "payments_queue_depth",
+				tags:  vec![],
+				kind:  MetricKind::Gauge,
+				value: 7,
+			},
+			MetricSample {
+				name:  "payments_processed",
+				tags:  vec![("processor", "fallback")],
+				kind:  MetricKind::Counter,
+				value: 1,
+			},
+		];
+
+		assert_eq!(
+			render(&samples),
+			"# TYPE rinha_payments_processed \
+			 counter\nrinha_payments_processed{processor=\"default\"} \
+			 3\nrinha_payments_processed{processor=\"fallback\"} 1\n# TYPE \
+			 rinha_payments_queue_depth gauge\nrinha_payments_queue_depth 7\n"
+		);
+	}
+}
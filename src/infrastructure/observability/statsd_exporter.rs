@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use tokio::net::UdpSocket;
+
+use crate::infrastructure::observability::metrics::{MetricKind, MetricSample};
+
+/// Pushes metric snapshots to a StatsD agent over UDP. Counters are sent as
+/// deltas since the previous flush; with `dogstatsd` enabled tags are sent
+/// using the DogStatsD `|#key:value` extension instead of being folded into
+/// the metric name.
+pub struct StatsdExporter {
+	socket:        UdpSocket,
+	prefix:        String,
+	dogstatsd:     bool,
+	last_counters: HashMap<String, u64>,
+}
+
+impl StatsdExporter {
+	pub async fn connect(
+		addr: &str,
+		prefix: String,
+		dogstatsd: bool,
+	) -> std::io::Result<Self> {
+		let socket = UdpSocket::bind("0.0.0.0:0").await?;
+		socket.connect(addr).await?;
+
+		Ok(Self {
+			socket,
+			prefix,
+			dogstatsd,
+			last_counters: HashMap::new(),
+		})
+	}
+
+	pub async fn flush(&mut self, samples: &[MetricSample]) -> std::io::Result<()> {
+		let lines = format_samples(
+			&self.prefix,
+			self.dogstatsd,
+			samples,
+			&mut self.last_counters,
+		);
+
+		if lines.is_empty() {
+			return Ok(());
+		}
+
+		self.socket.send(lines.join("\n").as_bytes()).await?;
+		Ok(())
+	}
+}
+
+fn format_samples(
+	prefix: &str,
+	dogstatsd: bool,
+	samples: &[MetricSample],
+	last_counters: &mut HashMap<String, u64>,
+) -> Vec<String> {
+	let mut lines = Vec::with_capacity(samples.len());
+
+	for sample in samples {
+		let mut name = format!("{prefix}.{}", sample.name);
+		let mut suffix = String::new();
+
+		if dogstatsd {
+			if !sample.tags.is_empty() {
+				let tags: Vec<String> = sample
+					.tags
+					.iter()
+					.map(|(k, v)| format!("{k}:{v}"))
+					.collect();
+				suffix = format!("|#{}", tags.join(","));
+			}
+		} else {
+			for (_, value) in &sample.tags {
+				name.push('.');
+				name.push_str(value);
+			}
+		}
+
+		let line = match sample.kind {
+			MetricKind::Counter => {
+				let key = format!("{name}{suffix}");
+				let previous = last_counters.insert(key, sample.value).unwrap_or(0);
+				let delta = sample.value.saturating_sub(previous);
+				if delta == 0 {
+					continue;
+				}
+				format!("{name}:{delta}|c{suffix}")
+			}
+			MetricKind::Gauge => format!("{name}:{}|g{suffix}", sample.value),
+		};
+
+		lines.push(line);
+	}
+
+	lines
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn samples(value: u64) -> Vec<MetricSample> {
+		vec![
+			MetricSample {
+				name: "payments_processed",
+				tags: vec![("processor", "default")],
+				kind: MetricKind::Counter,
+				value,
+			},
+			MetricSample {
+				name: "queue_depth",
+				tags: vec![],
+				kind: MetricKind::Gauge,
+				value,
+			},
+		]
+	}
+
+	#[test]
+	fn test_format_plain_statsd_folds_tags_into_name() {
+		let lines = format_samples("rinha", false, &samples(3), &mut HashMap::new());
+
+		assert_eq!(lines, vec![
+			"rinha.payments_processed.default:3|c",
+			"rinha.queue_depth:3|g",
+		]);
+	}
+
+	#[test]
+	fn test_format_dogstatsd_uses_tags() {
+		let lines = format_samples("rinha", true, &samples(3), &mut HashMap::new());
+
+		assert_eq!(lines, vec![
+			"rinha.payments_processed:3|c|#processor:default",
+			"rinha.queue_depth:3|g",
+		]);
+	}
+
+	#[test]
+	fn test_counters_are_sent_as_deltas() {
+		let mut last_counters = HashMap::new();
+
+		format_samples("rinha", false, &samples(3), &mut last_counters);
+		let lines = format_samples("rinha", false, &samples(5), &mut last_counters);
+
+		assert_eq!(lines, vec![
+			"rinha.payments_processed.default:2|c",
+			"rinha.queue_depth:5|g",
+		]);
+
+		let lines = format_samples("rinha", false, &samples(5), &mut last_counters);
+
+		assert_eq!(lines, vec!["rinha.queue_depth:5|g"]);
+	}
+}
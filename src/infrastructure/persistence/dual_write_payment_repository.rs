@@ -0,0 +1,329 @@
+use log::warn;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::domain::payment::Payment;
+use crate::domain::repository::{
+	DuplicateStage, FailureStream, PaymentFailure, PaymentRepository, PaymentStream,
+	RecordedFailure, SummaryBucket, SummaryBucketStream,
+};
+use crate::infrastructure::observability::log_redaction;
+
+/// Largest difference between two summary amounts still considered equal.
+const AMOUNT_TOLERANCE: f64 = 0.005;
+
+/// Writes payments to a primary and, optionally, a secondary repository while
+/// serving every read from the primary, so a new storage backend or key
+/// layout can be filled before switching over to it. Failures of the
+/// secondary are logged and never fail the operation, and summaries that
+/// differ between both repositories are logged as mismatches. Without a
+/// secondary it only forwards to the primary.
+#[derive(Clone)]
+pub struct DualWritePaymentRepository<P, S> {
+	primary:   P,
+	secondary: Option<S>,
+}
+
+impl<P: PaymentRepository, S: PaymentRepository> DualWritePaymentRepository<P, S> {
+	pub fn new(primary: P) -> Self {
+		Self {
+			primary,
+			secondary: None,
+		}
+	}
+
+	pub fn with_secondary(mut self, secondary: S) -> Self {
+		self.secondary = Some(secondary);
+		self
+	}
+
+	fn log_secondary_failure<T>(
+		operation: &str,
+		result: Result<T, Box<dyn std::error::Error + Send>>,
+	) {
+		if let Err(e) = result {
+			warn!("Secondary payment repository failed to {operation}: {e}");
+		}
+	}
+}
+
+impl<P: PaymentRepository, S: PaymentRepository> PaymentRepository
+	for DualWritePaymentRepository<P, S>
+{
+	async fn save(
+		&self,
+		payment: Payment,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		let Some(secondary) = &self.secondary else {
+			return self.primary.save(payment).await;
+		};
+
+		let correlation_id = log_redaction::correlation_id(payment.correlation_id);
+		let (primary, secondary) = tokio::join!(
+			self.primary.save(payment.clone()),
+			secondary.save(payment)
+		);
+		if let Err(e) = secondary {
+			warn!(
+				"Secondary payment repository failed to save payment \
+				 {correlation_id}: {e}"
+			);
+		}
+		primary
+	}
+
+	async fn get_summary_by_group(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> Result<(usize, f64), Box<dyn std::error::Error + Send>> {
+		let Some(secondary) = &self.secondary else {
+			return self
+				.primary
+				.get_summary_by_group(group, from_ts, to_ts)
+				.await;
+		};
+
+		let (primary, secondary) = tokio::join!(
+			self.primary.get_summary_by_group(group, from_ts, to_ts),
+			secondary.get_summary_by_group(group, from_ts, to_ts)
+		);
+		match (&primary, secondary) {
+			(Ok(primary), Ok(secondary))
+				if primary.0 != secondary.0 ||
+					(primary.1 - secondary.1).abs() > AMOUNT_TOLERANCE =>
+			{
+				warn!(
+					"Payment repositories disagree on the {group} summary from \
+					 {from_ts} to {to_ts}: primary {} payments of {}, secondary {} \
+					 payments of {}",
+					primary.0,
+					log_redaction::amount(primary.1),
+					secondary.0,
+					log_redaction::amount(secondary.1)
+				);
+			}
+			(_, Err(e)) => {
+				warn!(
+					"Secondary payment repository failed to summarize {group}: {e}"
+				)
+			}
+			_ => {}
+		}
+		primary
+	}
+
+	async fn get_summary_with_ids_before(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+		before: OffsetDateTime,
+	) -> Result<(usize, f64, Vec<Uuid>), Box<dyn std::error::Error + Send>> {
+		self.primary
+			.get_summary_with_ids_before(group, from_ts, to_ts, before)
+			.await
+	}
+
+	fn get_payments_stream(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> PaymentStream {
+		self.primary.get_payments_stream(group, from_ts, to_ts)
+	}
+
+	async fn get_payment_summary(
+		&self,
+		group: &str,
+		payment_id: &str,
+	) -> Result<Payment, Box<dyn std::error::Error + Send>> {
+		self.primary.get_payment_summary(group, payment_id).await
+	}
+
+	async fn is_already_processed(
+		&self,
+		payment_id: &str,
+	) -> Result<bool, Box<dyn std::error::Error + Send>> {
+		self.primary.is_already_processed(payment_id).await
+	}
+
+	async fn record_failure(
+		&self,
+		payment: &Payment,
+		group: &str,
+		failure: PaymentFailure,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		let Some(secondary) = &self.secondary else {
+			return self.primary.record_failure(payment, group, failure).await;
+		};
+
+		let (primary, secondary) = tokio::join!(
+			self.primary.record_failure(payment, group, failure),
+			secondary.record_failure(payment, group, failure)
+		);
+		Self::log_secondary_failure("record a payment failure", secondary);
+		primary
+	}
+
+	async fn get_failures_by_group(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> Result<(usize, usize), Box<dyn std::error::Error + Send>> {
+		self.primary
+			.get_failures_by_group(group, from_ts, to_ts)
+			.await
+	}
+
+	fn get_failures_stream(
+		&self,
+		group: &str,
+		failure: PaymentFailure,
+	) -> FailureStream {
+		self.primary.get_failures_stream(group, failure)
+	}
+
+	async fn restore_failure(
+		&self,
+		failure: &RecordedFailure,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		let Some(secondary) = &self.secondary else {
+			return self.primary.restore_failure(failure).await;
+		};
+
+		let (primary, secondary) = tokio::join!(
+			self.primary.restore_failure(failure),
+			secondary.restore_failure(failure)
+		);
+		Self::log_secondary_failure("restore a payment failure", secondary);
+		primary
+	}
+
+	async fn record_duplicate(
+		&self,
+		payment_id: &str,
+		stage: DuplicateStage,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		let Some(secondary) = &self.secondary else {
+			return self.primary.record_duplicate(payment_id, stage).await;
+		};
+
+		let (primary, secondary) = tokio::join!(
+			self.primary.record_duplicate(payment_id, stage),
+			secondary.record_duplicate(payment_id, stage)
+		);
+		Self::log_secondary_failure("record a duplicate payment", secondary);
+		primary
+	}
+
+	async fn get_duplicates(
+		&self,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> Result<(usize, usize), Box<dyn std::error::Error + Send>> {
+		self.primary.get_duplicates(from_ts, to_ts).await
+	}
+
+	async fn get_amount_buckets_by_group(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> Result<Vec<usize>, Box<dyn std::error::Error + Send>> {
+		self.primary
+			.get_amount_buckets_by_group(group, from_ts, to_ts)
+			.await
+	}
+
+	async fn get_latencies_by_group(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> Result<Vec<u64>, Box<dyn std::error::Error + Send>> {
+		self.primary
+			.get_latencies_by_group(group, from_ts, to_ts)
+			.await
+	}
+
+	async fn delete(
+		&self,
+		payments: &[Payment],
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		let Some(secondary) = &self.secondary else {
+			return self.primary.delete(payments).await;
+		};
+
+		let (primary, secondary) =
+			tokio::join!(self.primary.delete(payments), secondary.delete(payments));
+		Self::log_secondary_failure("delete payments", secondary);
+		primary
+	}
+
+	async fn remove_archived(
+		&self,
+		payments: &[Payment],
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		let Some(secondary) = &self.secondary else {
+			return self.primary.remove_archived(payments).await;
+		};
+
+		let (primary, secondary) = tokio::join!(
+			self.primary.remove_archived(payments),
+			secondary.remove_archived(payments)
+		);
+		Self::log_secondary_failure("remove archived payments", secondary);
+		primary
+	}
+
+	fn get_summary_buckets_stream(&self, group: &str) -> SummaryBucketStream {
+		self.primary.get_summary_buckets_stream(group)
+	}
+
+	async fn restore_summary_bucket(
+		&self,
+		bucket: &SummaryBucket,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		let Some(secondary) = &self.secondary else {
+			return self.primary.restore_summary_bucket(bucket).await;
+		};
+
+		let (primary, secondary) = tokio::join!(
+			self.primary.restore_summary_bucket(bucket),
+			secondary.restore_summary_bucket(bucket)
+		);
+		Self::log_secondary_failure("restore a summary bucket", secondary);
+		primary
+	}
+
+	async fn trim_older_than(
+		&self,
+		cutoff: OffsetDateTime,
+	) -> Result<usize, Box<dyn std::error::Error + Send>> {
+		let Some(secondary) = &self.secondary else {
+			return self.primary.trim_older_than(cutoff).await;
+		};
+
+		let (primary, secondary) = tokio::join!(
+			self.primary.trim_older_than(cutoff),
+			secondary.trim_older_than(cutoff)
+		);
+		Self::log_secondary_failure("trim payments", secondary);
+		primary
+	}
+
+	async fn clear(&self) -> Result<(), Box<dyn std::error::Error + Send>> {
+		let Some(secondary) = &self.secondary else {
+			return self.primary.clear().await;
+		};
+
+		let (primary, secondary) =
+			tokio::join!(self.primary.clear(), secondary.clear());
+		Self::log_secondary_failure("clear payments", secondary);
+		primary
+	}
+}
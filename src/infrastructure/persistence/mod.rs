@@ -1 +1,11 @@
+pub mod dual_write_payment_repository;
+#[cfg(feature = "postgres")]
+pub mod postgres_payment_archive;
+pub mod redis_clock;
+pub mod redis_connection;
+pub mod redis_key_space;
+pub mod redis_legacy_payment_store;
 pub mod redis_payment_repository;
+pub mod redis_run_epoch;
+pub mod summary_cache;
+pub mod timestamp_codec;
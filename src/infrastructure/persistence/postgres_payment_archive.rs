@@ -0,0 +1,128 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use log::error;
+use time::OffsetDateTime;
+use tokio_postgres::{Client, NoTls};
+use uuid::Uuid;
+
+use crate::domain::payment::Payment;
+use crate::domain::payment_archive::PaymentArchive;
+
+const CREATE_TABLE_SQL: &str = r#"
+    CREATE TABLE IF NOT EXISTS archived_payments (
+        correlation_id UUID PRIMARY KEY,
+        processed_by   TEXT NOT NULL,
+        amount         DOUBLE PRECISION NOT NULL,
+        requested_at   TIMESTAMPTZ NOT NULL,
+        processed_at   TIMESTAMPTZ
+    );
+    CREATE INDEX IF NOT EXISTS archived_payments_group_requested_at_idx
+        ON archived_payments (processed_by, requested_at);
+"#;
+
+#[derive(Clone)]
+pub struct PostgresPaymentArchive {
+	client: Arc<Client>,
+}
+
+impl PostgresPaymentArchive {
+	pub async fn connect(
+		database_url: &str,
+	) -> Result<Self, Box<dyn std::error::Error + Send>> {
+		let (client, connection) = tokio_postgres::connect(database_url, NoTls)
+			.await
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+		tokio::spawn(async move {
+			if let Err(e) = connection.await {
+				error!("Payment archive connection error: {e}");
+			}
+		});
+
+		client
+			.batch_execute(CREATE_TABLE_SQL)
+			.await
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+		Ok(Self {
+			client: Arc::new(client),
+		})
+	}
+}
+
+#[async_trait]
+impl PaymentArchive for PostgresPaymentArchive {
+	async fn archive(
+		&self,
+		payments: &[Payment],
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		if payments.is_empty() {
+			return Ok(());
+		}
+
+		let correlation_ids: Vec<Uuid> =
+			payments.iter().map(|p| p.correlation_id).collect();
+		let groups: Vec<String> = payments
+			.iter()
+			.map(|p| p.processed_by.clone().unwrap_or_default())
+			.collect();
+		let amounts: Vec<f64> = payments.iter().map(|p| p.amount).collect();
+		let requested_at: Vec<OffsetDateTime> = payments
+			.iter()
+			.map(|p| p.requested_at.unwrap_or(OffsetDateTime::UNIX_EPOCH))
+			.collect();
+		let processed_at: Vec<Option<OffsetDateTime>> =
+			payments.iter().map(|p| p.processed_at).collect();
+
+		self.client
+			.execute(
+				"INSERT INTO archived_payments (correlation_id, processed_by, \
+				 amount, requested_at, processed_at) SELECT * FROM \
+				 UNNEST($1::uuid[], $2::text[], $3::float8[], $4::timestamptz[], \
+				 $5::timestamptz[]) ON CONFLICT (correlation_id) DO NOTHING",
+				&[
+					&correlation_ids,
+					&groups,
+					&amounts,
+					&requested_at,
+					&processed_at,
+				],
+			)
+			.await
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+		Ok(())
+	}
+
+	async fn get_summary_by_group(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+		excluded: &[Uuid],
+	) -> Result<(usize, f64), Box<dyn std::error::Error + Send>> {
+		let row = self
+			.client
+			.query_one(
+				"SELECT COUNT(*), COALESCE(SUM(amount), 0) FROM archived_payments \
+				 WHERE processed_by = $1 AND requested_at BETWEEN $2 AND $3 AND \
+				 correlation_id <> ALL($4::uuid[])",
+				&[&group, &from_ts, &to_ts, &excluded],
+			)
+			.await
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+		let total_requests: i64 = row.get(0);
+		let total_amount: f64 = row.get(1);
+
+		Ok((total_requests as usize, total_amount))
+	}
+
+	async fn clear(&self) -> Result<(), Box<dyn std::error::Error + Send>> {
+		self.client
+			.batch_execute("TRUNCATE archived_payments")
+			.await
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)
+	}
+}
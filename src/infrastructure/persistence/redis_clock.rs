@@ -0,0 +1,151 @@
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use redis::{Client, RedisResult};
+use time::{Duration, OffsetDateTime};
+
+use crate::domain::clock::Clock;
+
+/// TIME round trips per sync. The one with the shortest round trip is kept,
+/// as it bounds the error of the offset best.
+const SAMPLES_PER_SYNC: usize = 3;
+/// Largest drift rate applied between syncs. Quartz clocks drift far less,
+/// so faster drift is noise from a slow round trip.
+const MAX_DRIFT: f64 = 500e-6;
+
+/// Uses the Redis server clock, shared by every instance, as the
+/// authoritative time. The offset to the system clock is measured by
+/// [`RedisClock::sync`], and how fast it drifts is extrapolated between
+/// syncs. Until the first sync it reads the system clock.
+#[derive(Clone)]
+pub struct RedisClock {
+	client: Client,
+	state:  Arc<Mutex<ClockState>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ClockState {
+	synced_at: Option<Instant>,
+	offset:    Duration,
+	/// Offset gained per unit of elapsed time.
+	drift:     f64,
+}
+
+impl ClockState {
+	fn offset_at(&self, now: Instant) -> Duration {
+		match self.synced_at {
+			Some(synced_at) => {
+				let elapsed = now.saturating_duration_since(synced_at);
+				self.offset +
+					Duration::seconds_f64(elapsed.as_secs_f64() * self.drift)
+			}
+			None => self.offset,
+		}
+	}
+
+	fn record(&mut self, offset: Duration, now: Instant) {
+		if let Some(synced_at) = self.synced_at {
+			let elapsed = now.saturating_duration_since(synced_at).as_secs_f64();
+			if elapsed > 0.0 {
+				self.drift = ((offset - self.offset).as_seconds_f64() / elapsed)
+					.clamp(-MAX_DRIFT, MAX_DRIFT);
+			}
+		}
+		self.synced_at = Some(now);
+		self.offset = offset;
+	}
+}
+
+impl RedisClock {
+	pub fn new(client: Client) -> Self {
+		Self {
+			client,
+			state: Arc::new(Mutex::new(ClockState {
+				synced_at: None,
+				offset:    Duration::ZERO,
+				drift:     0.0,
+			})),
+		}
+	}
+
+	/// Measures the offset between the Redis and system clocks again,
+	/// returning it.
+	pub async fn sync(&self) -> RedisResult<Duration> {
+		let mut con = self.client.get_multiplexed_async_connection().await?;
+
+		let mut best: Option<(Duration, Duration)> = None;
+		for _ in 0..SAMPLES_PER_SYNC {
+			let sent_at = OffsetDateTime::now_utc();
+			let (seconds, micros): (i64, i64) =
+				redis::cmd("TIME").query_async(&mut con).await?;
+			let received_at = OffsetDateTime::now_utc();
+
+			let round_trip = received_at - sent_at;
+			let server_time = OffsetDateTime::UNIX_EPOCH +
+				Duration::seconds(seconds) +
+				Duration::microseconds(micros);
+			let offset = server_time - (sent_at + round_trip / 2);
+
+			if best.is_none_or(|(best_round_trip, _)| round_trip < best_round_trip) {
+				best = Some((round_trip, offset));
+			}
+		}
+
+		let (_, offset) = best.expect("at least one TIME sample is taken");
+		self.state.lock().unwrap().record(offset, Instant::now());
+		Ok(offset)
+	}
+}
+
+impl Clock for RedisClock {
+	fn now(&self) -> OffsetDateTime {
+		let offset = self.state.lock().unwrap().offset_at(Instant::now());
+		OffsetDateTime::now_utc() + offset
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_offset_is_extrapolated_with_the_measured_drift() {
+		let start = Instant::now();
+		let mut state = ClockState {
+			synced_at: None,
+			offset:    Duration::ZERO,
+			drift:     0.0,
+		};
+
+		state.record(Duration::milliseconds(10), start);
+		assert_eq!(state.offset_at(start), Duration::milliseconds(10));
+
+		// 100µs gained over 10s is a 10ppm drift.
+		let second_sync = start + std::time::Duration::from_secs(10);
+		state.record(Duration::microseconds(10_100), second_sync);
+		let later = second_sync + std::time::Duration::from_secs(10);
+		let offset = state.offset_at(later);
+		assert!(
+			(offset - Duration::microseconds(10_200)).abs() <
+				Duration::microseconds(1)
+		);
+	}
+
+	#[test]
+	fn test_implausible_drift_is_clamped() {
+		let start = Instant::now();
+		let mut state = ClockState {
+			synced_at: None,
+			offset:    Duration::ZERO,
+			drift:     0.0,
+		};
+
+		state.record(Duration::ZERO, start);
+		state.record(
+			Duration::seconds(1),
+			start + std::time::Duration::from_secs(1),
+		);
+
+		assert_eq!(state.drift, MAX_DRIFT);
+	}
+}
@@ -0,0 +1,73 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use redis::aio::MultiplexedConnection;
+use redis::{Client, RedisResult};
+use tokio::sync::Mutex;
+
+/// Outcome of a [`SupervisedConnection::check`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConnectionHealth {
+	Healthy,
+	/// The connection was broken and a new one was established.
+	Rebuilt,
+	/// The connection was broken and Redis could not be reached again.
+	Broken,
+}
+
+/// A multiplexed Redis connection shared by every operation that does not
+/// block, instead of connecting once per operation. A connection left stale
+/// by a Redis restart only fails operations until the next
+/// [`SupervisedConnection::check`] rebuilds it.
+#[derive(Clone)]
+pub struct SupervisedConnection {
+	client:     Client,
+	connection: Arc<Mutex<Option<MultiplexedConnection>>>,
+}
+
+impl SupervisedConnection {
+	pub fn new(client: Client) -> Self {
+		Self {
+			client,
+			connection: Arc::new(Mutex::new(None)),
+		}
+	}
+
+	/// Returns the shared connection, connecting first if there is none.
+	pub async fn get(&self) -> RedisResult<MultiplexedConnection> {
+		let mut connection = self.connection.lock().await;
+		if let Some(connection) = connection.as_ref() {
+			return Ok(connection.clone());
+		}
+
+		let established = self.client.get_multiplexed_async_connection().await?;
+		*connection = Some(established.clone());
+		Ok(established)
+	}
+
+	/// Drops the shared connection after an operation failed on it, so the
+	/// next [`SupervisedConnection::get`] connects again.
+	pub async fn discard(&self) {
+		self.connection.lock().await.take();
+	}
+
+	/// PINGs Redis over the shared connection, replacing the connection when
+	/// the PING fails or takes longer than `timeout`.
+	pub async fn check(&self, timeout: Duration) -> ConnectionHealth {
+		if let Ok(mut connection) = self.get().await &&
+			let Ok(Ok(_)) = tokio::time::timeout(
+				timeout,
+				redis::cmd("PING").query_async::<String>(&mut connection),
+			)
+			.await
+		{
+			return ConnectionHealth::Healthy;
+		}
+
+		self.discard().await;
+		match self.get().await {
+			Ok(_) => ConnectionHealth::Rebuilt,
+			Err(_) => ConnectionHealth::Broken,
+		}
+	}
+}
@@ -0,0 +1,57 @@
+use redis::Client;
+
+use crate::infrastructure::config::redis::{
+	PAYMENTS_INGEST_STREAM_KEY, PAYMENTS_QUEUE_KEY, PROCESSED_PAYMENTS_SET_KEY,
+	QUARANTINED_MESSAGES_KEY_PREFIX,
+};
+
+/// Sizes of the Redis keys that grow with traffic, for spotting unbounded
+/// growth before Redis runs out of memory.
+#[derive(Clone)]
+pub struct RedisKeySpace {
+	client: Client,
+}
+
+impl RedisKeySpace {
+	pub fn new(client: Client) -> Self {
+		Self { client }
+	}
+
+	/// Returns the number of keys in the database, followed by the number of
+	/// entries of each tracked key.
+	pub async fn sizes(
+		&self,
+	) -> Result<Vec<(&'static str, u64)>, Box<dyn std::error::Error + Send>> {
+		let mut con = self
+			.client
+			.get_multiplexed_async_connection()
+			.await
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+		let (keys, processed, queued, quarantined, ingest): (
+			u64,
+			u64,
+			u64,
+			u64,
+			u64,
+		) = redis::pipe()
+			.cmd("DBSIZE")
+			.zcard(PROCESSED_PAYMENTS_SET_KEY)
+			.llen(PAYMENTS_QUEUE_KEY)
+			.llen(format!(
+				"{QUARANTINED_MESSAGES_KEY_PREFIX}:{PAYMENTS_QUEUE_KEY}"
+			))
+			.xlen(PAYMENTS_INGEST_STREAM_KEY)
+			.query_async(&mut con)
+			.await
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+		Ok(vec![
+			("keys", keys),
+			(PROCESSED_PAYMENTS_SET_KEY, processed),
+			(PAYMENTS_QUEUE_KEY, queued),
+			(QUARANTINED_MESSAGES_KEY_PREFIX, quarantined),
+			(PAYMENTS_INGEST_STREAM_KEY, ingest),
+		])
+	}
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use log::warn;
+use redis::{AsyncCommands, Client};
+
+use crate::domain::legacy_payment_store::{LegacyPaymentStore, LegacyPayments};
+use crate::infrastructure::config::redis::{
+	LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY, LEGACY_FALLBACK_PAYMENT_SUMMARY_KEY,
+	LEGACY_PROCESSED_CORRELATION_IDS_KEY, LEGACY_TOTAL_AMOUNT_FIELD,
+	LEGACY_TOTAL_REQUESTS_FIELD,
+};
+
+/// Reads the layout written by the legacy workers: one
+/// `payments_summary_{group}` hash per processor, mapping each correlation id
+/// to the JSON-serialized payment, plus the `processed_correlation_ids` set
+/// used for deduplication. Aggregate fields written for compatibility, see
+/// [`RedisPaymentRepository::with_legacy_summary`], are not payments.
+///
+/// [`RedisPaymentRepository::with_legacy_summary`]: crate::infrastructure::persistence::redis_payment_repository::RedisPaymentRepository::with_legacy_summary
+#[derive(Clone)]
+pub struct RedisLegacyPaymentStore {
+	client:         Client,
+	legacy_summary: bool,
+}
+
+impl RedisLegacyPaymentStore {
+	pub fn new(client: Client) -> Self {
+		Self {
+			client,
+			legacy_summary: false,
+		}
+	}
+
+	/// Keeps the aggregate fields of the summary hashes when the legacy
+	/// layout is removed, as the repository keeps them up to date, see
+	/// [`RedisPaymentRepository::with_legacy_summary`].
+	///
+	/// [`RedisPaymentRepository::with_legacy_summary`]: crate::infrastructure::persistence::redis_payment_repository::RedisPaymentRepository::with_legacy_summary
+	pub fn with_legacy_summary(mut self) -> Self {
+		self.legacy_summary = true;
+		self
+	}
+
+	fn is_aggregate_field(field: &str) -> bool {
+		field == LEGACY_TOTAL_REQUESTS_FIELD || field == LEGACY_TOTAL_AMOUNT_FIELD
+	}
+
+	pub fn summary_key(group: &str) -> Option<&'static str> {
+		match group {
+			"default" => Some(LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY),
+			"fallback" => Some(LEGACY_FALLBACK_PAYMENT_SUMMARY_KEY),
+			_ => None,
+		}
+	}
+}
+
+impl LegacyPaymentStore for RedisLegacyPaymentStore {
+	async fn load_payments(
+		&self,
+		group: &str,
+	) -> Result<LegacyPayments, Box<dyn std::error::Error + Send>> {
+		let Some(key) = Self::summary_key(group) else {
+			return Ok(LegacyPayments::default());
+		};
+
+		let mut con = self
+			.client
+			.get_multiplexed_async_connection()
+			.await
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+		let entries: HashMap<String, String> = con
+			.hgetall(key)
+			.await
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+		let mut loaded = LegacyPayments::default();
+		for (id, json) in entries
+			.into_iter()
+			.filter(|(id, _)| !Self::is_aggregate_field(id))
+		{
+			match serde_json::from_str(&json) {
+				Ok(payment) => loaded.payments.push(payment),
+				Err(e) => {
+					warn!("Unreadable legacy payment '{id}': {e}");
+					loaded.unreadable += 1;
+				}
+			}
+		}
+
+		Ok(loaded)
+	}
+
+	async fn remove(&self) -> Result<(), Box<dyn std::error::Error + Send>> {
+		let mut con = self
+			.client
+			.get_multiplexed_async_connection()
+			.await
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+		if !self.legacy_summary {
+			let _: () = con
+				.del(&[
+					LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY,
+					LEGACY_FALLBACK_PAYMENT_SUMMARY_KEY,
+					LEGACY_PROCESSED_CORRELATION_IDS_KEY,
+				])
+				.await
+				.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+			return Ok(());
+		}
+
+		let mut pipe = redis::pipe();
+		pipe.atomic();
+		for key in [
+			LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY,
+			LEGACY_FALLBACK_PAYMENT_SUMMARY_KEY,
+		] {
+			let fields: Vec<String> = con
+				.hkeys(key)
+				.await
+				.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+			let payment_fields: Vec<String> = fields
+				.into_iter()
+				.filter(|field| !Self::is_aggregate_field(field))
+				.collect();
+			if !payment_fields.is_empty() {
+				pipe.hdel(key, payment_fields).ignore();
+			}
+		}
+		pipe.del(LEGACY_PROCESSED_CORRELATION_IDS_KEY).ignore();
+
+		let _: () = pipe
+			.query_async(&mut con)
+			.await
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+		Ok(())
+	}
+}
@@ -1,110 +1,939 @@
-use async_trait::async_trait;
-use redis::{AsyncCommands, Client, Script};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::{StreamExt, TryStreamExt, stream};
+use log::{error, info};
+use redis::aio::MultiplexedConnection;
+use redis::{AsyncCommands, Client, RedisError, Script};
 use time::OffsetDateTime;
 use time::format_description::well_known::Rfc3339;
+use uuid::Uuid;
 
 use crate::domain::payment::Payment;
-use crate::domain::repository::PaymentRepository;
-use crate::infrastructure::config::redis::PROCESSED_PAYMENTS_SET_KEY;
+use crate::domain::payment_processor::PROCESSOR_GROUPS;
+use crate::domain::repository::{
+	AMOUNT_BUCKET_BOUNDS, DuplicateStage, FailureStream, PaymentFailure,
+	PaymentRepository, PaymentStream, RecordedFailure, RepositoryError,
+	SummaryBucket, SummaryBucketStream,
+};
+use crate::infrastructure::config::redis::{
+	ARCHIVED_PAYMENTS_SET_KEY, IN_FLIGHT_PAYMENTS_KEY, LEGACY_TOTAL_AMOUNT_FIELD,
+	LEGACY_TOTAL_REQUESTS_FIELD, PAYMENT_DUPLICATES_KEY_PREFIX,
+	PAYMENT_FAILURES_KEY_PREFIX, PAYMENT_SUMMARY_BUCKET_KEY_PREFIX,
+	PAYMENT_SUMMARY_BUCKETS_KEY_PREFIX, PROCESSED_PAYMENT_KEY_PREFIX,
+	PROCESSED_PAYMENTS_BLOOM_KEY, PROCESSED_PAYMENTS_SET_KEY,
+	PURGED_IN_FLIGHT_PAYMENTS_KEY, QUEUED_PAYMENT_KEY_PREFIX,
+};
+use crate::infrastructure::config::settings::{Config, DedupMode};
+use crate::infrastructure::observability::log_redaction;
+use crate::infrastructure::persistence::redis_connection::SupervisedConnection;
+use crate::infrastructure::persistence::redis_legacy_payment_store::RedisLegacyPaymentStore;
+use crate::infrastructure::persistence::summary_cache::SummaryCache;
+use crate::infrastructure::persistence::timestamp_codec::{
+	NANOS_PER_SECOND, SECONDS_SCORE_LIMIT, TimestampCodec,
+};
+
+const STREAM_BATCH_SIZE: isize = 500;
+const TRIM_BATCH_SIZE: usize = 1000;
+const SUMMARY_BUCKET: time::Duration = time::Duration::minutes(1);
+/// How long the payments in flight during a purge stay fenced off. Matches
+/// how long an entry of the in-flight registry is trusted.
+const PURGE_FENCE_TTL: Duration = Duration::from_secs(60);
+/// Lua helper returning the zero-based amount bucket of `amount`, given the
+/// comma separated [`AMOUNT_BUCKET_BOUNDS`].
+const AMOUNT_BUCKET_LUA: &str = r#"
+            local function amount_bucket(amount, bounds)
+                local bucket = 0
+                for bound in string.gmatch(bounds, "[^,]+") do
+                    if amount < tonumber(bound) then
+                        return bucket
+                    end
+                    bucket = bucket + 1
+                end
+                return bucket
+            end
+"#;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DedupStrategy {
+	SortedSet,
+	Bloom { capacity: u64, error_rate: f64 },
+	Expiring { ttl: Duration },
+}
+
+impl DedupStrategy {
+	pub fn from_config(config: &Config) -> Self {
+		match config.dedup_mode {
+			DedupMode::SortedSet => DedupStrategy::SortedSet,
+			DedupMode::Bloom => DedupStrategy::Bloom {
+				capacity:   config.dedup_bloom_capacity,
+				error_rate: config.dedup_bloom_error_rate,
+			},
+			DedupMode::Expiring => DedupStrategy::Expiring {
+				ttl: Duration::from_secs(config.dedup_ttl),
+			},
+		}
+	}
+}
 
 #[derive(Clone)]
 pub struct RedisPaymentRepository {
-	client: Client,
+	client:         Client,
+	retention:      Option<Duration>,
+	dedup:          DedupStrategy,
+	summary_cache:  Option<Arc<SummaryCache>>,
+	connection:     Option<SupervisedConnection>,
+	legacy_summary: bool,
 }
 
 impl RedisPaymentRepository {
 	pub fn new(client: Client) -> Self {
-		Self { client }
+		Self {
+			client,
+			retention: None,
+			dedup: DedupStrategy::SortedSet,
+			summary_cache: None,
+			connection: None,
+			legacy_summary: false,
+		}
+	}
+
+	/// Also keeps the aggregate totals of the legacy `payments_summary_{group}`
+	/// hashes up to date as payments are saved and deleted, for dashboards
+	/// that still read them. Trimming and archiving do not touch them: they
+	/// are totals since the last clear.
+	pub fn with_legacy_summary(mut self) -> Self {
+		self.legacy_summary = true;
+		self
+	}
+
+	/// Writes a processed payment, its dedup entry and its share of the legacy
+	/// summary at once, unless a purge fenced it off while it was in flight,
+	/// in which case nothing is written. Returns whether it was fenced off.
+	async fn save_using_lua(
+		&self,
+		con: &mut MultiplexedConnection,
+		payment_id: &str,
+		group: &str,
+		requested_at: i128,
+		amount: f64,
+		fields: &[(&str, String)],
+	) -> redis::RedisResult<bool> {
+		let lua = Script::new(
+			r#"
+            if redis.call("SISMEMBER", KEYS[3], ARGV[1]) == 1 then
+                return 1
+            end
+
+            redis.call("HSET", KEYS[1], "amount", ARGV[3])
+            for i = 11, #ARGV, 2 do
+                redis.call("HSET", KEYS[1], ARGV[i], ARGV[i + 1])
+            end
+            if tonumber(ARGV[4]) > 0 then
+                redis.call("EXPIRE", KEYS[1], ARGV[4])
+            end
+            redis.call("ZADD", KEYS[2], ARGV[2], ARGV[1])
+
+            if ARGV[5] ~= "" then
+                redis.call("SET", ARGV[5], 1, "EX", ARGV[6])
+            end
+            if ARGV[7] ~= "" then
+                redis.call("HINCRBY", ARGV[7], ARGV[8], 1)
+                redis.call("HINCRBYFLOAT", ARGV[7], ARGV[9], ARGV[3])
+            end
+            return 0
+        "#,
+		);
+
+		let (dedup_key, dedup_ttl) = match &self.dedup {
+			DedupStrategy::Expiring { ttl } => (
+				format!("{PROCESSED_PAYMENT_KEY_PREFIX}:{payment_id}"),
+				ttl.as_secs(),
+			),
+			_ => (String::new(), 0),
+		};
+		let legacy_key = RedisLegacyPaymentStore::summary_key(group)
+			.filter(|_| self.legacy_summary)
+			.unwrap_or_default();
+
+		let mut invocation =
+			lua.key(format!("payment_summary:{group}:{payment_id}"));
+		invocation
+			.key(PROCESSED_PAYMENTS_SET_KEY)
+			.key(PURGED_IN_FLIGHT_PAYMENTS_KEY)
+			.arg(payment_id)
+			.arg(requested_at.to_string())
+			.arg(amount.to_string())
+			.arg(
+				self.retention
+					.map_or(0, |retention| 2 * retention.as_secs()),
+			)
+			.arg(dedup_key)
+			.arg(dedup_ttl)
+			.arg(legacy_key)
+			.arg(LEGACY_TOTAL_REQUESTS_FIELD)
+			.arg(LEGACY_TOTAL_AMOUNT_FIELD)
+			// Keeps the optional fields starting at ARGV[11].
+			.arg("");
+		for (field, value) in fields {
+			invocation.arg(*field).arg(value);
+		}
+
+		invocation.invoke_async(con).await
+	}
+
+	/// Removes saved payments, taking each one still in the processed set out
+	/// of the legacy summary as well. Payments moved to the archive stay in
+	/// the legacy summary and, with sorted set dedup, are kept in the archived
+	/// set so they are still known as processed. Returns whether each payment
+	/// was removed, in order.
+	async fn delete_using_lua(
+		&self,
+		con: &mut MultiplexedConnection,
+		payments: &[Payment],
+		archived: bool,
+	) -> redis::RedisResult<Vec<bool>> {
+		let lua = Script::new(
+			r#"
+            local removed = {}
+            for i = 4, #ARGV, 4 do
+                local id = ARGV[i]
+                local legacy_key = ARGV[i + 2]
+                redis.call("DEL", "payment_summary:" .. ARGV[i + 1] .. ":" .. id)
+                -- Only payments still stored count in the legacy totals.
+                local was_stored = redis.call("ZREM", KEYS[1], id) == 1
+                if was_stored and legacy_key ~= "" then
+                    redis.call("HINCRBY", legacy_key, ARGV[1], -1)
+                    redis.call("HINCRBYFLOAT", legacy_key, ARGV[2], -tonumber(ARGV[i + 3]))
+                end
+                if ARGV[3] ~= "" then
+                    redis.call("SADD", ARGV[3], id)
+                end
+                removed[#removed + 1] = was_stored and 1 or 0
+            end
+            return removed
+        "#,
+		);
+
+		let mut invocation = lua.key(PROCESSED_PAYMENTS_SET_KEY);
+		invocation
+			.arg(LEGACY_TOTAL_REQUESTS_FIELD)
+			.arg(LEGACY_TOTAL_AMOUNT_FIELD)
+			.arg(match self.dedup {
+				DedupStrategy::SortedSet if archived => ARCHIVED_PAYMENTS_SET_KEY,
+				_ => "",
+			});
+		for payment in payments {
+			let group = payment.processed_by.as_deref().unwrap_or_default();
+			let legacy_key = RedisLegacyPaymentStore::summary_key(group)
+				.filter(|_| self.legacy_summary && !archived)
+				.unwrap_or_default();
+			invocation
+				.arg(payment.correlation_id.to_string())
+				.arg(group)
+				.arg(legacy_key)
+				.arg(payment.amount);
+		}
+
+		let removed: Vec<u8> = invocation.invoke_async(con).await?;
+		Ok(removed.into_iter().map(|removed| removed == 1).collect())
+	}
+
+	pub fn with_supervised_connection(
+		mut self,
+		connection: SupervisedConnection,
+	) -> Self {
+		self.connection = Some(connection);
+		self
+	}
+
+	async fn connection(&self) -> redis::RedisResult<MultiplexedConnection> {
+		match &self.connection {
+			Some(connection) => connection.get().await,
+			None => self.client.get_multiplexed_async_connection().await,
+		}
+	}
+
+	/// Serves summaries of the payments saved from now on from memory. Payments
+	/// are still written to Redis as they are saved. Only meant for a single
+	/// instance, as payments saved by other instances are not seen.
+	pub fn with_summary_cache(mut self) -> Self {
+		self.summary_cache =
+			Some(Arc::new(SummaryCache::new(OffsetDateTime::now_utc())));
+		self
+	}
+
+	pub fn with_dedup(mut self, dedup: DedupStrategy) -> Self {
+		self.dedup = dedup;
+		self
+	}
+
+	/// Fails if the dedup strategy relies on a Redis module that is not
+	/// loaded, so a missing module is found at startup rather than by the
+	/// payments.
+	pub async fn check_dedup_support(&self) -> redis::RedisResult<()> {
+		if let DedupStrategy::Bloom { .. } = self.dedup {
+			let mut con = self.connection().await?;
+			redis::cmd("BF.EXISTS")
+				.arg(PROCESSED_PAYMENTS_BLOOM_KEY)
+				.arg("")
+				.query_async::<bool>(&mut con)
+				.await?;
+		}
+
+		Ok(())
 	}
 
+	/// Expires payment records after twice the retention window. The retention
+	/// worker folds them into summary buckets well before that; the TTL only
+	/// bounds memory if the worker is not running.
+	pub fn with_retention(mut self, retention: Duration) -> Self {
+		self.retention = Some(retention);
+		self
+	}
+
+	/// Fences off the payments in flight on any instance, so they are not
+	/// saved once the purge is over. Returns how many were fenced off. Only
+	/// instances sharing the in-flight registry are seen, the others fence
+	/// nothing. Entries older than the fence lasts are left by dead instances,
+	/// and are removed instead.
+	async fn fence_in_flight_using_lua(
+		con: &mut MultiplexedConnection,
+	) -> redis::RedisResult<usize> {
+		let lua = Script::new(
+			r#"
+            redis.call("ZREMRANGEBYSCORE", KEYS[1], "-inf", "(" .. ARGV[2])
+            local members = redis.call("ZRANGEBYSCORE", KEYS[1], ARGV[2], "+inf")
+            for _, member in ipairs(members) do
+                -- Members are "<instance>:<correlation id>".
+                redis.call("SADD", KEYS[2], string.sub(member, -36))
+            end
+            if #members > 0 then
+                redis.call("EXPIRE", KEYS[2], ARGV[1])
+            end
+            return #members
+        "#,
+		);
+
+		lua.key(IN_FLIGHT_PAYMENTS_KEY)
+			.key(PURGED_IN_FLIGHT_PAYMENTS_KEY)
+			.arg(PURGE_FENCE_TTL.as_secs())
+			.arg(
+				OffsetDateTime::now_utc().unix_timestamp() -
+					PURGE_FENCE_TTL.as_secs() as i64,
+			)
+			.invoke_async(con)
+			.await
+	}
+
+	async fn trim_batch_using_lua(
+		con: &mut MultiplexedConnection,
+		cutoff_ts: i128,
+	) -> redis::RedisResult<usize> {
+		let lua = Script::new(
+			&[AMOUNT_BUCKET_LUA, r#"
+            local entries = redis.call(
+                "ZRANGEBYSCORE", KEYS[1], "-inf", "(" .. ARGV[1],
+                "WITHSCORES", "LIMIT", 0, ARGV[2]
+            )
+            local bucket_size = tonumber(ARGV[3])
+            local trimmed = 0
+
+            for i = 1, #entries, 2 do
+                local id = entries[i]
+                local score = tonumber(entries[i + 1])
+                local bucket = string.format(
+                    "%.0f", math.floor(score / bucket_size) * bucket_size
+                )
+
+                for g = 7, #ARGV do
+                    local group = ARGV[g]
+                    local key = "payment_summary:" .. group .. ":" .. id
+                    local amount = redis.call("HGET", key, "amount")
+                    if amount then
+                        local bucket_key = ARGV[4] .. ":" .. group .. ":" .. bucket
+                        redis.call("HINCRBY", bucket_key, "total_requests", 1)
+                        redis.call("HINCRBYFLOAT", bucket_key, "total_amount", amount)
+                        redis.call(
+                            "HINCRBY", bucket_key,
+                            "amount_bucket:" .. amount_bucket(tonumber(amount), ARGV[6]), 1
+                        )
+                        redis.call("ZADD", ARGV[5] .. ":" .. group, bucket, bucket)
+                        redis.call("DEL", key)
+                    end
+                end
+
+                redis.call("ZREM", KEYS[1], id)
+                trimmed = trimmed + 1
+            end
+
+            return trimmed
+        "#]
+			.concat(),
+		);
+
+		let mut invocation = lua.key(PROCESSED_PAYMENTS_SET_KEY);
+		invocation
+			.arg(cutoff_ts)
+			.arg(TRIM_BATCH_SIZE)
+			.arg(TimestampCodec::encode_duration(SUMMARY_BUCKET))
+			.arg(PAYMENT_SUMMARY_BUCKET_KEY_PREFIX)
+			.arg(PAYMENT_SUMMARY_BUCKETS_KEY_PREFIX)
+			.arg(Self::amount_bucket_bounds());
+		for group in PROCESSOR_GROUPS {
+			invocation.arg(group);
+		}
+
+		invocation.invoke_async(con).await
+	}
+
+	/// Rescales processed payment scores written in seconds by older versions
+	/// to the nanosecond scores used by range queries. Returns how many
+	/// scores were rewritten.
+	pub async fn normalize_timestamp_scores(
+		&self,
+	) -> Result<usize, Box<dyn std::error::Error + Send>> {
+		let mut con = self.connection().await.map_err(repository_error)?;
+
+		let mut total_normalized = 0;
+
+		loop {
+			let normalized = Self::normalize_batch_using_lua(&mut con)
+				.await
+				.map_err(repository_error)?;
+
+			total_normalized += normalized;
+
+			if normalized < TRIM_BATCH_SIZE {
+				return Ok(total_normalized);
+			}
+		}
+	}
+
+	async fn normalize_batch_using_lua(
+		con: &mut MultiplexedConnection,
+	) -> redis::RedisResult<usize> {
+		let lua = Script::new(
+			r#"
+            local entries = redis.call(
+                "ZRANGEBYSCORE", KEYS[1], "(0", "(" .. ARGV[1],
+                "WITHSCORES", "LIMIT", 0, ARGV[2]
+            )
+
+            for i = 1, #entries, 2 do
+                local score = tonumber(entries[i + 1]) * tonumber(ARGV[3])
+                redis.call("ZADD", KEYS[1], string.format("%.0f", score), entries[i])
+            end
+
+            return #entries / 2
+        "#,
+		);
+
+		lua.key(PROCESSED_PAYMENTS_SET_KEY)
+			.arg(SECONDS_SCORE_LIMIT)
+			.arg(TRIM_BATCH_SIZE)
+			.arg(NANOS_PER_SECOND)
+			.invoke_async(con)
+			.await
+	}
+
+	/// Totals of `group` within the range, along with the ids of the stored
+	/// payments they count that were requested before `before_ts`, if given.
 	async fn calculate_payments_summary_using_lua(
 		con: &mut redis::aio::MultiplexedConnection,
 		group: &str,
 		from_ts: i128,
 		to_ts: i128,
-	) -> redis::RedisResult<(usize, f64)> {
+		before_ts: Option<i128>,
+	) -> redis::RedisResult<(usize, f64, Vec<String>)> {
 		let lua = Script::new(
 			r#"
-            local ids = redis.call("ZRANGEBYSCORE", KEYS[1], ARGV[1], ARGV[2])
+            local entries = redis.call(
+                "ZRANGEBYSCORE", KEYS[1], ARGV[1], ARGV[2], "WITHSCORES"
+            )
+            local before = tonumber(ARGV[5])
             local total_requests = 0
             local total_amount = 0.0
+            local ids_before = {}
 
-            for i, id in ipairs(ids) do
+            for i = 1, #entries, 2 do
+                local id = entries[i]
                 local key = ARGV[3] .. ":" .. id
                 local amount = redis.call("HGET", key, "amount")
                 if amount then
                     total_requests = total_requests + 1
                     total_amount = total_amount + tonumber(amount)
+                    if before and tonumber(entries[i + 1]) < before then
+                        ids_before[#ids_before + 1] = id
+                    end
                 end
             end
 
-            return {tostring(total_requests), tostring(total_amount)}
+            local buckets = redis.call("ZRANGEBYSCORE", KEYS[2], ARGV[1], ARGV[2])
+            for i, bucket in ipairs(buckets) do
+                local totals = redis.call(
+                    "HMGET", ARGV[4] .. ":" .. bucket, "total_requests", "total_amount"
+                )
+                if totals[1] then
+                    total_requests = total_requests + tonumber(totals[1])
+                    total_amount = total_amount + tonumber(totals[2])
+                end
+            end
+
+            return {tostring(total_requests), tostring(total_amount), ids_before}
         "#,
 		);
 
-		let response: (String, String) = lua
+		let response: (String, String, Vec<String>) = lua
 			.key(PROCESSED_PAYMENTS_SET_KEY)
+			.key(format!("{PAYMENT_SUMMARY_BUCKETS_KEY_PREFIX}:{group}"))
 			.arg(from_ts)
 			.arg(to_ts)
 			.arg(format!("payment_summary:{group}"))
+			.arg(format!("{PAYMENT_SUMMARY_BUCKET_KEY_PREFIX}:{group}"))
+			.arg(before_ts.map(|ts| ts.to_string()).unwrap_or_default())
 			.invoke_async(con)
 			.await?;
 
 		Ok((
 			response.0.parse().unwrap_or_default(),
 			response.1.parse().unwrap_or_default(),
+			response.2,
 		))
 	}
+
+	/// Counts per amount bucket, from the payments still stored and the
+	/// summary buckets they were folded into. Buckets folded before the
+	/// counts were kept only add to the totals.
+	async fn calculate_amount_buckets_using_lua(
+		con: &mut MultiplexedConnection,
+		group: &str,
+		from_ts: i128,
+		to_ts: i128,
+	) -> redis::RedisResult<Vec<usize>> {
+		let lua = Script::new(
+			&[AMOUNT_BUCKET_LUA, r#"
+            local counts = {}
+            for i = 1, tonumber(ARGV[6]) do
+                counts[i] = 0
+            end
+
+            local ids = redis.call("ZRANGEBYSCORE", KEYS[1], ARGV[1], ARGV[2])
+            for i, id in ipairs(ids) do
+                local amount = redis.call("HGET", ARGV[3] .. ":" .. id, "amount")
+                if amount then
+                    local bucket = amount_bucket(tonumber(amount), ARGV[5]) + 1
+                    counts[bucket] = counts[bucket] + 1
+                end
+            end
+
+            local buckets = redis.call("ZRANGEBYSCORE", KEYS[2], ARGV[1], ARGV[2])
+            for i, bucket in ipairs(buckets) do
+                local key = ARGV[4] .. ":" .. bucket
+                for b = 1, #counts do
+                    local count = redis.call("HGET", key, "amount_bucket:" .. (b - 1))
+                    if count then
+                        counts[b] = counts[b] + tonumber(count)
+                    end
+                end
+            end
+
+            return counts
+        "#]
+			.concat(),
+		);
+
+		lua.key(PROCESSED_PAYMENTS_SET_KEY)
+			.key(format!("{PAYMENT_SUMMARY_BUCKETS_KEY_PREFIX}:{group}"))
+			.arg(from_ts)
+			.arg(to_ts)
+			.arg(format!("payment_summary:{group}"))
+			.arg(format!("{PAYMENT_SUMMARY_BUCKET_KEY_PREFIX}:{group}"))
+			.arg(Self::amount_bucket_bounds())
+			.arg(AMOUNT_BUCKET_BOUNDS.len() + 1)
+			.invoke_async(con)
+			.await
+	}
+
+	async fn collect_latencies_using_lua(
+		con: &mut MultiplexedConnection,
+		group: &str,
+		from_ts: i128,
+		to_ts: i128,
+	) -> redis::RedisResult<Vec<u64>> {
+		let lua = Script::new(
+			r#"
+            local latencies = {}
+            local ids = redis.call("ZRANGEBYSCORE", KEYS[1], ARGV[1], ARGV[2])
+            for i, id in ipairs(ids) do
+                local latency = redis.call("HGET", ARGV[3] .. ":" .. id, "latency_us")
+                if latency then
+                    latencies[#latencies + 1] = latency
+                end
+            end
+            return latencies
+        "#,
+		);
+
+		lua.key(PROCESSED_PAYMENTS_SET_KEY)
+			.arg(from_ts)
+			.arg(to_ts)
+			.arg(format!("payment_summary:{group}"))
+			.invoke_async(con)
+			.await
+	}
+
+	fn amount_bucket_bounds() -> String {
+		AMOUNT_BUCKET_BOUNDS
+			.map(|bound| bound.to_string())
+			.join(",")
+	}
+
+	fn failures_key(group: &str, failure: PaymentFailure) -> String {
+		format!("{PAYMENT_FAILURES_KEY_PREFIX}:{group}:{}", failure.as_str())
+	}
+
+	fn duplicates_key(stage: DuplicateStage) -> String {
+		format!("{PAYMENT_DUPLICATES_KEY_PREFIX}:{}", stage.as_str())
+	}
+
+	async fn delete_payments(
+		&self,
+		payments: &[Payment],
+		archived: bool,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		if payments.is_empty() {
+			return Ok(());
+		}
+
+		let mut con = self.connection().await.map_err(repository_error)?;
+
+		let removed = self
+			.delete_using_lua(&mut con, payments, archived)
+			.await
+			.map_err(repository_error)?;
+
+		if let Some(summary_cache) = &self.summary_cache {
+			for (payment, _) in
+				payments.iter().zip(removed).filter(|(_, removed)| *removed)
+			{
+				summary_cache.remove(
+					payment.processed_by.as_deref().unwrap_or_default(),
+					TimestampCodec::encode_optional(payment.requested_at),
+					payment.amount,
+				);
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Reads a summary bucket of `group` from its hash, given when it starts
+	/// as written in the bucket index.
+	fn summary_bucket_from_hash(
+		group: &str,
+		starts_at: &str,
+		map: &HashMap<String, String>,
+	) -> Option<SummaryBucket> {
+		let starts_at = TimestampCodec::decode(starts_at.parse().ok()?)?;
+		let requests = map.get("total_requests")?.parse().ok()?;
+		let amount = map.get("total_amount")?.parse().ok()?;
+		let amount_buckets =
+			if map.keys().any(|field| field.starts_with("amount_bucket:")) {
+				(0..=AMOUNT_BUCKET_BOUNDS.len())
+					.map(|bucket| {
+						map.get(&format!("amount_bucket:{bucket}"))
+							.and_then(|count| count.parse().ok())
+							.unwrap_or_default()
+					})
+					.collect()
+			} else {
+				Vec::new()
+			};
+
+		Some(SummaryBucket {
+			group: group.to_string(),
+			starts_at,
+			requests,
+			amount,
+			amount_buckets,
+		})
+	}
+
+	/// Reads a recorded failure from its member of the failures set,
+	/// `<correlation id>:<requested at>`.
+	fn failure_from_member(
+		group: &str,
+		failure: PaymentFailure,
+		member: &str,
+	) -> Option<RecordedFailure> {
+		let mut parts = member.splitn(3, ':');
+		let correlation_id = parts.next()?;
+		let requested_at: i128 = parts.next()?.parse().ok()?;
+		let failed_at = match parts.next() {
+			Some(failed_at) => TimestampCodec::decode(failed_at.parse().ok()?),
+			None => None,
+		};
+
+		Some(RecordedFailure {
+			correlation_id: Uuid::parse_str(correlation_id).ok()?,
+			group: group.to_string(),
+			failure,
+			requested_at: (requested_at != 0)
+				.then(|| TimestampCodec::decode(requested_at))
+				.flatten(),
+			failed_at,
+		})
+	}
+
+	fn payment_from_hash(
+		payment_id: &str,
+		map: &HashMap<String, String>,
+	) -> Option<Payment> {
+		let amount = map.get("amount")?.parse::<f64>().ok()?;
+		let requested_at = map
+			.get("requested_at")
+			.and_then(|odt| OffsetDateTime::parse(odt, &Rfc3339).ok());
+		let processed_at = map
+			.get("processed_at")
+			.and_then(|odt| OffsetDateTime::parse(odt, &Rfc3339).ok());
+		let processed_by = map.get("processed_by").cloned();
+		let tag = map.get("tag").cloned();
+		let epoch = map.get("epoch").and_then(|epoch| epoch.parse().ok());
+
+		Some(Payment {
+			correlation_id: Uuid::parse_str(payment_id).ok()?,
+			amount,
+			requested_at,
+			processed_at,
+			processed_by,
+			tag,
+			epoch,
+		})
+	}
+}
+
+/// Position of a payment stream over the processed ZSET. Pages are fetched
+/// with `ZRANGEBYSCORE ... LIMIT offset count` so that payments sharing the
+/// same score are never skipped between batches.
+struct PaymentStreamCursor {
+	client:   Client,
+	con:      Option<MultiplexedConnection>,
+	group:    String,
+	from_ts:  i128,
+	to_ts:    i128,
+	offset:   isize,
+	finished: bool,
+}
+
+impl PaymentStreamCursor {
+	async fn next_batch(&mut self) -> redis::RedisResult<Vec<Payment>> {
+		let con = match &mut self.con {
+			Some(con) => con,
+			None => self
+				.con
+				.insert(self.client.get_multiplexed_async_connection().await?),
+		};
+
+		let ids: Vec<String> = con
+			.zrangebyscore_limit(
+				PROCESSED_PAYMENTS_SET_KEY,
+				self.from_ts,
+				self.to_ts,
+				self.offset,
+				STREAM_BATCH_SIZE,
+			)
+			.await?;
+
+		self.offset += ids.len() as isize;
+		self.finished = (ids.len() as isize) < STREAM_BATCH_SIZE;
+
+		if ids.is_empty() {
+			return Ok(Vec::new());
+		}
+
+		let mut pipe = redis::pipe();
+		for id in &ids {
+			pipe.hgetall(format!("payment_summary:{}:{id}", self.group));
+		}
+		let hashes: Vec<HashMap<String, String>> = pipe.query_async(con).await?;
+
+		Ok(ids
+			.iter()
+			.zip(hashes.iter())
+			.filter_map(|(id, map)| {
+				RedisPaymentRepository::payment_from_hash(id, map)
+			})
+			.collect())
+	}
+}
+
+/// Position of a stream over every member of a sorted set, in score order.
+/// Pages are fetched by rank, [`STREAM_BATCH_SIZE`] members at a time.
+struct SortedSetCursor {
+	client:   Client,
+	con:      Option<MultiplexedConnection>,
+	key:      String,
+	offset:   isize,
+	finished: bool,
+}
+
+impl SortedSetCursor {
+	fn new(client: Client, key: String) -> Self {
+		Self {
+			client,
+			con: None,
+			key,
+			offset: 0,
+			finished: false,
+		}
+	}
+
+	/// Returns the next page of members, along with the connection it was
+	/// read over so the records they point to can be fetched with it.
+	async fn next_page(
+		&mut self,
+	) -> redis::RedisResult<(Vec<String>, &mut MultiplexedConnection)> {
+		let con = match self.con.take() {
+			Some(con) => con,
+			None => self.client.get_multiplexed_async_connection().await?,
+		};
+		let con = self.con.insert(con);
+
+		let members: Vec<String> = con
+			.zrange(&self.key, self.offset, self.offset + STREAM_BATCH_SIZE - 1)
+			.await?;
+
+		self.offset += members.len() as isize;
+		self.finished = (members.len() as isize) < STREAM_BATCH_SIZE;
+
+		Ok((members, con))
+	}
+
+	async fn next_summary_buckets(
+		&mut self,
+		group: &str,
+	) -> redis::RedisResult<Vec<SummaryBucket>> {
+		let (starts, con) = self.next_page().await?;
+		if starts.is_empty() {
+			return Ok(Vec::new());
+		}
+
+		let mut pipe = redis::pipe();
+		for starts_at in &starts {
+			pipe.hgetall(format!(
+				"{PAYMENT_SUMMARY_BUCKET_KEY_PREFIX}:{group}:{starts_at}"
+			));
+		}
+		let hashes: Vec<HashMap<String, String>> = pipe.query_async(con).await?;
+
+		Ok(starts
+			.iter()
+			.zip(hashes.iter())
+			.filter_map(|(starts_at, map)| {
+				RedisPaymentRepository::summary_bucket_from_hash(
+					group, starts_at, map,
+				)
+			})
+			.collect())
+	}
 }
 
-#[async_trait]
 impl PaymentRepository for RedisPaymentRepository {
 	async fn save(
 		&self,
 		payment: Payment,
 	) -> Result<(), Box<dyn std::error::Error + Send>> {
-		let mut con = self
-			.client
-			.get_multiplexed_async_connection()
-			.await
-			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+		let mut con = self.connection().await.map_err(repository_error)?;
 
 		let payment_id = payment.correlation_id.to_string();
 		let payment_group = payment.processed_by.unwrap_or_default();
-		let payment_key = format!("payment_summary:{payment_group}:{payment_id}");
+		let requested_at = TimestampCodec::encode_optional(payment.requested_at);
+		let latency_us = payment.requested_at.zip(payment.processed_at).map(
+			|(requested_at, processed_at)| {
+				(processed_at - requested_at).whole_microseconds().max(0) as i64
+			},
+		);
+		let cached_group =
+			self.summary_cache.as_ref().map(|_| payment_group.clone());
 
-		redis::pipe()
-			.atomic()
-			.hset(&payment_key, "amount", format!("{:.2}", payment.amount))
-			.hset_multiple(&payment_key, &[
-				(
-					"requested_at",
-					payment
-						.requested_at
-						.map(|ts| ts.to_string())
-						.unwrap_or_default(),
-				),
-				(
-					"processed_at",
-					payment
-						.processed_at
-						.map(|ts| ts.to_string())
-						.unwrap_or_default(),
-				),
-				("processed_by", payment_group),
-			])
-			.ignore()
-			.zadd(
-				PROCESSED_PAYMENTS_SET_KEY,
-				payment_id,
+		let mut fields = vec![
+			(
+				"requested_at",
 				payment
 					.requested_at
-					.map(|ts| ts.unix_timestamp_nanos())
+					.and_then(|ts| ts.format(&Rfc3339).ok())
+					.unwrap_or_default(),
+			),
+			(
+				"processed_at",
+				payment
+					.processed_at
+					.and_then(|ts| ts.format(&Rfc3339).ok())
 					.unwrap_or_default(),
+			),
+			("processed_by", payment_group.clone()),
+		];
+		if let Some(tag) = &payment.tag {
+			fields.push(("tag", tag.clone()));
+		}
+		if let Some(epoch) = payment.epoch {
+			fields.push(("epoch", epoch.to_string()));
+		}
+		if let Some(latency_us) = latency_us {
+			fields.push(("latency_us", latency_us.to_string()));
+		}
+
+		let fenced = self
+			.save_using_lua(
+				&mut con,
+				&payment_id,
+				&payment_group,
+				requested_at,
+				payment.amount,
+				&fields,
 			)
-			.query_async::<()>(&mut con)
 			.await
-			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+			.map_err(repository_error)?;
+		if fenced {
+			info!(
+				"Discarding payment {} processed across a purge",
+				log_redaction::correlation_id(payment.correlation_id)
+			);
+			return Ok(());
+		}
+
+		// The bloom filter is written once the payment is saved, so a failing
+		// module cannot fail the save of a payment the processor took.
+		if let DedupStrategy::Bloom {
+			capacity,
+			error_rate,
+		} = &self.dedup
+		{
+			let inserted: redis::RedisResult<()> = redis::cmd("BF.INSERT")
+				.arg(PROCESSED_PAYMENTS_BLOOM_KEY)
+				.arg("CAPACITY")
+				.arg(capacity)
+				.arg("ERROR")
+				.arg(error_rate)
+				.arg("ITEMS")
+				.arg(&payment_id)
+				.query_async(&mut con)
+				.await;
+			if let Err(e) = inserted {
+				error!(
+					"Failed to add payment {} to the dedup filter: {e}",
+					log_redaction::correlation_id(payment.correlation_id)
+				);
+			}
+		}
+
+		if let (Some(summary_cache), Some(group)) =
+			(&self.summary_cache, cached_group)
+		{
+			summary_cache.record(&group, requested_at, payment.amount);
+		}
 
 		Ok(())
 	}
@@ -115,59 +944,97 @@ impl PaymentRepository for RedisPaymentRepository {
 		from_ts: OffsetDateTime,
 		to_ts: OffsetDateTime,
 	) -> Result<(usize, f64), Box<dyn std::error::Error + Send>> {
-		let mut con = self
-			.client
-			.clone()
-			.get_multiplexed_async_connection()
-			.await
-			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
-		let (req, amt) = Self::calculate_payments_summary_using_lua(
+		if let Some(summary) = self
+			.summary_cache
+			.as_ref()
+			.and_then(|summary_cache| summary_cache.summary(group, from_ts, to_ts))
+		{
+			return Ok(summary);
+		}
+
+		let mut con = self.connection().await.map_err(repository_error)?;
+		let (req, amt, _) = Self::calculate_payments_summary_using_lua(
 			&mut con,
 			group,
-			from_ts.unix_timestamp_nanos(),
-			to_ts.unix_timestamp_nanos(),
+			TimestampCodec::encode(from_ts),
+			TimestampCodec::encode(to_ts),
+			None,
 		)
 		.await
-		.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+		.map_err(repository_error)?;
 		Ok((req, amt))
 	}
 
+	async fn get_summary_with_ids_before(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+		before: OffsetDateTime,
+	) -> Result<(usize, f64, Vec<Uuid>), Box<dyn std::error::Error + Send>> {
+		let mut con = self.connection().await.map_err(repository_error)?;
+		let (req, amt, ids) = Self::calculate_payments_summary_using_lua(
+			&mut con,
+			group,
+			TimestampCodec::encode(from_ts),
+			TimestampCodec::encode(to_ts),
+			Some(TimestampCodec::encode(before)),
+		)
+		.await
+		.map_err(repository_error)?;
+		Ok((
+			req,
+			amt,
+			ids.iter()
+				.filter_map(|id| Uuid::parse_str(id).ok())
+				.collect(),
+		))
+	}
+
+	fn get_payments_stream(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> PaymentStream {
+		let cursor = PaymentStreamCursor {
+			client:   self.client.clone(),
+			con:      None,
+			group:    group.to_string(),
+			from_ts:  TimestampCodec::encode(from_ts),
+			to_ts:    TimestampCodec::encode(to_ts),
+			offset:   0,
+			finished: false,
+		};
+
+		stream::try_unfold(cursor, |mut cursor| async move {
+			if cursor.finished {
+				return Ok(None);
+			}
+
+			let batch = cursor.next_batch().await.map_err(repository_error)?;
+
+			Ok(Some((stream::iter(batch.into_iter().map(Ok)), cursor)))
+		})
+		.try_flatten()
+		.boxed()
+	}
+
 	async fn get_payment_summary(
 		&self,
 		group: &str,
 		payment_id: &str,
 	) -> Result<Payment, Box<dyn std::error::Error + Send>> {
-		let mut con = self
-			.client
-			.get_multiplexed_async_connection()
-			.await
-			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+		let mut con = self.connection().await.map_err(repository_error)?;
 
 		let payment_key = format!("payment_summary:{group}:{payment_id}");
 		log::debug!("Retrieving payment summary for key: {}", payment_key);
-		let payment_data: Option<std::collections::HashMap<String, String>> =
+		let payment_data: Option<HashMap<String, String>> =
 			con.hgetall(&payment_key).await.ok();
 
 		if let Some(map) = payment_data &&
-			let Some(amount_str) = map.get("amount") &&
-			let Ok(amount) = amount_str.parse::<f64>()
+			let Some(payment) = Self::payment_from_hash(payment_id, &map)
 		{
-			let requested_at = map
-				.get("requested_at")
-				.and_then(|odt| OffsetDateTime::parse(odt, &Rfc3339).ok());
-			let processed_at = map
-				.get("processed_at")
-				.and_then(|odt| OffsetDateTime::parse(odt, &Rfc3339).ok());
-			let processed_by = map.get("processed_by").cloned();
-
-			let payment = Payment {
-				correlation_id: uuid::Uuid::parse_str(payment_id)
-					.expect("Valid UUID"),
-				amount,
-				requested_at,
-				processed_at,
-				processed_by,
-			};
 			return Ok(payment);
 		}
 
@@ -181,43 +1048,389 @@ impl PaymentRepository for RedisPaymentRepository {
 		&self,
 		payment_id: &str,
 	) -> Result<bool, Box<dyn std::error::Error + Send>> {
-		let mut con = self
-			.client
-			.clone()
-			.get_multiplexed_async_connection()
+		let mut con = self.connection().await.map_err(repository_error)?;
+
+		match &self.dedup {
+			DedupStrategy::SortedSet => {
+				// Archived payments leave the processed set, so they are
+				// looked up in the archived set as well.
+				let (score, archived): (Option<f64>, bool) = redis::pipe()
+					.zscore(PROCESSED_PAYMENTS_SET_KEY, payment_id)
+					.sismember(ARCHIVED_PAYMENTS_SET_KEY, payment_id)
+					.query_async(&mut con)
+					.await
+					.map_err(repository_error)?;
+
+				Ok(score.is_some() || archived)
+			}
+			DedupStrategy::Bloom { .. } => redis::cmd("BF.EXISTS")
+				.arg(PROCESSED_PAYMENTS_BLOOM_KEY)
+				.arg(payment_id)
+				.query_async(&mut con)
+				.await
+				.map_err(repository_error),
+			DedupStrategy::Expiring { .. } => con
+				.exists(format!("{PROCESSED_PAYMENT_KEY_PREFIX}:{payment_id}"))
+				.await
+				.map_err(repository_error),
+		}
+	}
+
+	async fn record_failure(
+		&self,
+		payment: &Payment,
+		group: &str,
+		failure: PaymentFailure,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		// Retries keep the time the payment was requested, so the member
+		// carries the time of the call too, for each call to be counted.
+		self.restore_failure(&RecordedFailure {
+			correlation_id: payment.correlation_id,
+			group: group.to_string(),
+			failure,
+			requested_at: payment.requested_at,
+			failed_at: Some(OffsetDateTime::now_utc()),
+		})
+		.await
+	}
+
+	async fn get_failures_by_group(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> Result<(usize, usize), Box<dyn std::error::Error + Send>> {
+		let mut con = self.connection().await.map_err(repository_error)?;
+
+		let from_ts = TimestampCodec::encode(from_ts);
+		let to_ts = TimestampCodec::encode(to_ts);
+		redis::pipe()
+			.zcount(
+				Self::failures_key(group, PaymentFailure::Failed),
+				from_ts,
+				to_ts,
+			)
+			.zcount(
+				Self::failures_key(group, PaymentFailure::Rejected),
+				from_ts,
+				to_ts,
+			)
+			.query_async(&mut con)
+			.await
+			.map_err(repository_error)
+	}
+
+	fn get_failures_stream(
+		&self,
+		group: &str,
+		failure: PaymentFailure,
+	) -> FailureStream {
+		let cursor = SortedSetCursor::new(
+			self.client.clone(),
+			Self::failures_key(group, failure),
+		);
+		let group = group.to_string();
+
+		stream::try_unfold((cursor, group), move |(mut cursor, group)| async move {
+			if cursor.finished {
+				return Ok(None);
+			}
+
+			let (members, _) = cursor.next_page().await.map_err(repository_error)?;
+			let failures: Vec<_> = members
+				.iter()
+				.filter_map(|member| {
+					Self::failure_from_member(&group, failure, member)
+				})
+				.collect();
+
+			Ok(Some((
+				stream::iter(failures.into_iter().map(Ok)),
+				(cursor, group),
+			)))
+		})
+		.try_flatten()
+		.boxed()
+	}
+
+	async fn restore_failure(
+		&self,
+		failure: &RecordedFailure,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		let mut con = self.connection().await.map_err(repository_error)?;
+
+		let requested_at = TimestampCodec::encode_optional(failure.requested_at);
+		let member = match failure.failed_at {
+			Some(failed_at) => format!(
+				"{}:{requested_at}:{}",
+				failure.correlation_id,
+				TimestampCodec::encode(failed_at)
+			),
+			None => format!("{}:{requested_at}", failure.correlation_id),
+		};
+		con.zadd(
+			Self::failures_key(&failure.group, failure.failure),
+			member,
+			requested_at,
+		)
+		.await
+		.map_err(repository_error)
+	}
+
+	async fn record_duplicate(
+		&self,
+		payment_id: &str,
+		stage: DuplicateStage,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		let mut con = self.connection().await.map_err(repository_error)?;
+
+		// Every submission is counted, so the member carries the time too.
+		let caught_at = TimestampCodec::encode(OffsetDateTime::now_utc());
+		con.zadd(
+			Self::duplicates_key(stage),
+			format!("{payment_id}:{caught_at}"),
+			caught_at,
+		)
+		.await
+		.map_err(repository_error)
+	}
+
+	async fn get_duplicates(
+		&self,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> Result<(usize, usize), Box<dyn std::error::Error + Send>> {
+		let mut con = self.connection().await.map_err(repository_error)?;
+
+		let from_ts = TimestampCodec::encode(from_ts);
+		let to_ts = TimestampCodec::encode(to_ts);
+		redis::pipe()
+			.zcount(Self::duplicates_key(DuplicateStage::Ingest), from_ts, to_ts)
+			.zcount(Self::duplicates_key(DuplicateStage::Worker), from_ts, to_ts)
+			.query_async(&mut con)
 			.await
-			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+			.map_err(repository_error)
+	}
+
+	async fn get_amount_buckets_by_group(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> Result<Vec<usize>, Box<dyn std::error::Error + Send>> {
+		let mut con = self.connection().await.map_err(repository_error)?;
+
+		Self::calculate_amount_buckets_using_lua(
+			&mut con,
+			group,
+			TimestampCodec::encode(from_ts),
+			TimestampCodec::encode(to_ts),
+		)
+		.await
+		.map_err(repository_error)
+	}
+
+	async fn get_latencies_by_group(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> Result<Vec<u64>, Box<dyn std::error::Error + Send>> {
+		let mut con = self.connection().await.map_err(repository_error)?;
 
-		let is_already_processed: Option<f64> = con
-			.zscore(PROCESSED_PAYMENTS_SET_KEY, payment_id)
+		Self::collect_latencies_using_lua(
+			&mut con,
+			group,
+			TimestampCodec::encode(from_ts),
+			TimestampCodec::encode(to_ts),
+		)
+		.await
+		.map_err(repository_error)
+	}
+
+	async fn delete(
+		&self,
+		payments: &[Payment],
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		self.delete_payments(payments, false).await
+	}
+
+	async fn remove_archived(
+		&self,
+		payments: &[Payment],
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		self.delete_payments(payments, true).await
+	}
+
+	fn get_summary_buckets_stream(&self, group: &str) -> SummaryBucketStream {
+		let cursor = SortedSetCursor::new(
+			self.client.clone(),
+			format!("{PAYMENT_SUMMARY_BUCKETS_KEY_PREFIX}:{group}"),
+		);
+		let group = group.to_string();
+
+		stream::try_unfold((cursor, group), |(mut cursor, group)| async move {
+			if cursor.finished {
+				return Ok(None);
+			}
+
+			let buckets = cursor
+				.next_summary_buckets(&group)
+				.await
+				.map_err(repository_error)?;
+
+			Ok(Some((
+				stream::iter(buckets.into_iter().map(Ok)),
+				(cursor, group),
+			)))
+		})
+		.try_flatten()
+		.boxed()
+	}
+
+	async fn restore_summary_bucket(
+		&self,
+		bucket: &SummaryBucket,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		let mut con = self.connection().await.map_err(repository_error)?;
+
+		let starts_at = TimestampCodec::encode(bucket.starts_at);
+		let bucket_key = format!(
+			"{PAYMENT_SUMMARY_BUCKET_KEY_PREFIX}:{}:{starts_at}",
+			bucket.group
+		);
+
+		let mut pipe = redis::pipe();
+		pipe.atomic()
+			.del(&bucket_key)
+			.ignore()
+			.hset_multiple(&bucket_key, &[
+				("total_requests", bucket.requests.to_string()),
+				("total_amount", bucket.amount.to_string()),
+			])
+			.ignore();
+		for (amount_bucket, count) in bucket.amount_buckets.iter().enumerate() {
+			pipe.hset(&bucket_key, format!("amount_bucket:{amount_bucket}"), count)
+				.ignore();
+		}
+		pipe.zadd(
+			format!("{PAYMENT_SUMMARY_BUCKETS_KEY_PREFIX}:{}", bucket.group),
+			starts_at.to_string(),
+			starts_at,
+		)
+		.ignore();
+
+		pipe.query_async(&mut con).await.map_err(repository_error)
+	}
+
+	async fn trim_older_than(
+		&self,
+		cutoff: OffsetDateTime,
+	) -> Result<usize, Box<dyn std::error::Error + Send>> {
+		let mut con = self.connection().await.map_err(repository_error)?;
+
+		if let Some(summary_cache) = &self.summary_cache {
+			summary_cache.trim(cutoff);
+		}
+
+		// Failures and duplicates are not folded into buckets, older ones are
+		// just dropped.
+		let max_score = format!("({}", TimestampCodec::encode(cutoff));
+		let mut pipe = redis::pipe();
+		for group in PROCESSOR_GROUPS {
+			for failure in [PaymentFailure::Failed, PaymentFailure::Rejected] {
+				pipe.zrembyscore(
+					Self::failures_key(group, failure),
+					"-inf",
+					&max_score,
+				)
+				.ignore();
+			}
+		}
+		for stage in [DuplicateStage::Ingest, DuplicateStage::Worker] {
+			pipe.zrembyscore(Self::duplicates_key(stage), "-inf", &max_score)
+				.ignore();
+		}
+		pipe.query_async::<()>(&mut con)
 			.await
-			.ok();
+			.map_err(repository_error)?;
+
+		let mut total_trimmed = 0;
+
+		loop {
+			let trimmed =
+				Self::trim_batch_using_lua(&mut con, TimestampCodec::encode(cutoff))
+					.await
+					.map_err(repository_error)?;
 
-		Ok(is_already_processed.is_some())
+			total_trimmed += trimmed;
+
+			if trimmed < TRIM_BATCH_SIZE {
+				return Ok(total_trimmed);
+			}
+		}
 	}
 
 	async fn clear(&self) -> Result<(), Box<dyn std::error::Error + Send>> {
-		let mut con = self
-			.client
-			.get_multiplexed_async_connection()
+		let mut con = self.connection().await.map_err(repository_error)?;
+
+		let fenced = Self::fence_in_flight_using_lua(&mut con)
 			.await
-			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+			.map_err(repository_error)?;
+		if fenced > 0 {
+			info!("Fenced off {fenced} payments in flight during the purge");
+		}
 
-		let keys: Vec<String> = con
+		let mut keys: Vec<String> = con
 			.keys("payment_summary:*")
 			.await
-			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+			.map_err(repository_error)?;
 
-		let _: () = con
-			.del(keys)
+		let dedup_keys: Vec<String> = con
+			.keys(format!("{PROCESSED_PAYMENT_KEY_PREFIX}:*"))
 			.await
-			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+			.map_err(repository_error)?;
+
+		keys.extend(dedup_keys);
 
-		let _: () = con
-			.del(PROCESSED_PAYMENTS_SET_KEY)
+		// Payments marked as queued are gone from the summaries, so they can
+		// be submitted again.
+		let queued_keys: Vec<String> = con
+			.keys(format!("{QUEUED_PAYMENT_KEY_PREFIX}:*"))
 			.await
-			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+			.map_err(repository_error)?;
+		keys.extend(queued_keys);
+		keys.push(PROCESSED_PAYMENTS_SET_KEY.to_string());
+		keys.push(ARCHIVED_PAYMENTS_SET_KEY.to_string());
+		if self.legacy_summary {
+			keys.extend(
+				PROCESSOR_GROUPS
+					.iter()
+					.filter_map(|group| RedisLegacyPaymentStore::summary_key(group))
+					.map(str::to_string),
+			);
+		}
+
+		let _: () = con.del(keys).await.map_err(repository_error)?;
+
+		if let Some(summary_cache) = &self.summary_cache {
+			summary_cache.clear();
+		}
 
 		Ok(())
 	}
 }
+
+/// Reports connection failures as [`RepositoryError::Unavailable`], so callers
+/// can tell an unreachable Redis from other failures.
+fn repository_error(e: RedisError) -> Box<dyn std::error::Error + Send> {
+	if e.is_io_error() ||
+		e.is_connection_refusal() ||
+		e.is_connection_dropped() ||
+		e.is_timeout()
+	{
+		Box::new(RepositoryError::Unavailable(e.to_string()))
+	} else {
+		Box::new(e)
+	}
+}
@@ -0,0 +1,38 @@
+use redis::{AsyncCommands, Client, RedisResult};
+
+use crate::infrastructure::config::redis::RUN_EPOCH_KEY;
+use crate::use_cases::run_epoch;
+
+/// Keeps the run epoch of this instance in line with the one shared by every
+/// instance through Redis. The epoch is read from memory when payments are
+/// accepted, so a new epoch reaches the other instances on their next
+/// [`RedisRunEpoch::sync`].
+#[derive(Clone)]
+pub struct RedisRunEpoch {
+	client: Client,
+}
+
+impl RedisRunEpoch {
+	pub fn new(client: Client) -> Self {
+		Self { client }
+	}
+
+	/// Reads the shared epoch into this instance, returning it.
+	pub async fn sync(&self) -> RedisResult<u64> {
+		let mut con = self.client.get_multiplexed_async_connection().await?;
+		let epoch: Option<u64> = con.get(RUN_EPOCH_KEY).await?;
+
+		let epoch = epoch.unwrap_or_default();
+		run_epoch::set_current_epoch(epoch);
+		Ok(epoch)
+	}
+
+	/// Starts a new epoch for every instance, returning it.
+	pub async fn advance(&self) -> RedisResult<u64> {
+		let mut con = self.client.get_multiplexed_async_connection().await?;
+		let epoch: u64 = con.incr(RUN_EPOCH_KEY, 1).await?;
+
+		run_epoch::set_current_epoch(epoch);
+		Ok(epoch)
+	}
+}
@@ -0,0 +1,197 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+
+use time::OffsetDateTime;
+
+use crate::infrastructure::persistence::timestamp_codec::TimestampCodec;
+
+/// Requests and amount of the payments requested at one instant. The amount
+/// is kept at full precision, as Redis does, and only rounded when served.
+#[derive(Debug, Default, Clone, Copy)]
+struct Totals {
+	requests: usize,
+	amount:   f64,
+}
+
+struct CachedSummaries {
+	/// Every payment requested from this instant on is in `groups`.
+	covers_from: i128,
+	groups:      HashMap<String, BTreeMap<i128, Totals>>,
+}
+
+/// In-memory copy of the payment summaries written by this process, so
+/// summaries can be served without a Redis round trip. Only complete when a
+/// single instance processes payments, and only for ranges starting after the
+/// cache was created.
+pub struct SummaryCache {
+	summaries: RwLock<CachedSummaries>,
+}
+
+impl SummaryCache {
+	pub fn new(covers_from: OffsetDateTime) -> Self {
+		Self {
+			summaries: RwLock::new(CachedSummaries {
+				covers_from: TimestampCodec::encode(covers_from),
+				groups:      HashMap::new(),
+			}),
+		}
+	}
+
+	pub fn record(&self, group: &str, requested_at: i128, amount: f64) {
+		let mut summaries = self.summaries.write().unwrap();
+		let totals = summaries
+			.groups
+			.entry(group.to_string())
+			.or_default()
+			.entry(requested_at)
+			.or_default();
+
+		totals.requests += 1;
+		totals.amount += amount;
+	}
+
+	pub fn remove(&self, group: &str, requested_at: i128, amount: f64) {
+		let mut summaries = self.summaries.write().unwrap();
+		let Some(payments) = summaries.groups.get_mut(group) else {
+			return;
+		};
+		let Some(totals) = payments.get_mut(&requested_at) else {
+			return;
+		};
+
+		totals.requests = totals.requests.saturating_sub(1);
+		totals.amount -= amount;
+		if totals.requests == 0 {
+			payments.remove(&requested_at);
+		}
+	}
+
+	/// Returns the totals of `group` within the range, or `None` when the
+	/// range starts before the payments the cache knows about.
+	pub fn summary(
+		&self,
+		group: &str,
+		from_ts: OffsetDateTime,
+		to_ts: OffsetDateTime,
+	) -> Option<(usize, f64)> {
+		let from = TimestampCodec::encode(from_ts);
+		let to = TimestampCodec::encode(to_ts);
+
+		let summaries = self.summaries.read().unwrap();
+		if from < summaries.covers_from {
+			return None;
+		}
+		if from > to {
+			return Some((0, 0.0));
+		}
+
+		let totals = summaries
+			.groups
+			.get(group)
+			.into_iter()
+			.flat_map(|payments| payments.range(from..=to))
+			.fold(Totals::default(), |sum, (_, totals)| Totals {
+				requests: sum.requests + totals.requests,
+				amount:   sum.amount + totals.amount,
+			});
+
+		Some((totals.requests, totals.amount))
+	}
+
+	/// Drops the payments requested before `cutoff`. Ranges starting before
+	/// it are no longer served from the cache.
+	pub fn trim(&self, cutoff: OffsetDateTime) {
+		let cutoff = TimestampCodec::encode(cutoff);
+
+		let mut summaries = self.summaries.write().unwrap();
+		summaries.covers_from = summaries.covers_from.max(cutoff);
+		for payments in summaries.groups.values_mut() {
+			*payments = payments.split_off(&cutoff);
+		}
+	}
+
+	pub fn clear(&self) {
+		self.summaries.write().unwrap().groups.clear();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use time::Duration;
+
+	use super::*;
+
+	#[test]
+	fn test_sums_payments_within_range() {
+		let start = OffsetDateTime::now_utc();
+		let cache = SummaryCache::new(start);
+		let at =
+			|seconds| TimestampCodec::encode(start + Duration::seconds(seconds));
+
+		cache.record("default", at(1), 10.10);
+		cache.record("default", at(2), 20.20);
+		cache.record("default", at(3), 30.30);
+		cache.record("fallback", at(2), 5.0);
+
+		assert_eq!(
+			cache.summary(
+				"default",
+				start + Duration::seconds(2),
+				start + Duration::seconds(3)
+			),
+			Some((2, 50.5))
+		);
+		assert_eq!(
+			cache.summary("fallback", start, start + Duration::seconds(3)),
+			Some((1, 5.0))
+		);
+
+		cache.remove("default", at(3), 30.30);
+
+		// Summed the way Redis sums them, without rounding to cents.
+		assert_eq!(
+			cache.summary("default", start, start + Duration::seconds(3)),
+			Some((2, 10.10 + 20.20))
+		);
+	}
+
+	#[test]
+	fn test_keeps_fractions_of_a_cent() {
+		let start = OffsetDateTime::now_utc();
+		let cache = SummaryCache::new(start);
+		let at = TimestampCodec::encode(start + Duration::seconds(1));
+
+		cache.record("default", at, 0.004);
+		cache.record("default", at, 0.004);
+		cache.record("default", at, 0.004);
+
+		let (requests, amount) = cache
+			.summary("default", start, start + Duration::seconds(2))
+			.unwrap();
+
+		assert_eq!(requests, 3);
+		assert!((amount - 0.012).abs() < 1e-9);
+	}
+
+	#[test]
+	fn test_does_not_serve_ranges_before_coverage() {
+		let start = OffsetDateTime::now_utc();
+		let cache = SummaryCache::new(start);
+
+		assert_eq!(
+			cache.summary("default", start - Duration::seconds(1), start),
+			None
+		);
+
+		cache.trim(start + Duration::seconds(10));
+
+		assert_eq!(
+			cache.summary(
+				"default",
+				start + Duration::seconds(5),
+				start + Duration::seconds(20)
+			),
+			None
+		);
+	}
+}
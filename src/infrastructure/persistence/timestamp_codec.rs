@@ -0,0 +1,75 @@
+use time::{Duration, OffsetDateTime};
+
+/// Scores below this magnitude cannot be nanoseconds of any timestamp after
+/// 1970-01-01T00:16:40Z, so they are taken to be seconds written by older
+/// versions. As seconds it covers every date up to the year 33658.
+pub const SECONDS_SCORE_LIMIT: i128 = 1_000_000_000_000;
+
+pub const NANOS_PER_SECOND: i128 = 1_000_000_000;
+
+/// The single place that converts timestamps to and from sorted set scores.
+/// Scores are nanoseconds since the Unix epoch; payments without a timestamp
+/// are scored `0`.
+pub struct TimestampCodec;
+
+impl TimestampCodec {
+	pub fn encode(timestamp: OffsetDateTime) -> i128 {
+		timestamp.unix_timestamp_nanos()
+	}
+
+	pub fn encode_optional(timestamp: Option<OffsetDateTime>) -> i128 {
+		timestamp.map(Self::encode).unwrap_or_default()
+	}
+
+	pub fn encode_duration(duration: Duration) -> i128 {
+		duration.whole_nanoseconds()
+	}
+
+	/// Decodes a score, accepting both nanosecond scores and the second
+	/// scores written before timestamps were normalized.
+	pub fn decode(score: i128) -> Option<OffsetDateTime> {
+		OffsetDateTime::from_unix_timestamp_nanos(Self::normalize(score)).ok()
+	}
+
+	/// Rescales a second-precision score to nanoseconds, leaving nanosecond
+	/// scores untouched.
+	pub fn normalize(score: i128) -> i128 {
+		if score.abs() < SECONDS_SCORE_LIMIT {
+			score * NANOS_PER_SECOND
+		} else {
+			score
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_round_trips_nanosecond_scores() {
+		let timestamp =
+			OffsetDateTime::from_unix_timestamp_nanos(1_752_580_800_123_456_789)
+				.unwrap();
+
+		assert_eq!(
+			TimestampCodec::decode(TimestampCodec::encode(timestamp)),
+			Some(timestamp)
+		);
+	}
+
+	#[test]
+	fn test_normalizes_second_scores() {
+		let timestamp = OffsetDateTime::from_unix_timestamp(1_752_580_800).unwrap();
+
+		assert_eq!(
+			TimestampCodec::normalize(1_752_580_800),
+			TimestampCodec::encode(timestamp)
+		);
+		assert_eq!(TimestampCodec::decode(1_752_580_800), Some(timestamp));
+		assert_eq!(
+			TimestampCodec::normalize(TimestampCodec::encode(timestamp)),
+			TimestampCodec::encode(timestamp)
+		);
+	}
+}
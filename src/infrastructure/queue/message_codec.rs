@@ -0,0 +1,169 @@
+use std::fmt;
+use std::io::{Read, Write};
+
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Flag set when the body was gzip-compressed.
+const COMPRESSED: u8 = 0b01;
+/// Flag set when a CRC32 of the stored body follows the flags byte.
+const CHECKSUMMED: u8 = 0b10;
+const CHECKSUM_LEN: usize = 4;
+
+/// The stored body does not match its checksum, or the frame is cut short.
+#[derive(Debug)]
+pub struct CorruptedMessageError;
+
+impl fmt::Display for CorruptedMessageError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "Queue message failed its checksum")
+	}
+}
+
+impl std::error::Error for CorruptedMessageError {}
+
+/// Turns queue messages into the bytes stored in Redis and back. Messages
+/// are stored as plain JSON unless compression or checksums are enabled, in
+/// which case a flags byte, the optional CRC32 and the body are stored. JSON
+/// never starts with a control byte, so it cannot be mistaken for flags.
+/// Every frame is decoded regardless of the local settings, so instances
+/// configured differently can share a queue.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MessageCodec {
+	compression_threshold: Option<usize>,
+	checksums:             bool,
+}
+
+impl MessageCodec {
+	/// Compresses messages whose JSON is at least `threshold` bytes long.
+	pub fn with_compression(mut self, threshold: usize) -> Self {
+		self.compression_threshold = Some(threshold);
+		self
+	}
+
+	/// Stores a CRC32 of every message, verified when it is decoded.
+	pub fn with_checksums(mut self) -> Self {
+		self.checksums = true;
+		self
+	}
+
+	pub fn encode<T: Serialize>(
+		&self,
+		message: &T,
+	) -> Result<Vec<u8>, Box<dyn std::error::Error + Send>> {
+		let json = serde_json::to_vec(message)
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+		let mut flags = 0;
+		let body = match self.compression_threshold {
+			Some(threshold) if json.len() >= threshold => {
+				flags |= COMPRESSED;
+				let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+				encoder
+					.write_all(&json)
+					.and_then(|_| encoder.finish())
+					.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?
+			}
+			_ => json,
+		};
+		if self.checksums {
+			flags |= CHECKSUMMED;
+		}
+
+		if flags == 0 {
+			return Ok(body);
+		}
+
+		let mut frame = Vec::with_capacity(1 + CHECKSUM_LEN + body.len());
+		frame.push(flags);
+		if flags & CHECKSUMMED != 0 {
+			frame.extend_from_slice(&crc32fast::hash(&body).to_be_bytes());
+		}
+		frame.extend_from_slice(&body);
+		Ok(frame)
+	}
+
+	pub fn decode<T: DeserializeOwned>(
+		&self,
+		bytes: &[u8],
+	) -> Result<T, Box<dyn std::error::Error + Send>> {
+		let (flags, mut body) = match bytes.split_first() {
+			Some((&flags, body)) if flags <= COMPRESSED | CHECKSUMMED => {
+				(flags, body)
+			}
+			_ => (0, bytes),
+		};
+
+		if flags & CHECKSUMMED != 0 {
+			if body.len() < CHECKSUM_LEN {
+				return Err(Box::new(CorruptedMessageError));
+			}
+			let (checksum, checksummed_body) = body.split_at(CHECKSUM_LEN);
+			if checksum != crc32fast::hash(checksummed_body).to_be_bytes() {
+				return Err(Box::new(CorruptedMessageError));
+			}
+			body = checksummed_body;
+		}
+
+		if flags & COMPRESSED != 0 {
+			let mut json = Vec::new();
+			GzDecoder::new(body)
+				.read_to_end(&mut json)
+				.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+			return serde_json::from_slice(&json)
+				.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>);
+		}
+
+		serde_json::from_slice(body)
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_keeps_small_messages_as_plain_json() {
+		let codec = MessageCodec::default().with_compression(64);
+
+		let bytes = codec.encode(&"small").unwrap();
+
+		assert_eq!(bytes, b"\"small\"");
+		assert_eq!(codec.decode::<String>(&bytes).unwrap(), "small");
+	}
+
+	#[test]
+	fn test_compresses_large_messages() {
+		let message = "x".repeat(1024);
+
+		let bytes = MessageCodec::default()
+			.with_compression(64)
+			.encode(&message)
+			.unwrap();
+
+		assert_eq!(bytes[0], COMPRESSED);
+		assert!(bytes.len() < message.len());
+		assert_eq!(
+			MessageCodec::default().decode::<String>(&bytes).unwrap(),
+			message
+		);
+	}
+
+	#[test]
+	fn test_detects_corrupted_checksummed_messages() {
+		let codec = MessageCodec::default().with_checksums();
+		let mut bytes = codec.encode(&vec![1, 2, 3]).unwrap();
+
+		assert_eq!(codec.decode::<Vec<u8>>(&bytes).unwrap(), vec![1, 2, 3]);
+
+		*bytes.last_mut().unwrap() = b'4';
+		assert!(codec.decode::<Vec<u8>>(&bytes).is_err());
+
+		bytes.truncate(3);
+		assert!(codec.decode::<Vec<u8>>(&bytes).is_err());
+	}
+}
@@ -1 +1,3 @@
+pub mod message_codec;
+pub mod redis_payment_ingest_stream;
 pub mod redis_payment_queue;
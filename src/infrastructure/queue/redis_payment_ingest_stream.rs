@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use redis::streams::{StreamReadOptions, StreamReadReply};
+use redis::{AsyncCommands, Client, RedisError};
+
+use crate::infrastructure::config::redis::{
+	PAYMENTS_INGEST_GROUP, PAYMENTS_INGEST_STREAM_KEY,
+};
+
+const DEFAULT_BATCH_SIZE: usize = 100;
+const DEFAULT_BLOCK_TIMEOUT: Duration = Duration::from_secs(1);
+/// Shortest block timeout, as Redis blocks forever on a timeout of zero.
+const MIN_BLOCK_TIMEOUT: Duration = Duration::from_millis(1);
+const DEFAULT_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+/// Id before every entry, to read the pending entries from the first one.
+pub const FIRST_ENTRY_ID: &str = "0";
+
+/// One entry published to the ingest stream, with its fields as strings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IngestEntry {
+	pub id:     String,
+	pub fields: HashMap<String, String>,
+}
+
+/// Redis stream where sidecar producers publish payments directly, read
+/// through a consumer group so that several instances share the entries.
+#[derive(Clone)]
+pub struct PaymentIngestStream {
+	client:         Client,
+	consumer:       String,
+	batch_size:     usize,
+	block_timeout:  Duration,
+	retry_interval: Duration,
+}
+
+impl PaymentIngestStream {
+	pub fn new(client: Client, consumer: impl Into<String>) -> Self {
+		Self {
+			client,
+			consumer: consumer.into(),
+			batch_size: DEFAULT_BATCH_SIZE,
+			block_timeout: DEFAULT_BLOCK_TIMEOUT,
+			retry_interval: DEFAULT_RETRY_INTERVAL,
+		}
+	}
+
+	/// How many entries a single read returns at most.
+	pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+		self.batch_size = batch_size;
+		self
+	}
+
+	/// How long a read blocks waiting for new entries before giving up, at
+	/// least a millisecond.
+	pub fn with_block_timeout(mut self, block_timeout: Duration) -> Self {
+		self.block_timeout = block_timeout.max(MIN_BLOCK_TIMEOUT);
+		self
+	}
+
+	/// How often entries read but never acknowledged, as they could not be
+	/// queued, are read again.
+	pub fn with_retry_interval(mut self, retry_interval: Duration) -> Self {
+		self.retry_interval = retry_interval;
+		self
+	}
+
+	pub fn retry_interval(&self) -> Duration {
+		self.retry_interval
+	}
+
+	/// Creates the stream and its consumer group unless they already exist.
+	/// The group starts at the beginning of the stream, so entries published
+	/// before the first instance started are ingested too.
+	pub async fn create_group(
+		&self,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		let mut con = self
+			.client
+			.get_multiplexed_async_connection()
+			.await
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+		let created: Result<(), RedisError> = con
+			.xgroup_create_mkstream(
+				PAYMENTS_INGEST_STREAM_KEY,
+				PAYMENTS_INGEST_GROUP,
+				FIRST_ENTRY_ID,
+			)
+			.await;
+
+		match created {
+			Err(e) if e.code() != Some("BUSYGROUP") => {
+				Err(Box::new(e) as Box<dyn std::error::Error + Send>)
+			}
+			_ => Ok(()),
+		}
+	}
+
+	/// Reads the next batch of entries. With `pending_after` set, returns
+	/// instead the entries delivered to this consumer earlier but never
+	/// acknowledged whose id comes after it, without blocking.
+	pub async fn read(
+		&self,
+		pending_after: Option<&str>,
+	) -> Result<Vec<IngestEntry>, Box<dyn std::error::Error + Send>> {
+		let mut con = self
+			.client
+			.get_multiplexed_async_connection()
+			.await
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+		let options = StreamReadOptions::default()
+			.group(PAYMENTS_INGEST_GROUP, &self.consumer)
+			.count(self.batch_size)
+			.block(self.block_timeout.as_millis() as usize);
+		let start = pending_after.unwrap_or(">");
+
+		let reply: Option<StreamReadReply> = con
+			.xread_options(&[PAYMENTS_INGEST_STREAM_KEY], &[start], &options)
+			.await
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+		Ok(reply
+			.into_iter()
+			.flat_map(|reply| reply.keys)
+			.flat_map(|key| key.ids)
+			.map(|entry| IngestEntry {
+				fields: entry
+					.map
+					.iter()
+					.filter_map(|(field, value)| {
+						redis::from_redis_value::<String>(value)
+							.ok()
+							.map(|value| (field.clone(), value))
+					})
+					.collect(),
+				id:     entry.id,
+			})
+			.collect())
+	}
+
+	/// Acknowledges the entries and removes them from the stream, as every
+	/// instance reads through the same group and nothing else replays them.
+	pub async fn ack(
+		&self,
+		ids: &[String],
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		if ids.is_empty() {
+			return Ok(());
+		}
+
+		let mut con = self
+			.client
+			.get_multiplexed_async_connection()
+			.await
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+		redis::pipe()
+			.atomic()
+			.xack(PAYMENTS_INGEST_STREAM_KEY, PAYMENTS_INGEST_GROUP, ids)
+			.ignore()
+			.xdel(PAYMENTS_INGEST_STREAM_KEY, ids)
+			.ignore()
+			.query_async::<()>(&mut con)
+			.await
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)
+	}
+}
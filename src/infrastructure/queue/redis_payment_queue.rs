@@ -1,22 +1,468 @@
-use async_trait::async_trait;
-use redis::{AsyncCommands, Client};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use log::{error, info, warn};
+use redis::aio::MultiplexedConnection;
+use redis::{AsyncCommands, Client, RedisError, Script};
+use time::OffsetDateTime;
 
 use crate::domain::payment::Payment;
-use crate::domain::queue::{Message, Queue};
-use crate::infrastructure::config::redis::PAYMENTS_QUEUE_KEY;
+use crate::domain::queue::{Message, Queue, QueueError};
+use crate::domain::repository::DuplicateStage;
+use crate::infrastructure::config::redis::{
+	DEAD_LETTERED_PAYMENTS_KEY_PREFIX, DELIVERED_SEQUENCES_KEY,
+	PAYMENT_DUPLICATES_KEY_PREFIX, PAYMENTS_QUEUE_KEY, PAYMENTS_SEQUENCE_KEY,
+	PROCESSOR_PAYMENTS_QUEUE_KEY_PREFIX, QUARANTINED_MESSAGES_KEY_PREFIX,
+	QUEUED_PAYMENT_KEY_PREFIX,
+};
+use crate::infrastructure::observability::log_redaction;
+use crate::infrastructure::observability::metrics::metrics;
+use crate::infrastructure::persistence::redis_connection::SupervisedConnection;
+use crate::infrastructure::persistence::timestamp_codec::TimestampCodec;
+use crate::infrastructure::queue::message_codec::MessageCodec;
+
+const DEFAULT_POP_TIMEOUT: Duration = Duration::from_secs(1);
+/// Shortest pop timeout: Redis blocks forever on a timeout of zero, and the
+/// workers would never see a pause or shutdown while the queue is empty.
+const MIN_POP_TIMEOUT: Duration = Duration::from_millis(1);
 
 #[derive(Clone)]
 pub struct PaymentQueue {
-	client: Client,
+	client:      Client,
+	key:         String,
+	track_depth: bool,
+	pop_timeout: Duration,
+	dedup_ttl:   Option<Duration>,
+	codec:       MessageCodec,
+	cutover:     Option<Arc<Cutover>>,
+	sequencing:  bool,
+	connection:  Option<SupervisedConnection>,
+	max_age:     Option<Duration>,
+}
+
+/// How many sequenced messages were queued and delivered so far.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SequenceReport {
+	pub issued:    u64,
+	pub delivered: u64,
+	pub queued:    u64,
+}
+
+impl SequenceReport {
+	/// Sequenced messages that were neither delivered nor are still queued,
+	/// that is, lost.
+	pub fn gaps(&self) -> u64 {
+		self.issued
+			.saturating_sub(self.delivered)
+			.saturating_sub(self.queued)
+	}
+}
+
+/// A previous queue key still consumed, ahead of the current one, until the
+/// cutover ends.
+struct Cutover {
+	key:         String,
+	/// How long the previous key must stay empty before the cutover ends on
+	/// its own. Without it the cutover lasts until it is no longer configured.
+	grace:       Option<Duration>,
+	empty_since: Mutex<Option<Instant>>,
+	drained:     AtomicBool,
 }
 
 impl PaymentQueue {
+	/// The ingest queue every accepted payment is pushed to.
 	pub fn new(client: Client) -> Self {
-		Self { client }
+		Self {
+			client,
+			key: PAYMENTS_QUEUE_KEY.to_string(),
+			track_depth: true,
+			pop_timeout: DEFAULT_POP_TIMEOUT,
+			dedup_ttl: None,
+			codec: MessageCodec::default(),
+			cutover: None,
+			sequencing: false,
+			connection: None,
+			max_age: None,
+		}
+	}
+
+	/// A queue holding the payments dispatched to a single processor. Its
+	/// depth is not part of the ingest queue depth gauge.
+	pub fn for_processor(client: Client, processor: &str) -> Self {
+		Self {
+			client,
+			key: format!("{PROCESSOR_PAYMENTS_QUEUE_KEY_PREFIX}:{processor}"),
+			track_depth: false,
+			pop_timeout: DEFAULT_POP_TIMEOUT,
+			dedup_ttl: None,
+			codec: MessageCodec::default(),
+			cutover: None,
+			sequencing: false,
+			connection: None,
+			max_age: None,
+		}
+	}
+
+	/// Stores the payments under `key` instead of the default key.
+	pub fn with_key(mut self, key: impl Into<String>) -> Self {
+		self.key = key.into();
+		self
+	}
+
+	/// Keeps consuming the payments left under `previous_key`, before those of
+	/// the current key, while new payments are only pushed to the current
+	/// key. Lets instances move to a new queue key without downtime or losing
+	/// the backlog, as instances not yet moved keep pushing to the previous
+	/// key. The previous key is read until it has stayed empty for `grace`,
+	/// or for as long as the queue is built with it when there is no grace;
+	/// see [`Self::drain_remaining`].
+	pub fn with_cutover_from(
+		mut self,
+		previous_key: impl Into<String>,
+		grace: Option<Duration>,
+	) -> Self {
+		self.cutover = Some(Arc::new(Cutover {
+			key: previous_key.into(),
+			grace,
+			empty_since: Mutex::new(None),
+			drained: AtomicBool::new(false),
+		}));
+		self
+	}
+
+	/// Numbers new messages with a sequence shared by every instance and
+	/// records which sequences were popped, so lost and doubly delivered
+	/// messages can be counted; see [`Self::sequence_report`]. Only meant for
+	/// the ingest queue, as messages popped from it are pushed again to the
+	/// processor queues.
+	pub fn with_sequencing(mut self) -> Self {
+		self.sequencing = true;
+		self
+	}
+
+	/// Runs every operation but pops over `connection`. Pops block, so they
+	/// keep a connection of their own to not hold up the shared one.
+	pub fn with_supervised_connection(
+		mut self,
+		connection: SupervisedConnection,
+	) -> Self {
+		self.connection = Some(connection);
+		self
+	}
+
+	async fn connection(&self) -> redis::RedisResult<MultiplexedConnection> {
+		match &self.connection {
+			Some(connection) => connection.get().await,
+			None => self.client.get_multiplexed_async_connection().await,
+		}
+	}
+
+	/// How long a pop blocks waiting for a payment before giving up, at least
+	/// a millisecond.
+	pub fn with_pop_timeout(mut self, pop_timeout: Duration) -> Self {
+		self.pop_timeout = pop_timeout.max(MIN_POP_TIMEOUT);
+		self
+	}
+
+	/// Drops new payments whose correlation id is still queued, so client
+	/// retries do not queue the same payment several times. A payment counts
+	/// as queued until it is popped, or for at most `ttl` in case it never is,
+	/// as for messages quarantined undecoded. Re-queued payments are always
+	/// pushed.
+	pub fn with_dedup(mut self, ttl: Duration) -> Self {
+		self.dedup_ttl = Some(ttl);
+		self
+	}
+
+	/// Gzip-compresses messages whose JSON reaches `threshold` bytes, to cut
+	/// Redis memory and traffic when payments carry large metadata.
+	pub fn with_compression(mut self, threshold: usize) -> Self {
+		self.codec = self.codec.with_compression(threshold);
+		self
+	}
+
+	/// Stores a checksum with every message. Popped messages that fail their
+	/// checksum or cannot be decoded are moved to a quarantine list for
+	/// inspection instead of being lost.
+	pub fn with_checksums(mut self) -> Self {
+		self.codec = self.codec.with_checksums();
+		self
+	}
+
+	/// Moves popped payments that have waited longer than `max_age` to a
+	/// dead-letter list instead of handing them out, for when paying fallback
+	/// fees on hopelessly late payments is worse than dropping them. A payment
+	/// waits from when it was last pushed, or from when it was first sent to
+	/// a processor if a failed attempt re-queued it.
+	pub fn with_max_age(mut self, max_age: Duration) -> Self {
+		self.max_age = Some(max_age);
+		self
+	}
+
+	/// How long a payment waited beyond the queue's max age, if it did.
+	fn overdue(&self, message: &Message<Payment>) -> Option<Duration> {
+		let max_age = self.max_age?;
+		let waiting_since = [message.enqueued_at, message.body.requested_at]
+			.into_iter()
+			.flatten()
+			.min()?;
+		let age =
+			Duration::try_from(OffsetDateTime::now_utc() - waiting_since).ok()?;
+		(age > max_age).then_some(age)
+	}
+
+	/// The previous key of a cutover while it still holds payments.
+	fn draining_key(&self) -> Option<&str> {
+		self.cutover
+			.as_deref()
+			.filter(|cutover| !cutover.drained.load(Ordering::Relaxed))
+			.map(|cutover| cutover.key.as_str())
+	}
+
+	/// Payments left under the previous key of a cutover, or `None` when
+	/// there is no cutover. Ends the cutover once the previous key has been
+	/// found empty for its whole grace period, after which it is no longer
+	/// read; see [`Self::cutover_ended`].
+	pub async fn drain_remaining(
+		&self,
+	) -> Result<Option<usize>, Box<dyn std::error::Error + Send>> {
+		let Some(cutover) = &self.cutover else {
+			return Ok(None);
+		};
+		if cutover.drained.load(Ordering::Relaxed) {
+			return Ok(Some(0));
+		}
+
+		let mut con = self.connection().await.map_err(queue_error)?;
+		let remaining: usize = con.llen(&cutover.key).await.map_err(queue_error)?;
+
+		let mut empty_since = cutover
+			.empty_since
+			.lock()
+			.unwrap_or_else(|poisoned| poisoned.into_inner());
+		if remaining > 0 {
+			*empty_since = None;
+		} else if let Some(grace) = cutover.grace {
+			let empty_since = *empty_since.get_or_insert_with(Instant::now);
+			if empty_since.elapsed() >= grace {
+				cutover.drained.store(true, Ordering::Relaxed);
+			}
+		}
+		Ok(Some(remaining))
+	}
+
+	/// Whether the previous key of a cutover is no longer read.
+	pub fn cutover_ended(&self) -> bool {
+		self.cutover
+			.as_deref()
+			.is_some_and(|cutover| cutover.drained.load(Ordering::Relaxed))
+	}
+
+	/// Key of the list the queue holds its payments in.
+	pub fn key(&self) -> &str {
+		&self.key
+	}
+
+	/// Up to `limit` of the payments most recently dead-lettered for waiting
+	/// past the queue's max age. Entries that no longer decode are skipped.
+	pub async fn dead_lettered_payments(
+		&self,
+		limit: usize,
+	) -> Result<Vec<Payment>, Box<dyn std::error::Error + Send>> {
+		let mut con = self.connection().await.map_err(queue_error)?;
+		let dead_letter_key =
+			format!("{DEAD_LETTERED_PAYMENTS_KEY_PREFIX}:{}", self.key);
+		let entries: Vec<Vec<u8>> = con
+			.lrange(&dead_letter_key, 0, limit as isize - 1)
+			.await
+			.map_err(queue_error)?;
+
+		Ok(entries
+			.iter()
+			.filter_map(|entry| self.codec.decode::<Message<Payment>>(entry).ok())
+			.map(|message| message.body)
+			.collect())
+	}
+
+	/// Compares the sequences issued with those delivered, or returns `None`
+	/// when the queue does not number its messages.
+	pub async fn sequence_report(
+		&self,
+	) -> Result<Option<SequenceReport>, Box<dyn std::error::Error + Send>> {
+		if !self.sequencing {
+			return Ok(None);
+		}
+
+		let mut con = self.connection().await.map_err(queue_error)?;
+		let (issued, delivered): (Option<u64>, u64) = redis::pipe()
+			.get(PAYMENTS_SEQUENCE_KEY)
+			.bitcount(DELIVERED_SEQUENCES_KEY)
+			.query_async(&mut con)
+			.await
+			.map_err(queue_error)?;
+		let queued = Queue::depth(self).await? as u64;
+
+		Ok(Some(SequenceReport {
+			issued: issued.unwrap_or_default(),
+			delivered,
+			queued,
+		}))
+	}
+
+	/// Marks the sequence of a message popped for the first time as delivered,
+	/// reporting it when it already was.
+	async fn record_delivery(
+		&self,
+		con: &mut redis::aio::MultiplexedConnection,
+		message: &Message<Payment>,
+	) {
+		let Some(sequence) = message.sequence.filter(|_| !message.is_retry()) else {
+			return;
+		};
+
+		match con
+			.setbit::<_, bool>(DELIVERED_SEQUENCES_KEY, sequence as usize, true)
+			.await
+		{
+			Ok(true) => {
+				warn!(
+					"Message {sequence} (payment {}) was delivered more than once",
+					log_redaction::correlation_id(message.body.correlation_id)
+				);
+				metrics().record_redelivered();
+			}
+			Ok(false) => {}
+			Err(e) => error!("Failed to record delivery of message {sequence}: {e}"),
+		}
+	}
+
+	/// Adds a payment dropped as already queued to the duplicates report,
+	/// where the payment repository reads it back.
+	async fn record_duplicate(
+		&self,
+		con: &mut redis::aio::MultiplexedConnection,
+		message: &Message<Payment>,
+	) {
+		let caught_at = TimestampCodec::encode(time::OffsetDateTime::now_utc());
+		let recorded: redis::RedisResult<()> = con
+			.zadd(
+				format!(
+					"{PAYMENT_DUPLICATES_KEY_PREFIX}:{}",
+					DuplicateStage::Ingest.as_str()
+				),
+				format!("{}:{caught_at}", message.body.correlation_id),
+				caught_at,
+			)
+			.await;
+		if let Err(e) = recorded {
+			error!(
+				"Failed to record duplicate payment {}: {e}",
+				log_redaction::correlation_id(message.body.correlation_id)
+			);
+		}
+	}
+
+	async fn dead_letter(
+		&self,
+		con: &mut redis::aio::MultiplexedConnection,
+		message: &Message<Payment>,
+		age: Duration,
+		serialized_message: Vec<u8>,
+	) {
+		warn!(
+			"Dead-lettering payment {} after {}ms in {}",
+			log_redaction::correlation_id(message.body.correlation_id),
+			age.as_millis(),
+			self.key
+		);
+		let dead_letter_key =
+			format!("{DEAD_LETTERED_PAYMENTS_KEY_PREFIX}:{}", self.key);
+		match con
+			.lpush::<_, _, ()>(&dead_letter_key, serialized_message)
+			.await
+		{
+			Ok(()) => metrics().record_dead_lettered(),
+			Err(e) => error!(
+				"Failed to dead-letter payment {} from {}: {e}",
+				log_redaction::correlation_id(message.body.correlation_id),
+				self.key
+			),
+		}
+	}
+
+	async fn quarantine(
+		&self,
+		con: &mut redis::aio::MultiplexedConnection,
+		serialized_message: Vec<u8>,
+	) {
+		let quarantine_key =
+			format!("{QUARANTINED_MESSAGES_KEY_PREFIX}:{}", self.key);
+		match con
+			.lpush::<_, _, ()>(&quarantine_key, serialized_message)
+			.await
+		{
+			Ok(()) => metrics().record_quarantined(),
+			Err(e) => error!("Failed to quarantine message from {}: {e}", self.key),
+		}
+	}
+
+	fn queued_marker_key(queue_key: &str, message: &Message<Payment>) -> String {
+		format!(
+			"{QUEUED_PAYMENT_KEY_PREFIX}:{queue_key}:{}",
+			message.body.correlation_id
+		)
+	}
+
+	/// Unmarks a popped payment as queued, so it can be submitted again.
+	async fn release_queued_marker(
+		&self,
+		con: &mut redis::aio::MultiplexedConnection,
+		queue_key: &str,
+		message: &Message<Payment>,
+	) {
+		if self.dedup_ttl.is_none() {
+			return;
+		}
+
+		let released: redis::RedisResult<()> =
+			con.del(Self::queued_marker_key(queue_key, message)).await;
+		if let Err(e) = released {
+			error!(
+				"Failed to unmark payment {} as queued: {e}",
+				log_redaction::correlation_id(message.body.correlation_id)
+			);
+		}
+	}
+
+	/// Pushes the message unless its id is marked as queued, marking it for
+	/// `ttl`. Returns whether it was pushed.
+	async fn push_unless_queued(
+		&self,
+		con: &mut redis::aio::MultiplexedConnection,
+		message: &Message<Payment>,
+		serialized_message: Vec<u8>,
+		ttl: Duration,
+	) -> redis::RedisResult<bool> {
+		let lua = Script::new(
+			r#"
+            if redis.call("SET", KEYS[2], 1, "NX", "PX", ARGV[2]) then
+                redis.call("LPUSH", KEYS[1], ARGV[1])
+                return 1
+            end
+            return 0
+        "#,
+		);
+
+		lua.key(&self.key)
+			.key(Self::queued_marker_key(&self.key, message))
+			.arg(serialized_message)
+			.arg(ttl.as_millis().max(1) as u64)
+			.invoke_async(con)
+			.await
 	}
 }
 
-#[async_trait]
 impl Queue<Payment> for PaymentQueue {
 	async fn pop(
 		&self,
@@ -26,43 +472,179 @@ impl Queue<Payment> for PaymentQueue {
 			.clone()
 			.get_multiplexed_async_connection()
 			.await
-			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+			.map_err(queue_error)?;
 
-		let popped_value: Option<(String, String)> = con
-			.brpop(PAYMENTS_QUEUE_KEY, 1.0)
-			.await
-			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+		// BRPOP pops from the first non-empty key, so a cutover drains the
+		// previous key first.
+		let keys = self
+			.draining_key()
+			.into_iter()
+			.chain([self.key.as_str()])
+			.collect::<Vec<_>>();
+		loop {
+			let popped_value: Option<(String, Vec<u8>)> = con
+				.brpop(&keys, self.pop_timeout.as_secs_f64())
+				.await
+				.map_err(queue_error)?;
+
+			let (queue_name, message_json) =
+				if let Some((queue_name, serialized_message)) = popped_value {
+					(queue_name, serialized_message)
+				} else {
+					return Ok(None);
+				};
+			if self.track_depth {
+				metrics().record_dequeued();
+			}
+
+			match self.codec.decode(&message_json) {
+				Ok(message) => {
+					self.release_queued_marker(&mut con, &queue_name, &message)
+						.await;
+					if self.sequencing {
+						self.record_delivery(&mut con, &message).await;
+					}
+					if let Some(age) = self.overdue(&message) {
+						self.dead_letter(&mut con, &message, age, message_json)
+							.await;
+						continue;
+					}
+					return Ok(Some(message));
+				}
+				Err(e) => {
+					error!(
+						"Quarantining undecodable message from {}: {e}",
+						self.key
+					);
+					self.quarantine(&mut con, message_json).await;
+					return Err(e);
+				}
+			}
+		}
+	}
+
+	async fn peek(
+		&self,
+		mut offset: usize,
+		limit: usize,
+	) -> Result<Vec<Message<Payment>>, Box<dyn std::error::Error + Send>> {
+		let mut con = self.connection().await.map_err(queue_error)?;
+
+		let mut messages = Vec::new();
+		for key in self.draining_key().into_iter().chain([self.key.as_str()]) {
+			let wanted = limit - messages.len();
+			if wanted == 0 {
+				break;
+			}
+
+			// Messages are pushed to the head and popped from the tail, so
+			// pages are counted from the tail, where pushes do not move them.
+			let stop = -(offset.min(isize::MAX as usize - 1) as isize) - 1;
+			let start =
+				stop.saturating_sub(wanted.min(isize::MAX as usize) as isize) + 1;
+			let serialized_messages: Vec<Vec<u8>> =
+				con.lrange(key, start, stop).await.map_err(queue_error)?;
+
+			if serialized_messages.is_empty() {
+				let len: usize = con.llen(key).await.map_err(queue_error)?;
+				offset = offset.saturating_sub(len);
+				continue;
+			}
+			offset = 0;
+			for message_json in serialized_messages.iter().rev() {
+				messages.push(self.codec.decode(message_json)?);
+			}
+		}
+		Ok(messages)
+	}
 
-		let message_json =
-			if let Some((_queue_name, serialized_message)) = popped_value {
-				serialized_message
-			} else {
-				return Ok(None);
-			};
+	async fn depth(&self) -> Result<usize, Box<dyn std::error::Error + Send>> {
+		let mut con = self.connection().await.map_err(queue_error)?;
 
-		let message: Message<Payment> = serde_json::from_str(&message_json)
-			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+		let mut depth = 0;
+		for key in self.draining_key().into_iter().chain([self.key.as_str()]) {
+			depth += con.llen::<_, usize>(key).await.map_err(queue_error)?;
+		}
+		Ok(depth)
+	}
+
+	async fn oldest_enqueued_at(
+		&self,
+	) -> Result<Option<OffsetDateTime>, Box<dyn std::error::Error + Send>> {
+		let mut con = self.connection().await.map_err(queue_error)?;
 
-		Ok(Some(message))
+		// The tail of the first non-empty key is the next message popped.
+		for key in self.draining_key().into_iter().chain([self.key.as_str()]) {
+			let oldest: Option<Vec<u8>> =
+				con.lindex(key, -1).await.map_err(queue_error)?;
+			if let Some(message_json) = oldest {
+				let message: Message<Payment> = self.codec.decode(&message_json)?;
+				return Ok(message.enqueued_at);
+			}
+		}
+		Ok(None)
 	}
 
 	async fn push(
 		&self,
-		message: Message<Payment>,
+		mut message: Message<Payment>,
 	) -> Result<(), Box<dyn std::error::Error + Send>> {
-		let mut con = self
-			.client
-			.get_multiplexed_async_connection()
-			.await
-			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+		let mut con = self.connection().await.map_err(queue_error)?;
 
-		let serialized_message = serde_json::to_string(&message)
-			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+		message.enqueued_at = Some(OffsetDateTime::now_utc());
 
-		let _: () = con
-			.lpush(PAYMENTS_QUEUE_KEY, serialized_message)
-			.await
-			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+		if self.sequencing && message.sequence.is_none() {
+			message.sequence = Some(
+				con.incr(PAYMENTS_SEQUENCE_KEY, 1)
+					.await
+					.map_err(queue_error)?,
+			);
+		}
+
+		let serialized_message = self.codec.encode(&message)?;
+
+		match self.dedup_ttl {
+			Some(ttl) if !message.is_retry() => {
+				let pushed = self
+					.push_unless_queued(&mut con, &message, serialized_message, ttl)
+					.await
+					.map_err(queue_error)?;
+				if !pushed {
+					info!(
+						"Payment {} is already queued. Dropping it.",
+						log_redaction::correlation_id(message.body.correlation_id)
+					);
+					metrics().record_duplicated();
+					self.record_duplicate(&mut con, &message).await;
+					// Its sequence is retired, so it is not counted as lost.
+					self.record_delivery(&mut con, &message).await;
+					return Ok(());
+				}
+			}
+			_ => {
+				let _: () = con
+					.lpush(&self.key, serialized_message)
+					.await
+					.map_err(queue_error)?;
+			}
+		}
+		if self.track_depth {
+			metrics().record_enqueued();
+		}
 		Ok(())
 	}
 }
+
+/// Reports connection failures as [`QueueError::Unavailable`], so callers can
+/// tell an unreachable Redis from other failures.
+fn queue_error(e: RedisError) -> Box<dyn std::error::Error + Send> {
+	if e.is_io_error() ||
+		e.is_connection_refusal() ||
+		e.is_connection_dropped() ||
+		e.is_timeout()
+	{
+		Box::new(QueueError::Unavailable(e.to_string()))
+	} else {
+		Box::new(e)
+	}
+}
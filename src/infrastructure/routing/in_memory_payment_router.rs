@@ -1,36 +1,319 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
-use async_trait::async_trait;
-use circuitbreaker_rs::{CircuitBreaker, DefaultPolicy};
+use circuitbreaker_rs::{CircuitBreaker, DefaultPolicy, State};
+use time::OffsetDateTime;
+use tokio::sync::{Notify, watch};
 
-use crate::domain::payment_processor::PaymentProcessor;
+use crate::domain::health_sample::HealthSample;
+use crate::domain::health_status::HealthStatus;
+use crate::domain::payment_processor::{PROCESSOR_GROUPS, PaymentProcessor};
 use crate::domain::payment_router::PaymentRouter;
+use crate::domain::processor_health_reporter::ProcessorHealthReporter;
+use crate::domain::processor_selection::ProcessorSelection;
+use crate::domain::processor_settings::ProcessorSettings;
+use crate::infrastructure::observability::breaker_events::BreakerEventSink;
+use crate::infrastructure::routing::slow_start::SlowStartPolicy;
 use crate::use_cases::process_payment::PaymentProcessingError;
 
+/// Health checks kept per processor, an hour of them at the default
+/// interval.
+pub const HEALTH_HISTORY_CAPACITY: usize = 720;
+
 #[derive(Clone)]
 pub struct InMemoryPaymentRouter {
-	pub processors:       Arc<RwLock<HashMap<String, PaymentProcessor>>>,
-	pub default_breaker:  CircuitBreaker<DefaultPolicy, PaymentProcessingError>,
-	pub fallback_breaker: CircuitBreaker<DefaultPolicy, PaymentProcessingError>,
+	pub processors:        Arc<RwLock<HashMap<String, PaymentProcessor>>>,
+	pub default_breaker:   CircuitBreaker<DefaultPolicy, PaymentProcessingError>,
+	pub fallback_breaker:  CircuitBreaker<DefaultPolicy, PaymentProcessingError>,
+	default_settings:      Arc<watch::Sender<ProcessorSettings>>,
+	fallback_settings:     Arc<watch::Sender<ProcessorSettings>>,
+	pub slow_start:        Option<Arc<SlowStartPolicy>>,
+	pub failure_threshold: u32,
+	pub success_threshold: u32,
+	/// Processors in the order payments try them.
+	preference:            [&'static str; 2],
+	health_checked:        Arc<Notify>,
+	reset_requested:       Arc<Notify>,
+	/// Latest health checks of each processor, oldest first.
+	health_history:        Arc<RwLock<HashMap<String, VecDeque<HealthSample>>>>,
+	selection_cache:       Option<Arc<SelectionCache>>,
+}
+
+/// Processor picked for the last payments, reused until it expires, either
+/// breaker changes state or the routing inputs are invalidated.
+struct SelectionCache {
+	ttl:        Duration,
+	/// Bumped every time a health check or setting changes what the router
+	/// knows about the processors.
+	generation: AtomicU64,
+	cached:     RwLock<Option<CachedSelection>>,
+}
+
+struct CachedSelection {
+	selection:      Option<ProcessorSelection>,
+	generation:     u64,
+	breaker_states: [State; 2],
+	expires_at:     Instant,
 }
 
 impl InMemoryPaymentRouter {
 	pub fn new() -> Self {
 		Self {
-			processors:       Arc::new(RwLock::new(HashMap::new())),
+			processors:        Arc::new(RwLock::new(HashMap::new())),
 			default_breaker:
 				CircuitBreaker::<DefaultPolicy, PaymentProcessingError>::builder()
+					.metric_sink(BreakerEventSink::new("default"))
 					.build(),
 			fallback_breaker:
 				CircuitBreaker::<DefaultPolicy, PaymentProcessingError>::builder()
+					.metric_sink(BreakerEventSink::new("fallback"))
 					.build(),
+			default_settings:  Arc::new(watch::Sender::new(ProcessorSettings::new(
+				"",
+			))),
+			fallback_settings: Arc::new(watch::Sender::new(ProcessorSettings::new(
+				"",
+			))),
+			slow_start:        None,
+			failure_threshold: 1,
+			success_threshold: 1,
+			preference:        PROCESSOR_GROUPS,
+			health_checked:    Arc::new(Notify::new()),
+			reset_requested:   Arc::new(Notify::new()),
+			health_history:    Arc::new(RwLock::new(HashMap::new())),
+			selection_cache:   None,
+		}
+	}
+
+	/// Sets the base URLs the processors are health checked at.
+	pub fn with_processor_urls(self, default: &str, fallback: &str) -> Self {
+		self.default_settings
+			.send_modify(|settings| settings.url = default.to_string());
+		self.fallback_settings
+			.send_modify(|settings| settings.url = fallback.to_string());
+		self
+	}
+
+	/// Tries `processor` first for every payment, and the other processor
+	/// only when it is unavailable. Useful when this instance runs closer to
+	/// the fallback processor than to the default one. Unknown names leave the
+	/// default preference in place.
+	pub fn with_preferred_processor(mut self, processor: &str) -> Self {
+		if let Some(preferred) =
+			PROCESSOR_GROUPS.iter().position(|name| *name == processor)
+		{
+			self.preference = PROCESSOR_GROUPS;
+			self.preference.swap(0, preferred);
 		}
+		self
+	}
+
+	/// Ramps traffic up over `window` when a processor's breaker closes again.
+	pub fn with_slow_start(mut self, window: Duration) -> Self {
+		self.slow_start = Some(Arc::new(SlowStartPolicy::new(window)));
+		self
+	}
+
+	/// Reuses the processor picked for a payment for the next ones during
+	/// `ttl`, instead of evaluating the routing conditions for each of them.
+	/// Health checks, reported failures, setting changes and breaker
+	/// transitions drop the cached pick right away. Ignored while slow start
+	/// is enabled, as it admits payments one by one.
+	pub fn with_selection_cache(mut self, ttl: Duration) -> Self {
+		self.selection_cache = Some(Arc::new(SelectionCache {
+			ttl,
+			generation: AtomicU64::new(0),
+			cached: RwLock::new(None),
+		}));
+		self
+	}
+
+	/// Requires `failure_threshold` consecutive failed health checks before a
+	/// processor is marked failing, and `success_threshold` consecutive
+	/// successful ones before it is marked healthy again.
+	pub fn with_flap_damping(
+		mut self,
+		failure_threshold: u32,
+		success_threshold: u32,
+	) -> Self {
+		self.failure_threshold = failure_threshold.max(1);
+		self.success_threshold = success_threshold.max(1);
+		self
+	}
+
+	/// Applies the outcome of a health check to the processor's state and
+	/// returns whether it is considered healthy afterwards. The first check of
+	/// a processor is taken at face value.
+	pub fn record_health_check(
+		&self,
+		name: &str,
+		url: &str,
+		healthy: bool,
+		min_response_time: u64,
+	) -> bool {
+		let initial_health = if healthy {
+			HealthStatus::Healthy
+		} else {
+			HealthStatus::Failing
+		};
+
+		let mut processors = self.processors.write().unwrap();
+		let processor = processors.entry(name.to_string()).or_insert_with(|| {
+			PaymentProcessor::new(name, url, initial_health, min_response_time)
+		});
+
+		let checked_at = OffsetDateTime::now_utc();
+		processor.set_url(url);
+		processor.min_response_time = min_response_time;
+		processor.last_updated = Some(checked_at);
+		processor.record_probe(
+			healthy,
+			self.failure_threshold,
+			self.success_threshold,
+		);
+
+		let sample = HealthSample {
+			checked_at,
+			reported_healthy: healthy,
+			healthy: processor.health.is_healthy(),
+			min_response_time,
+		};
+		let healthy = sample.healthy;
+		drop(processors);
+		self.invalidate_selection();
+		self.record_health_sample(name, sample);
+		self.health_checked.notify_waiters();
+
+		healthy
+	}
+
+	/// Health checks of a processor, oldest first, up to
+	/// [`HEALTH_HISTORY_CAPACITY`] of them. Returns `None` when there is no
+	/// processor with that name.
+	pub fn health_history(&self, name: &str) -> Option<Vec<HealthSample>> {
+		self.settings(name)?;
+		let history = self.health_history.read().unwrap();
+		Some(
+			history
+				.get(name)
+				.map(|samples| samples.iter().cloned().collect())
+				.unwrap_or_default(),
+		)
+	}
+
+	/// Forgets the health checks of a processor, leaving those of the other
+	/// one in place. Returns false when there is no processor with that name.
+	pub fn purge_health_history(&self, name: &str) -> bool {
+		if self.settings(name).is_none() {
+			return false;
+		}
+		self.health_history.write().unwrap().remove(name);
+		true
+	}
+
+	/// Resolves once every processor has been health checked at least once, so
+	/// payments are not consumed while the router still knows no processor.
+	pub async fn wait_for_health_checks(&self) {
+		loop {
+			let notified = self.health_checked.notified();
+			tokio::pin!(notified);
+			notified.as_mut().enable();
+
+			let checked = {
+				let processors = self.processors.read().unwrap();
+				PROCESSOR_GROUPS
+					.iter()
+					.all(|name| processors.contains_key(*name))
+			};
+			if checked {
+				return;
+			}
+
+			notified.await;
+		}
+	}
+
+	/// Forgets every processor, closes both breakers and clears their
+	/// counters and slow-start ramps, then asks the health monitor for an
+	/// immediate re-poll. No payment is routed until the processors have been
+	/// checked again. The health history is kept, see
+	/// [`Self::purge_health_history`].
+	pub fn reset(&self) {
+		self.processors.write().unwrap().clear();
+		self.invalidate_selection();
+		for breaker in [&self.default_breaker, &self.fallback_breaker] {
+			breaker.force_closed();
+			breaker.reset_stats();
+		}
+		if let Some(slow_start) = &self.slow_start {
+			slow_start.reset();
+		}
+		self.reset_requested.notify_one();
+	}
+
+	/// Resolves when [`Self::reset`] is called. A reset requested while nobody
+	/// waits is delivered to the next caller.
+	pub async fn reset_requested(&self) {
+		self.reset_requested.notified().await;
+	}
+
+	pub fn processor_settings(&self, name: &str) -> Option<ProcessorSettings> {
+		self.settings(name)
+			.map(|settings| settings.borrow().clone())
+	}
+
+	/// Follows the settings of a processor, see [`Self::configure_processor`].
+	pub fn watch_processor_settings(
+		&self,
+		name: &str,
+	) -> Option<watch::Receiver<ProcessorSettings>> {
+		self.settings(name).map(|settings| settings.subscribe())
+	}
+
+	/// Replaces the settings of a processor at runtime. Payments are sent to
+	/// the new URL right away and the health monitor re-checks the processor
+	/// there. Returns false when there is no processor with that name.
+	pub fn configure_processor(
+		&self,
+		name: &str,
+		settings: ProcessorSettings,
+	) -> bool {
+		let Some(sender) = self.settings(name) else {
+			return false;
+		};
+
+		if let Some(processor) = self.processors.write().unwrap().get_mut(name) {
+			processor.set_url(&settings.url);
+		}
+		sender.send_replace(settings);
+		self.invalidate_selection();
+		true
+	}
+
+	/// Copies of the processors the router knows, ordered by name.
+	pub fn processors_snapshot(&self) -> Vec<PaymentProcessor> {
+		let mut processors = self
+			.processors
+			.read()
+			.unwrap()
+			.values()
+			.cloned()
+			.collect::<Vec<_>>();
+		processors.sort_by(|a, b| a.name.cmp(&b.name));
+		processors
+	}
+
+	pub fn breaker_state(&self, name: &str) -> Option<State> {
+		self.breaker(name).map(CircuitBreaker::current_state)
 	}
 
 	pub fn update_processor_health(&self, processor: PaymentProcessor) {
 		let mut processors = self.processors.write().unwrap();
-		processors.insert(processor.name.clone(), processor);
+		processors.insert(processor.name.to_string(), processor);
+		drop(processors);
+		self.invalidate_selection();
 	}
 }
 
@@ -40,43 +323,146 @@ impl Default for InMemoryPaymentRouter {
 	}
 }
 
-#[async_trait]
 impl PaymentRouter for InMemoryPaymentRouter {
-	async fn get_processor_for_payment(
+	async fn get_processor_for_payment(&self) -> Option<ProcessorSelection> {
+		let Some(cache) = self
+			.selection_cache
+			.as_ref()
+			.filter(|_| self.slow_start.is_none())
+		else {
+			return self.select_processor();
+		};
+
+		let now = Instant::now();
+		let generation = cache.generation.load(Ordering::Acquire);
+		let breaker_states = [
+			self.default_breaker.current_state(),
+			self.fallback_breaker.current_state(),
+		];
+		if let Some(cached) = &*cache.cached.read().unwrap() &&
+			cached.generation == generation &&
+			cached.breaker_states == breaker_states &&
+			cached.expires_at > now
+		{
+			return cached.selection.clone();
+		}
+
+		let selection = self.select_processor();
+		*cache.cached.write().unwrap() = Some(CachedSelection {
+			selection: selection.clone(),
+			generation,
+			breaker_states,
+			expires_at: now + cache.ttl,
+		});
+		selection
+	}
+
+	async fn get_processor(&self, name: &str) -> Option<ProcessorSelection> {
+		self.available_processor(name)
+	}
+}
+
+impl ProcessorHealthReporter for InMemoryPaymentRouter {
+	/// A successful payment only clears the failure streak. Recovering a
+	/// failing processor is left to the health checks.
+	fn report_success(&self, processor: &str) {
+		let mut processors = self.processors.write().unwrap();
+		if let Some(processor) = processors.get_mut(processor) &&
+			processor.health.is_healthy()
+		{
+			processor.consecutive_failures = 0;
+		}
+	}
+
+	/// A failed payment counts like a failed health check.
+	fn report_failure(&self, processor: &str) {
+		let mut processors = self.processors.write().unwrap();
+		if let Some(processor) = processors.get_mut(processor) {
+			processor.record_probe(
+				false,
+				self.failure_threshold,
+				self.success_threshold,
+			);
+		}
+		drop(processors);
+		self.invalidate_selection();
+	}
+}
+
+impl InMemoryPaymentRouter {
+	fn breaker(
 		&self,
-	) -> Option<(
-		String,
-		String,
-		CircuitBreaker<DefaultPolicy, PaymentProcessingError>,
-	)> {
-		let processors = self.processors.read().unwrap();
+		name: &str,
+	) -> Option<&CircuitBreaker<DefaultPolicy, PaymentProcessingError>> {
+		match name {
+			"default" => Some(&self.default_breaker),
+			"fallback" => Some(&self.fallback_breaker),
+			_ => None,
+		}
+	}
 
-		if let Some(default_processor) = processors.get("default") &&
-			default_processor.health.is_healthy() &&
-			default_processor.min_response_time < 100 &&
-			!matches!(
-				self.default_breaker.current_state(),
-				circuitbreaker_rs::State::Open
-			) {
-			return Some((
-				default_processor.url.clone(),
-				default_processor.name.clone(),
-				self.default_breaker.clone(),
-			));
+	fn settings(&self, name: &str) -> Option<&watch::Sender<ProcessorSettings>> {
+		match name {
+			"default" => Some(&self.default_settings),
+			"fallback" => Some(&self.fallback_settings),
+			_ => None,
 		}
+	}
 
-		if let Some(fallback_processor) = processors.get("fallback") &&
-			fallback_processor.health.is_healthy() &&
-			fallback_processor.min_response_time < 100 &&
-			!matches!(
-				self.fallback_breaker.current_state(),
-				circuitbreaker_rs::State::Open
-			) {
-			return Some((
-				fallback_processor.url.clone(),
-				fallback_processor.name.clone(),
-				self.fallback_breaker.clone(),
-			));
+	fn select_processor(&self) -> Option<ProcessorSelection> {
+		let [preferred, other] = self.preference;
+		self.available_processor(preferred)
+			.or_else(|| self.available_processor(other))
+	}
+
+	fn invalidate_selection(&self) {
+		if let Some(cache) = &self.selection_cache {
+			cache.generation.fetch_add(1, Ordering::Release);
+		}
+	}
+
+	fn record_health_sample(&self, name: &str, sample: HealthSample) {
+		let mut history = self.health_history.write().unwrap();
+		let samples = history.entry(name.to_string()).or_default();
+		if samples.len() == HEALTH_HISTORY_CAPACITY {
+			samples.pop_front();
+		}
+		samples.push_back(sample);
+	}
+
+	fn available_processor(&self, name: &str) -> Option<ProcessorSelection> {
+		let breaker = self.breaker(name)?;
+		let (fee, max_response_time_ms) = self
+			.settings(name)
+			.map(|settings| {
+				let settings = settings.borrow();
+				(settings.fee, settings.max_response_time_ms)
+			})
+			.unwrap_or_default();
+
+		let now = Instant::now();
+		let state = breaker.current_state();
+		if let Some(slow_start) = &self.slow_start {
+			slow_start.observe(name, state, now);
+		}
+
+		let processors = self.processors.read().unwrap();
+		let processor = processors.get(name)?;
+
+		if processor.health.is_healthy() &&
+			processor.min_response_time < max_response_time_ms &&
+			!matches!(state, State::Open) &&
+			self.slow_start
+				.as_ref()
+				.is_none_or(|slow_start| slow_start.admit(name, now))
+		{
+			return Some(ProcessorSelection {
+				name: Arc::clone(&processor.name),
+				payments_urls: Arc::clone(&processor.payments_urls),
+				breaker: breaker.clone(),
+				fee,
+				expected_latency: Duration::from_millis(processor.min_response_time),
+			});
 		}
 
 		None
@@ -86,38 +472,45 @@ impl PaymentRouter for InMemoryPaymentRouter {
 #[cfg(test)]
 mod tests {
 
+	use std::time::Duration;
+
 	use circuitbreaker_rs::State;
 	use rinha_de_backend::domain::health_status::HealthStatus;
 	use rinha_de_backend::domain::payment_processor::PaymentProcessor;
 	use rinha_de_backend::domain::payment_router::PaymentRouter;
-	use rinha_de_backend::infrastructure::routing::in_memory_payment_router::InMemoryPaymentRouter;
+	use rinha_de_backend::domain::processor_health_reporter::ProcessorHealthReporter;
+	use rinha_de_backend::domain::processor_settings::ProcessorSettings;
+	use rinha_de_backend::infrastructure::routing::in_memory_payment_router::{
+		HEALTH_HISTORY_CAPACITY, InMemoryPaymentRouter,
+	};
 
 	#[tokio::test]
 	async fn test_get_processor_for_payment_default_healthy() {
 		let router = InMemoryPaymentRouter::new();
-		let default_processor = PaymentProcessor {
-			name:              "default".to_string(),
-			url:               "http://default.com".to_string(),
-			health:            HealthStatus::Healthy,
-			min_response_time: 50,
-		};
+		let default_processor = PaymentProcessor::new(
+			"default",
+			"http://default.com",
+			HealthStatus::Healthy,
+			50,
+		);
 		router.update_processor_health(default_processor.clone());
 
-		let (url, name, breaker) = router.get_processor_for_payment().await.unwrap();
-		assert_eq!(url, default_processor.url);
-		assert_eq!(name, default_processor.name);
-		assert_eq!(breaker.current_state(), State::Closed);
+		let selection = router.get_processor_for_payment().await.unwrap();
+		assert_eq!(selection.payments_urls, default_processor.payments_urls);
+		assert_eq!(selection.name, default_processor.name);
+		assert_eq!(selection.breaker.current_state(), State::Closed);
+		assert_eq!(selection.expected_latency, Duration::from_millis(50));
 	}
 
 	#[tokio::test]
 	async fn test_get_processor_for_payment_default_unhealthy() {
 		let router = InMemoryPaymentRouter::new();
-		let default_processor = PaymentProcessor {
-			name:              "default".to_string(),
-			url:               "http://default.com".to_string(),
-			health:            HealthStatus::Failing,
-			min_response_time: 50,
-		};
+		let default_processor = PaymentProcessor::new(
+			"default",
+			"http://default.com",
+			HealthStatus::Failing,
+			50,
+		);
 		router.update_processor_health(default_processor.clone());
 
 		let result = router.get_processor_for_payment().await;
@@ -127,12 +520,13 @@ mod tests {
 	#[tokio::test]
 	async fn test_get_processor_for_payment_default_slow() {
 		let router = InMemoryPaymentRouter::new();
-		let default_processor = PaymentProcessor {
-			name:              "default".to_string(),
-			url:               "http://default.com".to_string(),
-			health:            HealthStatus::Healthy,
-			min_response_time: 150, // Too slow
-		};
+		// Too slow
+		let default_processor = PaymentProcessor::new(
+			"default",
+			"http://default.com",
+			HealthStatus::Healthy,
+			150,
+		);
 		router.update_processor_health(default_processor.clone());
 
 		let result = router.get_processor_for_payment().await;
@@ -142,12 +536,12 @@ mod tests {
 	#[tokio::test]
 	async fn test_get_processor_for_payment_default_circuit_open() {
 		let router = InMemoryPaymentRouter::new();
-		let default_processor = PaymentProcessor {
-			name:              "default".to_string(),
-			url:               "http://default.com".to_string(),
-			health:            HealthStatus::Healthy,
-			min_response_time: 50,
-		};
+		let default_processor = PaymentProcessor::new(
+			"default",
+			"http://default.com",
+			HealthStatus::Healthy,
+			50,
+		);
 		router.update_processor_health(default_processor.clone());
 
 		router.default_breaker.force_open();
@@ -159,27 +553,28 @@ mod tests {
 	#[tokio::test]
 	async fn test_get_processor_for_payment_fallback_healthy() {
 		let router = InMemoryPaymentRouter::new();
-		let fallback_processor = PaymentProcessor {
-			name:              "fallback".to_string(),
-			url:               "http://fallback.com".to_string(),
-			health:            HealthStatus::Healthy,
-			min_response_time: 50,
-		};
+		let fallback_processor = PaymentProcessor::new(
+			"fallback",
+			"http://fallback.com",
+			HealthStatus::Healthy,
+			50,
+		);
 		router.update_processor_health(fallback_processor.clone());
 
 		// Ensure default is not chosen
-		let default_processor = PaymentProcessor {
-			name:              "default".to_string(),
-			url:               "http://default.com".to_string(),
-			health:            HealthStatus::Failing, // Make default unhealthy
-			min_response_time: 50,
-		};
+		// Make default unhealthy
+		let default_processor = PaymentProcessor::new(
+			"default",
+			"http://default.com",
+			HealthStatus::Failing,
+			50,
+		);
 		router.update_processor_health(default_processor.clone());
 
-		let (url, name, breaker) = router.get_processor_for_payment().await.unwrap();
-		assert_eq!(url, fallback_processor.url);
-		assert_eq!(name, fallback_processor.name);
-		assert_eq!(breaker.current_state(), State::Closed);
+		let selection = router.get_processor_for_payment().await.unwrap();
+		assert_eq!(selection.payments_urls, fallback_processor.payments_urls);
+		assert_eq!(selection.name, fallback_processor.name);
+		assert_eq!(selection.breaker.current_state(), State::Closed);
 	}
 
 	#[tokio::test]
@@ -189,19 +584,316 @@ mod tests {
 		assert!(result.is_none());
 	}
 
+	#[tokio::test]
+	async fn test_get_processor_for_payment_prefers_configured_processor() {
+		let router =
+			InMemoryPaymentRouter::new().with_preferred_processor("fallback");
+		for name in ["default", "fallback"] {
+			router.record_health_check(name, &format!("http://{name}.com"), true, 0);
+		}
+
+		let selection = router.get_processor_for_payment().await.unwrap();
+		assert_eq!(&*selection.name, "fallback");
+
+		router.fallback_breaker.force_open();
+		let selection = router.get_processor_for_payment().await.unwrap();
+		assert_eq!(&*selection.name, "default");
+	}
+
+	#[tokio::test]
+	async fn test_get_processor_returns_named_processor() {
+		let router = InMemoryPaymentRouter::new();
+		for name in ["default", "fallback"] {
+			router.update_processor_health(PaymentProcessor::new(
+				name,
+				&format!("http://{name}.com"),
+				HealthStatus::Healthy,
+				50,
+			));
+		}
+
+		let selection = router.get_processor("fallback").await.unwrap();
+		assert_eq!(
+			selection.payments_urls,
+			["http://fallback.com/payments".into()].into()
+		);
+		assert_eq!(&*selection.name, "fallback");
+	}
+
+	#[tokio::test]
+	async fn test_get_processor_lists_replica_endpoints_in_order() {
+		let router = InMemoryPaymentRouter::new();
+		router.update_processor_health(PaymentProcessor::new(
+			"default",
+			"http://default-1.com, http://default-2.com",
+			HealthStatus::Healthy,
+			50,
+		));
+
+		let selection = router.get_processor("default").await.unwrap();
+		assert_eq!(
+			selection.payments_urls,
+			[
+				"http://default-1.com/payments".into(),
+				"http://default-2.com/payments".into()
+			]
+			.into()
+		);
+	}
+
+	#[tokio::test]
+	async fn test_get_processor_unavailable() {
+		let router = InMemoryPaymentRouter::new();
+		router.update_processor_health(PaymentProcessor::new(
+			"fallback",
+			"http://fallback.com",
+			HealthStatus::Healthy,
+			50,
+		));
+		router.fallback_breaker.force_open();
+
+		assert!(router.get_processor("fallback").await.is_none());
+		assert!(router.get_processor("default").await.is_none());
+	}
+
 	#[tokio::test]
 	async fn test_update_processor_health() {
 		let router = InMemoryPaymentRouter::new();
-		let processor = PaymentProcessor {
-			name:              "test_processor".to_string(),
-			url:               "http://test.com".to_string(),
-			health:            HealthStatus::Healthy,
-			min_response_time: 100,
-		};
+		let processor = PaymentProcessor::new(
+			"test_processor",
+			"http://test.com",
+			HealthStatus::Healthy,
+			100,
+		);
 		router.update_processor_health(processor.clone());
 
 		let processors = router.processors.read().unwrap();
 		assert!(processors.contains_key("test_processor"));
 		assert_eq!(processors["test_processor"].url, processor.url);
 	}
+
+	#[tokio::test]
+	async fn test_record_health_check_damps_single_failures() {
+		let router = InMemoryPaymentRouter::new().with_flap_damping(3, 2);
+
+		assert!(router.record_health_check(
+			"default",
+			"http://default.com",
+			true,
+			0
+		));
+		assert!(router.record_health_check(
+			"default",
+			"http://default.com",
+			false,
+			0
+		));
+		assert!(router.record_health_check(
+			"default",
+			"http://default.com",
+			false,
+			0
+		));
+		assert!(router.get_processor("default").await.is_some());
+
+		assert!(!router.record_health_check(
+			"default",
+			"http://default.com",
+			false,
+			0
+		));
+		assert!(router.get_processor("default").await.is_none());
+
+		assert!(!router.record_health_check(
+			"default",
+			"http://default.com",
+			true,
+			0
+		));
+		assert!(router.record_health_check(
+			"default",
+			"http://default.com",
+			true,
+			0
+		));
+
+		let processors = router.processors.read().unwrap();
+		assert_eq!(processors["default"].consecutive_successes, 2);
+		assert_eq!(processors["default"].consecutive_failures, 0);
+	}
+
+	#[tokio::test]
+	async fn test_record_health_check_without_damping_follows_each_check() {
+		let router = InMemoryPaymentRouter::new();
+
+		assert!(!router.record_health_check(
+			"fallback",
+			"http://fallback.com",
+			false,
+			0
+		));
+		assert!(router.record_health_check(
+			"fallback",
+			"http://fallback.com",
+			true,
+			0
+		));
+		assert!(!router.record_health_check(
+			"fallback",
+			"http://fallback.com",
+			false,
+			0
+		));
+	}
+
+	#[tokio::test]
+	async fn test_reported_failures_demote_processor() {
+		let router = InMemoryPaymentRouter::new().with_flap_damping(2, 1);
+		router.record_health_check("default", "http://default.com", true, 0);
+
+		router.report_failure("default");
+		router.report_success("default");
+		router.report_failure("default");
+		assert!(router.get_processor("default").await.is_some());
+
+		router.report_failure("default");
+		assert!(router.get_processor("default").await.is_none());
+
+		router.report_success("default");
+		assert!(router.get_processor("default").await.is_none());
+	}
+
+	#[tokio::test]
+	async fn test_configure_processor_updates_routing_at_runtime() {
+		let router = InMemoryPaymentRouter::new()
+			.with_processor_urls("http://default.com", "http://fallback.com");
+		let mut watched = router.watch_processor_settings("default").unwrap();
+		router.record_health_check("default", "http://default.com", true, 150);
+		assert!(router.get_processor("default").await.is_none());
+
+		let settings = ProcessorSettings {
+			url:                  "http://default-2.com".to_string(),
+			fee:                  Some(0.05),
+			max_response_time_ms: 200,
+		};
+		assert!(router.configure_processor("default", settings.clone()));
+		assert!(!router.configure_processor("unknown", settings.clone()));
+
+		let selection = router.get_processor("default").await.unwrap();
+		assert_eq!(
+			selection.payments_urls,
+			["http://default-2.com/payments".into()].into()
+		);
+		assert_eq!(selection.fee, Some(0.05));
+		assert!(watched.has_changed().unwrap());
+		assert_eq!(*watched.borrow_and_update(), settings);
+	}
+
+	#[tokio::test]
+	async fn test_reset_forgets_processors_and_closes_breakers() {
+		let router = InMemoryPaymentRouter::new();
+		router.record_health_check("default", "http://default.com", true, 0);
+		router.default_breaker.force_open();
+
+		router.reset();
+
+		assert!(router.processors.read().unwrap().is_empty());
+		assert_eq!(router.breaker_state("default"), Some(State::Closed));
+		tokio::time::timeout(Duration::from_secs(1), router.reset_requested())
+			.await
+			.expect("Reset was not signalled");
+
+		router.record_health_check("default", "http://default.com", true, 0);
+		assert!(router.get_processor("default").await.is_some());
+	}
+
+	#[tokio::test]
+	async fn test_wait_for_health_checks_resolves_once_all_processors_checked() {
+		let router = InMemoryPaymentRouter::new();
+		let waiter = tokio::spawn({
+			let router = router.clone();
+			async move { router.wait_for_health_checks().await }
+		});
+
+		router.record_health_check("default", "http://default.com", true, 0);
+		tokio::task::yield_now().await;
+		assert!(!waiter.is_finished());
+
+		router.record_health_check("fallback", "http://fallback.com", false, 0);
+		tokio::time::timeout(std::time::Duration::from_secs(1), waiter)
+			.await
+			.expect("Router did not report the health checks")
+			.unwrap();
+	}
+
+	#[tokio::test]
+	async fn test_selection_cache_is_dropped_on_routing_changes() {
+		let router = InMemoryPaymentRouter::new()
+			.with_selection_cache(Duration::from_secs(60));
+		router.record_health_check("default", "http://default.com", true, 0);
+		router.record_health_check("fallback", "http://fallback.com", true, 0);
+		assert_eq!(
+			router
+				.get_processor_for_payment()
+				.await
+				.unwrap()
+				.name
+				.as_ref(),
+			"default"
+		);
+
+		// Bypasses the router, so only the cached pick can still be served.
+		router.processors.write().unwrap().clear();
+		assert_eq!(
+			router
+				.get_processor_for_payment()
+				.await
+				.unwrap()
+				.name
+				.as_ref(),
+			"default"
+		);
+
+		router.record_health_check("fallback", "http://fallback.com", true, 0);
+		assert_eq!(
+			router
+				.get_processor_for_payment()
+				.await
+				.unwrap()
+				.name
+				.as_ref(),
+			"fallback"
+		);
+
+		router.fallback_breaker.force_open();
+		assert!(router.get_processor_for_payment().await.is_none());
+	}
+
+	#[tokio::test]
+	async fn test_health_history_is_capped_and_purged_per_processor() {
+		let router = InMemoryPaymentRouter::new().with_flap_damping(2, 1);
+		router.record_health_check("default", "http://default.com", true, 10);
+		router.record_health_check("default", "http://default.com", false, 20);
+		router.record_health_check("fallback", "http://fallback.com", true, 0);
+
+		let history = router.health_history("default").unwrap();
+		assert_eq!(history.len(), 2);
+		assert!(!history[1].reported_healthy);
+		assert!(history[1].healthy);
+		assert_eq!(history[1].min_response_time, 20);
+
+		for _ in 0..HEALTH_HISTORY_CAPACITY {
+			router.record_health_check("default", "http://default.com", true, 30);
+		}
+		let history = router.health_history("default").unwrap();
+		assert_eq!(history.len(), HEALTH_HISTORY_CAPACITY);
+		assert!(history.iter().all(|sample| sample.min_response_time == 30));
+
+		router.reset();
+		assert!(router.purge_health_history("default"));
+		assert_eq!(router.health_history("default"), Some(vec![]));
+		assert_eq!(router.health_history("fallback").unwrap().len(), 1);
+		assert_eq!(router.health_history("unknown"), None);
+		assert!(!router.purge_health_history("unknown"));
+	}
 }
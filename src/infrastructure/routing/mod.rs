@@ -1 +1,2 @@
 pub mod in_memory_payment_router;
+pub mod slow_start;
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use circuitbreaker_rs::State;
+
+/// Share of payments sent to a processor right after its breaker closes.
+const INITIAL_FRACTION: f64 = 0.1;
+/// Absorbs the rounding error accumulated while adding up fractions.
+const CREDIT_EPSILON: f64 = 1e-9;
+
+/// Ramps traffic back up to a processor after its circuit breaker closes,
+/// from 10% of the payments routed to it up to 100% once `window` elapses.
+pub struct SlowStartPolicy {
+	window: Duration,
+	ramps:  Mutex<HashMap<String, Ramp>>,
+}
+
+struct Ramp {
+	closed:    bool,
+	closed_at: Option<Instant>,
+	credit:    f64,
+}
+
+impl Default for Ramp {
+	fn default() -> Self {
+		// Processors are assumed to be at full capacity until a breaker trips.
+		Self {
+			closed:    true,
+			closed_at: None,
+			credit:    0.0,
+		}
+	}
+}
+
+impl SlowStartPolicy {
+	pub fn new(window: Duration) -> Self {
+		Self {
+			window,
+			ramps: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Forgets every ramp, so processors are back at full capacity.
+	pub fn reset(&self) {
+		self.ramps.lock().unwrap().clear();
+	}
+
+	/// Records the current breaker state of `processor`, starting a ramp when
+	/// the breaker goes back to closed.
+	pub fn observe(&self, processor: &str, state: State, now: Instant) {
+		let mut ramps = self.ramps.lock().unwrap();
+		let ramp = ramps.entry(processor.to_string()).or_default();
+
+		match (state, ramp.closed) {
+			(State::Closed, false) => {
+				ramp.closed = true;
+				ramp.closed_at = Some(now);
+				ramp.credit = 0.0;
+			}
+			(State::Closed, true) => {}
+			_ => {
+				ramp.closed = false;
+				ramp.closed_at = None;
+			}
+		}
+	}
+
+	/// Whether the next payment may be sent to `processor`.
+	pub fn admit(&self, processor: &str, now: Instant) -> bool {
+		let mut ramps = self.ramps.lock().unwrap();
+		let Some(ramp) = ramps.get_mut(processor) else {
+			return true;
+		};
+		let Some(closed_at) = ramp.closed_at else {
+			return true;
+		};
+
+		let elapsed = now.saturating_duration_since(closed_at);
+		if elapsed >= self.window {
+			ramp.closed_at = None;
+			return true;
+		}
+
+		let progress = elapsed.as_secs_f64() / self.window.as_secs_f64();
+		ramp.credit += INITIAL_FRACTION + (1.0 - INITIAL_FRACTION) * progress;
+
+		if ramp.credit >= 1.0 - CREDIT_EPSILON {
+			ramp.credit -= 1.0;
+			return true;
+		}
+
+		false
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn admitted(policy: &SlowStartPolicy, attempts: usize, now: Instant) -> usize {
+		(0..attempts)
+			.filter(|_| policy.admit("default", now))
+			.count()
+	}
+
+	#[test]
+	fn test_admits_everything_without_a_recovery() {
+		let policy = SlowStartPolicy::new(Duration::from_secs(10));
+		let now = Instant::now();
+
+		policy.observe("default", State::Closed, now);
+
+		assert_eq!(admitted(&policy, 100, now), 100);
+	}
+
+	#[test]
+	fn test_ramps_up_after_breaker_closes() {
+		let policy = SlowStartPolicy::new(Duration::from_secs(10));
+		let start = Instant::now();
+
+		policy.observe("default", State::Open, start);
+		policy.observe("default", State::Closed, start);
+
+		assert_eq!(admitted(&policy, 100, start), 10);
+		assert_eq!(admitted(&policy, 100, start + Duration::from_secs(5)), 55);
+		assert_eq!(admitted(&policy, 100, start + Duration::from_secs(10)), 100);
+	}
+
+	#[test]
+	fn test_restarts_ramp_on_new_recovery() {
+		let policy = SlowStartPolicy::new(Duration::from_secs(10));
+		let start = Instant::now();
+
+		policy.observe("default", State::Open, start);
+		policy.observe("default", State::Closed, start);
+		admitted(&policy, 10, start + Duration::from_secs(20));
+
+		let later = start + Duration::from_secs(30);
+		policy.observe("default", State::HalfOpen, later);
+		policy.observe("default", State::Closed, later);
+
+		assert_eq!(admitted(&policy, 100, later), 10);
+	}
+}
@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use circuitbreaker_rs::State;
+use tokio::time::{Duration, sleep};
+
+use crate::domain::payment_processor::PROCESSOR_GROUPS;
+use crate::infrastructure::gateway::connection_warmer::ConnectionWarmer;
+use crate::infrastructure::routing::in_memory_payment_router::InMemoryPaymentRouter;
+
+/// How often the breakers are checked for processors coming back.
+const BREAKER_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Warms the connections to a processor up again whenever its circuit breaker
+/// closes, as the pooled connections are likely gone after an outage.
+pub async fn connection_warmup_worker(
+	router: InMemoryPaymentRouter,
+	warmer: Arc<ConnectionWarmer>,
+) {
+	let mut states: HashMap<&str, State> = HashMap::new();
+
+	loop {
+		for processor in PROCESSOR_GROUPS {
+			let Some(state) = router.breaker_state(processor) else {
+				continue;
+			};
+
+			if reclosed(states.insert(processor, state), state) {
+				warmer.warm_up(processor).await;
+			}
+		}
+
+		sleep(BREAKER_POLL_INTERVAL).await;
+	}
+}
+
+fn reclosed(previous: Option<State>, current: State) -> bool {
+	current == State::Closed && previous.is_some_and(|state| state != State::Closed)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_reclosed_only_when_breaker_closes_again() {
+		assert!(reclosed(Some(State::Open), State::Closed));
+		assert!(reclosed(Some(State::HalfOpen), State::Closed));
+		assert!(!reclosed(None, State::Closed));
+		assert!(!reclosed(Some(State::Closed), State::Closed));
+		assert!(!reclosed(Some(State::Closed), State::Open));
+	}
+}
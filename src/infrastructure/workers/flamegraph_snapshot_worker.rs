@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use log::{error, info};
+use pprof::ProfilerGuard;
+use time::OffsetDateTime;
+use tokio::time::{Duration, sleep};
+
+use crate::infrastructure::observability::flamegraph::{
+	rotate_snapshots, snapshot_path, write_flamegraph,
+};
+
+/// Writes a flamegraph of the samples collected since startup to `dir` every
+/// `interval`, keeping only the newest `keep` snapshots.
+pub async fn flamegraph_snapshot_worker(
+	guard: Arc<ProfilerGuard<'static>>,
+	dir: PathBuf,
+	interval: Duration,
+	keep: usize,
+) {
+	loop {
+		sleep(interval).await;
+
+		let path = snapshot_path(&dir, OffsetDateTime::now_utc().unix_timestamp());
+		match write_flamegraph(&guard, &path) {
+			Ok(()) => info!("Flamegraph snapshot written to {}", path.display()),
+			Err(e) => error!("Failed to write flamegraph snapshot: {e}"),
+		}
+
+		if let Err(e) = rotate_snapshots(&dir, keep) {
+			error!("Failed to rotate flamegraph snapshots: {e}");
+		}
+	}
+}
@@ -0,0 +1,212 @@
+use std::collections::HashSet;
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::time::Duration;
+
+use log::error;
+use redis::AsyncCommands;
+use time::OffsetDateTime;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::infrastructure::config::redis::IN_FLIGHT_PAYMENTS_KEY;
+use crate::infrastructure::persistence::redis_connection::SupervisedConnection;
+
+/// Entries older than this are left over by instances that died mid-payment
+/// and are no longer counted. Processing a payment is bounded by the
+/// processor timeouts, well below it.
+const STALE_AFTER: Duration = Duration::from_secs(60);
+
+static IN_FLIGHT_PAYMENTS: LazyLock<InFlightRegistry> =
+	LazyLock::new(InFlightRegistry::new);
+
+/// Process-wide registry of the payments popped from a queue but not saved
+/// or re-queued yet.
+pub fn in_flight_payments() -> &'static InFlightRegistry {
+	&IN_FLIGHT_PAYMENTS
+}
+
+pub struct InFlightRegistry {
+	local:  Mutex<HashSet<Uuid>>,
+	shared: OnceLock<SharedRegistry>,
+}
+
+struct SharedRegistry {
+	connection: SupervisedConnection,
+	instance:   String,
+	/// Members of the released payments, removed from Redis by
+	/// [`remove_released`], as a guard cannot wait for Redis when dropped.
+	released:   mpsc::UnboundedSender<String>,
+}
+
+impl SharedRegistry {
+	fn member(&self, correlation_id: Uuid) -> String {
+		format!("{}:{correlation_id}", self.instance)
+	}
+}
+
+impl InFlightRegistry {
+	pub fn new() -> Self {
+		Self {
+			local:  Mutex::new(HashSet::new()),
+			shared: OnceLock::new(),
+		}
+	}
+
+	/// Mirrors the payments in flight on this instance to Redis over
+	/// `connection`, so counts cover every instance. Only the first call has
+	/// any effect. Must be called within a Tokio runtime, which removes the
+	/// released payments from Redis in the background.
+	pub fn share_through(&self, connection: SupervisedConnection, instance: String) {
+		if self.shared.get().is_some() {
+			return;
+		}
+
+		let (released, receiver) = mpsc::unbounded_channel();
+		if self
+			.shared
+			.set(SharedRegistry {
+				connection: connection.clone(),
+				instance,
+				released,
+			})
+			.is_ok()
+		{
+			tokio::spawn(remove_released(connection, receiver));
+		}
+	}
+
+	/// Registers the payment as in flight until the returned guard is
+	/// dropped. Failing to mirror it to Redis only skews the shared count.
+	pub async fn track(&self, correlation_id: Uuid) -> InFlightPayment<'_> {
+		self.local.lock().unwrap().insert(correlation_id);
+
+		if let Some(shared) = self.shared.get() {
+			let added = async {
+				let mut con = shared.connection.get().await?;
+				con.zadd::<_, _, _, ()>(
+					IN_FLIGHT_PAYMENTS_KEY,
+					shared.member(correlation_id),
+					OffsetDateTime::now_utc().unix_timestamp(),
+				)
+				.await
+			};
+			if let Err(e) = added.await {
+				error!("Failed to register in-flight payment: {e}");
+				shared.connection.discard().await;
+			}
+		}
+
+		InFlightPayment {
+			registry: self,
+			correlation_id,
+		}
+	}
+
+	/// Whether the payments in flight are mirrored to Redis, see
+	/// [`InFlightRegistry::share_through`].
+	pub fn is_shared(&self) -> bool {
+		self.shared.get().is_some()
+	}
+
+	pub fn contains(&self, correlation_id: Uuid) -> bool {
+		self.local.lock().unwrap().contains(&correlation_id)
+	}
+
+	/// Payments in flight on this instance.
+	pub fn local_count(&self) -> usize {
+		self.local.lock().unwrap().len()
+	}
+
+	/// Payments in flight on every instance sharing the registry, or on this
+	/// one if it is not shared.
+	pub async fn count(&self) -> Result<usize, Box<dyn std::error::Error + Send>> {
+		let Some(shared) = self.shared.get() else {
+			return Ok(self.local_count());
+		};
+
+		let mut con = shared
+			.connection
+			.get()
+			.await
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+		// Stale entries are removed rather than skipped, so entries left by
+		// dead instances do not pile up.
+		let fresh_since = OffsetDateTime::now_utc().unix_timestamp() -
+			STALE_AFTER.as_secs() as i64;
+		let (count,): (usize,) = redis::pipe()
+			.atomic()
+			.zrembyscore(IN_FLIGHT_PAYMENTS_KEY, "-inf", format!("({fresh_since}"))
+			.ignore()
+			.zcard(IN_FLIGHT_PAYMENTS_KEY)
+			.query_async(&mut con)
+			.await
+			.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+		Ok(count)
+	}
+
+	fn release(&self, correlation_id: Uuid) {
+		self.local.lock().unwrap().remove(&correlation_id);
+
+		if let Some(shared) = self.shared.get() {
+			// Only fails once the runtime removing them is gone.
+			let _ = shared.released.send(shared.member(correlation_id));
+		}
+	}
+}
+
+/// Removes the members of the released payments from Redis, together with
+/// any released while the previous ones were being removed.
+async fn remove_released(
+	connection: SupervisedConnection,
+	mut receiver: mpsc::UnboundedReceiver<String>,
+) {
+	let mut members = Vec::new();
+	while receiver.recv_many(&mut members, usize::MAX).await > 0 {
+		let removed = async {
+			let mut con = connection.get().await?;
+			con.zrem::<_, _, ()>(IN_FLIGHT_PAYMENTS_KEY, &members).await
+		};
+		if let Err(e) = removed.await {
+			error!("Failed to release in-flight payments: {e}");
+			connection.discard().await;
+		}
+		members.clear();
+	}
+}
+
+impl Default for InFlightRegistry {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Keeps a payment registered as in flight while alive.
+pub struct InFlightPayment<'a> {
+	registry:       &'a InFlightRegistry,
+	correlation_id: Uuid,
+}
+
+impl Drop for InFlightPayment<'_> {
+	fn drop(&mut self) {
+		self.registry.release(self.correlation_id);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn test_payments_are_in_flight_until_released() {
+		let registry = InFlightRegistry::new();
+		let correlation_id = Uuid::new_v4();
+
+		let in_flight = registry.track(correlation_id).await;
+		assert!(registry.contains(correlation_id));
+		assert_eq!(registry.count().await.unwrap(), 1);
+
+		drop(in_flight);
+		assert!(!registry.contains(correlation_id));
+		assert_eq!(registry.count().await.unwrap(), 0);
+	}
+}
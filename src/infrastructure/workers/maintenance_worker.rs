@@ -0,0 +1,91 @@
+use log::{error, info};
+use time::OffsetDateTime;
+use tokio::time::{Duration, Interval, MissedTickBehavior, interval_at};
+
+use crate::domain::repository::PaymentRepository;
+use crate::infrastructure::observability::metrics::metrics;
+use crate::infrastructure::persistence::redis_key_space::RedisKeySpace;
+
+/// Housekeeping run by the maintenance worker. Each task runs on its own
+/// interval, and only when it is set.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MaintenanceTasks {
+	/// Trims processed payments older than the retention, folding them into
+	/// summary buckets, at the given interval.
+	pub trim_processed:   Option<(Duration, Duration)>,
+	/// Reports the sizes of the growing Redis keys at the given interval.
+	pub report_key_space: Option<Duration>,
+}
+
+impl MaintenanceTasks {
+	pub fn is_empty(&self) -> bool {
+		self.trim_processed.is_none() && self.report_key_space.is_none()
+	}
+}
+
+pub async fn maintenance_worker<R>(
+	payment_repo: R,
+	key_space: RedisKeySpace,
+	tasks: MaintenanceTasks,
+) where
+	R: PaymentRepository,
+{
+	let mut trim_interval =
+		tasks.trim_processed.map(|(_, interval)| schedule(interval));
+	let mut key_space_interval = tasks.report_key_space.map(schedule);
+
+	loop {
+		tokio::select! {
+			_ = next_tick(&mut trim_interval) => {
+				if let Some((retention, _)) = tasks.trim_processed {
+					trim_processed(&payment_repo, retention).await;
+				}
+			}
+			_ = next_tick(&mut key_space_interval) => {
+				report_key_space(&key_space).await;
+			}
+		}
+	}
+}
+
+fn schedule(period: Duration) -> Interval {
+	let mut interval = interval_at(tokio::time::Instant::now() + period, period);
+	interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+	interval
+}
+
+async fn next_tick(interval: &mut Option<Interval>) {
+	match interval {
+		Some(interval) => {
+			interval.tick().await;
+		}
+		None => std::future::pending().await,
+	}
+}
+
+async fn trim_processed<R: PaymentRepository>(
+	payment_repo: &R,
+	retention: Duration,
+) {
+	let cutoff = OffsetDateTime::now_utc() - retention;
+
+	match payment_repo.trim_older_than(cutoff).await {
+		Ok(0) => {}
+		Ok(trimmed) => {
+			info!("Trimmed {trimmed} payments requested before {cutoff}")
+		}
+		Err(e) => error!("Failed to trim expired payments: {e}"),
+	}
+}
+
+async fn report_key_space(key_space: &RedisKeySpace) {
+	match key_space.sizes().await {
+		Ok(sizes) => {
+			info!("Redis key space sizes: {sizes:?}");
+			for (key, size) in sizes {
+				metrics().set_key_space_size(key, size);
+			}
+		}
+		Err(e) => error!("Failed to read Redis key space sizes: {e}"),
+	}
+}
@@ -0,0 +1,18 @@
+use log::error;
+use tokio::time::{Duration, sleep};
+
+use crate::infrastructure::observability::metrics::metrics;
+use crate::infrastructure::observability::statsd_exporter::StatsdExporter;
+
+pub async fn metrics_exporter_worker(
+	mut exporter: StatsdExporter,
+	interval: Duration,
+) {
+	loop {
+		sleep(interval).await;
+
+		if let Err(e) = exporter.flush(&metrics().snapshot()).await {
+			error!("Failed to push metrics to StatsD: {e}");
+		}
+	}
+}
@@ -1,2 +1,23 @@
+pub mod connection_warmup_worker;
+#[cfg(feature = "perf")]
+pub mod flamegraph_snapshot_worker;
+pub mod in_flight_registry;
+pub mod maintenance_worker;
+pub mod metrics_exporter_worker;
+pub mod payment_archiver_worker;
+pub mod payment_dispatcher_worker;
+pub mod payment_ingest_worker;
 pub mod payment_processor_worker;
+pub mod payment_verification_worker;
+pub mod processor_discovery_worker;
 pub mod processor_health_monitor_worker;
+pub mod processor_queue_worker;
+pub mod queue_cutover_worker;
+pub mod queue_depth_reconciler_worker;
+pub mod redis_clock_sync_worker;
+pub mod redis_connection_supervisor_worker;
+pub mod requeue_pacer;
+pub mod retry_budget;
+pub mod run_epoch_sync_worker;
+pub mod sequence_audit_worker;
+pub mod worker_control;
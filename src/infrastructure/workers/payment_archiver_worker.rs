@@ -0,0 +1,68 @@
+use std::sync::Arc;
+
+use futures::{StreamExt, TryStreamExt};
+use log::{error, info};
+use time::OffsetDateTime;
+use tokio::time::{Duration, sleep};
+
+use crate::domain::payment::Payment;
+use crate::domain::payment_archive::PaymentArchive;
+use crate::domain::payment_processor::PROCESSOR_GROUPS;
+use crate::domain::repository::PaymentRepository;
+
+const ARCHIVE_BATCH_SIZE: usize = 500;
+
+pub async fn payment_archiver_worker<R>(
+	payment_repo: R,
+	archive: Arc<dyn PaymentArchive>,
+	hot_window: Duration,
+	interval: Duration,
+) where
+	R: PaymentRepository,
+{
+	loop {
+		sleep(interval).await;
+
+		let cutoff = OffsetDateTime::now_utc() - hot_window;
+
+		for group in PROCESSOR_GROUPS {
+			match archive_group(&payment_repo, archive.as_ref(), group, cutoff).await
+			{
+				Ok(0) => {}
+				Ok(archived) => info!("Archived {archived} {group} payments"),
+				Err(e) => error!("Failed to archive {group} payments: {e}"),
+			}
+		}
+	}
+}
+
+async fn archive_group<R: PaymentRepository>(
+	payment_repo: &R,
+	archive: &dyn PaymentArchive,
+	group: &str,
+	cutoff: OffsetDateTime,
+) -> Result<usize, Box<dyn std::error::Error + Send>> {
+	let mut total = 0;
+
+	// Each round opens a fresh stream: deleting archived payments shifts the
+	// offsets an open stream would continue from.
+	loop {
+		let batch: Vec<Payment> = payment_repo
+			.get_payments_stream(group, OffsetDateTime::UNIX_EPOCH, cutoff)
+			.take(ARCHIVE_BATCH_SIZE)
+			.try_collect()
+			.await?;
+
+		if batch.is_empty() {
+			return Ok(total);
+		}
+
+		archive.archive(&batch).await?;
+		payment_repo.remove_archived(&batch).await?;
+		total += batch.len();
+
+		if batch.len() < ARCHIVE_BATCH_SIZE {
+			return Ok(total);
+		}
+	}
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use log::{error, info, warn};
+use tokio::time::sleep;
+
+use crate::domain::payment::Payment;
+use crate::domain::payment_router::PaymentRouter;
+use crate::domain::queue::Queue;
+use crate::infrastructure::observability::log_redaction;
+use crate::infrastructure::observability::metrics::metrics;
+
+/// Delay before retrying a payment when no processor can take it, so the
+/// dispatcher does not spin on the ingest queue during an outage.
+const NO_PROCESSOR_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Moves payments from the ingest queue into the queue of the processor the
+/// router currently picks for them.
+pub async fn payment_dispatcher_worker<Q, R>(
+	ingest_queue: Q,
+	processor_queues: HashMap<String, Q>,
+	router: R,
+) where
+	Q: Queue<Payment> + Clone + Send + Sync + 'static,
+	R: PaymentRouter + Clone + Send + Sync + 'static,
+{
+	loop {
+		let message = match ingest_queue.pop().await {
+			Ok(Some(val)) => val,
+			Ok(None) => {
+				info!("No payments in queue, waiting...");
+				continue;
+			}
+			Err(e) => {
+				error!("Failed to pop from payments queue: {e}");
+				sleep(Duration::from_secs(1)).await;
+				continue;
+			}
+		};
+
+		let processor_queue = match router.get_processor_for_payment().await {
+			Some(selection) => processor_queues.get(&*selection.name),
+			None => None,
+		};
+
+		let Some(processor_queue) = processor_queue else {
+			warn!(
+				"No processor available for payment {}. Re-queueing.",
+				log_redaction::correlation_id(message.body.correlation_id)
+			);
+			if let Err(e) = ingest_queue.push(message).await {
+				error!("Failed to re-queue payment: {e}");
+			}
+			metrics().record_requeued();
+			sleep(NO_PROCESSOR_BACKOFF).await;
+			continue;
+		};
+
+		if let Err(e) = processor_queue.push(message.clone()).await {
+			error!("Failed to dispatch payment, re-queueing: {e}");
+			if let Err(e) = ingest_queue.push(message).await {
+				error!("Failed to re-queue payment: {e}");
+			}
+			metrics().record_requeued();
+		}
+	}
+}
@@ -0,0 +1,166 @@
+use log::{error, info, warn};
+use tokio::time::{Duration, Instant, sleep};
+use uuid::Uuid;
+
+use crate::domain::payment::Payment;
+use crate::domain::queue::Queue;
+use crate::infrastructure::queue::redis_payment_ingest_stream::{
+	FIRST_ENTRY_ID, IngestEntry, PaymentIngestStream,
+};
+use crate::use_cases::create_payment::CreatePaymentUseCase;
+use crate::use_cases::dto::CreatePaymentCommand;
+
+/// Moves payments published to the ingest stream into the payments queue,
+/// where they are processed exactly like payments received over HTTP.
+/// Entries that cannot be queued stay pending and are read again every retry
+/// interval of the stream, and when the worker restarts; malformed entries
+/// are dropped.
+pub async fn payment_ingest_worker<Q>(
+	stream: PaymentIngestStream,
+	create_payment: CreatePaymentUseCase<Q>,
+) where
+	Q: Queue<Payment>,
+{
+	if let Err(e) = stream.create_group().await {
+		error!("Failed to create payments ingest consumer group: {e}");
+	}
+
+	// Set while sweeping the pending entries, to the id of the last one read,
+	// so entries failing again do not keep the sweep from reaching the rest.
+	let mut pending_after = Some(FIRST_ENTRY_ID.to_string());
+	// Entries left pending by the current sweep.
+	let mut left_pending = 0;
+	// Unset until the entries pending at startup were caught up with.
+	let mut retry_at: Option<Instant> = None;
+
+	loop {
+		if pending_after.is_none() &&
+			retry_at.is_some_and(|retry_at| retry_at <= Instant::now())
+		{
+			pending_after = Some(FIRST_ENTRY_ID.to_string());
+		}
+
+		let entries = match stream.read(pending_after.as_deref()).await {
+			Ok(entries) => entries,
+			Err(e) => {
+				error!("Failed to read from payments ingest stream: {e}");
+				sleep(Duration::from_secs(1)).await;
+				continue;
+			}
+		};
+
+		let read = entries.len();
+		let last_read = entries.last().map(|entry| entry.id.clone());
+		let mut handled = Vec::with_capacity(read);
+
+		for entry in entries {
+			let Some(command) = ingest_command(&entry) else {
+				warn!("Dropping malformed payments ingest entry '{}'", entry.id);
+				handled.push(entry.id);
+				continue;
+			};
+
+			match create_payment.execute(command).await {
+				Ok(_) => handled.push(entry.id),
+				Err(e) => {
+					error!("Failed to queue ingested payment '{}': {e}", entry.id)
+				}
+			}
+		}
+
+		if let Err(e) = stream.ack(&handled).await {
+			error!("Failed to acknowledge ingested payments: {e}");
+		}
+
+		if pending_after.is_none() {
+			continue;
+		}
+		left_pending += read - handled.len();
+		if last_read.is_some() {
+			pending_after = last_read;
+			continue;
+		}
+
+		if retry_at.is_none() {
+			info!("Caught up with pending ingested payments");
+		}
+		if left_pending > 0 {
+			warn!(
+				"Leaving {left_pending} ingested payments pending until the next \
+				 retry"
+			);
+		}
+		pending_after = None;
+		left_pending = 0;
+		retry_at = Some(Instant::now() + stream.retry_interval());
+	}
+}
+
+/// Builds the payment command from the `correlationId`, `amount` and
+/// optional `tag` fields of an entry. The entry id doubles as request id.
+fn ingest_command(entry: &IngestEntry) -> Option<CreatePaymentCommand> {
+	let correlation_id = Uuid::parse_str(entry.fields.get("correlationId")?).ok()?;
+	let amount = entry.fields.get("amount")?.parse::<f64>().ok()?;
+	if !amount.is_finite() || amount <= 0.0 {
+		return None;
+	}
+
+	Some(CreatePaymentCommand {
+		correlation_id,
+		amount,
+		request_id: Some(entry.id.clone()),
+		tag: entry.fields.get("tag").cloned(),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use super::*;
+
+	fn entry(fields: &[(&str, &str)]) -> IngestEntry {
+		IngestEntry {
+			id:     "1-0".to_string(),
+			fields: fields
+				.iter()
+				.map(|(field, value)| (field.to_string(), value.to_string()))
+				.collect::<HashMap<_, _>>(),
+		}
+	}
+
+	#[test]
+	fn test_builds_command_from_entry_fields() {
+		let correlation_id = Uuid::new_v4();
+
+		let command = ingest_command(&entry(&[
+			("correlationId", &correlation_id.to_string()),
+			("amount", "19.90"),
+			("tag", "staging"),
+		]))
+		.unwrap();
+
+		assert_eq!(command.correlation_id, correlation_id);
+		assert_eq!(command.amount, 19.9);
+		assert_eq!(command.request_id.as_deref(), Some("1-0"));
+		assert_eq!(command.tag.as_deref(), Some("staging"));
+	}
+
+	#[test]
+	fn test_rejects_malformed_entries() {
+		let correlation_id = Uuid::new_v4().to_string();
+
+		assert!(ingest_command(&entry(&[("amount", "10")])).is_none());
+		assert!(
+			ingest_command(&entry(&[
+				("correlationId", &correlation_id),
+				("amount", "-1")
+			]))
+			.is_none()
+		);
+		assert!(
+			ingest_command(&entry(&[("correlationId", "nope"), ("amount", "10")]))
+				.is_none()
+		);
+	}
+}
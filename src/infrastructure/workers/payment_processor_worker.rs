@@ -1,4 +1,6 @@
-use std::time::Duration;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use circuitbreaker_rs::State;
 use log::{error, info, warn};
@@ -6,86 +8,280 @@ use tokio::time::sleep;
 
 use crate::domain::payment::Payment;
 use crate::domain::payment_router::PaymentRouter;
-use crate::domain::queue::Queue;
-use crate::domain::repository::PaymentRepository;
+use crate::domain::processor_selection::ProcessorSelection;
+use crate::domain::queue::{Message, Queue};
+use crate::domain::repository::{DuplicateStage, PaymentRepository};
+use crate::infrastructure::observability::error_reporting::{
+	self, REPEATED_FAILURES_THRESHOLD,
+};
+use crate::infrastructure::observability::log_redaction;
+use crate::infrastructure::observability::metrics::{WorkerMetrics, metrics};
+use crate::infrastructure::workers::in_flight_registry::in_flight_payments;
+use crate::infrastructure::workers::requeue_pacer::RequeuePacer;
+use crate::infrastructure::workers::retry_budget::RetryBudget;
+use crate::infrastructure::workers::worker_control::worker_control;
 use crate::use_cases::process_payment::ProcessPaymentUseCase;
 
+/// Pops payments from the queue and processes each one in its own task, as
+/// many at a time as the worker control allows.
 pub async fn payment_processing_worker<Q, PR, R>(
 	queue: Q,
 	payment_repo: PR,
 	process_payment_use_case: ProcessPaymentUseCase<PR>,
 	router: R,
+	retry_budget: RetryBudget,
+	requeue_pacer: RequeuePacer,
 ) where
 	Q: Queue<Payment> + Clone + Send + Sync + 'static,
 	PR: PaymentRepository + Clone + Send + Sync + 'static,
 	R: PaymentRouter + Clone + Send + Sync + 'static,
 {
+	let task = PaymentTask {
+		queue,
+		payment_repo,
+		process_payment_use_case,
+		router,
+		consecutive_failures: Arc::new(Mutex::new(HashMap::new())),
+		requeue_pacer: Arc::new(Mutex::new(requeue_pacer)),
+		worker: metrics().register_worker("payment"),
+	};
+
 	loop {
-		let message = match queue.pop().await {
+		// Taken before popping, so a payment counts as in flight from the
+		// moment it leaves the queue.
+		let permit = worker_control().acquire().await;
+
+		let waiting_since = Instant::now();
+		let message = match task.queue.pop().await {
 			Ok(Some(val)) => val,
 			Ok(None) => {
 				info!("No payments in queue, waiting...");
-				sleep(Duration::from_secs(1)).await;
+				task.worker.record_idle(waiting_since.elapsed());
 				continue;
 			}
 			Err(e) => {
 				error!("Failed to pop from payments queue: {e}");
+				drop(permit);
 				sleep(Duration::from_secs(1)).await;
 				continue;
 			}
 		};
+		let in_flight = in_flight_payments()
+			.track(message.body.correlation_id)
+			.await;
+
+		if message.is_retry() {
+			retry_budget.acquire().await;
+		}
 
+		let task = task.clone();
+		tokio::spawn(async move {
+			task.process(message).await;
+			drop(in_flight);
+			drop(permit);
+		});
+	}
+}
+
+/// What a payment popped by the shared worker needs to be processed, shared
+/// by the tasks processing them.
+#[derive(Clone)]
+struct PaymentTask<Q, PR, R>
+where
+	PR: PaymentRepository,
+{
+	queue:                    Q,
+	payment_repo:             PR,
+	process_payment_use_case: ProcessPaymentUseCase<PR>,
+	router:                   R,
+	consecutive_failures:     Arc<Mutex<HashMap<Arc<str>, u32>>>,
+	requeue_pacer:            Arc<Mutex<RequeuePacer>>,
+	worker:                   Arc<WorkerMetrics>,
+}
+
+impl<Q, PR, R> PaymentTask<Q, PR, R>
+where
+	Q: Queue<Payment>,
+	PR: PaymentRepository + Clone + Send + Sync + 'static,
+	R: PaymentRouter,
+{
+	async fn process(&self, mut message: Message<Payment>) {
+		let started_at = Instant::now();
 		let message_id = message.id;
 
-		info!("Started processing message with id '{}'", &message_id);
+		info!(
+			"Started processing message with id '{}' (request {})",
+			log_redaction::correlation_id(message_id),
+			message.request_id.as_deref().unwrap_or("-")
+		);
 
 		let payment: Payment = message.body.clone();
 
-		if let Ok(true) = payment_repo
+		if let Ok(true) = self
+			.payment_repo
 			.is_already_processed(&payment.correlation_id.to_string())
 			.await
 		{
-			info!("Payment already processed. Skipping it.");
-			continue;
+			skip_duplicate(&self.payment_repo, &payment).await;
+			self.worker.record_loop(started_at.elapsed());
+			return;
 		}
 
 		let mut processed = false;
 
-		if let Some((processor_url, processor_name, mut circuit_breaker)) =
-			router.get_processor_for_payment().await
-		{
-			if circuit_breaker.current_state() == State::Open {
+		if let Some(selection) = self.router.get_processor_for_payment().await {
+			if selection.breaker.current_state() == State::Open {
 				warn!(
-					"Circuit breaker for {processor_name} is open. Skipping \
-					 payment processing and re-queueing."
+					"Circuit breaker for {} is open. Skipping payment processing \
+					 and re-queueing.",
+					selection.name
 				);
-				if let Err(e) = queue.push(message).await {
+				if let Err(e) = self.queue.push(message.retried()).await {
 					error!("Failed to re-queue payment: {e}");
 				}
-				continue;
+				metrics().record_requeued();
+				self.worker.record_requeued();
+				self.worker.record_loop(started_at.elapsed());
+				self.pace(true).await;
+				return;
 			}
 
-			processed = process_payment_use_case
-				.execute(
-					payment.clone(),
-					processor_url,
-					processor_name,
-					&mut circuit_breaker,
-				)
-				.await
-				.unwrap_or(false);
+			processed = try_process_payment(
+				&self.process_payment_use_case,
+				&mut message.body,
+				message.request_id.as_deref(),
+				selection,
+				&self.consecutive_failures,
+				&self.worker,
+			)
+			.await;
 		}
 
 		if !processed {
 			warn!(
 				"Payment {} could not be processed by any processor. Re-queueing.",
-				payment.correlation_id
+				log_redaction::correlation_id(payment.correlation_id)
 			);
-			if let Err(e) = queue.push(message).await {
+			if let Err(e) = self.queue.push(message.retried()).await {
 				error!("Failed to re-queue payment: {e}");
 			}
+			metrics().record_requeued();
+			self.worker.record_requeued();
+		}
+
+		self.worker.record_loop(started_at.elapsed());
+		info!(
+			"Message with id '{}' processed.",
+			log_redaction::correlation_id(message_id)
+		);
+
+		self.pace(!processed).await;
+	}
+
+	/// Sleeps while requeues storm, holding the processing slot so fewer
+	/// payments are taken meanwhile.
+	async fn pace(&self, requeued: bool) {
+		let pause = self
+			.requeue_pacer
+			.lock()
+			.unwrap()
+			.record(requeued, Instant::now());
+		if let Some(pause) = pause {
+			sleep(pause).await;
+		}
+	}
+}
+
+/// Skips a payment that was already processed, adding it to the duplicates
+/// report. Losing the record only skews the report, so errors are logged.
+pub(crate) async fn skip_duplicate<PR>(payment_repo: &PR, payment: &Payment)
+where
+	PR: PaymentRepository,
+{
+	info!("Payment already processed. Skipping it.");
+	metrics().record_duplicated();
+
+	if let Err(e) = payment_repo
+		.record_duplicate(
+			&payment.correlation_id.to_string(),
+			DuplicateStage::Worker,
+		)
+		.await
+	{
+		error!(
+			"Failed to record duplicate payment {}: {e}",
+			log_redaction::correlation_id(payment.correlation_id)
+		);
+	}
+}
+
+/// Sends the payment to the given processor, recording the outcome and
+/// reporting processors that keep failing. Returns whether the payment was
+/// processed. The payment is stamped with the time it was first requested,
+/// which it keeps when re-queued.
+pub(crate) async fn try_process_payment<PR>(
+	process_payment_use_case: &ProcessPaymentUseCase<PR>,
+	payment: &mut Payment,
+	request_id: Option<&str>,
+	selection: ProcessorSelection,
+	consecutive_failures: &Mutex<HashMap<Arc<str>, u32>>,
+	worker: &WorkerMetrics,
+) -> bool
+where
+	PR: PaymentRepository + Clone + Send + Sync + 'static,
+{
+	let ProcessorSelection {
+		name: processor_name,
+		payments_urls,
+		breaker: mut circuit_breaker,
+		..
+	} = selection;
+	process_payment_use_case.stamp_requested_at(payment);
+
+	match process_payment_use_case
+		.execute(
+			payment.clone(),
+			request_id,
+			&payments_urls,
+			&processor_name,
+			&mut circuit_breaker,
+		)
+		.await
+	{
+		Ok(result) => {
+			consecutive_failures
+				.lock()
+				.unwrap()
+				.insert(Arc::clone(&processor_name), 0);
+			if result {
+				worker.record_processed();
+			}
+			result
 		}
+		Err(e) => {
+			metrics().record_failed();
+			worker.record_failed();
+			error_reporting::report_payment_error(
+				payment.correlation_id,
+				Some(&processor_name),
+				e.as_ref(),
+			);
 
-		info!("Message with id '{}' processed.", &message_id);
+			let failures = {
+				let mut consecutive_failures = consecutive_failures.lock().unwrap();
+				let failures = consecutive_failures
+					.entry(Arc::clone(&processor_name))
+					.or_default();
+				*failures += 1;
+				*failures
+			};
+			if failures == REPEATED_FAILURES_THRESHOLD {
+				error_reporting::report_repeated_processor_failures(
+					&processor_name,
+					failures,
+				);
+			}
+			false
+		}
 	}
 }
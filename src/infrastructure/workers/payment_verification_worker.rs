@@ -0,0 +1,203 @@
+use std::fmt;
+use std::future::ready;
+
+use futures::TryStreamExt;
+use log::{error, info};
+use time::OffsetDateTime;
+use tokio::time::{Duration, sleep};
+use uuid::Uuid;
+
+use crate::domain::payment_processor::PROCESSOR_GROUPS;
+use crate::domain::repository::PaymentRepository;
+use crate::infrastructure::observability::metrics::metrics;
+use crate::infrastructure::queue::redis_payment_queue::PaymentQueue;
+
+/// Span of processed payments checked on each run.
+const VERIFICATION_WINDOW: Duration = Duration::from_secs(60);
+/// How far behind now the checked span ends, so payments still being saved
+/// are not mistaken for missing ones.
+const SETTLE_TIME: Duration = Duration::from_secs(5);
+/// Most recently dead-lettered payments checked per queue on each run.
+const DEAD_LETTER_SAMPLE: usize = 100;
+/// Difference in summed amounts put down to floating point error.
+const AMOUNT_TOLERANCE: f64 = 0.005;
+
+/// An invariant of the stored payments found broken by the verifier.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InvariantViolation {
+	/// The summary of a processor disagrees with the payments it holds.
+	SummaryMismatch {
+		group:           &'static str,
+		from:            OffsetDateTime,
+		to:              OffsetDateTime,
+		summary:         (usize, f64),
+		stored_payments: (usize, f64),
+	},
+	/// A payment was dead-lettered by a queue and also processed.
+	ProcessedDeadLetter {
+		queue:          String,
+		correlation_id: Uuid,
+		requested_at:   Option<OffsetDateTime>,
+	},
+}
+
+impl fmt::Display for InvariantViolation {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			InvariantViolation::SummaryMismatch {
+				group,
+				from,
+				to,
+				summary,
+				stored_payments,
+			} => write!(
+				f,
+				"summary of the {group} processor from {from} to {to} has {} \
+				 payments totalling {}, but {} payments totalling {} are stored",
+				summary.0, summary.1, stored_payments.0, stored_payments.1
+			),
+			InvariantViolation::ProcessedDeadLetter {
+				queue,
+				correlation_id,
+				requested_at,
+			} => write!(
+				f,
+				"payment {correlation_id} requested at {requested_at:?} was \
+				 dead-lettered by queue '{queue}' but was also processed"
+			),
+		}
+	}
+}
+
+/// Audits the stored payments every `interval`: the summary of each processor
+/// must equal the sum of the payments it holds, and no payment dead-lettered
+/// by `payment_queues` may have been processed. Violations are logged with
+/// their context and exported, but never repaired.
+pub async fn payment_verification_worker<R>(
+	payment_repo: R,
+	payment_queues: Vec<PaymentQueue>,
+	interval: Duration,
+) where
+	R: PaymentRepository,
+{
+	loop {
+		sleep(interval).await;
+
+		let to = OffsetDateTime::now_utc() - SETTLE_TIME;
+		let from = to - VERIFICATION_WINDOW;
+		let mut violations = Vec::new();
+
+		for group in PROCESSOR_GROUPS {
+			match verify_summary(&payment_repo, group, from, to).await {
+				Ok(violation) => violations.extend(violation),
+				Err(e) => {
+					error!(
+						"Failed to verify the summary of the {group} processor: {e}"
+					)
+				}
+			}
+		}
+
+		for payment_queue in &payment_queues {
+			match verify_dead_letters(&payment_repo, payment_queue).await {
+				Ok(found) => violations.extend(found),
+				Err(e) => error!(
+					"Failed to verify the payments dead-lettered by queue '{}': {e}",
+					payment_queue.key()
+				),
+			}
+		}
+
+		if violations.is_empty() {
+			info!("Payment invariants hold from {from} to {to}");
+			continue;
+		}
+
+		metrics().record_invariant_violations(violations.len() as u64);
+		for violation in &violations {
+			error!("Payment invariant violated: {violation}");
+		}
+	}
+}
+
+async fn verify_summary<R: PaymentRepository>(
+	payment_repo: &R,
+	group: &'static str,
+	from: OffsetDateTime,
+	to: OffsetDateTime,
+) -> Result<Option<InvariantViolation>, Box<dyn std::error::Error + Send>> {
+	let summary = payment_repo.get_summary_by_group(group, from, to).await?;
+	let stored_payments = payment_repo
+		.get_payments_stream(group, from, to)
+		.try_fold((0, 0.0), |(total_requests, total_amount), payment| {
+			ready(Ok((total_requests + 1, total_amount + payment.amount)))
+		})
+		.await?;
+
+	Ok(summary_mismatch(group, from, to, summary, stored_payments))
+}
+
+async fn verify_dead_letters<R: PaymentRepository>(
+	payment_repo: &R,
+	payment_queue: &PaymentQueue,
+) -> Result<Vec<InvariantViolation>, Box<dyn std::error::Error + Send>> {
+	let mut violations = Vec::new();
+	for payment in payment_queue
+		.dead_lettered_payments(DEAD_LETTER_SAMPLE)
+		.await?
+	{
+		if payment_repo
+			.is_already_processed(&payment.correlation_id.to_string())
+			.await?
+		{
+			violations.push(InvariantViolation::ProcessedDeadLetter {
+				queue:          payment_queue.key().to_string(),
+				correlation_id: payment.correlation_id,
+				requested_at:   payment.requested_at,
+			});
+		}
+	}
+
+	Ok(violations)
+}
+
+fn summary_mismatch(
+	group: &'static str,
+	from: OffsetDateTime,
+	to: OffsetDateTime,
+	summary: (usize, f64),
+	stored_payments: (usize, f64),
+) -> Option<InvariantViolation> {
+	let matches = summary.0 == stored_payments.0 &&
+		(summary.1 - stored_payments.1).abs() <= AMOUNT_TOLERANCE;
+
+	(!matches).then_some(InvariantViolation::SummaryMismatch {
+		group,
+		from,
+		to,
+		summary,
+		stored_payments,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_summary_mismatch_tolerates_rounding_only() {
+		let to = OffsetDateTime::now_utc();
+		let from = to - VERIFICATION_WINDOW;
+
+		assert_eq!(
+			summary_mismatch("default", from, to, (3, 59.7), (3, 19.9 * 3.0)),
+			None
+		);
+		assert!(
+			summary_mismatch("default", from, to, (3, 59.7), (2, 39.8)).is_some()
+		);
+		assert!(
+			summary_mismatch("fallback", from, to, (2, 39.8), (2, 39.9)).is_some()
+		);
+	}
+}
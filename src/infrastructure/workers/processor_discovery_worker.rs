@@ -0,0 +1,44 @@
+use log::{info, warn};
+use tokio::time::{Duration, sleep};
+
+use crate::infrastructure::gateway::processor_discovery::{
+	DiscoverySource, ProcessorDiscovery,
+};
+use crate::infrastructure::routing::in_memory_payment_router::InMemoryPaymentRouter;
+
+/// Looks up the replicas of each processor in `sources` every `interval` and
+/// points the router at them when they change. A lookup that fails or finds
+/// no instances keeps the replicas known so far.
+pub async fn processor_discovery_worker(
+	router: InMemoryPaymentRouter,
+	discovery: ProcessorDiscovery,
+	sources: Vec<(&'static str, DiscoverySource)>,
+	interval: Duration,
+) {
+	loop {
+		for (processor, source) in &sources {
+			let urls = match discovery.discover(source).await {
+				Ok(urls) if urls.is_empty() => {
+					warn!("Discovered no instances of the {processor} processor");
+					continue;
+				}
+				Ok(urls) => urls.join(","),
+				Err(e) => {
+					warn!("Failed to discover the {processor} processor: {e}");
+					continue;
+				}
+			};
+
+			let Some(mut settings) = router.processor_settings(processor) else {
+				continue;
+			};
+			if settings.url != urls {
+				info!("Discovered the {processor} processor at {urls}");
+				settings.url = urls;
+				router.configure_processor(processor, settings);
+			}
+		}
+
+		sleep(interval).await;
+	}
+}
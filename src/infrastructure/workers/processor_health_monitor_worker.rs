@@ -1,80 +1,243 @@
 use log::error;
-use reqwest::Client;
-use tokio::time::{Duration, sleep};
+use reqwest::{Client, Response};
+use serde::Deserialize;
+use tokio::sync::watch;
+use tokio::time::{Duration, Instant, sleep_until};
 
-use crate::domain::health_status::HealthStatus;
-use crate::domain::payment_processor::PaymentProcessor;
+use crate::domain::payment_processor::replica_urls;
+use crate::domain::processor_settings::ProcessorSettings;
+use crate::infrastructure::alerting::processor_downtime_monitor::ProcessorDowntimeMonitor;
+use crate::infrastructure::config::settings::Config;
 use crate::infrastructure::routing::in_memory_payment_router::InMemoryPaymentRouter;
 
+/// The processors rate limit their health endpoint to one call every five
+/// seconds.
+const DEFAULT_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often a processor is health checked and how long a single check may
+/// take before the processor is considered failing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HealthCheckSchedule {
+	pub interval: Duration,
+	pub timeout:  Option<Duration>,
+}
+
+impl Default for HealthCheckSchedule {
+	fn default() -> Self {
+		Self {
+			interval: DEFAULT_HEALTH_CHECK_INTERVAL,
+			timeout:  None,
+		}
+	}
+}
+
+impl HealthCheckSchedule {
+	/// Builds the schedule of the given processor, preferring its own
+	/// overrides over the shared settings.
+	pub fn from_config(config: &Config, processor: &str) -> Self {
+		let (interval_ms, timeout_ms) = match processor {
+			"default" => (
+				config.default_health_check_interval_ms,
+				config.default_health_check_timeout_ms,
+			),
+			_ => (
+				config.fallback_health_check_interval_ms,
+				config.fallback_health_check_timeout_ms,
+			),
+		};
+
+		Self {
+			interval: Duration::from_millis(
+				interval_ms.unwrap_or(config.health_check_interval_ms),
+			),
+			timeout:  timeout_ms
+				.or(config.health_check_timeout_ms)
+				.map(Duration::from_millis),
+		}
+	}
+}
+
 pub async fn processor_health_monitor_worker(
 	router: InMemoryPaymentRouter,
 	http_client: Client,
-	default_processor_url: String,
-	fallback_processor_url: String,
+	mut downtime_monitor: ProcessorDowntimeMonitor,
+	default_schedule: HealthCheckSchedule,
+	fallback_schedule: HealthCheckSchedule,
 ) {
-	let urls = [
-		("default".to_string(), default_processor_url),
-		("fallback".to_string(), fallback_processor_url),
+	let now = Instant::now();
+	let mut targets = [
+		HealthCheckTarget::new("default", &router, default_schedule, now),
+		HealthCheckTarget::new("fallback", &router, fallback_schedule, now),
 	];
 
 	loop {
-		for (name, url) in &urls {
-			let health_url = format!("{url}/payments/service-health");
-
-			match http_client.get(&health_url).send().await {
-				Ok(resp) => {
-					if resp.status().is_success() {
-						match resp.json::<serde_json::Value>().await {
-							Ok(json) => {
-								let failing =
-									json["failing"].as_bool().unwrap_or(true);
-								let min_response_time =
-									json["minResponseTime"].as_i64().unwrap_or(0)
-										as u64;
-
-								let health_status = if failing {
-									HealthStatus::Failing
-								} else {
-									HealthStatus::Healthy
-								};
-
-								router.update_processor_health(PaymentProcessor {
-									name: name.clone(),
-									url: url.clone(),
-									health: health_status.clone(),
-									min_response_time,
-								});
-							}
-							Err(e) => {
-								error!(
-									"Failed to parse health check response for \
-									 {name}: {e}"
-								);
-							}
-						}
-					} else {
-						router.update_processor_health(PaymentProcessor {
-							name:              name.clone(),
-							url:               url.clone(),
-							health:            HealthStatus::Failing,
-							min_response_time: 0,
-						});
-					}
+		let now = Instant::now();
+
+		for target in targets.iter_mut().filter(|target| target.next_check <= now) {
+			target.healthy = check_processor(&router, &http_client, target).await;
+			target.next_check = now + target.schedule.interval;
+		}
+
+		let all_processors_failing = targets.iter().all(|target| !target.healthy);
+		downtime_monitor.observe(all_processors_failing);
+
+		let next_check = targets
+			.iter()
+			.map(|target| target.next_check)
+			.min()
+			.unwrap_or(now + DEFAULT_HEALTH_CHECK_INTERVAL);
+
+		let [default, fallback] = &mut targets;
+		tokio::select! {
+			_ = sleep_until(next_check) => {}
+			_ = router.reset_requested() => {
+				let now = Instant::now();
+				for target in targets.iter_mut() {
+					target.next_check = now;
 				}
-				Err(e) => {
-					error!("Failed to perform health check for {name}: {e}");
-					let processor = PaymentProcessor {
-						name:              name.clone(),
-						url:               url.clone(),
-						health:            HealthStatus::Failing,
-						min_response_time: 0,
-					};
-					router.update_processor_health(processor);
+			}
+			Ok(()) = default.settings.changed() => default.next_check = Instant::now(),
+			Ok(()) = fallback.settings.changed() => fallback.next_check = Instant::now(),
+		}
+	}
+}
+
+struct HealthCheckTarget {
+	name:       String,
+	/// Where the processor is checked, swapped when it is reconfigured.
+	settings:   watch::Receiver<ProcessorSettings>,
+	schedule:   HealthCheckSchedule,
+	next_check: Instant,
+	healthy:    bool,
+}
+
+impl HealthCheckTarget {
+	fn new(
+		name: &str,
+		router: &InMemoryPaymentRouter,
+		schedule: HealthCheckSchedule,
+		next_check: Instant,
+	) -> Self {
+		Self {
+			name: name.to_string(),
+			settings: router
+				.watch_processor_settings(name)
+				.expect("Router knows every processor group"),
+			schedule,
+			next_check,
+			healthy: false,
+		}
+	}
+}
+
+/// Body of the processors' health endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheckResponse {
+	pub failing:           Option<bool>,
+	pub min_response_time: Option<u64>,
+}
+
+impl HealthCheckResponse {
+	/// Whether the processor is healthy and the response time to expect from
+	/// it. A processor that does not say it is up is taken as failing, and
+	/// when it reports no response time, the time the health call itself took
+	/// is used instead.
+	pub fn assess(&self, call_latency: Duration) -> (bool, u64) {
+		(
+			!self.failing.unwrap_or(true),
+			self.min_response_time
+				.unwrap_or(call_latency.as_millis() as u64),
+		)
+	}
+}
+
+/// Requests the health of the first replica of the processor that accepts a
+/// connection.
+async fn request_health(
+	http_client: &Client,
+	url: &str,
+	target: &HealthCheckTarget,
+) -> reqwest::Result<Response> {
+	let mut replicas = replica_urls(url).peekable();
+
+	loop {
+		let replica = replicas.next().unwrap_or(url);
+		let mut request =
+			http_client.get(format!("{replica}/payments/service-health"));
+		if let Some(timeout) = target.schedule.timeout {
+			request = request.timeout(timeout);
+		}
+
+		match request.send().await {
+			Err(e) if e.is_connect() && replicas.peek().is_some() => continue,
+			result => return result,
+		}
+	}
+}
+
+/// Checks a single processor and updates the router with the result. Returns
+/// whether the router considers the processor healthy afterwards.
+async fn check_processor(
+	router: &InMemoryPaymentRouter,
+	http_client: &Client,
+	target: &HealthCheckTarget,
+) -> bool {
+	let name = &target.name;
+	let url = target.settings.borrow().url.clone();
+	let url = url.as_str();
+
+	let started_at = Instant::now();
+	match request_health(http_client, url, target).await {
+		Ok(resp) => {
+			if resp.status().is_success() {
+				match resp.json::<HealthCheckResponse>().await {
+					Ok(health) => {
+						let (healthy, min_response_time) =
+							health.assess(started_at.elapsed());
+
+						router.record_health_check(
+							name,
+							url,
+							healthy,
+							min_response_time,
+						)
+					}
+					Err(e) => {
+						error!(
+							"Failed to parse health check response for {name}: {e}"
+						);
+						false
+					}
 				}
+			} else {
+				router.record_health_check(name, url, false, 0)
 			}
 		}
+		Err(e) => {
+			error!("Failed to perform health check for {name}: {e}");
+			router.record_health_check(name, url, false, 0)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_assesses_reported_health() {
+		let health: HealthCheckResponse =
+			serde_json::from_str(r#"{"failing":false,"minResponseTime":42}"#)
+				.unwrap();
+
+		assert_eq!(health.assess(Duration::from_millis(7)), (true, 42));
+	}
+
+	#[test]
+	fn test_falls_back_to_call_latency_and_failing() {
+		let health: HealthCheckResponse = serde_json::from_str("{}").unwrap();
 
-		// Respect the 5-second rate limit for health checks
-		sleep(Duration::from_secs(5)).await;
+		assert_eq!(health.assess(Duration::from_millis(7)), (false, 7));
 	}
 }
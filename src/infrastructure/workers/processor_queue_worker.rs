@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use circuitbreaker_rs::State;
+use log::{error, info, warn};
+use tokio::time::sleep;
+
+use crate::domain::payment::Payment;
+use crate::domain::payment_router::PaymentRouter;
+use crate::domain::queue::{Message, Queue};
+use crate::domain::repository::PaymentRepository;
+use crate::infrastructure::observability::log_redaction;
+use crate::infrastructure::observability::metrics::{WorkerMetrics, metrics};
+use crate::infrastructure::workers::in_flight_registry::in_flight_payments;
+use crate::infrastructure::workers::payment_processor_worker::{
+	skip_duplicate, try_process_payment,
+};
+use crate::infrastructure::workers::requeue_pacer::RequeuePacer;
+use crate::infrastructure::workers::retry_budget::RetryBudget;
+use crate::infrastructure::workers::worker_control::worker_control;
+use crate::use_cases::process_payment::ProcessPaymentUseCase;
+
+/// Processes the payments dispatched to a single processor, each one in its
+/// own task, as many at a time as the worker control allows. Payments the
+/// processor cannot take are handed back to the ingest queue so the
+/// dispatcher can route them elsewhere.
+#[allow(clippy::too_many_arguments)]
+pub async fn processor_queue_worker<Q, PR, R>(
+	processor_name: String,
+	processor_queue: Q,
+	ingest_queue: Q,
+	payment_repo: PR,
+	process_payment_use_case: ProcessPaymentUseCase<PR>,
+	router: R,
+	retry_budget: RetryBudget,
+	requeue_pacer: RequeuePacer,
+) where
+	Q: Queue<Payment> + Clone + Send + Sync + 'static,
+	PR: PaymentRepository + Clone + Send + Sync + 'static,
+	R: PaymentRouter + Clone + Send + Sync + 'static,
+{
+	let task = ProcessorPaymentTask {
+		worker: metrics().register_worker(&processor_name),
+		processor_name,
+		ingest_queue,
+		payment_repo,
+		process_payment_use_case,
+		router,
+		consecutive_failures: Arc::new(Mutex::new(HashMap::new())),
+		requeue_pacer: Arc::new(Mutex::new(requeue_pacer)),
+	};
+	let processor_name = &task.processor_name;
+
+	loop {
+		// Taken before popping, so a payment counts as in flight from the
+		// moment it leaves the queue.
+		let permit = worker_control().acquire().await;
+
+		let waiting_since = Instant::now();
+		let message = match processor_queue.pop().await {
+			Ok(Some(val)) => val,
+			Ok(None) => {
+				info!("No payments in {processor_name} queue, waiting...");
+				task.worker.record_idle(waiting_since.elapsed());
+				continue;
+			}
+			Err(e) => {
+				error!("Failed to pop from {processor_name} payments queue: {e}");
+				drop(permit);
+				sleep(Duration::from_secs(1)).await;
+				continue;
+			}
+		};
+		let in_flight = in_flight_payments()
+			.track(message.body.correlation_id)
+			.await;
+
+		if message.is_retry() {
+			retry_budget.acquire().await;
+		}
+
+		let task = task.clone();
+		tokio::spawn(async move {
+			task.process(message).await;
+			drop(in_flight);
+			drop(permit);
+		});
+	}
+}
+
+/// What a payment popped from a processor queue needs to be processed,
+/// shared by the tasks processing them.
+#[derive(Clone)]
+struct ProcessorPaymentTask<Q, PR, R>
+where
+	PR: PaymentRepository,
+{
+	processor_name:           String,
+	ingest_queue:             Q,
+	payment_repo:             PR,
+	process_payment_use_case: ProcessPaymentUseCase<PR>,
+	router:                   R,
+	consecutive_failures:     Arc<Mutex<HashMap<Arc<str>, u32>>>,
+	requeue_pacer:            Arc<Mutex<RequeuePacer>>,
+	worker:                   Arc<WorkerMetrics>,
+}
+
+impl<Q, PR, R> ProcessorPaymentTask<Q, PR, R>
+where
+	Q: Queue<Payment>,
+	PR: PaymentRepository + Clone + Send + Sync + 'static,
+	R: PaymentRouter,
+{
+	async fn process(&self, mut message: Message<Payment>) {
+		let processor_name = &self.processor_name;
+		let started_at = Instant::now();
+		let payment: Payment = message.body.clone();
+
+		if let Ok(true) = self
+			.payment_repo
+			.is_already_processed(&payment.correlation_id.to_string())
+			.await
+		{
+			skip_duplicate(&self.payment_repo, &payment).await;
+			self.worker.record_loop(started_at.elapsed());
+			return;
+		}
+
+		let processed = match self.router.get_processor(processor_name).await {
+			Some(selection) if selection.breaker.current_state() != State::Open => {
+				try_process_payment(
+					&self.process_payment_use_case,
+					&mut message.body,
+					message.request_id.as_deref(),
+					selection,
+					&self.consecutive_failures,
+					&self.worker,
+				)
+				.await
+			}
+			_ => false,
+		};
+
+		if !processed {
+			warn!(
+				"Payment {} could not be processed by {processor_name}. Handing it \
+				 back for dispatch.",
+				log_redaction::correlation_id(payment.correlation_id)
+			);
+			if let Err(e) = self.ingest_queue.push(message.retried()).await {
+				error!("Failed to re-queue payment: {e}");
+			}
+			metrics().record_requeued();
+			self.worker.record_requeued();
+		}
+
+		self.worker.record_loop(started_at.elapsed());
+
+		// Sleeps holding the processing slot, so fewer payments are taken
+		// while requeues storm.
+		let pause = self
+			.requeue_pacer
+			.lock()
+			.unwrap()
+			.record(!processed, Instant::now());
+		if let Some(pause) = pause {
+			sleep(pause).await;
+		}
+	}
+}
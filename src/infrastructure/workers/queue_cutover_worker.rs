@@ -0,0 +1,39 @@
+use log::{error, info};
+use tokio::time::{Duration, sleep};
+
+use crate::infrastructure::queue::redis_payment_queue::PaymentQueue;
+
+/// Reports how many payments are left under the previous key of a queue
+/// cutover, and stops once the cutover has ended, after which the queue only
+/// reads its current key.
+pub async fn queue_cutover_worker(payment_queue: PaymentQueue, interval: Duration) {
+	let mut reported_empty = false;
+
+	loop {
+		match payment_queue.drain_remaining().await {
+			Ok(None) => return,
+			Ok(Some(_)) if payment_queue.cutover_ended() => {
+				info!("Queue cutover complete, the previous queue key is drained");
+				return;
+			}
+			Ok(Some(0)) => {
+				if !reported_empty {
+					info!(
+						"Previous queue key is empty, still read until the cutover \
+						 ends"
+					);
+					reported_empty = true;
+				}
+			}
+			Ok(Some(remaining)) => {
+				reported_empty = false;
+				info!(
+					"Queue cutover in progress, {remaining} payments left to drain"
+				)
+			}
+			Err(e) => error!("Failed to check the queue cutover progress: {e}"),
+		}
+
+		sleep(interval).await;
+	}
+}
@@ -0,0 +1,33 @@
+use log::error;
+use time::OffsetDateTime;
+use tokio::time::{Duration, Instant, sleep};
+
+use crate::domain::payment::Payment;
+use crate::domain::queue::Queue;
+use crate::infrastructure::observability::metrics::metrics;
+
+pub async fn queue_depth_reconciler_worker<Q>(payment_queue: Q, interval: Duration)
+where
+	Q: Queue<Payment>,
+{
+	loop {
+		match payment_queue.depth().await {
+			Ok(depth) => metrics().set_queue_depth(depth as u64),
+			Err(e) => error!("Failed to reconcile payments queue depth: {e}"),
+		}
+		match payment_queue.oldest_enqueued_at().await {
+			Ok(enqueued_at) => {
+				let lag = enqueued_at
+					.and_then(|at| {
+						Duration::try_from(OffsetDateTime::now_utc() - at).ok()
+					})
+					.unwrap_or_default();
+				metrics().set_queue_lag(lag);
+			}
+			Err(e) => error!("Failed to measure payments queue lag: {e}"),
+		}
+		metrics().sample_throughput(Instant::now().into_std());
+
+		sleep(interval).await;
+	}
+}
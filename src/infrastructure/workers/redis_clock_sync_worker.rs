@@ -0,0 +1,16 @@
+use log::warn;
+use tokio::time::{Duration, sleep};
+
+use crate::infrastructure::persistence::redis_clock::RedisClock;
+
+/// Measures the offset to the Redis clock every `interval`, so the drift of
+/// the system clock keeps being corrected.
+pub async fn redis_clock_sync_worker(clock: RedisClock, interval: Duration) {
+	loop {
+		sleep(interval).await;
+
+		if let Err(e) = clock.sync().await {
+			warn!("Failed to sync with the Redis clock: {e}");
+		}
+	}
+}
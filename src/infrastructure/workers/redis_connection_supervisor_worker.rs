@@ -0,0 +1,25 @@
+use log::{error, warn};
+use tokio::time::{Duration, sleep};
+
+use crate::infrastructure::persistence::redis_connection::{
+	ConnectionHealth, SupervisedConnection,
+};
+
+/// PINGs Redis over the shared connection every `interval`, rebuilding the
+/// connection as soon as it stops answering.
+pub async fn redis_connection_supervisor_worker(
+	connection: SupervisedConnection,
+	interval: Duration,
+) {
+	loop {
+		sleep(interval).await;
+
+		match connection.check(interval).await {
+			ConnectionHealth::Healthy => {}
+			ConnectionHealth::Rebuilt => {
+				warn!("Redis connection went stale and was rebuilt")
+			}
+			ConnectionHealth::Broken => error!("Redis is unreachable"),
+		}
+	}
+}
@@ -0,0 +1,139 @@
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+
+/// Outcomes needed in a window before its requeue ratio is trusted.
+const MIN_WINDOW_SAMPLES: u32 = 10;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(10);
+const MAX_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Keeps a worker from spinning on payments no processor can take. Once the
+/// share of requeued payments in a window reaches `max_ratio`, the worker is
+/// paced with a sleep that doubles on every further requeue, until a payment
+/// goes through again.
+#[derive(Clone)]
+pub struct RequeuePacer {
+	detection:    Option<StormDetection>,
+	window_start: Option<Instant>,
+	handled:      u32,
+	requeued:     u32,
+	backoff:      Option<Duration>,
+}
+
+#[derive(Clone, Copy)]
+struct StormDetection {
+	window:    Duration,
+	max_ratio: f64,
+}
+
+impl RequeuePacer {
+	pub fn new(window: Duration, max_ratio: f64) -> Self {
+		Self {
+			detection: Some(StormDetection { window, max_ratio }),
+			..Self::disabled()
+		}
+	}
+
+	pub fn disabled() -> Self {
+		Self {
+			detection:    None,
+			window_start: None,
+			handled:      0,
+			requeued:     0,
+			backoff:      None,
+		}
+	}
+
+	/// Records whether the last payment was requeued and returns how long the
+	/// worker should pause before taking the next one.
+	pub fn record(&mut self, requeued: bool, now: Instant) -> Option<Duration> {
+		let detection = self.detection?;
+
+		if !requeued && self.backoff.take().is_some() {
+			info!("Payments are going through again, leaving paced mode");
+		}
+
+		if let Some(backoff) = self.backoff {
+			let backoff = (backoff * 2).min(MAX_BACKOFF);
+			self.backoff = Some(backoff);
+			return Some(backoff);
+		}
+
+		let window_start = *self.window_start.get_or_insert(now);
+		self.handled += 1;
+		if requeued {
+			self.requeued += 1;
+		}
+
+		if now.saturating_duration_since(window_start) >= detection.window {
+			let ratio = self.requeued as f64 / self.handled as f64;
+			if self.handled >= MIN_WINDOW_SAMPLES && ratio >= detection.max_ratio {
+				warn!(
+					"{:.0}% of payments were requeued in the last {:?}, pacing the \
+					 worker",
+					ratio * 100.0,
+					detection.window
+				);
+				self.backoff = Some(INITIAL_BACKOFF);
+			}
+
+			self.window_start = None;
+			self.handled = 0;
+			self.requeued = 0;
+		}
+
+		self.backoff
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn storm(pacer: &mut RequeuePacer, start: Instant) -> Option<Duration> {
+		for _ in 0..MIN_WINDOW_SAMPLES {
+			pacer.record(true, start);
+		}
+		pacer.record(true, start + Duration::from_secs(1))
+	}
+
+	#[test]
+	fn test_disabled_pacer_never_pauses() {
+		let mut pacer = RequeuePacer::disabled();
+
+		assert_eq!(storm(&mut pacer, Instant::now()), None);
+	}
+
+	#[test]
+	fn test_paces_with_increasing_backoff_during_storm() {
+		let mut pacer = RequeuePacer::new(Duration::from_secs(1), 0.9);
+		let start = Instant::now();
+
+		assert_eq!(storm(&mut pacer, start), Some(INITIAL_BACKOFF));
+		assert_eq!(pacer.record(true, start), Some(INITIAL_BACKOFF * 2));
+		assert_eq!(pacer.record(true, start), Some(INITIAL_BACKOFF * 4));
+	}
+
+	#[test]
+	fn test_leaves_paced_mode_once_a_payment_goes_through() {
+		let mut pacer = RequeuePacer::new(Duration::from_secs(1), 0.9);
+		let start = Instant::now();
+
+		storm(&mut pacer, start);
+
+		assert_eq!(pacer.record(false, start), None);
+		assert_eq!(pacer.record(true, start), None);
+	}
+
+	#[test]
+	fn test_does_not_pace_below_ratio() {
+		let mut pacer = RequeuePacer::new(Duration::from_secs(1), 0.9);
+		let start = Instant::now();
+
+		for i in 0..MIN_WINDOW_SAMPLES {
+			pacer.record(i % 2 == 0, start);
+		}
+
+		assert_eq!(pacer.record(true, start + Duration::from_secs(1)), None);
+	}
+}
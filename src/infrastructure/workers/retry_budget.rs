@@ -0,0 +1,113 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::time::sleep;
+
+/// Token bucket shared by the payment workers that caps how many retried
+/// payments are attempted per second, so a backlog built up during an outage
+/// is released gradually once the processors recover.
+#[derive(Clone)]
+pub struct RetryBudget {
+	bucket: Option<Arc<Mutex<TokenBucket>>>,
+}
+
+struct TokenBucket {
+	capacity:       f64,
+	tokens:         f64,
+	refill_per_sec: f64,
+	last_refill:    Instant,
+}
+
+impl RetryBudget {
+	/// Allows `retries_per_second` retries, with bursts of up to one second
+	/// worth of retries.
+	pub fn new(retries_per_second: f64) -> Self {
+		let capacity = retries_per_second.max(1.0);
+
+		Self {
+			bucket: Some(Arc::new(Mutex::new(TokenBucket {
+				capacity,
+				tokens: capacity,
+				refill_per_sec: retries_per_second,
+				last_refill: Instant::now(),
+			}))),
+		}
+	}
+
+	pub fn unlimited() -> Self {
+		Self { bucket: None }
+	}
+
+	/// Waits until a retry is allowed by the budget.
+	pub async fn acquire(&self) {
+		while let Err(wait) = self.try_acquire(Instant::now()) {
+			sleep(wait).await;
+		}
+	}
+
+	/// Takes a token if one is available, otherwise returns how long to wait
+	/// for the next one.
+	fn try_acquire(&self, now: Instant) -> Result<(), Duration> {
+		let Some(bucket) = &self.bucket else {
+			return Ok(());
+		};
+
+		let mut bucket = bucket.lock().unwrap();
+
+		let elapsed = now.saturating_duration_since(bucket.last_refill);
+		bucket.tokens = (bucket.tokens +
+			elapsed.as_secs_f64() * bucket.refill_per_sec)
+			.min(bucket.capacity);
+		bucket.last_refill = now;
+
+		if bucket.tokens >= 1.0 {
+			bucket.tokens -= 1.0;
+			return Ok(());
+		}
+
+		Err(Duration::from_secs_f64(
+			(1.0 - bucket.tokens) / bucket.refill_per_sec,
+		))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_unlimited_budget_never_waits() {
+		let budget = RetryBudget::unlimited();
+		let now = Instant::now();
+
+		for _ in 0..1000 {
+			assert_eq!(budget.try_acquire(now), Ok(()));
+		}
+	}
+
+	#[test]
+	fn test_budget_allows_burst_then_waits() {
+		let budget = RetryBudget::new(2.0);
+		let now = Instant::now();
+
+		assert_eq!(budget.try_acquire(now), Ok(()));
+		assert_eq!(budget.try_acquire(now), Ok(()));
+		assert_eq!(budget.try_acquire(now), Err(Duration::from_millis(500)));
+	}
+
+	#[test]
+	fn test_budget_refills_over_time() {
+		let budget = RetryBudget::new(2.0);
+		let now = Instant::now();
+
+		budget.try_acquire(now).unwrap();
+		budget.try_acquire(now).unwrap();
+
+		assert_eq!(budget.try_acquire(now + Duration::from_millis(500)), Ok(()));
+		assert!(
+			budget
+				.try_acquire(now + Duration::from_millis(500))
+				.is_err()
+		);
+	}
+}
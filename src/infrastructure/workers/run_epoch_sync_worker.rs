@@ -0,0 +1,16 @@
+use log::warn;
+use tokio::time::{Duration, sleep};
+
+use crate::infrastructure::persistence::redis_run_epoch::RedisRunEpoch;
+
+/// Reads the shared run epoch every `interval`, so payments accepted by this
+/// instance are stamped with an epoch started through another one.
+pub async fn run_epoch_sync_worker(run_epoch: RedisRunEpoch, interval: Duration) {
+	loop {
+		sleep(interval).await;
+
+		if let Err(e) = run_epoch.sync().await {
+			warn!("Failed to sync the run epoch: {e}");
+		}
+	}
+}
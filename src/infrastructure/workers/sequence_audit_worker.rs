@@ -0,0 +1,28 @@
+use log::{error, info, warn};
+use tokio::time::{Duration, sleep};
+
+use crate::infrastructure::observability::metrics::metrics;
+use crate::infrastructure::queue::redis_payment_queue::PaymentQueue;
+
+/// Periodically compares the message sequences issued by the payments queue
+/// with those delivered, exporting and logging the messages that went
+/// missing. Returns right away when the queue does not number its messages.
+pub async fn sequence_audit_worker(payment_queue: PaymentQueue, interval: Duration) {
+	loop {
+		match payment_queue.sequence_report().await {
+			Ok(None) => return,
+			Ok(Some(report)) => {
+				let gaps = report.gaps();
+				metrics().set_sequence_gaps(gaps);
+				if gaps > 0 {
+					warn!("{gaps} queued messages went missing: {report:?}");
+				} else {
+					info!("Message sequence audit: {report:?}");
+				}
+			}
+			Err(e) => error!("Failed to audit message sequences: {e}"),
+		}
+
+		sleep(interval).await;
+	}
+}
@@ -0,0 +1,259 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{LazyLock, Mutex};
+
+use tokio::sync::{Notify, watch};
+
+static WORKER_CONTROL: LazyLock<WorkerControl> = LazyLock::new(WorkerControl::new);
+
+/// Process-wide switches the payment workers check between payments, so an
+/// operator can pause processing or cap how many payments are in flight.
+pub fn worker_control() -> &'static WorkerControl {
+	&WORKER_CONTROL
+}
+
+#[derive(Debug)]
+pub struct WorkerControl {
+	paused:      watch::Sender<bool>,
+	concurrency: AtomicUsize,
+	in_flight:   AtomicUsize,
+	released:    Notify,
+	drains:      Mutex<Drains>,
+}
+
+/// Drains under way, and whether the workers were paused before the first of
+/// them began.
+#[derive(Debug, Default)]
+struct Drains {
+	active:     usize,
+	was_paused: bool,
+}
+
+impl WorkerControl {
+	pub fn new() -> Self {
+		Self {
+			paused:      watch::Sender::new(false),
+			concurrency: AtomicUsize::new(usize::MAX),
+			in_flight:   AtomicUsize::new(0),
+			released:    Notify::new(),
+			drains:      Mutex::default(),
+		}
+	}
+
+	pub fn pause(&self) {
+		self.paused.send_replace(true);
+	}
+
+	pub fn resume(&self) {
+		self.paused.send_replace(false);
+	}
+
+	pub fn is_paused(&self) -> bool {
+		*self.paused.borrow()
+	}
+
+	/// Resolves immediately unless the workers are paused, in which case it
+	/// waits for them to be resumed.
+	pub async fn wait_until_resumed(&self) {
+		let mut paused = self.paused.subscribe();
+		let _ = paused.wait_for(|paused| !paused).await;
+	}
+
+	/// Pauses the workers and waits for the payments they are processing to be
+	/// saved, so the caller can change the saved payments without a worker
+	/// racing it. Workers hold a slot while popping, so a payment popped
+	/// during the drain is waited for as well. The workers are resumed when the
+	/// last of the guards of overlapping drains is dropped, unless they were
+	/// already paused before the first drain.
+	pub async fn drain(&self) -> DrainGuard<'_> {
+		// Taken before waiting, so giving up on the drain resumes the workers.
+		let guard = {
+			let mut drains = self.drains.lock().unwrap();
+			let was_paused = self.paused.send_replace(true);
+			if drains.active == 0 {
+				drains.was_paused = was_paused;
+			}
+			drains.active += 1;
+			DrainGuard { control: self }
+		};
+		loop {
+			let released = self.released.notified();
+			tokio::pin!(released);
+			released.as_mut().enable();
+
+			if self.in_flight() == 0 {
+				return guard;
+			}
+
+			released.await;
+		}
+	}
+
+	/// Caps how many payments the workers process at the same time.
+	pub fn set_concurrency(&self, concurrency: usize) {
+		self.concurrency
+			.store(concurrency.max(1), Ordering::Relaxed);
+		self.released.notify_waiters();
+	}
+
+	pub fn concurrency(&self) -> usize {
+		self.concurrency.load(Ordering::Relaxed)
+	}
+
+	pub fn in_flight(&self) -> usize {
+		self.in_flight.load(Ordering::Relaxed)
+	}
+
+	/// Waits for a processing slot, and for the workers to be resumed if they
+	/// are paused. The slot is released when the returned permit is dropped.
+	pub async fn acquire(&self) -> ProcessingPermit<'_> {
+		loop {
+			self.wait_until_resumed().await;
+
+			let released = self.released.notified();
+			tokio::pin!(released);
+			released.as_mut().enable();
+
+			let acquired = self.in_flight.fetch_update(
+				Ordering::AcqRel,
+				Ordering::Acquire,
+				|in_flight| {
+					(in_flight < self.concurrency()).then_some(in_flight + 1)
+				},
+			);
+			if acquired.is_ok() {
+				let permit = ProcessingPermit { control: self };
+				// Paused while taking the slot: give it back, as a drain may
+				// already have seen no payments in flight.
+				if self.is_paused() {
+					drop(permit);
+					continue;
+				}
+				return permit;
+			}
+
+			released.await;
+		}
+	}
+}
+
+impl Default for WorkerControl {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+pub struct ProcessingPermit<'a> {
+	control: &'a WorkerControl,
+}
+
+impl Drop for ProcessingPermit<'_> {
+	fn drop(&mut self) {
+		self.control.in_flight.fetch_sub(1, Ordering::AcqRel);
+		self.control.released.notify_waiters();
+	}
+}
+
+pub struct DrainGuard<'a> {
+	control: &'a WorkerControl,
+}
+
+impl Drop for DrainGuard<'_> {
+	fn drop(&mut self) {
+		let mut drains = self.control.drains.lock().unwrap();
+		drains.active -= 1;
+		if drains.active == 0 && !drains.was_paused {
+			self.control.resume();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use super::*;
+
+	#[tokio::test]
+	async fn test_wait_until_resumed_blocks_while_paused() {
+		let control = WorkerControl::new();
+		control.wait_until_resumed().await;
+
+		control.pause();
+		assert!(
+			tokio::time::timeout(
+				Duration::from_millis(20),
+				control.wait_until_resumed()
+			)
+			.await
+			.is_err()
+		);
+
+		control.resume();
+		control.wait_until_resumed().await;
+	}
+
+	#[tokio::test]
+	async fn test_acquire_respects_concurrency() {
+		let control = WorkerControl::new();
+		control.set_concurrency(1);
+
+		let permit = control.acquire().await;
+		assert_eq!(control.in_flight(), 1);
+		assert!(
+			tokio::time::timeout(Duration::from_millis(20), control.acquire())
+				.await
+				.is_err()
+		);
+
+		drop(permit);
+		let _permit = control.acquire().await;
+
+		control.set_concurrency(2);
+		let _second_permit = control.acquire().await;
+		assert_eq!(control.in_flight(), 2);
+	}
+
+	#[tokio::test]
+	async fn test_drain_waits_for_payments_in_flight() {
+		let control = WorkerControl::new();
+		let permit = control.acquire().await;
+
+		assert!(
+			tokio::time::timeout(Duration::from_millis(20), control.drain())
+				.await
+				.is_err()
+		);
+		assert!(!control.is_paused());
+
+		drop(permit);
+		let drained = control.drain().await;
+		assert!(
+			tokio::time::timeout(Duration::from_millis(20), control.acquire())
+				.await
+				.is_err()
+		);
+		assert_eq!(control.in_flight(), 0);
+
+		drop(drained);
+		assert!(!control.is_paused());
+		let _permit = control.acquire().await;
+	}
+
+	#[tokio::test]
+	async fn test_overlapping_drains_resume_when_the_last_ends() {
+		let control = WorkerControl::new();
+
+		let first = control.drain().await;
+		let second = control.drain().await;
+
+		drop(first);
+		assert!(control.is_paused());
+
+		drop(second);
+		assert!(!control.is_paused());
+
+		control.pause();
+		drop(control.drain().await);
+		assert!(control.is_paused());
+	}
+}
@@ -1,78 +1,1012 @@
+use std::collections::HashMap;
+use std::net::ToSocketAddrs;
+use std::path::Path;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use actix_web::{App, HttpServer, web};
-use log::info;
-use reqwest::Client;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
+use actix_web::{App, HttpServer, ResponseError, middleware, web};
+use log::{error, info, warn};
+use reqwest::{Certificate, Client, Identity};
+use tokio::runtime::Runtime;
+use tokio::task::JoinHandle;
 
 pub mod adapters;
+#[cfg(feature = "client")]
+pub mod client;
+#[cfg(feature = "contract")]
+pub mod contract;
 pub mod domain;
+#[cfg(feature = "harness")]
+pub mod harness;
 pub mod infrastructure;
 pub mod use_cases;
 
-use crate::adapters::web::handlers::{payments, payments_purge, payments_summary};
-use crate::infrastructure::config::settings::Config;
-use crate::infrastructure::persistence::redis_payment_repository::RedisPaymentRepository;
+use crate::adapters::web::admin_auth::{AdminToken, require_admin_token};
+use crate::adapters::web::admin_command::AdminCommandDispatcher;
+use crate::adapters::web::endpoint_timeout::{
+	EndpointTimeouts, enforce_endpoint_timeouts,
+};
+use crate::adapters::web::errors::{ApiError, json_error};
+use crate::adapters::web::handlers::{
+	admin_ws, configure_processor, current_run_epoch, debug_vars, effective_config,
+	export_snapshot, import_snapshot, list_feature_flags, list_processors,
+	pause_workers, payments, payments_duplicates, payments_purge, payments_summary,
+	processor_health_history, prometheus_metrics, purge_processor_health_history,
+	reset_router, resume_workers, set_feature_flag, set_workers_concurrency,
+	start_run_epoch,
+};
+use crate::adapters::web::listener;
+use crate::adapters::web::method_probe::answer_method_probes;
+use crate::adapters::web::request_id::propagate_request_id;
+use crate::adapters::web::state::{AppState, DebugVarsState, SharedPaymentQueue};
+use crate::domain::payment_archive::PaymentArchive;
+use crate::domain::payment_processor::PROCESSOR_GROUPS;
+use crate::infrastructure::alerting::processor_downtime_monitor::ProcessorDowntimeMonitor;
+use crate::infrastructure::config::feature_flags::feature_flags;
+use crate::infrastructure::config::settings::{Config, DedupMode, QueueMode};
+use crate::infrastructure::config::worker_budget::WorkerBudget;
+use crate::infrastructure::gateway::caching_resolver::CachingResolver;
+use crate::infrastructure::gateway::connection_warmer::ConnectionWarmer;
+use crate::infrastructure::gateway::processor_discovery::{
+	DiscoverySource, ProcessorDiscovery,
+};
+use crate::infrastructure::observability::benchmark_report::BenchmarkReport;
+use crate::infrastructure::observability::metrics::metrics;
+use crate::infrastructure::observability::statsd_exporter::StatsdExporter;
+use crate::infrastructure::persistence::dual_write_payment_repository::DualWritePaymentRepository;
+#[cfg(feature = "postgres")]
+use crate::infrastructure::persistence::postgres_payment_archive::PostgresPaymentArchive;
+use crate::infrastructure::persistence::redis_clock::RedisClock;
+use crate::infrastructure::persistence::redis_connection::SupervisedConnection;
+use crate::infrastructure::persistence::redis_key_space::RedisKeySpace;
+use crate::infrastructure::persistence::redis_legacy_payment_store::RedisLegacyPaymentStore;
+use crate::infrastructure::persistence::redis_payment_repository::{
+	DedupStrategy, RedisPaymentRepository,
+};
+use crate::infrastructure::persistence::redis_run_epoch::RedisRunEpoch;
+use crate::infrastructure::queue::redis_payment_ingest_stream::PaymentIngestStream;
 use crate::infrastructure::queue::redis_payment_queue::PaymentQueue;
 use crate::infrastructure::routing::in_memory_payment_router::InMemoryPaymentRouter;
+use crate::infrastructure::workers::connection_warmup_worker::connection_warmup_worker;
+use crate::infrastructure::workers::in_flight_registry::in_flight_payments;
+use crate::infrastructure::workers::maintenance_worker::{
+	MaintenanceTasks, maintenance_worker,
+};
+use crate::infrastructure::workers::metrics_exporter_worker::metrics_exporter_worker;
+use crate::infrastructure::workers::payment_archiver_worker::payment_archiver_worker;
+use crate::infrastructure::workers::payment_dispatcher_worker::payment_dispatcher_worker;
+use crate::infrastructure::workers::payment_ingest_worker::payment_ingest_worker;
 use crate::infrastructure::workers::payment_processor_worker::payment_processing_worker;
-use crate::infrastructure::workers::processor_health_monitor_worker::processor_health_monitor_worker;
+use crate::infrastructure::workers::payment_verification_worker::payment_verification_worker;
+use crate::infrastructure::workers::processor_discovery_worker::processor_discovery_worker;
+use crate::infrastructure::workers::processor_health_monitor_worker::{
+	HealthCheckSchedule, processor_health_monitor_worker,
+};
+use crate::infrastructure::workers::processor_queue_worker::processor_queue_worker;
+use crate::infrastructure::workers::queue_cutover_worker::queue_cutover_worker;
+use crate::infrastructure::workers::queue_depth_reconciler_worker::queue_depth_reconciler_worker;
+use crate::infrastructure::workers::redis_clock_sync_worker::redis_clock_sync_worker;
+use crate::infrastructure::workers::redis_connection_supervisor_worker::redis_connection_supervisor_worker;
+use crate::infrastructure::workers::requeue_pacer::RequeuePacer;
+use crate::infrastructure::workers::retry_budget::RetryBudget;
+use crate::infrastructure::workers::run_epoch_sync_worker::run_epoch_sync_worker;
+use crate::infrastructure::workers::sequence_audit_worker::sequence_audit_worker;
+use crate::infrastructure::workers::worker_control::worker_control;
 use crate::use_cases::create_payment::CreatePaymentUseCase;
+use crate::use_cases::dto::GetPaymentSummaryQuery;
 use crate::use_cases::get_payment_summary::GetPaymentSummaryUseCase;
+use crate::use_cases::migrate_legacy_payments::MigrateLegacyPaymentsUseCase;
 use crate::use_cases::process_payment::ProcessPaymentUseCase;
-use crate::use_cases::purge_payments::PurgePaymentsUseCase;
+
+/// The repository processed payments are stored in, mirrored to a second
+/// Redis while `dual_write_redis_url` is set.
+pub type AppPaymentRepository =
+	DualWritePaymentRepository<RedisPaymentRepository, RedisPaymentRepository>;
+
+/// The components wired from the configuration, shared by the HTTP app and
+/// the background workers.
+#[derive(Clone)]
+pub struct AppContext {
+	pub config:           Arc<Config>,
+	pub redis_client:     redis::Client,
+	/// Shared Redis connection kept alive by the supervisor worker, when
+	/// `redis_keepalive_interval_ms` is set.
+	pub redis_connection: Option<SupervisedConnection>,
+	/// Clock payments are stamped with when `redis_clock_sync_interval_ms` is
+	/// set, instead of the system clock.
+	pub redis_clock:      Option<RedisClock>,
+	/// Benchmark run new payments are stamped with, shared through Redis.
+	pub run_epoch:        RedisRunEpoch,
+	pub http_client:      Client,
+	pub router:           InMemoryPaymentRouter,
+	pub payment_queue:    PaymentQueue,
+	/// Queue of each processor, when payments are dispatched to them.
+	pub processor_queues: HashMap<String, PaymentQueue>,
+	pub payment_repo:     AppPaymentRepository,
+	pub payment_archive:  Option<Arc<dyn PaymentArchive>>,
+	pub started_at:       Instant,
+}
+
+impl AppContext {
+	pub async fn from_config(config: Arc<Config>) -> Self {
+		let redis_client = redis::Client::open(config.redis_url.clone())
+			.expect("Invalid Redis URL");
+
+		let mut router = InMemoryPaymentRouter::new()
+			.with_processor_urls(
+				&config.default_payment_processor_url,
+				&config.fallback_payment_processor_url,
+			)
+			.with_flap_damping(
+				config.health_check_failure_threshold,
+				config.health_check_success_threshold,
+			);
+		if let Some(window_ms) = config.slow_start_window_ms {
+			router = router.with_slow_start(Duration::from_millis(window_ms));
+		}
+		if config.routing_cache_ttl_ms > 0 {
+			router = router.with_selection_cache(Duration::from_millis(
+				config.routing_cache_ttl_ms,
+			));
+		}
+		if let Some(processor) = &config.preferred_processor {
+			if !PROCESSOR_GROUPS.contains(&processor.as_str()) {
+				warn!("Ignoring unknown preferred processor '{processor}'");
+			}
+			router = router.with_preferred_processor(processor);
+		}
+
+		if config.admin_token.is_none() {
+			warn!("No admin token set, refusing admin routes that change state");
+		}
+
+		if let Some(flags) = &config.feature_flags {
+			for flag in feature_flags().enable_all(flags) {
+				warn!("Ignoring unknown feature flag '{flag}'");
+			}
+		}
+
+		let redis_payment_repository = |client: &redis::Client| {
+			let mut payment_repo = RedisPaymentRepository::new(client.clone())
+				.with_dedup(DedupStrategy::from_config(&config));
+			if let Some(retention) = config.payment_retention {
+				payment_repo =
+					payment_repo.with_retention(Duration::from_secs(retention));
+			}
+			payment_repo
+		};
+
+		let redis_connection = config
+			.redis_keepalive_interval_ms
+			.map(|_| SupervisedConnection::new(redis_client.clone()));
+
+		if config.in_flight_registry {
+			in_flight_payments().share_through(
+				redis_connection.clone().unwrap_or_else(|| {
+					SupervisedConnection::new(redis_client.clone())
+				}),
+				instance_name(),
+			);
+		}
+
+		let redis_clock = match config.redis_clock_sync_interval_ms {
+			Some(_) => {
+				let clock = RedisClock::new(redis_client.clone());
+				match clock.sync().await {
+					Ok(offset) => {
+						info!("Using the Redis clock, {offset} off the system clock")
+					}
+					Err(e) => warn!("Failed to sync with the Redis clock: {e}"),
+				}
+				Some(clock)
+			}
+			None => None,
+		};
+
+		let run_epoch = RedisRunEpoch::new(redis_client.clone());
+		match run_epoch.sync().await {
+			Ok(0) => {}
+			Ok(epoch) => info!("Stamping payments with run epoch {epoch}"),
+			Err(e) => warn!("Failed to read the run epoch: {e}"),
+		}
+
+		let mut primary_repo = redis_payment_repository(&redis_client);
+		match primary_repo.check_dedup_support().await {
+			Ok(()) => {}
+			Err(e) if e.is_io_error() || e.is_connection_refusal() => {
+				warn!("Failed to check the dedup mode against Redis: {e}")
+			}
+			Err(e) => panic!(
+				"Dedup mode {:?} is not supported by Redis: {e}",
+				config.dedup_mode
+			),
+		}
+		if config.dedup_mode == DedupMode::Bloom &&
+			config.payment_retention.is_none()
+		{
+			warn!(
+				"Bloom dedup without payment_retention: the processed payments set \
+				 still grows with every payment"
+			);
+		}
+		if config.summary_cache {
+			primary_repo = primary_repo.with_summary_cache();
+		}
+		if config.legacy_summary_compat {
+			primary_repo = primary_repo.with_legacy_summary();
+		}
+		if let Some(connection) = &redis_connection {
+			primary_repo =
+				primary_repo.with_supervised_connection(connection.clone());
+		}
+		let mut payment_repo = DualWritePaymentRepository::new(primary_repo);
+		if let Some(url) = &config.dual_write_redis_url {
+			info!("Mirroring processed payments to a second Redis");
+			let secondary_client = redis::Client::open(url.clone())
+				.expect("Invalid dual-write Redis URL");
+			payment_repo = payment_repo
+				.with_secondary(redis_payment_repository(&secondary_client));
+		}
+
+		let mut payment_queue = PaymentQueue::new(redis_client.clone())
+			.with_pop_timeout(Duration::from_millis(config.queue_pop_timeout_ms));
+		if let Some(key) = &config.queue_key {
+			payment_queue = payment_queue.with_key(key.clone());
+		}
+		if let Some(previous_key) = &config.queue_cutover_from {
+			info!("Draining payments queued under '{previous_key}'");
+			payment_queue = payment_queue.with_cutover_from(
+				previous_key.clone(),
+				config.queue_cutover_grace_ms.map(Duration::from_millis),
+			);
+		}
+		if let Some(ttl) = config.queue_dedup_ttl {
+			payment_queue = payment_queue.with_dedup(Duration::from_secs(ttl));
+		}
+		if let Some(threshold) = config.queue_compression_threshold {
+			payment_queue = payment_queue.with_compression(threshold);
+		}
+		if config.queue_checksums {
+			payment_queue = payment_queue.with_checksums();
+		}
+		if config.queue_sequencing {
+			payment_queue = payment_queue.with_sequencing();
+		}
+		if let Some(max_age) = config.queue_max_age_ms {
+			payment_queue =
+				payment_queue.with_max_age(Duration::from_millis(max_age));
+		}
+		if let Some(connection) = &redis_connection {
+			payment_queue =
+				payment_queue.with_supervised_connection(connection.clone());
+		}
+
+		let processor_queues = match config.queue_mode {
+			QueueMode::Shared => HashMap::new(),
+			QueueMode::PerProcessor => PROCESSOR_GROUPS
+				.iter()
+				.map(|processor| {
+					let mut processor_queue =
+						PaymentQueue::for_processor(redis_client.clone(), processor)
+							.with_pop_timeout(Duration::from_millis(
+								config.queue_pop_timeout_ms,
+							));
+					if let Some(threshold) = config.queue_compression_threshold {
+						processor_queue =
+							processor_queue.with_compression(threshold);
+					}
+					if config.queue_checksums {
+						processor_queue = processor_queue.with_checksums();
+					}
+					if let Some(max_age) = config.queue_max_age_ms {
+						processor_queue = processor_queue
+							.with_max_age(Duration::from_millis(max_age));
+					}
+					if let Some(connection) = &redis_connection {
+						processor_queue = processor_queue
+							.with_supervised_connection(connection.clone());
+					}
+					(processor.to_string(), processor_queue)
+				})
+				.collect(),
+		};
+
+		Self {
+			payment_queue,
+			processor_queues,
+			payment_archive: connect_payment_archive(&config).await,
+			started_at: Instant::now(),
+			http_client: Client::new(),
+			redis_client,
+			redis_connection,
+			redis_clock,
+			run_epoch,
+			router,
+			payment_repo,
+			config,
+		}
+	}
+
+	fn archive_hot_window(&self) -> Duration {
+		Duration::from_secs(self.config.archive_hot_window_hours * 60 * 60)
+	}
+}
+
+/// Handles of the spawned background workers.
+pub struct WorkerHandles(Vec<JoinHandle<()>>);
+
+impl WorkerHandles {
+	pub fn abort(&self) {
+		for handle in &self.0 {
+			handle.abort();
+		}
+	}
+}
 
 pub async fn run(config: Arc<Config>) -> std::io::Result<()> {
 	env_logger::init();
 
-	let redis_client =
-		redis::Client::open(config.redis_url.clone()).expect("Invalid Redis URL");
+	let context = AppContext::from_config(config).await;
+	let worker_runtime = context.config.worker_runtime_threads.map(worker_runtime);
+	let _workers = match &worker_runtime {
+		Some(runtime) => {
+			let context = context.clone();
+			runtime
+				.spawn(async move { start_workers(&context).await })
+				.await
+				.expect("Failed to start the background workers")
+		}
+		None => start_workers(&context).await,
+	};
 
-	let http_client = Client::new();
+	let result = serve(context.clone(), ("0.0.0.0", 9999)).await;
+	if let Some(report_url) = &context.config.report_url {
+		write_benchmark_report(&context, Path::new(report_url)).await;
+	}
+	// A runtime cannot be dropped from async code, so it is not waited for.
+	if let Some(runtime) = worker_runtime {
+		runtime.shutdown_background();
+	}
+	result
+}
+
+/// Runtime the background workers run on when they are kept apart from the
+/// actix server, so neither can starve the other of threads.
+fn worker_runtime(threads: usize) -> Runtime {
+	tokio::runtime::Builder::new_multi_thread()
+		.worker_threads(threads.max(1))
+		.thread_name("payment-worker")
+		.enable_all()
+		.build()
+		.expect("Failed to build the worker runtime")
+}
+
+/// Summarizes the run into `dir`, next to the flamegraph of `perf` builds.
+async fn write_benchmark_report(context: &AppContext, dir: &Path) {
+	let query = GetPaymentSummaryQuery {
+		from:           None,
+		to:             None,
+		failures:       false,
+		amount_buckets: false,
+		latency:        false,
+		epoch:          None,
+	};
+	let summary = match GetPaymentSummaryUseCase::new(context.payment_repo.clone())
+		.execute(query)
+		.await
+	{
+		Ok(summary) => summary,
+		Err(e) => {
+			error!("Failed to summarize payments for the benchmark report: {e}");
+			return;
+		}
+	};
+
+	let fees = PROCESSOR_GROUPS
+		.map(|name| context.router.processor_settings(name).and_then(|s| s.fee));
+	let report = BenchmarkReport::new(&summary, fees, metrics());
+	match report.write_to(dir) {
+		Ok(()) => info!("Benchmark report written to {}", dir.display()),
+		Err(e) => error!("Failed to write the benchmark report: {e}"),
+	}
+}
+
+/// Name of this instance, stable across restarts of the same container.
+fn instance_name() -> String {
+	std::env::var("HOSTNAME")
+		.unwrap_or_else(|_| format!("rinha-{}", std::process::id()))
+}
+
+/// Spawns the background workers enabled by the configuration.
+pub async fn start_workers(context: &AppContext) -> WorkerHandles {
+	let config = &context.config;
+	let mut handles = Vec::new();
+
+	let dns_resolver = config
+		.processor_dns_ttl_ms
+		.map(|ttl_ms| Arc::new(CachingResolver::new(Duration::from_millis(ttl_ms))));
+
+	let discovery_sources = processor_discovery_sources(config);
+	if !discovery_sources.is_empty() {
+		info!("Starting processor discovery worker...");
+		handles.push(tokio::spawn(processor_discovery_worker(
+			context.router.clone(),
+			ProcessorDiscovery::new(context.http_client.clone()),
+			discovery_sources,
+			Duration::from_millis(config.processor_discovery_interval_ms),
+		)));
+	}
 
 	info!("Starting health check worker...");
+	handles.push(tokio::spawn(processor_health_monitor_worker(
+		context.router.clone(),
+		processor_http_client(config, dns_resolver.as_ref()),
+		ProcessorDowntimeMonitor::from_config(config),
+		HealthCheckSchedule::from_config(config, "default"),
+		HealthCheckSchedule::from_config(config, "fallback"),
+	)));
+
+	let startup_wait = Duration::from_millis(config.health_check_interval_ms);
+	if tokio::time::timeout(startup_wait, context.router.wait_for_health_checks())
+		.await
+		.is_err()
+	{
+		warn!(
+			"Processors were not health checked within {startup_wait:?}, starting \
+			 workers anyway"
+		);
+	}
+
+	let mut process_payment_use_case = ProcessPaymentUseCase::new(
+		context.payment_repo.clone(),
+		context.http_client.clone(),
+	)
+	.with_health_reporter(Arc::new(context.router.clone()))
+	.with_metrics(metrics())
+	.with_requested_at_format(config.processor_requested_at_format);
+	if let Some(clock) = &context.redis_clock {
+		process_payment_use_case =
+			process_payment_use_case.with_clock(Arc::new(clock.clone()));
+	}
+	if let Some(dns_resolver) = &dns_resolver {
+		process_payment_use_case =
+			process_payment_use_case.with_dns_resolver(dns_resolver.clone());
+	}
+	if let Some(timeout_ms) = config.processor_request_timeout_ms {
+		process_payment_use_case = process_payment_use_case
+			.with_request_timeout(Duration::from_millis(timeout_ms));
+	}
+	let processor_clients: HashMap<&str, Client> = PROCESSOR_GROUPS
+		.iter()
+		.map(|processor| {
+			(
+				*processor,
+				processor_http_client(config, dns_resolver.as_ref()),
+			)
+		})
+		.collect();
+	for processor in PROCESSOR_GROUPS {
+		process_payment_use_case = process_payment_use_case
+			.with_processor_client(processor, processor_clients[processor].clone());
+
+		let timeout_ms = match processor {
+			"default" => config.default_processor_request_timeout_ms,
+			_ => config.fallback_processor_request_timeout_ms,
+		};
+		if let Some(timeout_ms) = timeout_ms {
+			process_payment_use_case = process_payment_use_case
+				.with_processor_request_timeout(
+					processor,
+					Duration::from_millis(timeout_ms),
+				);
+		}
+	}
+
+	if let Some(connections) = config.processor_warmup_connections {
+		let warmer = Arc::new(PROCESSOR_GROUPS.iter().fold(
+			ConnectionWarmer::new(connections),
+			|warmer, processor| {
+				let url = match *processor {
+					"default" => &config.default_payment_processor_url,
+					_ => &config.fallback_payment_processor_url,
+				};
+				warmer.with_target(
+					processor,
+					url,
+					processor_clients[processor].clone(),
+				)
+			},
+		));
+
+		info!("Warming up {connections} connections to each processor...");
+		warmer.warm_up_all().await;
+
+		handles.push(tokio::spawn(connection_warmup_worker(
+			context.router.clone(),
+			warmer,
+		)));
+	}
+
+	// Without a budget, as many payments are processed at a time as there are
+	// workers popping them.
+	let worker_concurrency = WorkerBudget::from_config(config)
+		.worker_concurrency
+		.unwrap_or(match config.queue_mode {
+			QueueMode::Shared => 1,
+			QueueMode::PerProcessor => {
+				config.default_processor_workers + config.fallback_processor_workers
+			}
+		});
+	info!("Processing up to {worker_concurrency} payments at a time");
+	worker_control().set_concurrency(worker_concurrency);
+
+	let retry_budget = match config.retry_budget_per_second {
+		Some(retries_per_second) => RetryBudget::new(retries_per_second),
+		None => RetryBudget::unlimited(),
+	};
+
+	let pop_timeout = Duration::from_millis(config.queue_pop_timeout_ms);
+	let requeue_pacer = match config.requeue_storm_ratio {
+		Some(max_ratio) => RequeuePacer::new(
+			Duration::from_millis(config.requeue_storm_window_ms),
+			max_ratio,
+		),
+		None => RequeuePacer::disabled(),
+	};
+
+	let mut verified_queues = vec![context.payment_queue.clone()];
+	match config.queue_mode {
+		QueueMode::Shared => {
+			info!("Starting payment processing worker...");
+			handles.push(tokio::spawn(payment_processing_worker(
+				context.payment_queue.clone(),
+				context.payment_repo.clone(),
+				process_payment_use_case,
+				context.router.clone(),
+				retry_budget,
+				requeue_pacer,
+			)));
+		}
+		QueueMode::PerProcessor => {
+			let processor_queues = context.processor_queues.clone();
+			for (processor, processor_queue) in &processor_queues {
+				let workers = match processor.as_str() {
+					"default" => config.default_processor_workers,
+					_ => config.fallback_processor_workers,
+				};
+
+				info!("Starting {workers} {processor} processor queue workers...");
+				for _ in 0..workers {
+					handles.push(tokio::spawn(processor_queue_worker(
+						processor.clone(),
+						processor_queue.clone(),
+						context.payment_queue.clone(),
+						context.payment_repo.clone(),
+						process_payment_use_case.clone(),
+						context.router.clone(),
+						retry_budget.clone(),
+						requeue_pacer.clone(),
+					)));
+				}
+			}
+
+			verified_queues.extend(processor_queues.values().cloned());
+
+			info!("Starting payment dispatcher worker...");
+			handles.push(tokio::spawn(payment_dispatcher_worker(
+				context.payment_queue.clone(),
+				processor_queues,
+				context.router.clone(),
+			)));
+		}
+	}
 
-	let in_memory_router = InMemoryPaymentRouter::new();
-
-	tokio::spawn(processor_health_monitor_worker(
-		in_memory_router.clone(),
-		http_client.clone(),
-		config.default_payment_processor_url.clone(),
-		config.fallback_payment_processor_url.clone(),
-	));
-
-	info!("Starting payment processing worker...");
-	let payment_queue = PaymentQueue::new(redis_client.clone());
-	let payment_repo = RedisPaymentRepository::new(redis_client.clone());
-
-	let process_payment_use_case =
-		ProcessPaymentUseCase::new(payment_repo.clone(), http_client.clone());
-
-	tokio::spawn(payment_processing_worker(
-		payment_queue.clone(),
-		payment_repo.clone(),
-		process_payment_use_case,
-		in_memory_router.clone(),
-	));
-
-	info!("Starting Actix-Web server on 0.0.0.0:9999...");
-
-	let create_payment_use_case = CreatePaymentUseCase::new(payment_queue.clone());
-	let get_payment_summary_use_case =
-		GetPaymentSummaryUseCase::new(payment_repo.clone());
-	let purge_payments_use_case = PurgePaymentsUseCase::new(payment_repo.clone());
-
-	HttpServer::new(move || {
-		App::new()
-			.app_data(web::Data::new(create_payment_use_case.clone()))
-			.app_data(web::Data::new(get_payment_summary_use_case.clone()))
-			.app_data(web::Data::new(purge_payments_use_case.clone()))
-			.service(payments)
-			.service(payments_summary)
-			.service(payments_purge)
+	if config.ingest_stream {
+		// Stable per container, so a restarted instance picks up the entries
+		// it read but never acknowledged.
+		let consumer = instance_name();
+
+		info!("Starting payment ingest worker as consumer '{consumer}'...");
+		handles.push(tokio::spawn(payment_ingest_worker(
+			PaymentIngestStream::new(context.redis_client.clone(), consumer)
+				.with_batch_size(config.ingest_batch_size)
+				.with_block_timeout(pop_timeout)
+				.with_retry_interval(Duration::from_millis(
+					config.ingest_retry_interval_ms,
+				)),
+			CreatePaymentUseCase::new(context.payment_queue.clone()),
+		)));
+	}
+
+	if let (Some(connection), Some(interval_ms)) = (
+		&context.redis_connection,
+		config.redis_keepalive_interval_ms,
+	) {
+		info!("Starting Redis connection supervisor worker...");
+		handles.push(tokio::spawn(redis_connection_supervisor_worker(
+			connection.clone(),
+			Duration::from_millis(interval_ms),
+		)));
+	}
+
+	if let (Some(clock), Some(interval_ms)) =
+		(&context.redis_clock, config.redis_clock_sync_interval_ms)
+	{
+		info!("Starting Redis clock sync worker...");
+		handles.push(tokio::spawn(redis_clock_sync_worker(
+			clock.clone(),
+			Duration::from_millis(interval_ms),
+		)));
+	}
+
+	info!("Starting run epoch sync worker...");
+	handles.push(tokio::spawn(run_epoch_sync_worker(
+		context.run_epoch.clone(),
+		Duration::from_millis(config.run_epoch_sync_interval_ms),
+	)));
+
+	info!("Starting queue depth reconciler worker...");
+	handles.push(tokio::spawn(queue_depth_reconciler_worker(
+		context.payment_queue.clone(),
+		Duration::from_secs(config.queue_depth_reconcile_interval),
+	)));
+
+	if config.queue_sequencing {
+		info!("Starting message sequence audit worker...");
+		handles.push(tokio::spawn(sequence_audit_worker(
+			context.payment_queue.clone(),
+			Duration::from_secs(config.queue_depth_reconcile_interval),
+		)));
+	}
+
+	if let Some(interval_ms) = config.verification_interval_ms {
+		info!("Starting payment verification worker...");
+		handles.push(tokio::spawn(payment_verification_worker(
+			context.payment_repo.clone(),
+			verified_queues,
+			Duration::from_millis(interval_ms),
+		)));
+	}
+
+	if config.queue_cutover_from.is_some() {
+		info!("Starting queue cutover worker...");
+		handles.push(tokio::spawn(queue_cutover_worker(
+			context.payment_queue.clone(),
+			Duration::from_secs(config.queue_depth_reconcile_interval),
+		)));
+	}
+
+	let maintenance_tasks = MaintenanceTasks {
+		trim_processed:   config.processed_payments_retention().map(|retention| {
+			(
+				Duration::from_secs(retention),
+				Duration::from_secs(config.payment_retention_interval),
+			)
+		}),
+		report_key_space: config.key_space_report_interval.map(Duration::from_secs),
+	};
+	if !maintenance_tasks.is_empty() {
+		info!("Starting maintenance worker: {maintenance_tasks:?}");
+		handles.push(tokio::spawn(maintenance_worker(
+			context.payment_repo.clone(),
+			RedisKeySpace::new(context.redis_client.clone()),
+			maintenance_tasks,
+		)));
+	}
+
+	if let Some(archive) = &context.payment_archive {
+		info!("Starting payment archiver worker...");
+		handles.push(tokio::spawn(payment_archiver_worker(
+			context.payment_repo.clone(),
+			archive.clone(),
+			context.archive_hot_window(),
+			Duration::from_secs(config.archive_interval),
+		)));
+	}
+
+	if let Some(statsd_addr) = &config.metrics_statsd_addr {
+		info!("Starting StatsD metrics exporter to {statsd_addr}...");
+		match StatsdExporter::connect(
+			statsd_addr,
+			config.metrics_statsd_prefix.clone(),
+			config.metrics_dogstatsd,
+		)
+		.await
+		{
+			Ok(exporter) => {
+				handles.push(tokio::spawn(metrics_exporter_worker(
+					exporter,
+					Duration::from_secs(config.metrics_statsd_interval),
+				)));
+			}
+			Err(e) => error!("Failed to start StatsD metrics exporter: {e}"),
+		}
+	}
+
+	WorkerHandles(handles)
+}
+
+/// Builds the HTTP application with every use case and route registered.
+pub fn build_app(
+	context: &AppContext,
+) -> App<
+	impl ServiceFactory<
+		ServiceRequest,
+		Config = (),
+		Response = ServiceResponse<impl MessageBody + use<>>,
+		Error = actix_web::Error,
+		InitError = (),
+	> + use<>,
+> {
+	let mut state = AppState::new(
+		Arc::new(context.payment_queue.clone()),
+		Arc::new(context.payment_repo.clone()),
+	)
+	.with_processor_queues(
+		context
+			.processor_queues
+			.iter()
+			.map(|(processor, processor_queue)| {
+				(
+					processor.clone(),
+					Arc::new(processor_queue.clone()) as SharedPaymentQueue,
+				)
+			})
+			.collect(),
+	);
+	if let Some(archive) = &context.payment_archive {
+		state = state.with_archive(
+			archive.clone(),
+			time::Duration::try_from(context.archive_hot_window())
+				.expect("Archive hot window out of range"),
+		);
+	}
+
+	App::new()
+		.wrap(middleware::from_fn(require_admin_token))
+		.wrap(middleware::from_fn(answer_method_probes))
+		.wrap(middleware::from_fn(enforce_endpoint_timeouts))
+		.wrap(middleware::from_fn(propagate_request_id))
+		.app_data(web::Data::new(EndpointTimeouts {
+			payments: Duration::from_millis(
+				context.config.server_payments_timeout_ms,
+			),
+			summary:  Duration::from_millis(
+				context.config.server_summary_timeout_ms,
+			),
+		}))
+		.app_data(web::Data::new(AdminToken::new(
+			context.config.admin_token.clone(),
+		)))
+		.app_data(web::JsonConfig::default().error_handler(json_error))
+		.app_data(web::Data::new(state))
+		.app_data(web::Data::new(context.router.clone()))
+		.app_data(web::Data::new(context.run_epoch.clone()))
+		.app_data(web::Data::new(AdminCommandDispatcher::new(
+			context.router.clone(),
+		)))
+		.app_data(web::Data::new(DebugVarsState::new(
+			context.started_at,
+			&context.config,
+		)))
+		.service(payments)
+		.service(payments_summary)
+		.service(payments_duplicates)
+		.service(payments_purge)
+		.service(export_snapshot)
+		.service(import_snapshot)
+		.service(admin_ws)
+		.service(pause_workers)
+		.service(resume_workers)
+		.service(set_workers_concurrency)
+		.service(effective_config)
+		.service(list_feature_flags)
+		.service(set_feature_flag)
+		.service(list_processors)
+		.service(reset_router)
+		.service(configure_processor)
+		.service(processor_health_history)
+		.service(purge_processor_health_history)
+		.service(current_run_epoch)
+		.service(start_run_epoch)
+		.service(debug_vars)
+		.service(prometheus_metrics)
+		.default_service(web::to(|| async {
+			ApiError::NotFoundError.error_response()
+		}))
+}
+
+/// Serves the HTTP application on `addr` until the server is stopped.
+pub async fn serve(
+	context: AppContext,
+	addr: impl ToSocketAddrs,
+) -> std::io::Result<()> {
+	let config = context.config.clone();
+
+	info!("Starting Actix-Web server...");
+
+	let mut server = HttpServer::new(move || build_app(&context))
+		.keep_alive(Duration::from_secs(config.server_keepalive));
+	if let Some(timeout_ms) = config.server_client_request_timeout_ms {
+		server = server.client_request_timeout(Duration::from_millis(timeout_ms));
+	}
+	if let Some(max_connections) = config.server_max_connections {
+		server = server.max_connections(max_connections);
+	}
+	if let Some(backlog) = config.server_backlog {
+		server = server.backlog(backlog);
+	}
+	let server_workers = WorkerBudget::from_config(&config).server_workers;
+	if let Some(workers) = server_workers {
+		server = server.workers(workers);
+	}
+
+	// With the reuseport feature, one SO_REUSEPORT listener per worker, so
+	// accepts are spread by the kernel rather than contended on one queue.
+	let listeners = if cfg!(feature = "reuseport") {
+		Some(server_workers.unwrap_or_else(|| {
+			std::thread::available_parallelism().map_or(1, |cpus| cpus.get())
+		}))
+	} else {
+		config.server_reuse_port.then_some(1)
+	};
+
+	let Some(listeners) = listeners else {
+		return server.bind(addr)?.run().await;
+	};
+	let backlog = config
+		.server_backlog
+		.map_or(listener::DEFAULT_BACKLOG, |backlog| backlog as i32);
+	for addr in addr.to_socket_addrs()? {
+		for tcp_listener in listener::reuse_port_listeners(addr, listeners, backlog)?
+		{
+			server = server.listen(tcp_listener)?;
+		}
+	}
+	server.run().await
+}
+
+/// Builds a client with its own connection pool, so each processor gets an
+/// isolated pool. With `processor_http2` set, payments are multiplexed over
+/// HTTP/2 connections instead of taking one connection each; the processors
+/// are reached over plain HTTP, so HTTP/2 is spoken without negotiation.
+/// Processors behind mutual TLS get the configured client certificate and CA
+/// bundle.
+fn processor_http_client(
+	config: &Config,
+	dns_resolver: Option<&Arc<CachingResolver>>,
+) -> Client {
+	let mut builder = Client::builder();
+	if let Some(dns_resolver) = dns_resolver {
+		builder = builder.dns_resolver(dns_resolver.clone());
+	}
+	if let Some(max_idle) = config.processor_pool_max_idle_per_host {
+		builder = builder.pool_max_idle_per_host(max_idle);
+	}
+	if config.processor_http2 {
+		builder = builder.http2_prior_knowledge().http2_adaptive_window(true);
+	}
+
+	if let Some(ca_bundle_path) = &config.processor_tls_ca_bundle_path {
+		let ca_bundle = std::fs::read(ca_bundle_path)
+			.expect("Failed to read processor CA bundle");
+		for certificate in Certificate::from_pem_bundle(&ca_bundle)
+			.expect("Invalid processor CA bundle")
+		{
+			builder = builder.add_root_certificate(certificate);
+		}
+	}
+
+	match (
+		&config.processor_tls_client_cert_path,
+		&config.processor_tls_client_key_path,
+	) {
+		(Some(cert_path), Some(key_path)) => {
+			let mut pem = std::fs::read(cert_path)
+				.expect("Failed to read processor client certificate");
+			pem.extend(
+				std::fs::read(key_path)
+					.expect("Failed to read processor client key"),
+			);
+			builder = builder.identity(
+				Identity::from_pem(&pem).expect("Invalid processor client identity"),
+			);
+		}
+		(None, None) => {}
+		_ => warn!(
+			"Both APP_PROCESSOR_TLS_CLIENT_CERT_PATH and \
+			 APP_PROCESSOR_TLS_CLIENT_KEY_PATH are needed for client certificates; \
+			 connecting to the processors without one"
+		),
+	}
+
+	builder
+		.build()
+		.expect("Failed to build processor HTTP client")
+}
+
+/// Discovery sources of the processors that have one. Invalid sources are
+/// logged and skipped, leaving the processor at its configured URL.
+fn processor_discovery_sources(
+	config: &Config,
+) -> Vec<(&'static str, DiscoverySource)> {
+	[
+		(PROCESSOR_GROUPS[0], &config.default_processor_discovery),
+		(PROCESSOR_GROUPS[1], &config.fallback_processor_discovery),
+	]
+	.into_iter()
+	.filter_map(|(processor, source)| {
+		match source.as_deref()?.parse::<DiscoverySource>() {
+			Ok(source) => Some((processor, source)),
+			Err(e) => {
+				error!("Not discovering the {processor} processor: {e}");
+				None
+			}
+		}
 	})
-	.keep_alive(Duration::from_secs(config.server_keepalive))
-	.bind(("0.0.0.0", 9999))?
-	.run()
-	.await
+	.collect()
+}
+
+#[cfg(feature = "postgres")]
+async fn connect_payment_archive(
+	config: &Config,
+) -> Option<Arc<dyn PaymentArchive>> {
+	let database_url = config.archive_database_url.as_deref()?;
+
+	match PostgresPaymentArchive::connect(database_url).await {
+		Ok(archive) => Some(Arc::new(archive)),
+		Err(e) => {
+			error!("Failed to connect to the payment archive: {e}");
+			None
+		}
+	}
+}
+
+#[cfg(not(feature = "postgres"))]
+async fn connect_payment_archive(
+	config: &Config,
+) -> Option<Arc<dyn PaymentArchive>> {
+	if config.archive_database_url.is_some() {
+		warn!(
+			"APP_ARCHIVE_DATABASE_URL is set but the postgres feature is disabled; \
+			 payments will not be archived"
+		);
+	}
+
+	None
+}
+
+pub async fn migrate(config: Arc<Config>) -> std::io::Result<()> {
+	env_logger::init();
+
+	let redis_client =
+		redis::Client::open(config.redis_url.clone()).expect("Invalid Redis URL");
+
+	info!("Migrating legacy Redis key layout...");
+
+	let mut payment_repo = RedisPaymentRepository::new(redis_client.clone());
+	let mut legacy_store = RedisLegacyPaymentStore::new(redis_client);
+	if config.legacy_summary_compat {
+		payment_repo = payment_repo.with_legacy_summary();
+		legacy_store = legacy_store.with_legacy_summary();
+	}
+	let migrate_use_case =
+		MigrateLegacyPaymentsUseCase::new(legacy_store, payment_repo.clone());
+
+	migrate_use_case
+		.execute()
+		.await
+		.map_err(|e| std::io::Error::other(e.to_string()))?;
+
+	info!("Normalizing processed payment timestamps...");
+
+	let normalized = payment_repo
+		.normalize_timestamp_scores()
+		.await
+		.map_err(|e| std::io::Error::other(e.to_string()))?;
+	info!("Normalized {normalized} second-precision timestamps");
+
+	Ok(())
 }
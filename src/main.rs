@@ -1,34 +1,54 @@
 use std::sync::Arc;
 
-#[cfg(feature = "perf")]
-use pprof::flamegraph::Options;
 use rinha_de_backend::infrastructure::config::settings::Config;
-use rinha_de_backend::run;
+#[cfg(feature = "perf")]
+use rinha_de_backend::infrastructure::observability::flamegraph::write_flamegraph;
+use rinha_de_backend::infrastructure::observability::{
+	error_reporting, log_redaction,
+};
+#[cfg(feature = "perf")]
+use rinha_de_backend::infrastructure::workers::flamegraph_snapshot_worker::flamegraph_snapshot_worker;
+use rinha_de_backend::use_cases::summary_rounding;
+use rinha_de_backend::{migrate, run};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
 	#[cfg(feature = "perf")]
-	let guard = pprof::ProfilerGuardBuilder::default()
-		.frequency(1000)
-		.blocklist(&["libc", "libgcc", "pthread", "vdso"])
-		.build()
-		.unwrap();
+	let guard = Arc::new(
+		pprof::ProfilerGuardBuilder::default()
+			.frequency(1000)
+			.blocklist(&["libc", "libgcc", "pthread", "vdso"])
+			.build()
+			.unwrap(),
+	);
 
 	let config = Arc::new(Config::load().expect("Failed to load configuration"));
+	let _error_reporting_guard = error_reporting::init(&config);
+	log_redaction::init(&config);
+	summary_rounding::init(&config);
+
+	if std::env::args().nth(1).as_deref() == Some("migrate") {
+		return migrate(config).await;
+	}
+
+	#[cfg(feature = "perf")]
+	if let (Some(report_url), Some(interval)) =
+		(&config.report_url, config.flamegraph_snapshot_interval)
+	{
+		tokio::spawn(flamegraph_snapshot_worker(
+			guard.clone(),
+			report_url.into(),
+			std::time::Duration::from_secs(interval),
+			config.flamegraph_snapshots,
+		));
+	}
+
 	let result = run(config.clone()).await;
 
 	#[cfg(feature = "perf")]
-	if let Ok(report) = guard.report().build() {
-		if let Some(report_url) = &config.report_url {
-			let path = format!("{report_url}/flamegraph.svg");
-			let mut file = std::fs::File::create(&path)?;
-			let mut options = Options::default();
-			options.title = "rinha-de-backend".to_string();
-			options.count_name = "samples".to_string();
-			report
-				.flamegraph_with_options(&mut file, &mut options)
-				.unwrap();
-		}
+	if let Some(report_url) = &config.report_url {
+		let path = std::path::Path::new(report_url).join("flamegraph.svg");
+		write_flamegraph(&guard, &path)?;
 	}
 
 	result
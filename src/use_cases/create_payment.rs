@@ -1,6 +1,10 @@
+use std::time::Instant;
+
 use crate::domain::payment::Payment;
 use crate::domain::queue::{Message, Queue};
-use crate::use_cases::dto::CreatePaymentCommand;
+use crate::infrastructure::observability::metrics::metrics;
+use crate::use_cases::dto::{CreatePaymentCommand, CreatePaymentResult};
+use crate::use_cases::run_epoch;
 
 #[derive(Clone)]
 pub struct CreatePaymentUseCase<Q: Queue<Payment>> {
@@ -15,17 +19,28 @@ impl<Q: Queue<Payment>> CreatePaymentUseCase<Q> {
 	pub async fn execute(
 		&self,
 		command: CreatePaymentCommand,
-	) -> Result<(), Box<dyn std::error::Error + Send>> {
+	) -> Result<CreatePaymentResult, Box<dyn std::error::Error + Send>> {
 		let payment = Payment {
 			correlation_id: command.correlation_id,
 			amount:         command.amount,
 			requested_at:   None,
 			processed_at:   None,
 			processed_by:   None,
+			tag:            command.tag,
+			epoch:          run_epoch::stamp(),
 		};
 
+		let started_at = Instant::now();
 		self.payment_queue
-			.push(Message::with(command.correlation_id, payment))
-			.await
+			.push(
+				Message::with(command.correlation_id, payment)
+					.with_request_id(command.request_id),
+			)
+			.await?;
+		let queue_latency = started_at.elapsed();
+
+		metrics().record_received();
+		metrics().record_queue_push(queue_latency);
+		Ok(CreatePaymentResult { queue_latency })
 	}
 }
@@ -1,27 +1,280 @@
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
-use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use time::{OffsetDateTime, UtcOffset};
 use uuid::Uuid;
 
+use crate::domain::payment::Payment;
+use crate::domain::queue::Message;
+use crate::domain::repository::{RecordedFailure, SummaryBucket};
+use crate::infrastructure::config::settings::RequestedAtFormat;
+use crate::use_cases::summary_rounding;
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct CreatePaymentCommand {
 	pub correlation_id: Uuid,
 	pub amount:         f64,
+	pub request_id:     Option<String>,
+	pub tag:            Option<String>,
+}
+
+/// Outcome of queueing a payment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CreatePaymentResult {
+	/// Time spent pushing the payment to the queue.
+	pub queue_latency: Duration,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct GetPaymentSummaryQuery {
-	pub from: Option<OffsetDateTime>,
-	pub to:   Option<OffsetDateTime>,
+	pub from:           Option<OffsetDateTime>,
+	pub to:             Option<OffsetDateTime>,
+	/// Adds the calls that did not process payments to each processor.
+	pub failures:       bool,
+	/// Adds the payment counts per amount bucket to each processor.
+	pub amount_buckets: bool,
+	/// Adds the processing latency of each processor.
+	pub latency:        bool,
+	/// Sums only the payments accepted in this run epoch, `0` being the
+	/// payments accepted before any epoch was started.
+	pub epoch:          Option<u64>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct PaymentSummaryResult {
 	pub total_requests: usize,
+	/// Full precision total, rounded when serialized, see
+	/// [`summary_rounding`].
+	#[serde(serialize_with = "summary_rounding::serialize")]
 	pub total_amount:   f64,
+	/// Only reported when asked for, see [`PaymentFailures`].
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub failures:       Option<PaymentFailures>,
+	/// Only reported when asked for, see [`AmountBucketCount`].
+	#[serde(
+		rename = "amountBuckets",
+		default,
+		skip_serializing_if = "Option::is_none"
+	)]
+	pub amount_buckets: Option<Vec<AmountBucketCount>>,
+	/// Only reported when asked for, see [`ProcessingLatency`].
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub latency:        Option<ProcessingLatency>,
+}
+
+/// Time from a payment being requested to being processed by a processor,
+/// the end-to-end lag the rinha scoring cares about. Payments already folded
+/// into summary buckets are not included.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ProcessingLatency {
+	/// Payments the latency was measured on.
+	pub count:  usize,
+	#[serde(rename = "avgMs")]
+	pub avg_ms: f64,
+	#[serde(rename = "p95Ms")]
+	pub p95_ms: f64,
+}
+
+/// Payments of a processor whose amount falls in `range`, such as `<10`,
+/// `10-100` or `>=100`. Payments archived out of the repository are not
+/// counted.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct AmountBucketCount {
+	pub range: String,
+	pub count: usize,
+}
+
+/// Calls to a processor that did not process a payment, to assess the
+/// routing decisions after the fact. A payment is counted once per failed
+/// call, and failures are only kept for the retention window.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct PaymentFailures {
+	/// Calls that errored, timed out or got a server error.
+	pub failed:   usize,
+	/// Calls the processor refused with a client error.
+	pub rejected: usize,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct PaymentsSummaryResponse {
 	pub default:  PaymentSummaryResult,
 	pub fallback: PaymentSummaryResult,
+	/// Only reported when asked for, see [`PendingPayments`].
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub pending:  Option<PendingPayments>,
+}
+
+/// Payments accepted but not counted in the summary totals yet, so the
+/// totals can be compared with the processors' records while payments are
+/// still flowing.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct PendingPayments {
+	pub queued:        usize,
+	pub in_processing: usize,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct GetDuplicatesReportQuery {
+	pub from: Option<OffsetDateTime>,
+	pub to:   Option<OffsetDateTime>,
+}
+
+/// Payments submitted again while already queued or processed, as client
+/// retries do. Duplicates are only kept for the retention window.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct DuplicatesReport {
+	/// Dropped when queued, only counted when queue dedup is enabled.
+	pub ingest: usize,
+	/// Skipped by the workers, as they were already processed.
+	pub worker: usize,
+	pub total:  usize,
+}
+
+/// Body of a payment processor call, in the exact shape of the processors'
+/// contract. Bookkeeping fields of [`Payment`], such as the tag or the
+/// processor, never leave this service.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct PaymentProcessorRequest {
+	#[serde(rename = "correlationId")]
+	pub correlation_id: Uuid,
+	pub amount:         f64,
+	#[serde(rename = "requestedAt")]
+	pub requested_at:   String,
+}
+
+impl PaymentProcessorRequest {
+	/// Maps a payment to the processor body. Payments are stamped before being
+	/// sent, so a missing `requested_at` only falls back to the current time.
+	pub fn from_payment(payment: &Payment, format: RequestedAtFormat) -> Self {
+		let requested_at = payment
+			.requested_at
+			.unwrap_or_else(OffsetDateTime::now_utc)
+			.to_offset(UtcOffset::UTC);
+
+		let requested_at = match format {
+			RequestedAtFormat::Millis => requested_at.format(format_description!(
+				"[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond \
+				 digits:3]Z"
+			)),
+			RequestedAtFormat::Rfc3339 => requested_at.format(&Rfc3339),
+		}
+		.expect("UTC timestamps are always formattable");
+
+		Self {
+			correlation_id: payment.correlation_id,
+			amount: payment.amount,
+			requested_at,
+		}
+	}
+}
+
+/// One line of a payments snapshot archive.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SnapshotRecord {
+	Queued(Message<Payment>),
+	/// A payment waiting in the queue of a single processor.
+	Dispatched {
+		processor: String,
+		message:   Message<Payment>,
+	},
+	Processed(Payment),
+	SummaryBucket(SummaryBucket),
+	Failure(RecordedFailure),
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq)]
+pub struct SnapshotImportReport {
+	pub queued:    usize,
+	pub processed: usize,
+	pub skipped:   usize,
+	pub buckets:   usize,
+	pub failures:  usize,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+	use time::macros::datetime;
+
+	use super::*;
+
+	fn payment() -> Payment {
+		Payment {
+			correlation_id: Uuid::parse_str("4a7901b8-7d26-4d9d-aa19-4dc1c7cf60b3")
+				.unwrap(),
+			amount:         19.9,
+			requested_at:   Some(datetime!(2025-07-15 15:34:56.789123 +03:00)),
+			processed_at:   None,
+			processed_by:   Some("default".to_string()),
+			tag:            Some("staging".to_string()),
+			epoch:          Some(2),
+		}
+	}
+
+	#[test]
+	fn test_processor_request_matches_processor_contract() {
+		let request = PaymentProcessorRequest::from_payment(
+			&payment(),
+			RequestedAtFormat::Millis,
+		);
+
+		assert_eq!(
+			serde_json::to_value(&request).unwrap(),
+			json!({
+				"correlationId": "4a7901b8-7d26-4d9d-aa19-4dc1c7cf60b3",
+				"amount": 19.9,
+				"requestedAt": "2025-07-15T12:34:56.789Z",
+			})
+		);
+	}
+
+	#[test]
+	fn test_processor_request_can_keep_full_precision() {
+		let request = PaymentProcessorRequest::from_payment(
+			&payment(),
+			RequestedAtFormat::Rfc3339,
+		);
+
+		assert_eq!(request.requested_at, "2025-07-15T12:34:56.789123Z");
+	}
+
+	#[test]
+	fn test_snapshot_records_keep_summary_buckets_and_dispatched_payments() {
+		let bucket = SnapshotRecord::SummaryBucket(SummaryBucket {
+			group:          "default".to_string(),
+			starts_at:      datetime!(2025-07-15 12:34:00 UTC),
+			requests:       2,
+			amount:         39.8,
+			amount_buckets: vec![0, 2, 0],
+		});
+
+		assert_eq!(
+			serde_json::to_value(&bucket).unwrap(),
+			json!({
+				"type": "summary_bucket",
+				"group": "default",
+				"starts_at": "2025-07-15T12:34:00Z",
+				"requests": 2,
+				"amount": 39.8,
+				"amount_buckets": [0, 2, 0],
+			})
+		);
+
+		let dispatched = SnapshotRecord::Dispatched {
+			processor: "fallback".to_string(),
+			message:   Message::with(Uuid::nil(), payment()),
+		};
+		let line = serde_json::to_vec(&dispatched).unwrap();
+
+		match serde_json::from_slice(&line).unwrap() {
+			SnapshotRecord::Dispatched { processor, message } => {
+				assert_eq!(processor, "fallback");
+				assert_eq!(message.body.correlation_id, payment().correlation_id);
+			}
+			record => panic!("Unexpected snapshot record {record:?}"),
+		}
+	}
 }
@@ -0,0 +1,40 @@
+use time::{Date, OffsetDateTime, Time};
+
+use crate::domain::repository::PaymentRepository;
+use crate::use_cases::dto::{DuplicatesReport, GetDuplicatesReportQuery};
+use crate::use_cases::time_bound;
+
+/// Counts the payments caught as duplicates, so client retry storms show up
+/// instead of being silently absorbed.
+#[derive(Clone)]
+pub struct GetDuplicatesReportUseCase<R: PaymentRepository> {
+	payment_repo: R,
+}
+
+impl<R: PaymentRepository> GetDuplicatesReportUseCase<R> {
+	pub fn new(payment_repo: R) -> Self {
+		Self { payment_repo }
+	}
+
+	pub async fn execute(
+		&self,
+		query: GetDuplicatesReportQuery,
+	) -> Result<DuplicatesReport, Box<dyn std::error::Error + Send>> {
+		let from = query
+			.from
+			.map(time_bound::to_utc)
+			.unwrap_or(OffsetDateTime::UNIX_EPOCH);
+		let to = query
+			.to
+			.map(time_bound::to_utc)
+			.unwrap_or(Date::MAX.with_time(Time::MAX).assume_utc());
+
+		let (ingest, worker) = self.payment_repo.get_duplicates(from, to).await?;
+
+		Ok(DuplicatesReport {
+			ingest,
+			worker,
+			total: ingest + worker,
+		})
+	}
+}
@@ -1,20 +1,157 @@
-use std::ops::{Add, Sub};
+use std::future::ready;
+use std::ops::Sub;
+use std::sync::Arc;
 
-use time::OffsetDateTime;
+use futures::TryStreamExt;
+use time::{Date, OffsetDateTime, Time};
 
-use crate::domain::repository::PaymentRepository;
+use crate::domain::payment_archive::PaymentArchive;
+use crate::domain::repository::{AMOUNT_BUCKET_BOUNDS, PaymentRepository};
 use crate::use_cases::dto::{
-	GetPaymentSummaryQuery, PaymentSummaryResult, PaymentsSummaryResponse,
+	AmountBucketCount, GetPaymentSummaryQuery, PaymentFailures,
+	PaymentSummaryResult, PaymentsSummaryResponse, ProcessingLatency,
 };
+use crate::use_cases::time_bound;
 
 #[derive(Clone)]
 pub struct GetPaymentSummaryUseCase<R: PaymentRepository> {
 	payment_repo: R,
+	archive:      Option<(Arc<dyn PaymentArchive>, time::Duration)>,
 }
 
 impl<R: PaymentRepository> GetPaymentSummaryUseCase<R> {
 	pub fn new(payment_repo: R) -> Self {
-		Self { payment_repo }
+		Self {
+			payment_repo,
+			archive: None,
+		}
+	}
+
+	/// Merges totals from `archive` whenever the requested range starts
+	/// before the `hot_window` kept in the repository.
+	pub fn with_archive(
+		mut self,
+		archive: Arc<dyn PaymentArchive>,
+		hot_window: time::Duration,
+	) -> Self {
+		self.archive = Some((archive, hot_window));
+		self
+	}
+
+	async fn get_summary_by_group(
+		&self,
+		group: &str,
+		from: OffsetDateTime,
+		to: OffsetDateTime,
+	) -> Result<(usize, f64), Box<dyn std::error::Error + Send>> {
+		let Some((archive, hot_window)) = &self.archive else {
+			return self
+				.payment_repo
+				.get_summary_by_group(group, from, to)
+				.await;
+		};
+
+		let hot_cutoff = OffsetDateTime::now_utc().sub(*hot_window);
+		if from >= hot_cutoff {
+			return self
+				.payment_repo
+				.get_summary_by_group(group, from, to)
+				.await;
+		}
+
+		// Payments being archived are in both tiers until they are removed
+		// from the repository, so the ones it still counts are left out of
+		// the archive totals.
+		let (total_requests, total_amount, stored_ids) = self
+			.payment_repo
+			.get_summary_with_ids_before(group, from, to, hot_cutoff)
+			.await?;
+		let (archived_requests, archived_amount) = archive
+			.get_summary_by_group(group, from, to, &stored_ids)
+			.await?;
+
+		Ok((
+			total_requests + archived_requests,
+			total_amount + archived_amount,
+		))
+	}
+
+	/// Totals of the payments of one run epoch, read payment by payment as
+	/// the totals kept by the repository span every epoch. Payments folded
+	/// into retention buckets or moved to the archive are left out.
+	async fn get_epoch_summary_by_group(
+		&self,
+		group: &str,
+		from: OffsetDateTime,
+		to: OffsetDateTime,
+		epoch: u64,
+	) -> Result<(usize, f64), Box<dyn std::error::Error + Send>> {
+		self.payment_repo
+			.get_payments_stream(group, from, to)
+			.try_filter(|payment| ready(payment.epoch.unwrap_or_default() == epoch))
+			.try_fold((0, 0.0), |(total_requests, total_amount), payment| {
+				ready(Ok((total_requests + 1, total_amount + payment.amount)))
+			})
+			.await
+	}
+
+	async fn get_group_result(
+		&self,
+		group: &str,
+		from: OffsetDateTime,
+		to: OffsetDateTime,
+		query: &GetPaymentSummaryQuery,
+	) -> Result<PaymentSummaryResult, Box<dyn std::error::Error + Send>> {
+		let (total_requests, total_amount) = match query.epoch {
+			Some(epoch) => {
+				self.get_epoch_summary_by_group(group, from, to, epoch)
+					.await?
+			}
+			None => self.get_summary_by_group(group, from, to).await?,
+		};
+
+		let failures = if query.failures {
+			let (failed, rejected) = self
+				.payment_repo
+				.get_failures_by_group(group, from, to)
+				.await?;
+			Some(PaymentFailures { failed, rejected })
+		} else {
+			None
+		};
+
+		let amount_buckets = if query.amount_buckets {
+			let counts = self
+				.payment_repo
+				.get_amount_buckets_by_group(group, from, to)
+				.await?;
+			Some(
+				amount_bucket_ranges()
+					.zip(counts)
+					.map(|(range, count)| AmountBucketCount { range, count })
+					.collect(),
+			)
+		} else {
+			None
+		};
+
+		let latency = if query.latency {
+			let latencies = self
+				.payment_repo
+				.get_latencies_by_group(group, from, to)
+				.await?;
+			Some(processing_latency(latencies))
+		} else {
+			None
+		};
+
+		Ok(PaymentSummaryResult {
+			total_requests,
+			total_amount,
+			failures,
+			amount_buckets,
+			latency,
+		})
 	}
 
 	pub async fn execute(
@@ -23,30 +160,53 @@ impl<R: PaymentRepository> GetPaymentSummaryUseCase<R> {
 	) -> Result<PaymentsSummaryResponse, Box<dyn std::error::Error + Send>> {
 		let from = query
 			.from
-			.unwrap_or(OffsetDateTime::now_utc().sub(time::Duration::days(30)));
+			.map(time_bound::to_utc)
+			.unwrap_or(OffsetDateTime::UNIX_EPOCH);
 		let to = query
 			.to
-			.unwrap_or(OffsetDateTime::now_utc().add(time::Duration::days(30)));
-
-		let (default_total_requests, default_total_amount) = self
-			.payment_repo
-			.get_summary_by_group("default", from, to)
-			.await?;
-
-		let (fallback_total_requests, fallback_total_amount) = self
-			.payment_repo
-			.get_summary_by_group("fallback", from, to)
-			.await?;
+			.map(time_bound::to_utc)
+			.unwrap_or(Date::MAX.with_time(Time::MAX).assume_utc());
 
 		Ok(PaymentsSummaryResponse {
-			default:  PaymentSummaryResult {
-				total_requests: default_total_requests,
-				total_amount:   default_total_amount,
-			},
-			fallback: PaymentSummaryResult {
-				total_requests: fallback_total_requests,
-				total_amount:   fallback_total_amount,
-			},
+			default:  self.get_group_result("default", from, to, &query).await?,
+			fallback: self.get_group_result("fallback", from, to, &query).await?,
+			pending:  None,
 		})
 	}
 }
+
+/// Average and nearest-rank 95th percentile of `latencies`, given in
+/// microseconds.
+fn processing_latency(mut latencies: Vec<u64>) -> ProcessingLatency {
+	let count = latencies.len();
+	if count == 0 {
+		return ProcessingLatency {
+			count,
+			avg_ms: 0.0,
+			p95_ms: 0.0,
+		};
+	}
+
+	latencies.sort_unstable();
+	let total_us: u64 = latencies.iter().sum();
+	let p95_index = (count * 95).div_ceil(100) - 1;
+
+	ProcessingLatency {
+		count,
+		avg_ms: total_us as f64 / count as f64 / 1000.0,
+		p95_ms: latencies[p95_index] as f64 / 1000.0,
+	}
+}
+
+/// Labels of the amount buckets, in the order of [`AMOUNT_BUCKET_BOUNDS`].
+fn amount_bucket_ranges() -> impl Iterator<Item = String> {
+	let lower_bounds = std::iter::once(None).chain(AMOUNT_BUCKET_BOUNDS.map(Some));
+	let upper_bounds = AMOUNT_BUCKET_BOUNDS.map(Some).into_iter().chain([None]);
+
+	lower_bounds.zip(upper_bounds).map(|bounds| match bounds {
+		(None, Some(upper)) => format!("<{upper}"),
+		(Some(lower), Some(upper)) => format!("{lower}-{upper}"),
+		(Some(lower), None) => format!(">={lower}"),
+		(None, None) => "any".to_string(),
+	})
+}
@@ -0,0 +1,28 @@
+use crate::domain::payment::Payment;
+use crate::domain::queue::Queue;
+use crate::infrastructure::workers::in_flight_registry::in_flight_payments;
+use crate::use_cases::dto::PendingPayments;
+
+/// Counts the payments accepted but not yet part of the summary totals.
+#[derive(Clone)]
+pub struct GetPendingPaymentsUseCase<Q: Queue<Payment>> {
+	payment_queue: Q,
+}
+
+impl<Q: Queue<Payment>> GetPendingPaymentsUseCase<Q> {
+	pub fn new(payment_queue: Q) -> Self {
+		Self { payment_queue }
+	}
+
+	/// The queued count covers every instance, as they share the queue, while
+	/// the in-processing count only covers this instance's workers unless the
+	/// in-flight registry is shared, see [`in_flight_payments`].
+	pub async fn execute(
+		&self,
+	) -> Result<PendingPayments, Box<dyn std::error::Error + Send>> {
+		Ok(PendingPayments {
+			queued:        self.payment_queue.depth().await?,
+			in_processing: in_flight_payments().count().await?,
+		})
+	}
+}
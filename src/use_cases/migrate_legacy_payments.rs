@@ -0,0 +1,92 @@
+use std::error::Error;
+
+use log::{info, warn};
+
+use crate::domain::legacy_payment_store::LegacyPaymentStore;
+use crate::domain::payment_processor::PROCESSOR_GROUPS;
+use crate::domain::repository::PaymentRepository;
+use crate::infrastructure::observability::log_redaction;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MigrationReport {
+	pub migrated:   usize,
+	pub skipped:    usize,
+	/// Legacy entries that could not be read as payments.
+	pub unreadable: usize,
+	/// Legacy payments carrying neither `requestedAt` nor `processedAt`,
+	/// which cannot be placed in any summary range.
+	pub undated:    usize,
+}
+
+#[derive(Clone)]
+pub struct MigrateLegacyPaymentsUseCase<L: LegacyPaymentStore, R: PaymentRepository>
+{
+	legacy_store: L,
+	payment_repo: R,
+}
+
+impl<L: LegacyPaymentStore, R: PaymentRepository>
+	MigrateLegacyPaymentsUseCase<L, R>
+{
+	pub fn new(legacy_store: L, payment_repo: R) -> Self {
+		Self {
+			legacy_store,
+			payment_repo,
+		}
+	}
+
+	pub async fn execute(&self) -> Result<MigrationReport, Box<dyn Error + Send>> {
+		let mut report = MigrationReport::default();
+
+		for group in PROCESSOR_GROUPS {
+			let loaded = self.legacy_store.load_payments(group).await?;
+			report.unreadable += loaded.unreadable;
+
+			for mut payment in loaded.payments {
+				if self
+					.payment_repo
+					.is_already_processed(&payment.correlation_id.to_string())
+					.await?
+				{
+					report.skipped += 1;
+					continue;
+				}
+
+				// Legacy records may predate `requested_at`. Records without
+				// any timestamp are left for an operator rather than counted
+				// as requested now.
+				payment.requested_at = payment.requested_at.or(payment.processed_at);
+				if payment.requested_at.is_none() {
+					warn!(
+						"Legacy payment {} has no timestamp; not migrated",
+						log_redaction::correlation_id(payment.correlation_id)
+					);
+					report.undated += 1;
+					continue;
+				}
+				payment.processed_by = Some(group.to_string());
+
+				self.payment_repo.save(payment).await?;
+				report.migrated += 1;
+			}
+		}
+
+		info!(
+			"Legacy payments migrated: {} migrated, {} skipped, {} unreadable, {} \
+			 undated",
+			report.migrated, report.skipped, report.unreadable, report.undated
+		);
+
+		// Removing the legacy layout would lose the payments left behind.
+		if report.unreadable > 0 || report.undated > 0 {
+			warn!(
+				"Keeping the legacy key layout: some payments could not be migrated"
+			);
+			return Ok(report);
+		}
+
+		self.legacy_store.remove().await?;
+
+		Ok(report)
+	}
+}
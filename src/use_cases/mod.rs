@@ -1,5 +1,12 @@
 pub mod create_payment;
 pub mod dto;
+pub mod get_duplicates_report;
 pub mod get_payment_summary;
+pub mod get_pending_payments;
+pub mod migrate_legacy_payments;
+pub mod payments_snapshot;
 pub mod process_payment;
 pub mod purge_payments;
+pub mod run_epoch;
+pub mod summary_rounding;
+pub mod time_bound;
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt, stream};
+use time::{Date, OffsetDateTime, Time};
+
+use crate::domain::payment::Payment;
+use crate::domain::payment_processor::PROCESSOR_GROUPS;
+use crate::domain::queue::{Message, Queue};
+use crate::domain::repository::{PaymentFailure, PaymentRepository};
+use crate::use_cases::dto::{SnapshotImportReport, SnapshotRecord};
+
+pub type SnapshotStream =
+	BoxStream<'static, Result<SnapshotRecord, Box<dyn Error + Send>>>;
+
+/// Queued messages read at a time while exporting.
+const QUEUE_PAGE_SIZE: usize = 500;
+
+/// Exports and imports the queued and processed payments, along with the
+/// summary buckets trimmed payments were folded into and the recorded
+/// processor failures.
+#[derive(Clone)]
+pub struct PaymentsSnapshotUseCase<Q: Queue<Payment>, R: PaymentRepository> {
+	payment_queue:    Q,
+	processor_queues: HashMap<String, Q>,
+	payment_repo:     R,
+}
+
+impl<Q: Queue<Payment> + Clone, R: PaymentRepository> PaymentsSnapshotUseCase<Q, R> {
+	pub fn new(payment_queue: Q, payment_repo: R) -> Self {
+		Self {
+			payment_queue,
+			processor_queues: HashMap::new(),
+			payment_repo,
+		}
+	}
+
+	/// Exports the payments waiting in each processor queue too, and imports
+	/// them back into the queue of their processor, or into the ingest queue
+	/// when there is none.
+	pub fn with_processor_queues(
+		mut self,
+		processor_queues: HashMap<String, Q>,
+	) -> Self {
+		self.processor_queues = processor_queues;
+		self
+	}
+
+	pub async fn export(&self) -> Result<SnapshotStream, Box<dyn Error + Send>> {
+		let from = OffsetDateTime::UNIX_EPOCH;
+		let to = Date::MAX.with_time(Time::MAX).assume_utc();
+
+		let mut records = vec![Self::queued(
+			self.payment_queue.clone(),
+			SnapshotRecord::Queued,
+		)];
+		for processor in PROCESSOR_GROUPS {
+			if let Some(processor_queue) = self.processor_queues.get(processor) {
+				records.push(Self::queued(processor_queue.clone(), |message| {
+					SnapshotRecord::Dispatched {
+						processor: processor.to_string(),
+						message,
+					}
+				}));
+			}
+		}
+		for group in PROCESSOR_GROUPS {
+			records.push(
+				self.payment_repo
+					.get_payments_stream(group, from, to)
+					.map_ok(SnapshotRecord::Processed)
+					.boxed(),
+			);
+		}
+		for group in PROCESSOR_GROUPS {
+			records.push(
+				self.payment_repo
+					.get_summary_buckets_stream(group)
+					.map_ok(SnapshotRecord::SummaryBucket)
+					.boxed(),
+			);
+		}
+		for group in PROCESSOR_GROUPS {
+			for failure in [PaymentFailure::Failed, PaymentFailure::Rejected] {
+				records.push(
+					self.payment_repo
+						.get_failures_stream(group, failure)
+						.map_ok(SnapshotRecord::Failure)
+						.boxed(),
+				);
+			}
+		}
+
+		Ok(stream::iter(records).flatten().boxed())
+	}
+
+	/// Reads the messages of `queue` a page at a time, so a long queue is
+	/// never held in memory at once. Messages popped while it is read may be
+	/// left out.
+	fn queued(
+		queue: Q,
+		into_record: impl Fn(Message<Payment>) -> SnapshotRecord + Send + 'static,
+	) -> SnapshotStream {
+		stream::try_unfold(
+			(queue, 0, false),
+			|(queue, offset, finished)| async move {
+				if finished {
+					return Ok(None);
+				}
+
+				let page = queue.peek(offset, QUEUE_PAGE_SIZE).await?;
+				let read = page.len();
+
+				Ok(Some((
+					stream::iter(page.into_iter().map(Ok)),
+					(queue, offset + read, read < QUEUE_PAGE_SIZE),
+				)))
+			},
+		)
+		.try_flatten()
+		.map_ok(into_record)
+		.boxed()
+	}
+
+	pub async fn import(
+		&self,
+		record: SnapshotRecord,
+		report: &mut SnapshotImportReport,
+	) -> Result<(), Box<dyn Error + Send>> {
+		match record {
+			SnapshotRecord::Queued(message) => {
+				self.payment_queue.push(message).await?;
+				report.queued += 1;
+			}
+			SnapshotRecord::Dispatched { processor, message } => {
+				match self.processor_queues.get(&processor) {
+					Some(processor_queue) => processor_queue.push(message).await?,
+					None => self.payment_queue.push(message).await?,
+				}
+				report.queued += 1;
+			}
+			SnapshotRecord::Processed(payment) => {
+				if self
+					.payment_repo
+					.is_already_processed(&payment.correlation_id.to_string())
+					.await?
+				{
+					report.skipped += 1;
+				} else {
+					self.payment_repo.save(payment).await?;
+					report.processed += 1;
+				}
+			}
+			SnapshotRecord::SummaryBucket(bucket) => {
+				self.payment_repo.restore_summary_bucket(&bucket).await?;
+				report.buckets += 1;
+			}
+			SnapshotRecord::Failure(failure) => {
+				self.payment_repo.restore_failure(&failure).await?;
+				report.failures += 1;
+			}
+		}
+
+		Ok(())
+	}
+}
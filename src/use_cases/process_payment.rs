@@ -1,13 +1,25 @@
+use std::collections::HashMap;
 use std::error::Error;
 use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
 
 use circuitbreaker_rs::{BreakerError, CircuitBreaker, DefaultPolicy};
 use log::error;
-use reqwest::Client;
-use time::OffsetDateTime;
+use reqwest::{Client, Response};
 
+use crate::domain::clock::{Clock, SystemClock};
 use crate::domain::payment::Payment;
-use crate::domain::repository::PaymentRepository;
+use crate::domain::payment_metrics::{NoPaymentMetrics, PaymentMetrics};
+use crate::domain::processor_health_reporter::ProcessorHealthReporter;
+use crate::domain::repository::{PaymentFailure, PaymentRepository};
+use crate::infrastructure::config::settings::RequestedAtFormat;
+use crate::infrastructure::gateway::caching_resolver::CachingResolver;
+use crate::infrastructure::observability::log_redaction;
+use crate::use_cases::dto::PaymentProcessorRequest;
+
+/// Header carrying the id of the request a payment was submitted with.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
 
 #[derive(Debug)]
 pub struct PaymentProcessingError(pub String);
@@ -28,8 +40,16 @@ impl From<Box<dyn Error + Send + Sync + 'static>> for PaymentProcessingError {
 
 #[derive(Clone)]
 pub struct ProcessPaymentUseCase<R: PaymentRepository> {
-	payment_repo: R,
-	http_client:  Client,
+	payment_repo:        R,
+	http_client:         Client,
+	processor_clients:   HashMap<String, Client>,
+	request_timeout:     Option<Duration>,
+	request_timeouts:    HashMap<String, Duration>,
+	health_reporter:     Option<Arc<dyn ProcessorHealthReporter>>,
+	metrics:             &'static dyn PaymentMetrics,
+	dns_resolver:        Option<Arc<CachingResolver>>,
+	requested_at_format: RequestedAtFormat,
+	clock:               Arc<dyn Clock>,
 }
 
 impl<R: PaymentRepository> ProcessPaymentUseCase<R> {
@@ -37,35 +57,213 @@ impl<R: PaymentRepository> ProcessPaymentUseCase<R> {
 		Self {
 			payment_repo,
 			http_client,
+			processor_clients: HashMap::new(),
+			request_timeout: None,
+			request_timeouts: HashMap::new(),
+			health_reporter: None,
+			metrics: &NoPaymentMetrics,
+			dns_resolver: None,
+			requested_at_format: RequestedAtFormat::default(),
+			clock: Arc::new(SystemClock),
 		}
 	}
 
+	/// How `requestedAt` is written in the bodies sent to the processors.
+	pub fn with_requested_at_format(mut self, format: RequestedAtFormat) -> Self {
+		self.requested_at_format = format;
+		self
+	}
+
+	/// Stamps payments with `clock` instead of the system clock.
+	pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+		self.clock = clock;
+		self
+	}
+
+	/// Bounds each call to a processor, independently of any timeout set on
+	/// the client itself.
+	pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+		self.request_timeout = Some(timeout);
+		self
+	}
+
+	/// Overrides the per-call timeout for a single processor.
+	pub fn with_processor_request_timeout(
+		mut self,
+		processor: &str,
+		timeout: Duration,
+	) -> Self {
+		self.request_timeouts.insert(processor.to_string(), timeout);
+		self
+	}
+
+	/// Sends the payments of `processor` through a dedicated client, so a slow
+	/// processor can only tie up the connections of its own pool.
+	pub fn with_processor_client(mut self, processor: &str, client: Client) -> Self {
+		self.processor_clients.insert(processor.to_string(), client);
+		self
+	}
+
+	/// Reports every call that reached the processor, so failures from live
+	/// traffic feed the health model as well.
+	pub fn with_health_reporter(
+		mut self,
+		health_reporter: Arc<dyn ProcessorHealthReporter>,
+	) -> Self {
+		self.health_reporter = Some(health_reporter);
+		self
+	}
+
+	/// Records the processed payments in `metrics`.
+	pub fn with_metrics(mut self, metrics: &'static dyn PaymentMetrics) -> Self {
+		self.metrics = metrics;
+		self
+	}
+
+	/// Drops the cached address of a processor as soon as connecting to it
+	/// fails, instead of waiting for the entry to expire.
+	pub fn with_dns_resolver(mut self, dns_resolver: Arc<CachingResolver>) -> Self {
+		self.dns_resolver = Some(dns_resolver);
+		self
+	}
+
+	fn report_outcome(&self, processor: &str, success: bool) {
+		if let Some(health_reporter) = &self.health_reporter {
+			if success {
+				health_reporter.report_success(processor);
+			} else {
+				health_reporter.report_failure(processor);
+			}
+		}
+	}
+
+	/// Posts the payment to the first replica that accepts a connection. Any
+	/// other error stops the attempt, as the payment may have reached the
+	/// processor.
+	async fn send_payment(
+		&self,
+		http_client: &Client,
+		payments_urls: &[Arc<str>],
+		payment: &Payment,
+		request_id: Option<&str>,
+		request_timeout: Option<Duration>,
+	) -> Result<Response, PaymentProcessingError> {
+		let mut last_error = None;
+		let body =
+			PaymentProcessorRequest::from_payment(payment, self.requested_at_format);
+
+		for payments_url in payments_urls {
+			let mut request = http_client.post(&**payments_url).json(&body);
+			if let Some(timeout) = request_timeout {
+				request = request.timeout(timeout);
+			}
+			if let Some(request_id) = request_id {
+				request = request.header(REQUEST_ID_HEADER, request_id);
+			}
+
+			match request.send().await {
+				Ok(response) => return Ok(response),
+				Err(e) => {
+					self.invalidate_address(&e);
+					let connect_failed = e.is_connect();
+					last_error = Some(e);
+					if !connect_failed {
+						break;
+					}
+				}
+			}
+		}
+
+		Err(PaymentProcessingError(last_error.map_or_else(
+			|| "No processor URL".to_string(),
+			|e| e.to_string(),
+		)))
+	}
+
+	/// Keeps track of the calls that did not process the payment, for the
+	/// failures breakdown of the summary. Losing one only skews that
+	/// breakdown, so errors are logged and the payment goes on.
+	async fn record_failure(
+		&self,
+		payment: &Payment,
+		processor: &str,
+		failure: PaymentFailure,
+	) {
+		if let Err(e) = self
+			.payment_repo
+			.record_failure(payment, processor, failure)
+			.await
+		{
+			error!(
+				"Failed to record {} payment {}: {e}",
+				failure.as_str(),
+				log_redaction::correlation_id(payment.correlation_id)
+			);
+		}
+	}
+
+	fn invalidate_address(&self, error: &reqwest::Error) {
+		if let Some(dns_resolver) = &self.dns_resolver &&
+			error.is_connect() &&
+			let Some(host) = error.url().and_then(|url| url.host_str())
+		{
+			dns_resolver.invalidate(host);
+		}
+	}
+
+	fn request_timeout_for(&self, processor: &str) -> Option<Duration> {
+		self.request_timeouts
+			.get(processor)
+			.copied()
+			.or(self.request_timeout)
+	}
+
+	fn client_for(&self, processor: &str) -> &Client {
+		self.processor_clients
+			.get(processor)
+			.unwrap_or(&self.http_client)
+	}
+
+	/// Stamps `requested_at` on the first attempt to send `payment` and keeps
+	/// it on later ones, so a retry lands in the same summary bucket as the
+	/// processor recorded for an earlier attempt it may have processed.
+	pub fn stamp_requested_at(&self, payment: &mut Payment) {
+		payment.requested_at.get_or_insert_with(|| self.clock.now());
+	}
+
 	pub async fn execute(
 		&self,
 		mut payment: Payment,
-		processor_url: String,
-		processed_by: String,
+		request_id: Option<&str>,
+		payments_urls: &[Arc<str>],
+		processed_by: &str,
 		circuit_breaker: &mut CircuitBreaker<DefaultPolicy, PaymentProcessingError>,
 	) -> Result<bool, Box<dyn Error + Send>> {
-		payment.requested_at = Some(OffsetDateTime::now_utc());
+		self.stamp_requested_at(&mut payment);
+		let http_client = self.client_for(processed_by);
+		let request_timeout = self.request_timeout_for(processed_by);
 
 		let result: Result<bool, BreakerError<PaymentProcessingError>> =
 			circuit_breaker
 				.call_async(|| async {
 					let response = self
-						.http_client
-						.post(format!("{processor_url}/payments"))
-						.json(&payment)
-						.send()
-						.await
-						.map_err(|e| PaymentProcessingError(e.to_string()))?;
+						.send_payment(
+							http_client,
+							payments_urls,
+							&payment,
+							request_id,
+							request_timeout,
+						)
+						.await?;
 
 					if response.status().is_success() {
 						Ok(true)
 					} else {
 						error!(
-							"Processor returned non-success status for {}: {}",
-							payment.correlation_id,
+							"Processor returned non-success status for {} (request \
+							 {}): {}",
+							log_redaction::correlation_id(payment.correlation_id),
+							request_id.unwrap_or("-"),
 							response.status()
 						);
 
@@ -80,14 +278,35 @@ impl<R: PaymentRepository> ProcessPaymentUseCase<R> {
 				})
 				.await;
 
+		match &result {
+			Ok(_) => self.report_outcome(processed_by, true),
+			Err(BreakerError::Operation(_)) => {
+				self.report_outcome(processed_by, false)
+			}
+			Err(_) => {}
+		}
+
+		match &result {
+			Ok(false) => {
+				self.record_failure(&payment, processed_by, PaymentFailure::Rejected)
+					.await
+			}
+			Err(BreakerError::Open) | Ok(true) => {}
+			Err(_) => {
+				self.record_failure(&payment, processed_by, PaymentFailure::Failed)
+					.await
+			}
+		}
+
 		match result {
 			Ok(result) => {
 				if !result {
 					Ok(false)
 				} else {
-					payment.processed_at = Some(OffsetDateTime::now_utc());
-					payment.processed_by = Some(processed_by);
+					payment.processed_at = Some(self.clock.now());
+					payment.processed_by = Some(processed_by.to_string());
 					self.payment_repo.save(payment).await?;
+					self.metrics.record_processed(processed_by);
 					Ok(true)
 				}
 			}
@@ -1,18 +1,91 @@
 use std::error::Error;
+use std::future::ready;
+use std::sync::Arc;
 
+use futures::TryStreamExt;
+use time::{Date, OffsetDateTime, Time};
+
+use crate::domain::payment::Payment;
+use crate::domain::payment_archive::PaymentArchive;
+use crate::domain::payment_processor::PROCESSOR_GROUPS;
 use crate::domain::repository::PaymentRepository;
 
+const PURGE_BATCH_SIZE: usize = 500;
+
 #[derive(Clone)]
 pub struct PurgePaymentsUseCase<R: PaymentRepository> {
 	repository: R,
+	archive:    Option<Arc<dyn PaymentArchive>>,
 }
 
 impl<R: PaymentRepository> PurgePaymentsUseCase<R> {
 	pub fn new(repository: R) -> Self {
-		Self { repository }
+		Self {
+			repository,
+			archive: None,
+		}
+	}
+
+	pub fn with_archive(mut self, archive: Arc<dyn PaymentArchive>) -> Self {
+		self.archive = Some(archive);
+		self
 	}
 
 	pub async fn execute(&self) -> Result<(), Box<dyn Error + Send>> {
-		self.repository.clear().await
+		self.repository.clear().await?;
+
+		if let Some(archive) = &self.archive {
+			archive.clear().await?;
+		}
+
+		Ok(())
+	}
+
+	/// Removes only the processed payments tagged with `tag`, returning how
+	/// many were removed. Payments already folded into retention buckets or
+	/// moved to the archive no longer carry their tag and are left alone.
+	pub async fn execute_for_tag(
+		&self,
+		tag: &str,
+	) -> Result<usize, Box<dyn Error + Send>> {
+		self.purge_matching(|payment| payment.tag.as_deref() == Some(tag))
+			.await
+	}
+
+	/// Removes only the processed payments of the run epoch `epoch`, with the
+	/// same limits as [`PurgePaymentsUseCase::execute_for_tag`].
+	pub async fn execute_for_epoch(
+		&self,
+		epoch: u64,
+	) -> Result<usize, Box<dyn Error + Send>> {
+		self.purge_matching(|payment| payment.epoch.unwrap_or_default() == epoch)
+			.await
+	}
+
+	async fn purge_matching(
+		&self,
+		matches: impl Fn(&Payment) -> bool,
+	) -> Result<usize, Box<dyn Error + Send>> {
+		let from = OffsetDateTime::UNIX_EPOCH;
+		let to = Date::MAX.with_time(Time::MAX).assume_utc();
+		let mut purged = 0;
+
+		for group in PROCESSOR_GROUPS {
+			// Collected up front, as deleting while paging would shift the
+			// payments that are yet to be read.
+			let tagged: Vec<Payment> = self
+				.repository
+				.get_payments_stream(group, from, to)
+				.try_filter(|payment| ready(matches(payment)))
+				.try_collect()
+				.await?;
+
+			for batch in tagged.chunks(PURGE_BATCH_SIZE) {
+				self.repository.delete(batch).await?;
+			}
+			purged += tagged.len();
+		}
+
+		Ok(purged)
 	}
 }
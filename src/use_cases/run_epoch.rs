@@ -0,0 +1,20 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Epoch of the current benchmark run, `0` until one is started.
+static CURRENT_EPOCH: AtomicU64 = AtomicU64::new(0);
+
+/// Epoch of the benchmark run payments accepted now belong to. Starting a new
+/// epoch makes the summary and purge of the next run independent of the
+/// payments left by the previous one, without purging them.
+pub fn current_epoch() -> u64 {
+	CURRENT_EPOCH.load(Ordering::Relaxed)
+}
+
+pub fn set_current_epoch(epoch: u64) {
+	CURRENT_EPOCH.store(epoch, Ordering::Relaxed);
+}
+
+/// The epoch to stamp on a payment accepted now, if one was started.
+pub fn stamp() -> Option<u64> {
+	Some(current_epoch()).filter(|epoch| *epoch > 0)
+}
@@ -0,0 +1,78 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use serde::Serializer;
+
+use crate::infrastructure::config::settings::{Config, SummaryRounding};
+
+static SUMMARY_ROUNDING: AtomicU8 = AtomicU8::new(SummaryRounding::HalfEven as u8);
+
+/// Summed amounts closer than this many cents to a whole cent are taken as
+/// that cent, so float noise from adding amounts up does not truncate
+/// `0.3` into `0.29`.
+const CENT_TOLERANCE: f64 = 1e-6;
+
+/// Applies the configured rounding to every summary served from now on.
+pub fn init(config: &Config) {
+	set_summary_rounding(config.summary_rounding);
+}
+
+pub fn set_summary_rounding(rounding: SummaryRounding) {
+	SUMMARY_ROUNDING.store(rounding as u8, Ordering::Relaxed);
+}
+
+pub fn summary_rounding() -> SummaryRounding {
+	match SUMMARY_ROUNDING.load(Ordering::Relaxed) {
+		rounding if rounding == SummaryRounding::None as u8 => SummaryRounding::None,
+		rounding if rounding == SummaryRounding::Truncate as u8 => {
+			SummaryRounding::Truncate
+		}
+		_ => SummaryRounding::HalfEven,
+	}
+}
+
+pub fn round_amount(amount: f64, rounding: SummaryRounding) -> f64 {
+	let cents = amount * 100.0;
+	let nearest = cents.round();
+	if (cents - nearest).abs() <= CENT_TOLERANCE.max(cents.abs() * 1e-12) {
+		return match rounding {
+			SummaryRounding::None => amount,
+			_ => nearest / 100.0,
+		};
+	}
+
+	match rounding {
+		SummaryRounding::None => amount,
+		SummaryRounding::Truncate => cents.trunc() / 100.0,
+		SummaryRounding::HalfEven => cents.round_ties_even() / 100.0,
+	}
+}
+
+/// Serializes an amount total with the configured [`SummaryRounding`].
+pub fn serialize<S>(amount: &f64, serializer: S) -> Result<S::Ok, S::Error>
+where
+	S: Serializer,
+{
+	serializer.serialize_f64(round_amount(*amount, summary_rounding()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_rounds_totals_per_mode() {
+		let noisy = 0.1 + 0.2;
+
+		assert_eq!(round_amount(noisy, SummaryRounding::None), noisy);
+		assert_eq!(round_amount(noisy, SummaryRounding::Truncate), 0.3);
+		assert_eq!(round_amount(noisy, SummaryRounding::HalfEven), 0.3);
+		assert_eq!(round_amount(19.999, SummaryRounding::Truncate), 19.99);
+		assert_eq!(round_amount(19.999, SummaryRounding::HalfEven), 20.0);
+		assert_eq!(round_amount(0.125, SummaryRounding::HalfEven), 0.12);
+		assert_eq!(round_amount(0.375, SummaryRounding::HalfEven), 0.38);
+		assert_eq!(
+			round_amount(415542345.98, SummaryRounding::Truncate),
+			415542345.98
+		);
+	}
+}
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Deserializer};
+use time::format_description::well_known::{Iso8601, Rfc3339};
+use time::{Duration, OffsetDateTime, UtcOffset};
+
+/// Parses a bound of a time range into UTC. Accepted forms are:
+///
+/// - RFC 3339 timestamps with any offset, e.g. `2025-07-15T10:30:00-03:00`;
+/// - ISO 8601 basic format, e.g. `20250715T103000Z`;
+/// - milliseconds since the Unix epoch, e.g. `1752575400000`;
+/// - `now` and offsets relative to it, such as `-5m`, `now-1h` or `+30s`, with
+///   `s`, `m`, `h` and `d` units.
+///
+/// A space is read as `+`, as an unencoded `+` in a query string decodes to
+/// one.
+pub fn parse_time_bound(
+	value: &str,
+	now: OffsetDateTime,
+) -> Result<OffsetDateTime, String> {
+	let value = value.trim().replace(' ', "+");
+	let relative = value.strip_prefix("now").unwrap_or(&value);
+
+	let parsed = if relative.is_empty() {
+		Ok(now)
+	} else if let Some(offset) = relative.strip_prefix('-') {
+		parse_offset(offset).map(|offset| now - offset)
+	} else if let Some(offset) = relative.strip_prefix('+') {
+		parse_offset(offset).map(|offset| now + offset)
+	} else if value.bytes().all(|byte| byte.is_ascii_digit()) {
+		parse_epoch_millis(&value)
+	} else {
+		OffsetDateTime::parse(&value, &Rfc3339)
+			.or_else(|_| OffsetDateTime::parse(&value, &Iso8601::DEFAULT))
+			.map_err(|e| format!("invalid time bound '{value}': {e}"))
+	};
+
+	parsed.map(to_utc)
+}
+
+/// Converts a bound to UTC, so every range reaching the repository shares the
+/// same offset.
+pub fn to_utc(timestamp: OffsetDateTime) -> OffsetDateTime {
+	timestamp.to_offset(UtcOffset::UTC)
+}
+
+fn parse_epoch_millis(value: &str) -> Result<OffsetDateTime, String> {
+	let invalid = || format!("invalid epoch milliseconds '{value}'");
+
+	let millis: i128 = value.parse().map_err(|_| invalid())?;
+	OffsetDateTime::from_unix_timestamp_nanos(millis * 1_000_000)
+		.map_err(|_| invalid())
+}
+
+fn parse_offset(offset: &str) -> Result<Duration, String> {
+	let invalid = || format!("invalid relative time '{offset}'");
+
+	let unit_at = offset.len().checked_sub(1).ok_or_else(invalid)?;
+	let (amount, unit) = offset.split_at(unit_at);
+	let amount: i64 = amount.parse().map_err(|_| invalid())?;
+
+	match unit {
+		"s" => Ok(Duration::seconds(amount)),
+		"m" => Ok(Duration::minutes(amount)),
+		"h" => Ok(Duration::hours(amount)),
+		"d" => Ok(Duration::days(amount)),
+		_ => Err(invalid()),
+	}
+}
+
+/// Deserializes an optional range bound with [`parse_time_bound`].
+pub fn deserialize<'de, D>(
+	deserializer: D,
+) -> Result<Option<OffsetDateTime>, D::Error>
+where
+	D: Deserializer<'de>,
+{
+	Option::<String>::deserialize(deserializer)?
+		.map(|value| parse_time_bound(&value, OffsetDateTime::now_utc()))
+		.transpose()
+		.map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn at(value: &str) -> OffsetDateTime {
+		OffsetDateTime::parse(value, &Rfc3339).unwrap()
+	}
+
+	fn now() -> OffsetDateTime {
+		at("2025-07-15T12:00:00Z")
+	}
+
+	#[test]
+	fn test_parses_now_and_relative_bounds() {
+		assert_eq!(parse_time_bound("now", now()), Ok(now()));
+		assert_eq!(
+			parse_time_bound("-5m", now()),
+			Ok(at("2025-07-15T11:55:00Z"))
+		);
+		assert_eq!(
+			parse_time_bound("now-1h", now()),
+			Ok(at("2025-07-15T11:00:00Z"))
+		);
+		assert_eq!(
+			parse_time_bound("+2d", now()),
+			Ok(at("2025-07-17T12:00:00Z"))
+		);
+		assert_eq!(
+			parse_time_bound("now 30s", now()),
+			Ok(at("2025-07-15T12:00:30Z"))
+		);
+	}
+
+	#[test]
+	fn test_parses_absolute_bounds_into_utc() {
+		for value in [
+			"2025-07-15T10:30:00Z",
+			"2025-07-15T07:30:00-03:00",
+			"2025-07-15T13:30:00 03:00",
+			"20250715T103000Z",
+			"20250715T133000+0300",
+			"1752575400000",
+		] {
+			let parsed = parse_time_bound(value, now()).unwrap();
+
+			assert_eq!(parsed, at("2025-07-15T10:30:00Z"), "parsing '{value}'");
+			assert_eq!(parsed.offset(), UtcOffset::UTC, "parsing '{value}'");
+		}
+	}
+
+	#[test]
+	fn test_rejects_invalid_bounds() {
+		for value in [
+			"yesterday",
+			"-5",
+			"-m",
+			"now-5w",
+			"-",
+			"2025-07-15T10:30:00",
+			"2025-07-15",
+		] {
+			assert!(
+				parse_time_bound(value, now()).is_err(),
+				"accepted '{value}'"
+			);
+		}
+	}
+}
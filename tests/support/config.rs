@@ -0,0 +1,101 @@
+use rinha_de_backend::infrastructure::config::settings::{
+	Config, DedupMode, LogRedaction, QueueMode, SummaryRounding,
+};
+
+/// A configuration with every optional feature disabled.
+pub fn test_config(redis_url: &str) -> Config {
+	Config {
+		redis_url: redis_url.to_string(),
+		redis_keepalive_interval_ms: None,
+		redis_clock_sync_interval_ms: None,
+		run_epoch_sync_interval_ms: 1000,
+		default_payment_processor_url: "http://localhost:8080".to_string(),
+		fallback_payment_processor_url: "http://localhost:8081".to_string(),
+		server_keepalive: 60,
+		server_client_request_timeout_ms: None,
+		server_max_connections: None,
+		server_backlog: None,
+		server_workers: None,
+		server_reuse_port: false,
+		worker_concurrency: None,
+		autotune_workers: false,
+		worker_runtime_threads: None,
+		server_payments_timeout_ms: 1000,
+		server_summary_timeout_ms: 5000,
+		admin_token: None,
+		report_url: None,
+		flamegraph_snapshot_interval: None,
+		flamegraph_snapshots: 5,
+		sentry_dsn: None,
+		alert_webhook_url: None,
+		alert_slack_webhook_url: None,
+		alert_downtime_window: 30,
+		metrics_statsd_addr: None,
+		metrics_statsd_prefix: "rinha".to_string(),
+		metrics_statsd_interval: 10,
+		metrics_dogstatsd: false,
+		payment_retention: None,
+		payment_retention_interval: 60,
+		key_space_report_interval: None,
+		dedup_mode: DedupMode::SortedSet,
+		dedup_ttl: 3600,
+		dedup_retention: None,
+		dedup_bloom_capacity: 1_000_000,
+		dedup_bloom_error_rate: 0.001,
+		archive_database_url: None,
+		dual_write_redis_url: None,
+		archive_hot_window_hours: 24,
+		archive_interval: 60,
+		queue_depth_reconcile_interval: 5,
+		queue_mode: QueueMode::Shared,
+		default_processor_workers: 1,
+		fallback_processor_workers: 1,
+		processor_pool_max_idle_per_host: None,
+		processor_warmup_connections: None,
+		processor_http2: false,
+		processor_tls_client_cert_path: None,
+		processor_tls_client_key_path: None,
+		processor_tls_ca_bundle_path: None,
+		processor_dns_ttl_ms: None,
+		processor_request_timeout_ms: None,
+		processor_requested_at_format: Default::default(),
+		default_processor_request_timeout_ms: None,
+		fallback_processor_request_timeout_ms: None,
+		retry_budget_per_second: None,
+		slow_start_window_ms: None,
+		preferred_processor: None,
+		feature_flags: None,
+		requeue_storm_ratio: None,
+		requeue_storm_window_ms: 1000,
+		queue_pop_timeout_ms: 1000,
+		queue_max_age_ms: None,
+		queue_dedup_ttl: None,
+		queue_key: None,
+		queue_cutover_from: None,
+		queue_cutover_grace_ms: None,
+		queue_compression_threshold: None,
+		queue_checksums: false,
+		queue_sequencing: false,
+		verification_interval_ms: None,
+		in_flight_registry: false,
+		legacy_summary_compat: false,
+		summary_cache: false,
+		ingest_stream: false,
+		ingest_batch_size: 100,
+		ingest_retry_interval_ms: 5000,
+		health_check_interval_ms: 5000,
+		default_health_check_interval_ms: None,
+		fallback_health_check_interval_ms: None,
+		health_check_timeout_ms: None,
+		default_health_check_timeout_ms: None,
+		fallback_health_check_timeout_ms: None,
+		health_check_failure_threshold: 1,
+		health_check_success_threshold: 1,
+		routing_cache_ttl_ms: 0,
+		default_processor_discovery: None,
+		fallback_processor_discovery: None,
+		processor_discovery_interval_ms: 10000,
+		log_redaction: LogRedaction::Off,
+		summary_rounding: SummaryRounding::HalfEven,
+	}
+}
@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 
+pub mod config;
 pub mod payment_processor_container;
 pub mod postgresql_container;
 pub mod redis_container;
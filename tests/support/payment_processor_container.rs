@@ -1,3 +1,4 @@
+use rinha_de_backend::infrastructure::gateway::processor_admin_client::ProcessorAdminClient;
 use testcontainers::core::wait::HttpWaitStrategy;
 use testcontainers::core::{ContainerPort, WaitFor};
 use testcontainers::runners::AsyncRunner;
@@ -16,12 +17,24 @@ pub async fn setup_payment_processors()
 	(default_processor_container, fallback_processor_container)
 }
 
+const ADMIN_TOKEN: &str = "123";
+
 pub struct PaymentProcessorTestContainer {
 	pub url:       String,
 	pub container: testcontainers::ContainerAsync<GenericImage>,
 	pub database:  PostgresTestContainer,
 }
 
+impl PaymentProcessorTestContainer {
+	pub fn admin_client(&self) -> ProcessorAdminClient {
+		ProcessorAdminClient::new(
+			reqwest::Client::new(),
+			self.url.clone(),
+			ADMIN_TOKEN.to_string(),
+		)
+	}
+}
+
 async fn setup_payment_processor(
 	transaction_fee: f64,
 	rate_limit: i8,
@@ -39,7 +52,7 @@ async fn setup_payment_processor(
 			.with_env_var("DB_CONNECTION_STRING", database_url)
 			.with_env_var("TRANSACTION_FEE", transaction_fee.to_string())
 			.with_env_var("RATE_LIMIT_SECONDS", rate_limit.to_string())
-			.with_env_var("INITIAL_TOKEN", "123")
+			.with_env_var("INITIAL_TOKEN", ADMIN_TOKEN)
 			.start()
 			.await
 			.unwrap();
@@ -1,5 +1,6 @@
 use redis::AsyncCommands;
 use rinha_de_backend::infrastructure::config::redis::{
+	LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY, LEGACY_FALLBACK_PAYMENT_SUMMARY_KEY,
 	PAYMENTS_QUEUE_KEY, PROCESSED_PAYMENTS_SET_KEY,
 };
 use testcontainers::GenericImage;
@@ -8,6 +9,7 @@ use testcontainers::runners::AsyncRunner;
 
 pub struct RedisTestContainer {
 	pub client:    redis::Client,
+	pub url:       String,
 	pub container: testcontainers::ContainerAsync<GenericImage>,
 }
 
@@ -26,7 +28,7 @@ pub async fn get_test_redis_client() -> RedisTestContainer {
 		.unwrap();
 	let host_port = container.get_host_port_ipv4(6379).await;
 	let redis_url = format!("redis://127.0.0.1:{}", host_port.unwrap());
-	let client = redis::Client::open(redis_url).expect("Invalid Redis URL");
+	let client = redis::Client::open(redis_url.clone()).expect("Invalid Redis URL");
 	let mut con = client
 		.get_multiplexed_async_connection()
 		.await
@@ -37,16 +39,20 @@ pub async fn get_test_redis_client() -> RedisTestContainer {
 		.await
 		.expect("Failed to clear payments_queue");
 	let _: () = con
-		.del("payments_summary_default")
+		.del(LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY)
 		.await
 		.expect("Failed to clear payments_summary_default");
 	let _: () = con
-		.del("payments_summary_fallback")
+		.del(LEGACY_FALLBACK_PAYMENT_SUMMARY_KEY)
 		.await
 		.expect("Failed to clear payments_summary_fallback");
 	let _: () = con
 		.del(PROCESSED_PAYMENTS_SET_KEY)
 		.await
-		.expect("Failed to clear processed_correlation_ids");
-	RedisTestContainer { client, container }
+		.expect("Failed to clear processed_payments");
+	RedisTestContainer {
+		client,
+		url: redis_url,
+		container,
+	}
 }
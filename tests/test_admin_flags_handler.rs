@@ -0,0 +1,54 @@
+use std::collections::BTreeMap;
+
+use actix_web::{App, test};
+use rinha_de_backend::adapters::web::handlers::{
+	list_feature_flags, set_feature_flag,
+};
+use rinha_de_backend::infrastructure::config::feature_flags::{
+	FeatureFlag, feature_flags,
+};
+
+#[actix_web::test]
+async fn test_toggle_feature_flag() {
+	let app = test::init_service(
+		App::new()
+			.service(list_feature_flags)
+			.service(set_feature_flag),
+	)
+	.await;
+
+	let req = test::TestRequest::put()
+		.uri("/admin/flags/fire_and_forget_ingest")
+		.set_json(serde_json::json!({ "enabled": true }))
+		.to_request();
+	let flags: BTreeMap<String, bool> =
+		test::call_and_read_body_json(&app, req).await;
+
+	assert_eq!(flags.get("fire_and_forget_ingest"), Some(&true));
+	assert!(feature_flags().is_enabled(FeatureFlag::FireAndForgetIngest));
+
+	let req = test::TestRequest::put()
+		.uri("/admin/flags/fire_and_forget_ingest")
+		.set_json(serde_json::json!({ "enabled": false }))
+		.to_request();
+	test::call_service(&app, req).await;
+
+	let req = test::TestRequest::get().uri("/admin/flags").to_request();
+	let flags: BTreeMap<String, bool> =
+		test::call_and_read_body_json(&app, req).await;
+
+	assert_eq!(flags.get("fire_and_forget_ingest"), Some(&false));
+}
+
+#[actix_web::test]
+async fn test_set_unknown_feature_flag() {
+	let app = test::init_service(App::new().service(set_feature_flag)).await;
+
+	let req = test::TestRequest::put()
+		.uri("/admin/flags/hedging")
+		.set_json(serde_json::json!({ "enabled": true }))
+		.to_request();
+	let resp = test::call_service(&app, req).await;
+
+	assert_eq!(resp.status(), 404);
+}
@@ -0,0 +1,153 @@
+use actix_web::http::StatusCode;
+use actix_web::{App, test, web};
+use circuitbreaker_rs::State;
+use rinha_de_backend::adapters::web::handlers::{
+	configure_processor, list_processors, processor_health_history,
+	purge_processor_health_history, reset_router,
+};
+use rinha_de_backend::adapters::web::schema::ProcessorStatus;
+use rinha_de_backend::domain::health_sample::HealthSample;
+use rinha_de_backend::domain::processor_settings::ProcessorSettings;
+use rinha_de_backend::infrastructure::routing::in_memory_payment_router::InMemoryPaymentRouter;
+
+#[actix_web::test]
+async fn test_reset_router() {
+	let router = InMemoryPaymentRouter::new();
+	router.record_health_check("default", "http://default.com", true, 0);
+	router.fallback_breaker.force_open();
+	let app = test::init_service(
+		App::new()
+			.app_data(web::Data::new(router.clone()))
+			.service(reset_router),
+	)
+	.await;
+
+	let req = test::TestRequest::post()
+		.uri("/admin/router/reset")
+		.to_request();
+	let resp = test::call_service(&app, req).await;
+
+	assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+	assert!(router.processors.read().unwrap().is_empty());
+	assert_eq!(router.breaker_state("fallback"), Some(State::Closed));
+}
+
+#[actix_web::test]
+async fn test_configure_processor() {
+	let router = InMemoryPaymentRouter::new()
+		.with_processor_urls("http://default.com", "http://fallback.com");
+	let app = test::init_service(
+		App::new()
+			.app_data(web::Data::new(router.clone()))
+			.service(configure_processor),
+	)
+	.await;
+
+	let req = test::TestRequest::put()
+		.uri("/admin/processors/fallback")
+		.set_json(serde_json::json!({
+			"url": "http://fallback-2.com",
+			"fee": 0.15,
+		}))
+		.to_request();
+	let settings: ProcessorSettings = test::call_and_read_body_json(&app, req).await;
+
+	assert_eq!(settings.url, "http://fallback-2.com");
+	assert_eq!(settings.max_response_time_ms, 100);
+	assert_eq!(router.processor_settings("fallback"), Some(settings));
+
+	for (uri, url, status) in [
+		(
+			"/admin/processors/unknown",
+			"http://unknown.com",
+			StatusCode::NOT_FOUND,
+		),
+		(
+			"/admin/processors/default",
+			"default.com",
+			StatusCode::UNPROCESSABLE_ENTITY,
+		),
+	] {
+		let req = test::TestRequest::put()
+			.uri(uri)
+			.set_json(serde_json::json!({ "url": url }))
+			.to_request();
+		let resp = test::call_service(&app, req).await;
+		assert_eq!(resp.status(), status);
+	}
+	assert_eq!(
+		router.processor_settings("default").unwrap().url,
+		"http://default.com"
+	);
+}
+
+#[actix_web::test]
+async fn test_list_processors() {
+	let router = InMemoryPaymentRouter::new();
+	router.record_health_check("fallback", "http://fallback.com", false, 0);
+	router.record_health_check("default", "http://default.com", true, 40);
+	router.default_breaker.force_open();
+	let app = test::init_service(
+		App::new()
+			.app_data(web::Data::new(router.clone()))
+			.service(list_processors),
+	)
+	.await;
+
+	let req = test::TestRequest::get()
+		.uri("/admin/processors")
+		.to_request();
+	let processors: Vec<ProcessorStatus> =
+		test::call_and_read_body_json(&app, req).await;
+
+	assert_eq!(processors.len(), 2);
+	assert_eq!(processors[0].name, "default");
+	assert_eq!(processors[0].health, "healthy");
+	assert_eq!(processors[0].min_response_time, 40);
+	assert_eq!(processors[0].breaker.as_deref(), Some("open"));
+	assert!(processors[0].last_updated.is_some());
+	assert_eq!(processors[1].name, "fallback");
+	assert_eq!(processors[1].health, "failing");
+	assert_eq!(processors[1].breaker.as_deref(), Some("closed"));
+}
+
+#[actix_web::test]
+async fn test_processor_health_history() {
+	let router = InMemoryPaymentRouter::new();
+	router.record_health_check("default", "http://default.com", true, 40);
+	router.record_health_check("default", "http://default.com", false, 0);
+	router.record_health_check("fallback", "http://fallback.com", true, 10);
+	let app = test::init_service(
+		App::new()
+			.app_data(web::Data::new(router.clone()))
+			.service(processor_health_history)
+			.service(purge_processor_health_history),
+	)
+	.await;
+
+	let req = test::TestRequest::get()
+		.uri("/admin/processors/default/history")
+		.to_request();
+	let history: Vec<HealthSample> = test::call_and_read_body_json(&app, req).await;
+
+	assert_eq!(history.len(), 2);
+	assert!(history[0].healthy);
+	assert_eq!(history[0].min_response_time, 40);
+	assert!(!history[1].healthy);
+
+	let req = test::TestRequest::delete()
+		.uri("/admin/processors/default/history")
+		.to_request();
+	let resp = test::call_service(&app, req).await;
+	assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+	assert_eq!(router.health_history("default"), Some(vec![]));
+	assert_eq!(router.health_history("fallback").unwrap().len(), 1);
+
+	for req in [
+		test::TestRequest::get().uri("/admin/processors/unknown/history"),
+		test::TestRequest::delete().uri("/admin/processors/unknown/history"),
+	] {
+		let resp = test::call_service(&app, req.to_request()).await;
+		assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+	}
+}
@@ -0,0 +1,54 @@
+use actix_web::http::StatusCode;
+use actix_web::{App, test};
+use rinha_de_backend::adapters::web::handlers::{
+	pause_workers, resume_workers, set_workers_concurrency,
+};
+use rinha_de_backend::adapters::web::schema::WorkersStatus;
+use rinha_de_backend::infrastructure::workers::worker_control::worker_control;
+
+#[actix_web::test]
+async fn test_pause_and_resume_workers() {
+	let app = test::init_service(
+		App::new().service(pause_workers).service(resume_workers),
+	)
+	.await;
+
+	let req = test::TestRequest::post()
+		.uri("/admin/workers/pause")
+		.to_request();
+	let status: WorkersStatus = test::call_and_read_body_json(&app, req).await;
+
+	assert!(status.paused);
+	assert!(worker_control().is_paused());
+
+	let req = test::TestRequest::post()
+		.uri("/admin/workers/resume")
+		.to_request();
+	let status: WorkersStatus = test::call_and_read_body_json(&app, req).await;
+
+	assert!(!status.paused);
+	assert!(!worker_control().is_paused());
+}
+
+#[actix_web::test]
+async fn test_set_workers_concurrency() {
+	let app = test::init_service(App::new().service(set_workers_concurrency)).await;
+
+	let req = test::TestRequest::put()
+		.uri("/admin/workers/concurrency")
+		.set_json(serde_json::json!({ "concurrency": 4 }))
+		.to_request();
+	let status: WorkersStatus = test::call_and_read_body_json(&app, req).await;
+
+	assert_eq!(status.concurrency, Some(4));
+	assert_eq!(worker_control().concurrency(), 4);
+
+	let req = test::TestRequest::put()
+		.uri("/admin/workers/concurrency")
+		.set_json(serde_json::json!({ "concurrency": 0 }))
+		.to_request();
+	let resp = test::call_service(&app, req).await;
+
+	assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+	assert_eq!(worker_control().concurrency(), 4);
+}
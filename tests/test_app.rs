@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use actix_web::test;
+use rinha_de_backend::adapters::web::schema::PaymentRequest;
+use rinha_de_backend::domain::queue::Queue;
+use rinha_de_backend::use_cases::dto::PaymentsSummaryResponse;
+use rinha_de_backend::{AppContext, build_app, start_workers};
+use uuid::Uuid;
+
+mod support;
+
+use crate::support::config::test_config;
+use crate::support::redis_container::get_test_redis_client;
+
+#[actix_web::test]
+async fn test_build_app_registers_payment_routes() {
+	let redis_container = get_test_redis_client().await;
+	let context =
+		AppContext::from_config(Arc::new(test_config(&redis_container.url))).await;
+
+	let app = test::init_service(build_app(&context)).await;
+
+	let payment_req = PaymentRequest {
+		correlation_id: Uuid::new_v4(),
+		amount:         19.9,
+	};
+	let req = test::TestRequest::post()
+		.uri("/payments")
+		.set_json(&payment_req)
+		.to_request();
+	let resp = test::call_service(&app, req).await;
+
+	assert!(resp.status().is_success());
+
+	let message = context.payment_queue.pop().await.unwrap().unwrap();
+	assert_eq!(message.body.correlation_id, payment_req.correlation_id);
+
+	let req = test::TestRequest::get()
+		.uri("/payments-summary")
+		.to_request();
+	let summary: PaymentsSummaryResponse =
+		test::call_and_read_body_json(&app, req).await;
+
+	assert_eq!(summary.default.total_requests, 0);
+	assert_eq!(summary.fallback.total_requests, 0);
+}
+
+#[actix_web::test]
+async fn test_start_workers_can_be_aborted() {
+	let redis_container = get_test_redis_client().await;
+	let context =
+		AppContext::from_config(Arc::new(test_config(&redis_container.url))).await;
+
+	let workers = start_workers(&context).await;
+	workers.abort();
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "client")]
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rinha_de_backend::client::{
+	PaymentRequest, PaymentsClient, PaymentsSummaryFilter, PurgePaymentsFilter,
+};
+use rinha_de_backend::{AppContext, serve};
+use uuid::Uuid;
+
+mod support;
+
+use crate::support::config::test_config;
+use crate::support::redis_container::get_test_redis_client;
+
+#[actix_web::test]
+async fn test_client_submits_summarizes_and_purges_payments() {
+	let redis_container = get_test_redis_client().await;
+	let context =
+		AppContext::from_config(Arc::new(test_config(&redis_container.url))).await;
+	let addr = std::net::TcpListener::bind("127.0.0.1:0")
+		.unwrap()
+		.local_addr()
+		.unwrap();
+	actix_web::rt::spawn(serve(context, addr));
+
+	let client =
+		PaymentsClient::new(reqwest::Client::new(), format!("http://{addr}/"))
+			.with_tag("load");
+	let payment = PaymentRequest {
+		correlation_id: Uuid::new_v4(),
+		amount:         19.9,
+	};
+	let mut submitted = client.submit_payment(&payment).await;
+	for _ in 0..50 {
+		if submitted.is_ok() {
+			break;
+		}
+		tokio::time::sleep(Duration::from_millis(100)).await;
+		submitted = client.submit_payment(&payment).await;
+	}
+	let response = submitted.unwrap();
+	assert_eq!(response.payment.correlation_id, payment.correlation_id);
+	assert_eq!(response.status, "queued");
+
+	let summary = client
+		.get_summary(&PaymentsSummaryFilter {
+			pending: true,
+			..PaymentsSummaryFilter::default()
+		})
+		.await
+		.unwrap();
+	assert_eq!(summary.default.total_requests, 0);
+	assert_eq!(summary.pending.unwrap().queued, 1);
+
+	let purged = client
+		.purge_payments(&PurgePaymentsFilter {
+			tag: Some("load".to_string()),
+			..PurgePaymentsFilter::default()
+		})
+		.await
+		.unwrap();
+	assert_eq!(purged, "Purged 0 payments tagged 'load'");
+}
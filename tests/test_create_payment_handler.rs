@@ -1,10 +1,13 @@
+use std::sync::Arc;
+
 use actix_web::{App, test, web};
 use rinha_de_backend::adapters::web::handlers::payments;
 use rinha_de_backend::adapters::web::schema::PaymentRequest;
+use rinha_de_backend::adapters::web::state::AppState;
 use rinha_de_backend::domain::payment::Payment;
 use rinha_de_backend::domain::queue::Queue;
+use rinha_de_backend::infrastructure::persistence::redis_payment_repository::RedisPaymentRepository;
 use rinha_de_backend::infrastructure::queue::redis_payment_queue::PaymentQueue;
-use rinha_de_backend::use_cases::create_payment::CreatePaymentUseCase;
 use uuid::Uuid;
 
 mod support;
@@ -16,11 +19,14 @@ async fn test_payments_post_returns_success() {
 	let redis_container = get_test_redis_client().await;
 	let redis_client = redis_container.client.clone();
 	let payment_queue = PaymentQueue::new(redis_client.clone());
-	let create_payment_use_case = CreatePaymentUseCase::new(payment_queue.clone());
+	let state = AppState::new(
+		Arc::new(payment_queue.clone()),
+		Arc::new(RedisPaymentRepository::new(redis_client.clone())),
+	);
 
 	let app = test::init_service(
 		App::new()
-			.app_data(web::Data::new(create_payment_use_case.clone()))
+			.app_data(web::Data::new(state.clone()))
 			.service(payments),
 	)
 	.await;
@@ -38,6 +44,10 @@ async fn test_payments_post_returns_success() {
 
 	assert!(resp.status().is_success());
 
+	let body: serde_json::Value = test::read_body_json(resp).await;
+	assert_eq!(body["status"], "queued");
+	assert!(body["queueDepth"].is_u64());
+
 	let message = payment_queue.pop().await.unwrap().unwrap();
 	let deserialized_payment: Payment = message.body;
 
@@ -53,11 +63,14 @@ async fn test_payments_post_redis_failure() {
 	let redis_container = get_test_redis_client().await;
 	let redis_client = redis_container.client.clone();
 	let payment_queue = PaymentQueue::new(redis_client.clone());
-	let create_payment_use_case = CreatePaymentUseCase::new(payment_queue.clone());
+	let state = AppState::new(
+		Arc::new(payment_queue.clone()),
+		Arc::new(RedisPaymentRepository::new(redis_client.clone())),
+	);
 
 	let app = test::init_service(
 		App::new()
-			.app_data(web::Data::new(create_payment_use_case.clone()))
+			.app_data(web::Data::new(state.clone()))
 			.service(payments),
 	)
 	.await;
@@ -78,3 +91,38 @@ async fn test_payments_post_redis_failure() {
 
 	assert!(resp.status().is_server_error());
 }
+
+#[actix_web::test]
+async fn test_payments_post_accepts_string_amount() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let payment_queue = PaymentQueue::new(redis_client.clone());
+	let state = AppState::new(
+		Arc::new(payment_queue.clone()),
+		Arc::new(RedisPaymentRepository::new(redis_client.clone())),
+	);
+
+	let app = test::init_service(
+		App::new()
+			.app_data(web::Data::new(state.clone()))
+			.service(payments),
+	)
+	.await;
+
+	let correlation_id = Uuid::new_v4();
+	let req = test::TestRequest::post()
+		.uri("/payments")
+		.set_json(serde_json::json!({
+			"correlationId": correlation_id,
+			"amount": "100.50",
+		}))
+		.to_request();
+	let resp = test::call_service(&app, req).await;
+
+	assert!(resp.status().is_success());
+
+	let message = payment_queue.pop().await.unwrap().unwrap();
+
+	assert_eq!(message.body.correlation_id, correlation_id);
+	assert_eq!(message.body.amount, 100.5);
+}
@@ -0,0 +1,98 @@
+use rinha_de_backend::domain::payment::Payment;
+use rinha_de_backend::domain::repository::PaymentRepository;
+use rinha_de_backend::infrastructure::persistence::dual_write_payment_repository::DualWritePaymentRepository;
+use rinha_de_backend::infrastructure::persistence::redis_payment_repository::RedisPaymentRepository;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+mod support;
+
+use crate::support::redis_container::get_test_redis_client;
+
+#[tokio::test]
+async fn test_writes_to_both_repositories_and_reads_from_primary() {
+	let primary_container = get_test_redis_client().await;
+	let secondary_container = get_test_redis_client().await;
+	let primary = RedisPaymentRepository::new(primary_container.client.clone());
+	let secondary = RedisPaymentRepository::new(secondary_container.client.clone());
+	let payment_repo = DualWritePaymentRepository::new(primary.clone())
+		.with_secondary(secondary.clone());
+
+	let now = OffsetDateTime::now_utc();
+	let payment = Payment {
+		correlation_id: Uuid::new_v4(),
+		amount:         42.5,
+		requested_at:   Some(now),
+		processed_at:   Some(now),
+		processed_by:   Some("default".to_string()),
+		tag:            None,
+		epoch:          None,
+	};
+	payment_repo.save(payment.clone()).await.unwrap();
+
+	let from = OffsetDateTime::UNIX_EPOCH;
+	let to = now + time::Duration::minutes(1);
+	for repo in [&primary, &secondary] {
+		assert_eq!(
+			repo.get_summary_by_group("default", from, to)
+				.await
+				.unwrap(),
+			(1, 42.5)
+		);
+	}
+
+	// Reads are served by the primary alone.
+	secondary.clear().await.unwrap();
+	assert_eq!(
+		payment_repo
+			.get_summary_by_group("default", from, to)
+			.await
+			.unwrap(),
+		(1, 42.5)
+	);
+	assert!(
+		payment_repo
+			.is_already_processed(&payment.correlation_id.to_string())
+			.await
+			.unwrap()
+	);
+}
+
+#[tokio::test]
+async fn test_secondary_failures_do_not_fail_writes() {
+	let primary_container = get_test_redis_client().await;
+	let secondary_container = get_test_redis_client().await;
+	let primary = RedisPaymentRepository::new(primary_container.client.clone());
+	let payment_repo = DualWritePaymentRepository::new(primary.clone())
+		.with_secondary(RedisPaymentRepository::new(
+			secondary_container.client.clone(),
+		));
+
+	let _ = secondary_container.container.stop().await;
+
+	let now = OffsetDateTime::now_utc();
+	payment_repo
+		.save(Payment {
+			correlation_id: Uuid::new_v4(),
+			amount:         10.0,
+			requested_at:   Some(now),
+			processed_at:   Some(now),
+			processed_by:   Some("fallback".to_string()),
+			tag:            None,
+			epoch:          None,
+		})
+		.await
+		.unwrap();
+
+	assert_eq!(
+		primary
+			.get_summary_by_group(
+				"fallback",
+				OffsetDateTime::UNIX_EPOCH,
+				now + time::Duration::minutes(1)
+			)
+			.await
+			.unwrap(),
+		(1, 10.0)
+	);
+}
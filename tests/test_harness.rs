@@ -0,0 +1,42 @@
+#![cfg(feature = "harness")]
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rinha_de_backend::AppContext;
+use rinha_de_backend::harness::SmokeTest;
+
+mod support;
+
+use crate::support::config::test_config;
+use crate::support::payment_processor_container::setup_payment_processors;
+use crate::support::redis_container::get_test_redis_client;
+
+#[actix_web::test]
+async fn test_smoke_test_summary_is_consistent() {
+	let redis_container = get_test_redis_client().await;
+	let (default_processor_container, fallback_processor_container) =
+		setup_payment_processors().await;
+
+	let mut config = test_config(&redis_container.url);
+	config.default_payment_processor_url = default_processor_container.url.clone();
+	config.fallback_payment_processor_url = fallback_processor_container.url.clone();
+	let context = AppContext::from_config(Arc::new(config)).await;
+
+	let smoke_test = SmokeTest {
+		payments: 50,
+		timeout: Duration::from_secs(60),
+		..SmokeTest::default()
+	};
+	let report = smoke_test
+		.run(
+			&context,
+			&default_processor_container.admin_client(),
+			&fallback_processor_container.admin_client(),
+		)
+		.await
+		.unwrap();
+
+	assert_eq!(report.accepted, 50);
+	assert!(report.is_consistent(), "{report:?}");
+}
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use redis::AsyncCommands;
+use rinha_de_backend::infrastructure::config::redis::IN_FLIGHT_PAYMENTS_KEY;
+use rinha_de_backend::infrastructure::persistence::redis_connection::SupervisedConnection;
+use rinha_de_backend::infrastructure::workers::in_flight_registry::InFlightRegistry;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+mod support;
+
+use crate::support::redis_container::get_test_redis_client;
+
+#[tokio::test]
+async fn test_shared_registries_count_payments_of_every_instance() {
+	let redis_container = get_test_redis_client().await;
+	let first = InFlightRegistry::new();
+	first.share_through(
+		SupervisedConnection::new(redis_container.client.clone()),
+		"first".to_string(),
+	);
+	let second = InFlightRegistry::new();
+	second.share_through(
+		SupervisedConnection::new(redis_container.client.clone()),
+		"second".to_string(),
+	);
+
+	let correlation_id = Uuid::new_v4();
+	let first_payment = first.track(correlation_id).await;
+	let _second_payment = second.track(correlation_id).await;
+
+	assert_eq!(first.local_count(), 1);
+	assert_eq!(first.count().await.unwrap(), 2);
+
+	drop(first_payment);
+	// Releases are mirrored to Redis in the background.
+	tokio::time::sleep(Duration::from_millis(200)).await;
+
+	assert_eq!(first.local_count(), 0);
+	assert_eq!(second.count().await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_count_removes_stale_payments() {
+	let redis_container = get_test_redis_client().await;
+	let registry = InFlightRegistry::new();
+	registry.share_through(
+		SupervisedConnection::new(redis_container.client.clone()),
+		"first".to_string(),
+	);
+
+	let mut con = redis_container
+		.client
+		.get_multiplexed_async_connection()
+		.await
+		.unwrap();
+	let _: () = con
+		.zadd(
+			IN_FLIGHT_PAYMENTS_KEY,
+			format!("dead-instance:{}", Uuid::new_v4()),
+			(OffsetDateTime::now_utc() - time::Duration::hours(1)).unix_timestamp(),
+		)
+		.await
+		.unwrap();
+	let _in_flight = registry.track(Uuid::new_v4()).await;
+
+	assert_eq!(registry.count().await.unwrap(), 1);
+	let members: usize = con.zcard(IN_FLIGHT_PAYMENTS_KEY).await.unwrap();
+	assert_eq!(members, 1);
+}
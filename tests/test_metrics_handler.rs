@@ -0,0 +1,18 @@
+use actix_web::{App, test};
+use rinha_de_backend::adapters::web::handlers::prometheus_metrics;
+use rinha_de_backend::infrastructure::observability::metrics::metrics;
+
+#[actix_web::test]
+async fn test_prometheus_metrics_exposes_every_sample() {
+	let app = test::init_service(App::new().service(prometheus_metrics)).await;
+
+	metrics().record_received();
+
+	let req = test::TestRequest::get().uri("/metrics").to_request();
+	let body = test::call_and_read_body(&app, req).await;
+	let body = std::str::from_utf8(&body).unwrap();
+
+	assert!(body.contains("# TYPE rinha_payments_received counter\n"));
+	assert!(body.contains("rinha_payments_processed{processor=\"default\"} "));
+	assert!(body.contains("# TYPE rinha_payments_queue_depth gauge\n"));
+}
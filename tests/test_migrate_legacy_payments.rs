@@ -0,0 +1,274 @@
+use redis::AsyncCommands;
+use rinha_de_backend::domain::payment::Payment;
+use rinha_de_backend::domain::repository::PaymentRepository;
+use rinha_de_backend::infrastructure::config::redis::{
+	LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY, LEGACY_FALLBACK_PAYMENT_SUMMARY_KEY,
+	LEGACY_PROCESSED_CORRELATION_IDS_KEY, LEGACY_TOTAL_AMOUNT_FIELD,
+	LEGACY_TOTAL_REQUESTS_FIELD,
+};
+use rinha_de_backend::infrastructure::persistence::redis_legacy_payment_store::RedisLegacyPaymentStore;
+use rinha_de_backend::infrastructure::persistence::redis_payment_repository::RedisPaymentRepository;
+use rinha_de_backend::use_cases::migrate_legacy_payments::{
+	MigrateLegacyPaymentsUseCase, MigrationReport,
+};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+mod support;
+
+use crate::support::redis_container::get_test_redis_client;
+
+fn legacy_payment(amount: f64) -> Payment {
+	Payment {
+		correlation_id: Uuid::new_v4(),
+		amount,
+		requested_at: Some(OffsetDateTime::now_utc()),
+		processed_at: None,
+		processed_by: None,
+		tag: None,
+		epoch: None,
+	}
+}
+
+async fn seed_legacy_payment(
+	con: &mut redis::aio::MultiplexedConnection,
+	key: &str,
+	payment: &Payment,
+) {
+	let _: () = con
+		.hset(
+			key,
+			payment.correlation_id.to_string(),
+			serde_json::to_string(payment).unwrap(),
+		)
+		.await
+		.unwrap();
+	let _: () = con
+		.sadd(
+			LEGACY_PROCESSED_CORRELATION_IDS_KEY,
+			payment.correlation_id.to_string(),
+		)
+		.await
+		.unwrap();
+}
+
+#[tokio::test]
+async fn test_migrate_legacy_payments_converts_to_new_layout() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let mut con = redis_client
+		.get_multiplexed_async_connection()
+		.await
+		.unwrap();
+
+	let default_payment = legacy_payment(100.5);
+	let fallback_payment = legacy_payment(42.25);
+	seed_legacy_payment(
+		&mut con,
+		LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY,
+		&default_payment,
+	)
+	.await;
+	seed_legacy_payment(
+		&mut con,
+		LEGACY_FALLBACK_PAYMENT_SUMMARY_KEY,
+		&fallback_payment,
+	)
+	.await;
+
+	let payment_repo = RedisPaymentRepository::new(redis_client.clone());
+	let migrate_use_case = MigrateLegacyPaymentsUseCase::new(
+		RedisLegacyPaymentStore::new(redis_client.clone()),
+		payment_repo.clone(),
+	);
+
+	let report = migrate_use_case.execute().await.unwrap();
+
+	assert_eq!(report, MigrationReport {
+		migrated:   2,
+		skipped:    0,
+		unreadable: 0,
+		undated:    0,
+	});
+
+	let migrated_default = payment_repo
+		.get_payment_summary("default", &default_payment.correlation_id.to_string())
+		.await
+		.unwrap();
+	assert_eq!(migrated_default.amount, 100.5);
+	assert_eq!(migrated_default.processed_by.unwrap(), "default");
+
+	let migrated_fallback = payment_repo
+		.get_payment_summary(
+			"fallback",
+			&fallback_payment.correlation_id.to_string(),
+		)
+		.await
+		.unwrap();
+	assert_eq!(migrated_fallback.amount, 42.25);
+	assert_eq!(migrated_fallback.processed_by.unwrap(), "fallback");
+
+	let legacy_keys: usize = con
+		.exists(&[
+			LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY,
+			LEGACY_FALLBACK_PAYMENT_SUMMARY_KEY,
+			LEGACY_PROCESSED_CORRELATION_IDS_KEY,
+		])
+		.await
+		.unwrap();
+	assert_eq!(legacy_keys, 0);
+}
+
+#[tokio::test]
+async fn test_migrate_legacy_payments_skips_already_migrated_payments() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let mut con = redis_client
+		.get_multiplexed_async_connection()
+		.await
+		.unwrap();
+
+	let payment = legacy_payment(10.0);
+	seed_legacy_payment(&mut con, LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY, &payment)
+		.await;
+
+	let payment_repo = RedisPaymentRepository::new(redis_client.clone());
+	payment_repo
+		.save(Payment {
+			processed_by: Some("default".to_string()),
+			tag: None,
+			epoch: None,
+			..payment.clone()
+		})
+		.await
+		.unwrap();
+
+	let migrate_use_case = MigrateLegacyPaymentsUseCase::new(
+		RedisLegacyPaymentStore::new(redis_client.clone()),
+		payment_repo.clone(),
+	);
+
+	let report = migrate_use_case.execute().await.unwrap();
+
+	assert_eq!(report, MigrationReport {
+		migrated:   0,
+		skipped:    1,
+		unreadable: 0,
+		undated:    0,
+	});
+}
+
+#[tokio::test]
+async fn test_migrate_legacy_payments_keeps_legacy_summary_totals() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let mut con = redis_client
+		.get_multiplexed_async_connection()
+		.await
+		.unwrap();
+
+	let payment = legacy_payment(100.5);
+	seed_legacy_payment(&mut con, LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY, &payment)
+		.await;
+
+	let payment_repo =
+		RedisPaymentRepository::new(redis_client.clone()).with_legacy_summary();
+	let migrate_use_case = MigrateLegacyPaymentsUseCase::new(
+		RedisLegacyPaymentStore::new(redis_client.clone()).with_legacy_summary(),
+		payment_repo.clone(),
+	);
+
+	let report = migrate_use_case.execute().await.unwrap();
+
+	assert_eq!(report, MigrationReport {
+		migrated:   1,
+		skipped:    0,
+		unreadable: 0,
+		undated:    0,
+	});
+
+	let fields: Vec<String> =
+		con.hkeys(LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY).await.unwrap();
+	assert!(!fields.contains(&payment.correlation_id.to_string()));
+
+	let total_requests: i64 = con
+		.hget(
+			LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY,
+			LEGACY_TOTAL_REQUESTS_FIELD,
+		)
+		.await
+		.unwrap();
+	let total_amount: f64 = con
+		.hget(
+			LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY,
+			LEGACY_TOTAL_AMOUNT_FIELD,
+		)
+		.await
+		.unwrap();
+	assert_eq!((total_requests, total_amount), (1, 100.5));
+
+	let processed_ids: bool = con
+		.exists(LEGACY_PROCESSED_CORRELATION_IDS_KEY)
+		.await
+		.unwrap();
+	assert!(!processed_ids);
+}
+
+#[tokio::test]
+async fn test_migrate_legacy_payments_keeps_layout_with_payments_left_behind() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let mut con = redis_client
+		.get_multiplexed_async_connection()
+		.await
+		.unwrap();
+
+	let payment = legacy_payment(10.0);
+	let undated_payment = Payment {
+		requested_at: None,
+		..legacy_payment(20.0)
+	};
+	seed_legacy_payment(&mut con, LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY, &payment)
+		.await;
+	seed_legacy_payment(
+		&mut con,
+		LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY,
+		&undated_payment,
+	)
+	.await;
+	let _: () = con
+		.hset(LEGACY_FALLBACK_PAYMENT_SUMMARY_KEY, "broken", "not json")
+		.await
+		.unwrap();
+
+	let payment_repo = RedisPaymentRepository::new(redis_client.clone());
+	let migrate_use_case = MigrateLegacyPaymentsUseCase::new(
+		RedisLegacyPaymentStore::new(redis_client.clone()),
+		payment_repo.clone(),
+	);
+
+	let report = migrate_use_case.execute().await.unwrap();
+
+	assert_eq!(report, MigrationReport {
+		migrated:   1,
+		skipped:    0,
+		unreadable: 1,
+		undated:    1,
+	});
+	assert!(
+		!payment_repo
+			.is_already_processed(&undated_payment.correlation_id.to_string())
+			.await
+			.unwrap()
+	);
+
+	let legacy_keys: usize = con
+		.exists(&[
+			LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY,
+			LEGACY_FALLBACK_PAYMENT_SUMMARY_KEY,
+			LEGACY_PROCESSED_CORRELATION_IDS_KEY,
+		])
+		.await
+		.unwrap();
+	assert_eq!(legacy_keys, 3);
+}
@@ -0,0 +1,194 @@
+#![cfg(feature = "postgres")]
+
+use std::ops::Sub;
+use std::sync::Arc;
+
+use rinha_de_backend::domain::payment::Payment;
+use rinha_de_backend::domain::payment_archive::PaymentArchive;
+use rinha_de_backend::domain::repository::PaymentRepository;
+use rinha_de_backend::infrastructure::persistence::redis_payment_repository::RedisPaymentRepository;
+use rinha_de_backend::infrastructure::workers::payment_archiver_worker::payment_archiver_worker;
+use rinha_de_backend::use_cases::dto::GetPaymentSummaryQuery;
+use rinha_de_backend::use_cases::get_payment_summary::GetPaymentSummaryUseCase;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+mod support;
+
+use crate::support::postgresql_container::{
+	connect_payment_archive, setup_archive_postgresql_container,
+};
+use crate::support::redis_container::get_test_redis_client;
+
+fn processed_payment(
+	group: &str,
+	amount: f64,
+	requested_at: OffsetDateTime,
+) -> Payment {
+	Payment {
+		correlation_id: Uuid::new_v4(),
+		amount,
+		requested_at: Some(requested_at),
+		processed_at: Some(requested_at),
+		processed_by: Some(group.to_string()),
+		tag: None,
+		epoch: None,
+	}
+}
+
+fn summary_query() -> GetPaymentSummaryQuery {
+	GetPaymentSummaryQuery {
+		from:           None,
+		to:             None,
+		failures:       false,
+		amount_buckets: false,
+		latency:        false,
+		epoch:          None,
+	}
+}
+
+#[tokio::test]
+async fn test_postgres_payment_archive_summarizes_archived_payments() {
+	let postgres_container = setup_archive_postgresql_container().await;
+	let archive = connect_payment_archive(&postgres_container.database_url).await;
+	let now = OffsetDateTime::now_utc();
+
+	let default_payment = processed_payment("default", 10.5, now);
+	let excluded_payment = processed_payment("default", 20.0, now);
+	let fallback_payment = processed_payment("fallback", 30.0, now);
+	let payments = [
+		default_payment.clone(),
+		excluded_payment.clone(),
+		fallback_payment,
+	];
+
+	archive.archive(&payments).await.unwrap();
+	// Archiving the same payments again does not count them twice.
+	archive.archive(&payments).await.unwrap();
+
+	let from = now.sub(Duration::minutes(1));
+	let to = now + Duration::minutes(1);
+	assert_eq!(
+		archive
+			.get_summary_by_group("default", from, to, &[])
+			.await
+			.unwrap(),
+		(2, 30.5)
+	);
+	assert_eq!(
+		archive
+			.get_summary_by_group("default", from, to, &[
+				excluded_payment.correlation_id
+			])
+			.await
+			.unwrap(),
+		(1, 10.5)
+	);
+	assert_eq!(
+		archive
+			.get_summary_by_group("fallback", from, to, &[])
+			.await
+			.unwrap(),
+		(1, 30.0)
+	);
+
+	archive.clear().await.unwrap();
+	assert_eq!(
+		archive
+			.get_summary_by_group("default", from, to, &[])
+			.await
+			.unwrap(),
+		(0, 0.0)
+	);
+}
+
+#[tokio::test]
+async fn test_payment_archiver_worker_moves_aged_payments_to_the_archive() {
+	let redis_container = get_test_redis_client().await;
+	let postgres_container = setup_archive_postgresql_container().await;
+	let archive = connect_payment_archive(&postgres_container.database_url).await;
+	let payment_repo = RedisPaymentRepository::new(redis_container.client.clone());
+	let now = OffsetDateTime::now_utc();
+
+	let aged_payment =
+		processed_payment("default", 10.0, now.sub(Duration::hours(2)));
+	let recent_payment = processed_payment("default", 20.0, now);
+	payment_repo.save(aged_payment.clone()).await.unwrap();
+	payment_repo.save(recent_payment.clone()).await.unwrap();
+
+	let worker_handle = tokio::spawn(payment_archiver_worker(
+		payment_repo.clone(),
+		Arc::new(archive.clone()),
+		std::time::Duration::from_secs(60 * 60),
+		std::time::Duration::from_millis(100),
+	));
+	tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+	worker_handle.abort();
+
+	let from = now.sub(Duration::hours(3));
+	let to = now + Duration::minutes(1);
+	assert_eq!(
+		payment_repo
+			.get_summary_by_group("default", from, to)
+			.await
+			.unwrap(),
+		(1, 20.0)
+	);
+	assert_eq!(
+		archive
+			.get_summary_by_group("default", from, to, &[])
+			.await
+			.unwrap(),
+		(1, 10.0)
+	);
+	// Archived payments are still known as processed, so a redelivery is
+	// not charged again.
+	assert!(
+		payment_repo
+			.is_already_processed(&aged_payment.correlation_id.to_string())
+			.await
+			.unwrap()
+	);
+}
+
+#[tokio::test]
+async fn test_summary_counts_payments_being_archived_once() {
+	let redis_container = get_test_redis_client().await;
+	let postgres_container = setup_archive_postgresql_container().await;
+	let archive: Arc<dyn PaymentArchive> =
+		Arc::new(connect_payment_archive(&postgres_container.database_url).await);
+	let payment_repo = RedisPaymentRepository::new(redis_container.client.clone());
+	let now = OffsetDateTime::now_utc();
+
+	let archived_payment =
+		processed_payment("default", 10.0, now.sub(Duration::hours(3)));
+	let aged_payment =
+		processed_payment("default", 20.0, now.sub(Duration::hours(2)));
+	let recent_payment = processed_payment("default", 30.0, now);
+	archive
+		.archive(std::slice::from_ref(&archived_payment))
+		.await
+		.unwrap();
+	payment_repo.save(aged_payment.clone()).await.unwrap();
+	payment_repo.save(recent_payment).await.unwrap();
+
+	let use_case = GetPaymentSummaryUseCase::new(payment_repo.clone())
+		.with_archive(archive.clone(), Duration::hours(1));
+
+	// The aged payment is archived but not yet removed from Redis.
+	archive
+		.archive(std::slice::from_ref(&aged_payment))
+		.await
+		.unwrap();
+	let summary = use_case.execute(summary_query()).await.unwrap();
+	assert_eq!(summary.default.total_requests, 3);
+	assert_eq!(summary.default.total_amount, 60.0);
+
+	payment_repo
+		.remove_archived(std::slice::from_ref(&aged_payment))
+		.await
+		.unwrap();
+	let summary = use_case.execute(summary_query()).await.unwrap();
+	assert_eq!(summary.default.total_requests, 3);
+	assert_eq!(summary.default.total_amount, 60.0);
+}
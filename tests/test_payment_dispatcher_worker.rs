@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use rinha_de_backend::domain::health_status::HealthStatus;
+use rinha_de_backend::domain::payment::Payment;
+use rinha_de_backend::domain::payment_processor::PaymentProcessor;
+use rinha_de_backend::domain::queue::{Message, Queue};
+use rinha_de_backend::infrastructure::queue::redis_payment_queue::PaymentQueue;
+use rinha_de_backend::infrastructure::routing::in_memory_payment_router::InMemoryPaymentRouter;
+use rinha_de_backend::infrastructure::workers::payment_dispatcher_worker::payment_dispatcher_worker;
+use tokio::time::Duration;
+use uuid::Uuid;
+
+mod support;
+
+use crate::support::redis_container::get_test_redis_client;
+
+fn router_with(
+	default: HealthStatus,
+	fallback: HealthStatus,
+) -> InMemoryPaymentRouter {
+	let router = InMemoryPaymentRouter::new();
+
+	for (name, health) in [("default", default), ("fallback", fallback)] {
+		router.update_processor_health(PaymentProcessor::new(
+			name,
+			&format!("http://{name}"),
+			health,
+			0,
+		));
+	}
+
+	router
+}
+
+fn message() -> Message<Payment> {
+	Message::with(Uuid::new_v4(), Payment {
+		correlation_id: Uuid::new_v4(),
+		amount:         42.0,
+		requested_at:   None,
+		processed_at:   None,
+		processed_by:   None,
+		tag:            None,
+		epoch:          None,
+	})
+}
+
+#[tokio::test]
+async fn test_payment_dispatcher_worker_routes_to_processor_queue() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let ingest_queue = PaymentQueue::new(redis_client.clone());
+	let default_queue = PaymentQueue::for_processor(redis_client.clone(), "default");
+	let fallback_queue =
+		PaymentQueue::for_processor(redis_client.clone(), "fallback");
+	let processor_queues = HashMap::from([
+		("default".to_string(), default_queue.clone()),
+		("fallback".to_string(), fallback_queue.clone()),
+	]);
+	let router = router_with(HealthStatus::Failing, HealthStatus::Healthy);
+
+	let message = message();
+	ingest_queue.push(message.clone()).await.unwrap();
+
+	let worker_handle = tokio::spawn(payment_dispatcher_worker(
+		ingest_queue.clone(),
+		processor_queues,
+		router,
+	));
+
+	tokio::time::sleep(Duration::from_secs(2)).await;
+	worker_handle.abort();
+
+	assert_eq!(ingest_queue.depth().await.unwrap(), 0);
+	assert_eq!(default_queue.depth().await.unwrap(), 0);
+
+	let dispatched = fallback_queue.pop().await.unwrap().unwrap();
+	assert_eq!(dispatched.id, message.id);
+}
+
+#[tokio::test]
+async fn test_payment_dispatcher_worker_keeps_payment_when_no_processor() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let ingest_queue = PaymentQueue::new(redis_client.clone());
+	let default_queue = PaymentQueue::for_processor(redis_client.clone(), "default");
+	let fallback_queue =
+		PaymentQueue::for_processor(redis_client.clone(), "fallback");
+	let processor_queues = HashMap::from([
+		("default".to_string(), default_queue.clone()),
+		("fallback".to_string(), fallback_queue.clone()),
+	]);
+	let router = router_with(HealthStatus::Failing, HealthStatus::Failing);
+
+	ingest_queue.push(message()).await.unwrap();
+
+	let worker_handle = tokio::spawn(payment_dispatcher_worker(
+		ingest_queue.clone(),
+		processor_queues,
+		router,
+	));
+
+	tokio::time::sleep(Duration::from_secs(2)).await;
+	worker_handle.abort();
+
+	assert_eq!(default_queue.depth().await.unwrap(), 0);
+	assert_eq!(fallback_queue.depth().await.unwrap(), 0);
+	assert_eq!(ingest_queue.peek(0, 10).await.unwrap().len(), 1);
+}
@@ -0,0 +1,259 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use redis::AsyncCommands;
+use rinha_de_backend::domain::payment::Payment;
+use rinha_de_backend::domain::queue::{Message, Queue, QueueError};
+use rinha_de_backend::infrastructure::config::redis::PAYMENTS_INGEST_STREAM_KEY;
+use rinha_de_backend::infrastructure::queue::redis_payment_ingest_stream::PaymentIngestStream;
+use rinha_de_backend::infrastructure::queue::redis_payment_queue::PaymentQueue;
+use rinha_de_backend::infrastructure::workers::payment_ingest_worker::payment_ingest_worker;
+use rinha_de_backend::use_cases::create_payment::CreatePaymentUseCase;
+use time::OffsetDateTime;
+use tokio::time::Duration;
+use uuid::Uuid;
+
+mod support;
+
+use crate::support::redis_container::get_test_redis_client;
+
+/// Fails the first push, as an unreachable queue would, and forwards the
+/// rest to `queue`.
+#[derive(Clone)]
+struct FailingOnceQueue {
+	queue:  PaymentQueue,
+	failed: Arc<AtomicBool>,
+}
+
+impl Queue<Payment> for FailingOnceQueue {
+	async fn pop(
+		&self,
+	) -> Result<Option<Message<Payment>>, Box<dyn std::error::Error + Send>> {
+		self.queue.pop().await
+	}
+
+	async fn peek(
+		&self,
+		offset: usize,
+		limit: usize,
+	) -> Result<Vec<Message<Payment>>, Box<dyn std::error::Error + Send>> {
+		self.queue.peek(offset, limit).await
+	}
+
+	async fn depth(&self) -> Result<usize, Box<dyn std::error::Error + Send>> {
+		self.queue.depth().await
+	}
+
+	async fn oldest_enqueued_at(
+		&self,
+	) -> Result<Option<OffsetDateTime>, Box<dyn std::error::Error + Send>> {
+		self.queue.oldest_enqueued_at().await
+	}
+
+	async fn push(
+		&self,
+		message: Message<Payment>,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		if !self.failed.swap(true, Ordering::Relaxed) {
+			return Err(Box::new(QueueError::Unavailable("queue down".to_string())));
+		}
+		self.queue.push(message).await
+	}
+}
+
+/// Always fails to push `broken`, and fails the first push of any other
+/// payment, forwarding the rest to `queue`.
+#[derive(Clone)]
+struct FlakyQueue {
+	queue:  PaymentQueue,
+	broken: Uuid,
+	failed: Arc<Mutex<HashSet<Uuid>>>,
+}
+
+impl Queue<Payment> for FlakyQueue {
+	async fn pop(
+		&self,
+	) -> Result<Option<Message<Payment>>, Box<dyn std::error::Error + Send>> {
+		self.queue.pop().await
+	}
+
+	async fn peek(
+		&self,
+		offset: usize,
+		limit: usize,
+	) -> Result<Vec<Message<Payment>>, Box<dyn std::error::Error + Send>> {
+		self.queue.peek(offset, limit).await
+	}
+
+	async fn depth(&self) -> Result<usize, Box<dyn std::error::Error + Send>> {
+		self.queue.depth().await
+	}
+
+	async fn oldest_enqueued_at(
+		&self,
+	) -> Result<Option<OffsetDateTime>, Box<dyn std::error::Error + Send>> {
+		self.queue.oldest_enqueued_at().await
+	}
+
+	async fn push(
+		&self,
+		message: Message<Payment>,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		let correlation_id = message.body.correlation_id;
+		if correlation_id == self.broken ||
+			self.failed.lock().unwrap().insert(correlation_id)
+		{
+			return Err(Box::new(QueueError::Unavailable("queue down".to_string())));
+		}
+		self.queue.push(message).await
+	}
+}
+
+#[tokio::test]
+async fn test_payment_ingest_worker_queues_published_payments() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let payment_queue = PaymentQueue::new(redis_client.clone());
+	let correlation_id = Uuid::new_v4();
+
+	let mut con = redis_client
+		.get_multiplexed_async_connection()
+		.await
+		.unwrap();
+	let _: String = con
+		.xadd(PAYMENTS_INGEST_STREAM_KEY, "*", &[
+			("correlationId", correlation_id.to_string()),
+			("amount", "19.90".to_string()),
+		])
+		.await
+		.unwrap();
+	let _: String = con
+		.xadd(PAYMENTS_INGEST_STREAM_KEY, "*", &[("amount", "bogus")])
+		.await
+		.unwrap();
+
+	let worker = tokio::spawn(payment_ingest_worker(
+		PaymentIngestStream::new(redis_client.clone(), "test-consumer")
+			.with_block_timeout(Duration::from_millis(100)),
+		CreatePaymentUseCase::new(payment_queue.clone()),
+	));
+
+	let message = tokio::time::timeout(Duration::from_secs(5), async {
+		loop {
+			if let Some(message) = payment_queue.pop().await.unwrap() {
+				return message;
+			}
+		}
+	})
+	.await
+	.expect("Ingested payment was not queued");
+	tokio::time::sleep(Duration::from_millis(500)).await;
+	worker.abort();
+
+	assert_eq!(message.body.correlation_id, correlation_id);
+	assert_eq!(message.body.amount, 19.9);
+
+	let remaining: usize = con.xlen(PAYMENTS_INGEST_STREAM_KEY).await.unwrap();
+	assert_eq!(remaining, 0);
+}
+
+#[tokio::test]
+async fn test_payment_ingest_worker_retries_entries_it_failed_to_queue() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let payment_queue = PaymentQueue::new(redis_client.clone());
+	let correlation_id = Uuid::new_v4();
+
+	let mut con = redis_client
+		.get_multiplexed_async_connection()
+		.await
+		.unwrap();
+	let _: String = con
+		.xadd(PAYMENTS_INGEST_STREAM_KEY, "*", &[
+			("correlationId", correlation_id.to_string()),
+			("amount", "19.90".to_string()),
+		])
+		.await
+		.unwrap();
+
+	let worker = tokio::spawn(payment_ingest_worker(
+		PaymentIngestStream::new(redis_client.clone(), "test-consumer")
+			.with_block_timeout(Duration::from_millis(100))
+			.with_retry_interval(Duration::from_millis(200)),
+		CreatePaymentUseCase::new(FailingOnceQueue {
+			queue:  payment_queue.clone(),
+			failed: Arc::new(AtomicBool::new(false)),
+		}),
+	));
+
+	let message = tokio::time::timeout(Duration::from_secs(5), async {
+		loop {
+			if let Some(message) = payment_queue.pop().await.unwrap() {
+				return message;
+			}
+		}
+	})
+	.await
+	.expect("Ingested payment was not retried");
+	tokio::time::sleep(Duration::from_millis(500)).await;
+	worker.abort();
+
+	assert_eq!(message.body.correlation_id, correlation_id);
+
+	let remaining: usize = con.xlen(PAYMENTS_INGEST_STREAM_KEY).await.unwrap();
+	assert_eq!(remaining, 0);
+}
+
+#[tokio::test]
+async fn test_payment_ingest_worker_retries_entries_behind_failing_ones() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let payment_queue = PaymentQueue::new(redis_client.clone());
+	let broken = Uuid::new_v4();
+	let correlation_id = Uuid::new_v4();
+
+	let mut con = redis_client
+		.get_multiplexed_async_connection()
+		.await
+		.unwrap();
+	for correlation_id in [broken, correlation_id] {
+		let _: String = con
+			.xadd(PAYMENTS_INGEST_STREAM_KEY, "*", &[
+				("correlationId", correlation_id.to_string()),
+				("amount", "19.90".to_string()),
+			])
+			.await
+			.unwrap();
+	}
+
+	// A batch of one, so the entry failing on every retry fills the first
+	// batch of each sweep.
+	let worker = tokio::spawn(payment_ingest_worker(
+		PaymentIngestStream::new(redis_client.clone(), "test-consumer")
+			.with_batch_size(1)
+			.with_block_timeout(Duration::from_millis(100))
+			.with_retry_interval(Duration::from_millis(200)),
+		CreatePaymentUseCase::new(FlakyQueue {
+			queue: payment_queue.clone(),
+			broken,
+			failed: Arc::default(),
+		}),
+	));
+
+	let message = tokio::time::timeout(Duration::from_secs(5), async {
+		loop {
+			if let Some(message) = payment_queue.pop().await.unwrap() {
+				return message;
+			}
+		}
+	})
+	.await
+	.expect("Ingested payment behind a failing one was not retried");
+	worker.abort();
+
+	assert_eq!(message.body.correlation_id, correlation_id);
+
+	let remaining: usize = con.xlen(PAYMENTS_INGEST_STREAM_KEY).await.unwrap();
+	assert_eq!(remaining, 1);
+}
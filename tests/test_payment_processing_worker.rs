@@ -8,6 +8,8 @@ use rinha_de_backend::infrastructure::persistence::redis_payment_repository::Red
 use rinha_de_backend::infrastructure::queue::redis_payment_queue::PaymentQueue;
 use rinha_de_backend::infrastructure::routing::in_memory_payment_router::InMemoryPaymentRouter;
 use rinha_de_backend::infrastructure::workers::payment_processor_worker::payment_processing_worker;
+use rinha_de_backend::infrastructure::workers::requeue_pacer::RequeuePacer;
+use rinha_de_backend::infrastructure::workers::retry_budget::RetryBudget;
 use rinha_de_backend::use_cases::process_payment::ProcessPaymentUseCase;
 use time::OffsetDateTime;
 use tokio::time::Duration;
@@ -37,20 +39,12 @@ async fn test_payment_processing_worker_default_success() {
 	let router = InMemoryPaymentRouter::new();
 
 	// Set up processor health
-	let default_processor = PaymentProcessor {
-		name:              "default".to_string(),
-		url:               default_url.clone(),
-		health:            HealthStatus::Healthy,
-		min_response_time: 0,
-	};
+	let default_processor =
+		PaymentProcessor::new("default", &default_url, HealthStatus::Healthy, 0);
 	router.update_processor_health(default_processor);
 
-	let fallback_processor = PaymentProcessor {
-		name:              "fallback".to_string(),
-		url:               fallback_url.clone(),
-		health:            HealthStatus::Failing,
-		min_response_time: 0,
-	};
+	let fallback_processor =
+		PaymentProcessor::new("fallback", &fallback_url, HealthStatus::Failing, 0);
 	router.update_processor_health(fallback_processor);
 
 	let payment_to_process = Payment {
@@ -59,13 +53,19 @@ async fn test_payment_processing_worker_default_success() {
 		requested_at:   None,
 		processed_at:   None,
 		processed_by:   None,
+		tag:            None,
+		epoch:          None,
 	};
 
 	// Push payment to queue
 	redis_queue
 		.push(Message {
-			id:   Uuid::new_v4(),
-			body: payment_to_process.clone(),
+			id:          Uuid::new_v4(),
+			body:        payment_to_process.clone(),
+			attempts:    0,
+			request_id:  None,
+			sequence:    None,
+			enqueued_at: None,
 		})
 		.await
 		.unwrap();
@@ -75,6 +75,8 @@ async fn test_payment_processing_worker_default_success() {
 		payment_repo.clone(),
 		process_payment_use_case.clone(),
 		router.clone(),
+		RetryBudget::unlimited(),
+		RequeuePacer::disabled(),
 	));
 
 	// Give the worker some time to process the payment
@@ -116,20 +118,12 @@ async fn test_payment_processing_worker_fallback_success() {
 	let router = InMemoryPaymentRouter::new();
 
 	// Set up processor health
-	let default_processor = PaymentProcessor {
-		name:              "default".to_string(),
-		url:               default_url.clone(),
-		health:            HealthStatus::Failing,
-		min_response_time: 10000,
-	};
+	let default_processor =
+		PaymentProcessor::new("default", &default_url, HealthStatus::Failing, 10000);
 	router.update_processor_health(default_processor);
 
-	let fallback_processor = PaymentProcessor {
-		name:              "fallback".to_string(),
-		url:               fallback_url.clone(),
-		health:            HealthStatus::Healthy,
-		min_response_time: 10,
-	};
+	let fallback_processor =
+		PaymentProcessor::new("fallback", &fallback_url, HealthStatus::Healthy, 10);
 	router.update_processor_health(fallback_processor);
 
 	let payment_to_process = Payment {
@@ -138,12 +132,18 @@ async fn test_payment_processing_worker_fallback_success() {
 		requested_at:   None,
 		processed_at:   None,
 		processed_by:   None,
+		tag:            None,
+		epoch:          None,
 	};
 
 	payment_queue
 		.push(Message {
-			id:   Uuid::new_v4(),
-			body: payment_to_process.clone(),
+			id:          Uuid::new_v4(),
+			body:        payment_to_process.clone(),
+			attempts:    0,
+			request_id:  None,
+			sequence:    None,
+			enqueued_at: None,
 		})
 		.await
 		.unwrap();
@@ -153,6 +153,8 @@ async fn test_payment_processing_worker_fallback_success() {
 		payment_repo.clone(),
 		process_payment_use_case.clone(),
 		router.clone(),
+		RetryBudget::unlimited(),
+		RequeuePacer::disabled(),
 	));
 
 	// Give the worker some time to process the payment
@@ -189,20 +191,20 @@ async fn test_payment_processing_worker_requeue_message_given_processor_are_down
 	let router = InMemoryPaymentRouter::new();
 
 	// Set up processors to be failing
-	let default_processor = PaymentProcessor {
-		name:              "default".to_string(),
-		url:               "http://non-existent-url:8080".to_string(),
-		health:            HealthStatus::Failing,
-		min_response_time: 0,
-	};
+	let default_processor = PaymentProcessor::new(
+		"default",
+		"http://non-existent-url:8080",
+		HealthStatus::Failing,
+		0,
+	);
 	router.update_processor_health(default_processor);
 
-	let fallback_processor = PaymentProcessor {
-		name:              "fallback".to_string(),
-		url:               "http://non-existent-url:8080".to_string(),
-		health:            HealthStatus::Failing,
-		min_response_time: 0,
-	};
+	let fallback_processor = PaymentProcessor::new(
+		"fallback",
+		"http://non-existent-url:8080",
+		HealthStatus::Failing,
+		0,
+	);
 	router.update_processor_health(fallback_processor);
 
 	let payment_to_process = Payment {
@@ -211,6 +213,8 @@ async fn test_payment_processing_worker_requeue_message_given_processor_are_down
 		requested_at:   None,
 		processed_at:   None,
 		processed_by:   None,
+		tag:            None,
+		epoch:          None,
 	};
 
 	// Push payment to queue
@@ -227,6 +231,8 @@ async fn test_payment_processing_worker_requeue_message_given_processor_are_down
 		payment_repo.clone(),
 		process_payment_use_case.clone(),
 		router.clone(),
+		RetryBudget::unlimited(),
+		RequeuePacer::disabled(),
 	));
 
 	// Give the worker some time to attempt processing and re-queue
@@ -266,20 +272,12 @@ async fn test_payment_processing_worker_skip_processed_message() {
 	let router = InMemoryPaymentRouter::new();
 
 	// Set up processor health
-	let default_processor = PaymentProcessor {
-		name:              "default".to_string(),
-		url:               default_url.clone(),
-		health:            HealthStatus::Healthy,
-		min_response_time: 0,
-	};
+	let default_processor =
+		PaymentProcessor::new("default", &default_url, HealthStatus::Healthy, 0);
 	router.update_processor_health(default_processor);
 
-	let fallback_processor = PaymentProcessor {
-		name:              "fallback".to_string(),
-		url:               fallback_url.clone(),
-		health:            HealthStatus::Failing,
-		min_response_time: 0,
-	};
+	let fallback_processor =
+		PaymentProcessor::new("fallback", &fallback_url, HealthStatus::Failing, 0);
 	router.update_processor_health(fallback_processor);
 
 	let payment_to_process = Payment {
@@ -288,6 +286,8 @@ async fn test_payment_processing_worker_skip_processed_message() {
 		requested_at:   None,
 		processed_at:   None,
 		processed_by:   None,
+		tag:            None,
+		epoch:          None,
 	};
 
 	// Pre-process the payment to simulate it being already processed
@@ -297,6 +297,8 @@ async fn test_payment_processing_worker_skip_processed_message() {
 		requested_at:   Some(OffsetDateTime::now_utc()),
 		processed_at:   Some(OffsetDateTime::now_utc()),
 		processed_by:   Some("default".to_string()),
+		tag:            None,
+		epoch:          None,
 	};
 	payment_repo.save(pre_processed_payment).await.unwrap();
 
@@ -314,6 +316,8 @@ async fn test_payment_processing_worker_skip_processed_message() {
 		payment_repo.clone(),
 		process_payment_use_case.clone(),
 		router.clone(),
+		RetryBudget::unlimited(),
+		RequeuePacer::disabled(),
 	));
 
 	// Give the worker some time to process
@@ -357,6 +361,8 @@ async fn test_payment_processing_worker_redis_failure() {
 		payment_repo,
 		process_payment_use_case,
 		router,
+		RetryBudget::unlimited(),
+		RequeuePacer::disabled(),
 	));
 
 	// Give the worker some time to run
@@ -389,20 +395,12 @@ async fn test_payment_processing_worker_circuit_breaker_open() {
 	let router = InMemoryPaymentRouter::new();
 
 	// Set up processors
-	let default_processor = PaymentProcessor {
-		name:              "default".to_string(),
-		url:               default_url.clone(),
-		health:            HealthStatus::Healthy,
-		min_response_time: 0,
-	};
+	let default_processor =
+		PaymentProcessor::new("default", &default_url, HealthStatus::Healthy, 0);
 	router.update_processor_health(default_processor);
 
-	let fallback_processor = PaymentProcessor {
-		name:              "fallback".to_string(),
-		url:               fallback_url.clone(),
-		health:            HealthStatus::Healthy,
-		min_response_time: 0,
-	};
+	let fallback_processor =
+		PaymentProcessor::new("fallback", &fallback_url, HealthStatus::Healthy, 0);
 	router.update_processor_health(fallback_processor);
 
 	// Force the circuit breaker to open
@@ -415,13 +413,19 @@ async fn test_payment_processing_worker_circuit_breaker_open() {
 		requested_at:   None,
 		processed_at:   None,
 		processed_by:   None,
+		tag:            None,
+		epoch:          None,
 	};
 
 	// Push payment to queue
 	redis_queue
 		.push(Message {
-			id:   Uuid::new_v4(),
-			body: payment_to_process.clone(),
+			id:          Uuid::new_v4(),
+			body:        payment_to_process.clone(),
+			attempts:    0,
+			request_id:  None,
+			sequence:    None,
+			enqueued_at: None,
 		})
 		.await
 		.unwrap();
@@ -431,6 +435,8 @@ async fn test_payment_processing_worker_circuit_breaker_open() {
 		payment_repo.clone(),
 		process_payment_use_case.clone(),
 		router.clone(),
+		RetryBudget::unlimited(),
+		RequeuePacer::disabled(),
 	));
 
 	// Give the worker some time to attempt processing
@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use actix_web::{App, test, web};
+use rinha_de_backend::adapters::web::handlers::payments_duplicates;
+use rinha_de_backend::adapters::web::state::AppState;
+use rinha_de_backend::domain::repository::{DuplicateStage, PaymentRepository};
+use rinha_de_backend::infrastructure::persistence::redis_payment_repository::RedisPaymentRepository;
+use rinha_de_backend::infrastructure::queue::redis_payment_queue::PaymentQueue;
+use rinha_de_backend::use_cases::dto::DuplicatesReport;
+use uuid::Uuid;
+
+mod support;
+
+use crate::support::redis_container::get_test_redis_client;
+
+#[actix_web::test]
+async fn test_payments_duplicates_reports_duplicates_per_stage() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let payment_repo = RedisPaymentRepository::new(redis_client.clone());
+
+	for stage in [
+		DuplicateStage::Ingest,
+		DuplicateStage::Worker,
+		DuplicateStage::Worker,
+	] {
+		payment_repo
+			.record_duplicate(&Uuid::new_v4().to_string(), stage)
+			.await
+			.unwrap();
+	}
+
+	let state = AppState::new(
+		Arc::new(PaymentQueue::new(redis_client.clone())),
+		Arc::new(payment_repo),
+	);
+	let app = test::init_service(
+		App::new()
+			.app_data(web::Data::new(state))
+			.service(payments_duplicates),
+	)
+	.await;
+
+	let req = test::TestRequest::get()
+		.uri("/payments-duplicates?from=-5m")
+		.to_request();
+	let report: DuplicatesReport = test::call_and_read_body_json(&app, req).await;
+
+	assert_eq!(report, DuplicatesReport {
+		ingest: 1,
+		worker: 2,
+		total:  3,
+	});
+
+	let req = test::TestRequest::get()
+		.uri("/payments-duplicates?to=-5m")
+		.to_request();
+	let report: DuplicatesReport = test::call_and_read_body_json(&app, req).await;
+
+	assert_eq!(report.total, 0);
+}
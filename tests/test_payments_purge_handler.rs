@@ -1,8 +1,11 @@
+use std::sync::Arc;
+
 use actix_web::{App, test, web};
 use rinha_de_backend::adapters::web::handlers::payments_purge;
+use rinha_de_backend::adapters::web::state::AppState;
 use rinha_de_backend::domain::repository::PaymentRepository;
 use rinha_de_backend::infrastructure::persistence::redis_payment_repository::RedisPaymentRepository;
-use rinha_de_backend::use_cases::purge_payments::PurgePaymentsUseCase;
+use rinha_de_backend::infrastructure::queue::redis_payment_queue::PaymentQueue;
 use time::OffsetDateTime;
 use uuid::Uuid;
 
@@ -17,12 +20,14 @@ async fn test_payments_purge_returns_success() {
 	let redis_container = get_test_redis_client().await;
 	let redis_client = redis_container.client.clone();
 	let payment_repository = RedisPaymentRepository::new(redis_client.clone());
-	let purge_payments_use_case =
-		PurgePaymentsUseCase::new(payment_repository.clone());
+	let state = AppState::new(
+		Arc::new(PaymentQueue::new(redis_client.clone())),
+		Arc::new(payment_repository.clone()),
+	);
 
 	let app = test::init_service(
 		App::new()
-			.app_data(web::Data::new(purge_payments_use_case.clone()))
+			.app_data(web::Data::new(state.clone()))
 			.service(payments_purge),
 	)
 	.await;
@@ -34,6 +39,8 @@ async fn test_payments_purge_returns_success() {
 		requested_at:   Some(OffsetDateTime::now_utc()),
 		processed_at:   Some(OffsetDateTime::now_utc()),
 		processed_by:   Some("group1".to_string()),
+		tag:            None,
+		epoch:          None,
 	};
 	let payment2 = Payment {
 		correlation_id: Uuid::new_v4(),
@@ -41,6 +48,8 @@ async fn test_payments_purge_returns_success() {
 		requested_at:   Some(OffsetDateTime::now_utc()),
 		processed_at:   Some(OffsetDateTime::now_utc()),
 		processed_by:   Some("group2".to_string()),
+		tag:            None,
+		epoch:          None,
 	};
 	payment_repository.save(payment1.clone()).await.unwrap();
 	payment_repository.save(payment2.clone()).await.unwrap();
@@ -76,3 +85,67 @@ async fn test_payments_purge_returns_success() {
 	assert!(!is_processed1_after_purge);
 	assert!(!is_processed2_after_purge);
 }
+
+#[actix_web::test]
+async fn test_payments_purge_by_tag_keeps_untagged_payments() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let payment_repository = RedisPaymentRepository::new(redis_client.clone());
+	let state = AppState::new(
+		Arc::new(PaymentQueue::new(redis_client.clone())),
+		Arc::new(payment_repository.clone()),
+	);
+
+	let app = test::init_service(
+		App::new()
+			.app_data(web::Data::new(state.clone()))
+			.service(payments_purge),
+	)
+	.await;
+
+	let staging_payment = Payment {
+		correlation_id: Uuid::new_v4(),
+		amount:         100.0,
+		requested_at:   Some(OffsetDateTime::now_utc()),
+		processed_at:   Some(OffsetDateTime::now_utc()),
+		processed_by:   Some("default".to_string()),
+		tag:            Some("staging".to_string()),
+		epoch:          None,
+	};
+	let production_payment = Payment {
+		correlation_id: Uuid::new_v4(),
+		amount:         200.0,
+		requested_at:   Some(OffsetDateTime::now_utc()),
+		processed_at:   Some(OffsetDateTime::now_utc()),
+		processed_by:   Some("default".to_string()),
+		tag:            None,
+		epoch:          None,
+	};
+	payment_repository
+		.save(staging_payment.clone())
+		.await
+		.unwrap();
+	payment_repository
+		.save(production_payment.clone())
+		.await
+		.unwrap();
+
+	let req = test::TestRequest::post()
+		.uri("/purge-payments?tag=staging")
+		.to_request();
+	let resp = test::call_service(&app, req).await;
+
+	assert!(resp.status().is_success());
+	assert!(
+		!payment_repository
+			.is_already_processed(&staging_payment.correlation_id.to_string())
+			.await
+			.unwrap()
+	);
+	assert!(
+		payment_repository
+			.is_already_processed(&production_payment.correlation_id.to_string())
+			.await
+			.unwrap()
+	);
+}
@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use actix_web::{App, test, web};
+use rinha_de_backend::adapters::web::handlers::{
+	MAX_SNAPSHOT_RECORD_LEN, export_snapshot, import_snapshot,
+};
+use rinha_de_backend::adapters::web::state::{AppState, SharedPaymentQueue};
+use rinha_de_backend::domain::payment::Payment;
+use rinha_de_backend::domain::queue::{Message, Queue};
+use rinha_de_backend::domain::repository::{PaymentFailure, PaymentRepository};
+use rinha_de_backend::infrastructure::persistence::redis_payment_repository::RedisPaymentRepository;
+use rinha_de_backend::infrastructure::queue::redis_payment_queue::PaymentQueue;
+use rinha_de_backend::use_cases::dto::{SnapshotImportReport, SnapshotRecord};
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+mod support;
+
+use crate::support::redis_container::get_test_redis_client;
+
+fn payment(processed_by: Option<&str>) -> Payment {
+	Payment {
+		correlation_id: Uuid::new_v4(),
+		amount:         19.9,
+		requested_at:   processed_by.map(|_| OffsetDateTime::now_utc()),
+		processed_at:   processed_by.map(|_| OffsetDateTime::now_utc()),
+		processed_by:   processed_by.map(str::to_string),
+		tag:            None,
+		epoch:          None,
+	}
+}
+
+#[actix_web::test]
+async fn test_export_and_import_snapshot_roundtrip() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let payment_queue = PaymentQueue::new(redis_client.clone());
+	let default_queue = PaymentQueue::for_processor(redis_client.clone(), "default");
+	let payment_repo = RedisPaymentRepository::new(redis_client.clone());
+	let state = AppState::new(
+		Arc::new(payment_queue.clone()),
+		Arc::new(payment_repo.clone()),
+	)
+	.with_processor_queues(HashMap::from([(
+		"default".to_string(),
+		Arc::new(default_queue.clone()) as SharedPaymentQueue,
+	)]));
+
+	let app = test::init_service(
+		App::new()
+			.app_data(web::Data::new(state.clone()))
+			.service(export_snapshot)
+			.service(import_snapshot),
+	)
+	.await;
+
+	let queued_payment = payment(None);
+	let dispatched_payment = payment(None);
+	let default_payment = payment(Some("default"));
+	let fallback_payment = payment(Some("fallback"));
+	let now = OffsetDateTime::now_utc();
+	let trimmed_payment = Payment {
+		requested_at: Some(now - Duration::hours(2)),
+		..payment(Some("default"))
+	};
+
+	payment_queue
+		.push(Message::with(
+			queued_payment.correlation_id,
+			queued_payment.clone(),
+		))
+		.await
+		.unwrap();
+	default_queue
+		.push(Message::with(
+			dispatched_payment.correlation_id,
+			dispatched_payment.clone(),
+		))
+		.await
+		.unwrap();
+	payment_repo.save(default_payment.clone()).await.unwrap();
+	payment_repo.save(fallback_payment.clone()).await.unwrap();
+	payment_repo.save(trimmed_payment).await.unwrap();
+	assert_eq!(
+		payment_repo
+			.trim_older_than(now - Duration::hours(1))
+			.await
+			.unwrap(),
+		1
+	);
+	payment_repo
+		.record_failure(&default_payment, "fallback", PaymentFailure::Failed)
+		.await
+		.unwrap();
+
+	let req = test::TestRequest::get().uri("/admin/snapshot").to_request();
+	let resp = test::call_service(&app, req).await;
+	assert!(resp.status().is_success());
+
+	let archive = test::read_body(resp).await;
+	let records: Vec<SnapshotRecord> = archive
+		.split(|byte| *byte == b'\n')
+		.filter(|line| !line.is_empty())
+		.map(|line| serde_json::from_slice(line).unwrap())
+		.collect();
+	assert_eq!(records.len(), 6);
+
+	payment_repo.clear().await.unwrap();
+	payment_queue.pop().await.unwrap().unwrap();
+	default_queue.pop().await.unwrap().unwrap();
+
+	let req = test::TestRequest::post()
+		.uri("/admin/snapshot")
+		.set_payload(archive)
+		.to_request();
+	let resp = test::call_service(&app, req).await;
+	assert!(resp.status().is_success());
+
+	let report: SnapshotImportReport = test::read_body_json(resp).await;
+	assert_eq!(report, SnapshotImportReport {
+		queued:    2,
+		processed: 2,
+		skipped:   0,
+		buckets:   1,
+		failures:  1,
+	});
+
+	let restored_message = payment_queue.pop().await.unwrap().unwrap();
+	assert_eq!(
+		restored_message.body.correlation_id,
+		queued_payment.correlation_id
+	);
+
+	let restored_dispatched = default_queue.pop().await.unwrap().unwrap();
+	assert_eq!(
+		restored_dispatched.body.correlation_id,
+		dispatched_payment.correlation_id
+	);
+
+	let (requests, amount) = payment_repo
+		.get_summary_by_group(
+			"default",
+			now - Duration::hours(3),
+			now + Duration::minutes(1),
+		)
+		.await
+		.unwrap();
+	assert_eq!(requests, 2);
+	assert!((amount - 2.0 * default_payment.amount).abs() < 1e-9);
+	assert_eq!(
+		payment_repo
+			.get_failures_by_group(
+				"fallback",
+				now - Duration::hours(3),
+				now + Duration::minutes(1),
+			)
+			.await
+			.unwrap(),
+		(1, 0)
+	);
+
+	let restored_default = payment_repo
+		.get_payment_summary("default", &default_payment.correlation_id.to_string())
+		.await
+		.unwrap();
+	assert_eq!(restored_default.amount, default_payment.amount);
+	assert!(restored_default.requested_at.is_some());
+}
+
+#[actix_web::test]
+async fn test_import_snapshot_rejects_invalid_records() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let state = AppState::new(
+		Arc::new(PaymentQueue::new(redis_client.clone())),
+		Arc::new(RedisPaymentRepository::new(redis_client.clone())),
+	);
+
+	let app = test::init_service(
+		App::new()
+			.app_data(web::Data::new(state))
+			.service(import_snapshot),
+	)
+	.await;
+
+	let req = test::TestRequest::post()
+		.uri("/admin/snapshot")
+		.set_payload("this is not a snapshot record\n")
+		.to_request();
+	let resp = test::call_service(&app, req).await;
+
+	assert!(resp.status().is_client_error());
+}
+
+#[actix_web::test]
+async fn test_import_snapshot_rejects_records_over_the_length_limit() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let state = AppState::new(
+		Arc::new(PaymentQueue::new(redis_client.clone())),
+		Arc::new(RedisPaymentRepository::new(redis_client.clone())),
+	);
+
+	let app = test::init_service(
+		App::new()
+			.app_data(web::Data::new(state))
+			.service(import_snapshot),
+	)
+	.await;
+
+	let req = test::TestRequest::post()
+		.uri("/admin/snapshot")
+		.set_payload(vec![b'{'; MAX_SNAPSHOT_RECORD_LEN + 1])
+		.to_request();
+	let resp = test::call_service(&app, req).await;
+
+	assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+}
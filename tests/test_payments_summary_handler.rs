@@ -5,11 +5,15 @@ use std::time::Duration;
 use actix_web::{App, test, web};
 use futures::future::join_all;
 use rinha_de_backend::adapters::web::handlers::payments_summary;
+use rinha_de_backend::adapters::web::state::AppState;
 use rinha_de_backend::domain::payment::Payment;
+use rinha_de_backend::domain::queue::{Message, Queue};
 use rinha_de_backend::domain::repository::PaymentRepository;
 use rinha_de_backend::infrastructure::persistence::redis_payment_repository::RedisPaymentRepository;
-use rinha_de_backend::use_cases::dto::PaymentsSummaryResponse;
-use rinha_de_backend::use_cases::get_payment_summary::GetPaymentSummaryUseCase;
+use rinha_de_backend::infrastructure::queue::redis_payment_queue::PaymentQueue;
+use rinha_de_backend::use_cases::dto::{
+	AmountBucketCount, PaymentsSummaryResponse, PendingPayments, ProcessingLatency,
+};
 use time::OffsetDateTime;
 use tokio::time::timeout;
 use uuid::Uuid;
@@ -23,12 +27,14 @@ async fn test_payments_summary_get_empty() {
 	let redis_container = get_test_redis_client().await;
 	let redis_client = redis_container.client.clone();
 	let redis_repo = RedisPaymentRepository::new(redis_client.clone());
-	let get_payment_summary_use_case =
-		GetPaymentSummaryUseCase::new(redis_repo.clone());
+	let state = AppState::new(
+		Arc::new(PaymentQueue::new(redis_client.clone())),
+		Arc::new(redis_repo.clone()),
+	);
 
 	let app = test::init_service(
 		App::new()
-			.app_data(web::Data::new(get_payment_summary_use_case.clone()))
+			.app_data(web::Data::new(state.clone()))
 			.service(payments_summary),
 	)
 	.await;
@@ -63,6 +69,8 @@ async fn test_get_payments_summary_without_filter_returns_all_data() {
 			requested_at:   Some(now),
 			processed_at:   Some(now),
 			processed_by:   Some("default".to_string()),
+			tag:            None,
+			epoch:          None,
 		})
 		.await
 		.unwrap();
@@ -74,6 +82,8 @@ async fn test_get_payments_summary_without_filter_returns_all_data() {
 			requested_at:   Some(now),
 			processed_at:   Some(now),
 			processed_by:   Some("default".to_string()),
+			tag:            None,
+			epoch:          None,
 		})
 		.await
 		.unwrap();
@@ -85,17 +95,21 @@ async fn test_get_payments_summary_without_filter_returns_all_data() {
 			requested_at:   Some(now),
 			processed_at:   Some(now),
 			processed_by:   Some("fallback".to_string()),
+			tag:            None,
+			epoch:          None,
 		})
 		.await
 		.unwrap();
 
 	let redis_repo = RedisPaymentRepository::new(redis_client.clone());
-	let get_payment_summary_use_case =
-		GetPaymentSummaryUseCase::new(redis_repo.clone());
+	let state = AppState::new(
+		Arc::new(PaymentQueue::new(redis_client.clone())),
+		Arc::new(redis_repo.clone()),
+	);
 
 	let app = test::init_service(
 		App::new()
-			.app_data(web::Data::new(get_payment_summary_use_case.clone()))
+			.app_data(web::Data::new(state.clone()))
 			.service(payments_summary),
 	)
 	.await;
@@ -120,12 +134,14 @@ async fn test_payments_summary_get_redis_failure() {
 	let redis_container = get_test_redis_client().await;
 	let redis_client = redis_container.client.clone();
 	let redis_repo = RedisPaymentRepository::new(redis_client.clone());
-	let get_payment_summary_use_case =
-		GetPaymentSummaryUseCase::new(redis_repo.clone());
+	let state = AppState::new(
+		Arc::new(PaymentQueue::new(redis_client.clone())),
+		Arc::new(redis_repo.clone()),
+	);
 
 	let app = test::init_service(
 		App::new()
-			.app_data(web::Data::new(get_payment_summary_use_case.clone()))
+			.app_data(web::Data::new(state.clone()))
 			.service(payments_summary),
 	)
 	.await;
@@ -146,8 +162,10 @@ async fn test_payments_summary_get_with_filter_simple_iso_8601() {
 	let redis_container = get_test_redis_client().await;
 	let redis_client = redis_container.client.clone();
 	let payment_repo = RedisPaymentRepository::new(redis_client.clone());
-	let get_payment_summary_use_case =
-		GetPaymentSummaryUseCase::new(payment_repo.clone());
+	let state = AppState::new(
+		Arc::new(PaymentQueue::new(redis_client.clone())),
+		Arc::new(payment_repo.clone()),
+	);
 
 	let now = OffsetDateTime::now_utc();
 
@@ -158,6 +176,8 @@ async fn test_payments_summary_get_with_filter_simple_iso_8601() {
 			requested_at:   Some(now),
 			processed_at:   Some(now),
 			processed_by:   Some("default".to_string()),
+			tag:            None,
+			epoch:          None,
 		})
 		.await
 		.unwrap();
@@ -171,6 +191,8 @@ async fn test_payments_summary_get_with_filter_simple_iso_8601() {
 			requested_at:   Some(one_hour_ago),
 			processed_at:   Some(one_hour_ago),
 			processed_by:   Some("default".to_string()),
+			tag:            None,
+			epoch:          None,
 		})
 		.await
 		.unwrap();
@@ -182,13 +204,15 @@ async fn test_payments_summary_get_with_filter_simple_iso_8601() {
 			requested_at:   Some(now),
 			processed_at:   Some(now),
 			processed_by:   Some("fallback".to_string()),
+			tag:            None,
+			epoch:          None,
 		})
 		.await
 		.unwrap();
 
 	let app = test::init_service(
 		App::new()
-			.app_data(web::Data::new(get_payment_summary_use_case.clone()))
+			.app_data(web::Data::new(state.clone()))
 			.service(payments_summary),
 	)
 	.await;
@@ -231,6 +255,8 @@ async fn test_payments_summary_get_with_extended_iso_8601() {
 			requested_at:   Some(now),
 			processed_at:   Some(now),
 			processed_by:   Some("default".to_string()),
+			tag:            None,
+			epoch:          None,
 		})
 		.await
 		.unwrap();
@@ -244,17 +270,21 @@ async fn test_payments_summary_get_with_extended_iso_8601() {
 			requested_at:   Some(ten_hours_ago),
 			processed_at:   Some(ten_hours_ago),
 			processed_by:   Some("default".to_string()),
+			tag:            None,
+			epoch:          None,
 		})
 		.await
 		.unwrap();
 
 	let redis_repo = RedisPaymentRepository::new(redis_client.clone());
-	let get_payment_summary_use_case =
-		GetPaymentSummaryUseCase::new(redis_repo.clone());
+	let state = AppState::new(
+		Arc::new(PaymentQueue::new(redis_client.clone())),
+		Arc::new(redis_repo.clone()),
+	);
 
 	let app = test::init_service(
 		App::new()
-			.app_data(web::Data::new(get_payment_summary_use_case.clone()))
+			.app_data(web::Data::new(state.clone()))
 			.service(payments_summary),
 	)
 	.await;
@@ -355,6 +385,8 @@ async fn test_payments_summary_decimal_precision() {
 			requested_at:   Some(now),
 			processed_at:   Some(now),
 			processed_by:   Some("default".to_string()),
+			tag:            None,
+			epoch:          None,
 		})
 		.await
 		.unwrap();
@@ -366,6 +398,8 @@ async fn test_payments_summary_decimal_precision() {
 			requested_at:   Some(now),
 			processed_at:   Some(now),
 			processed_by:   Some("default".to_string()),
+			tag:            None,
+			epoch:          None,
 		})
 		.await
 		.unwrap();
@@ -377,17 +411,21 @@ async fn test_payments_summary_decimal_precision() {
 			requested_at:   Some(now),
 			processed_at:   Some(now),
 			processed_by:   Some("fallback".to_string()),
+			tag:            None,
+			epoch:          None,
 		})
 		.await
 		.unwrap();
 
 	let redis_repo = RedisPaymentRepository::new(redis_client.clone());
-	let get_payment_summary_use_case =
-		GetPaymentSummaryUseCase::new(redis_repo.clone());
+	let state = AppState::new(
+		Arc::new(PaymentQueue::new(redis_client.clone())),
+		Arc::new(redis_repo.clone()),
+	);
 
 	let app = test::init_service(
 		App::new()
-			.app_data(web::Data::new(get_payment_summary_use_case.clone()))
+			.app_data(web::Data::new(state.clone()))
 			.service(payments_summary),
 	)
 	.await;
@@ -406,3 +444,296 @@ async fn test_payments_summary_decimal_precision() {
 	assert_eq!(summary.fallback.total_requests, 1);
 	assert_eq!(summary.fallback.total_amount, 501.00); // 500.999 rounds to 501.00
 }
+
+#[actix_web::test]
+async fn test_payments_summary_get_with_open_ended_relative_range() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let payment_repo = RedisPaymentRepository::new(redis_client.clone());
+	let state = AppState::new(
+		Arc::new(PaymentQueue::new(redis_client.clone())),
+		Arc::new(payment_repo.clone()),
+	);
+
+	let now = OffsetDateTime::now_utc();
+	let two_hours_ago = now.sub(time::Duration::hours(2));
+
+	for (amount, processed_at) in [(100.0, now), (200.0, two_hours_ago)] {
+		payment_repo
+			.save(Payment {
+				correlation_id: Uuid::new_v4(),
+				amount,
+				requested_at: Some(processed_at),
+				processed_at: Some(processed_at),
+				processed_by: Some("default".to_string()),
+				tag: None,
+				epoch: None,
+			})
+			.await
+			.unwrap();
+	}
+
+	let app = test::init_service(
+		App::new()
+			.app_data(web::Data::new(state.clone()))
+			.service(payments_summary),
+	)
+	.await;
+
+	for (query, expected_amount) in [("from=-1h", 100.0), ("to=now-1h", 200.0)] {
+		let req = test::TestRequest::get()
+			.uri(&format!("/payments-summary?{query}"))
+			.to_request();
+		let resp = test::call_service(&app, req).await;
+
+		assert!(resp.status().is_success());
+
+		let summary: PaymentsSummaryResponse = test::read_body_json(resp).await;
+
+		assert_eq!(summary.default.total_requests, 1);
+		assert_eq!(summary.default.total_amount, expected_amount);
+	}
+}
+
+#[actix_web::test]
+async fn test_payments_summary_reports_pending_payments_when_asked() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let payment_queue = PaymentQueue::new(redis_client.clone());
+	let state = AppState::new(
+		Arc::new(payment_queue.clone()),
+		Arc::new(RedisPaymentRepository::new(redis_client.clone())),
+	);
+
+	for amount in [10.0, 20.0] {
+		let correlation_id = Uuid::new_v4();
+		payment_queue
+			.push(Message::with(correlation_id, Payment {
+				correlation_id,
+				amount,
+				requested_at: None,
+				processed_at: None,
+				processed_by: None,
+				tag: None,
+				epoch: None,
+			}))
+			.await
+			.unwrap();
+	}
+
+	let app = test::init_service(
+		App::new()
+			.app_data(web::Data::new(state.clone()))
+			.service(payments_summary),
+	)
+	.await;
+
+	let req = test::TestRequest::get()
+		.uri("/payments-summary?pending=true")
+		.to_request();
+	let summary: PaymentsSummaryResponse =
+		test::read_body_json(test::call_service(&app, req).await).await;
+
+	assert_eq!(summary.default.total_requests, 0);
+	assert_eq!(
+		summary.pending,
+		Some(PendingPayments {
+			queued:        2,
+			in_processing: 0,
+		})
+	);
+
+	let req = test::TestRequest::get()
+		.uri("/payments-summary")
+		.to_request();
+	let summary: PaymentsSummaryResponse =
+		test::read_body_json(test::call_service(&app, req).await).await;
+
+	assert_eq!(summary.pending, None);
+}
+
+#[actix_web::test]
+async fn test_payments_summary_breaks_down_amount_buckets_when_asked() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let payment_repo = RedisPaymentRepository::new(redis_client.clone());
+	let now = OffsetDateTime::now_utc();
+
+	for amount in [9.99, 42.0, 100.0, 1000.0] {
+		payment_repo
+			.save(Payment {
+				correlation_id: Uuid::new_v4(),
+				amount,
+				requested_at: Some(now),
+				processed_at: Some(now),
+				processed_by: Some("default".to_string()),
+				tag: None,
+				epoch: None,
+			})
+			.await
+			.unwrap();
+	}
+
+	let state = AppState::new(
+		Arc::new(PaymentQueue::new(redis_client.clone())),
+		Arc::new(payment_repo),
+	);
+	let app = test::init_service(
+		App::new()
+			.app_data(web::Data::new(state.clone()))
+			.service(payments_summary),
+	)
+	.await;
+
+	let req = test::TestRequest::get()
+		.uri("/payments-summary?breakdown=amount_buckets")
+		.to_request();
+	let summary: PaymentsSummaryResponse =
+		test::read_body_json(test::call_service(&app, req).await).await;
+
+	let bucket = |range: &str, count| AmountBucketCount {
+		range: range.to_string(),
+		count,
+	};
+	assert_eq!(
+		summary.default.amount_buckets,
+		Some(vec![
+			bucket("<10", 1),
+			bucket("10-100", 1),
+			bucket(">=100", 2)
+		])
+	);
+	assert_eq!(
+		summary.fallback.amount_buckets,
+		Some(vec![
+			bucket("<10", 0),
+			bucket("10-100", 0),
+			bucket(">=100", 0)
+		])
+	);
+
+	let req = test::TestRequest::get()
+		.uri("/payments-summary")
+		.to_request();
+	let summary: PaymentsSummaryResponse =
+		test::read_body_json(test::call_service(&app, req).await).await;
+
+	assert_eq!(summary.default.amount_buckets, None);
+}
+
+#[actix_web::test]
+async fn test_payments_summary_reports_processing_latency_when_asked() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let payment_repo = RedisPaymentRepository::new(redis_client.clone());
+	let now = OffsetDateTime::now_utc();
+
+	for lag_ms in 1..=20 {
+		payment_repo
+			.save(Payment {
+				correlation_id: Uuid::new_v4(),
+				amount:         10.0,
+				requested_at:   Some(now),
+				processed_at:   Some(now.add(time::Duration::milliseconds(lag_ms))),
+				processed_by:   Some("default".to_string()),
+				tag:            None,
+				epoch:          None,
+			})
+			.await
+			.unwrap();
+	}
+
+	let state = AppState::new(
+		Arc::new(PaymentQueue::new(redis_client.clone())),
+		Arc::new(payment_repo),
+	);
+	let app = test::init_service(
+		App::new()
+			.app_data(web::Data::new(state.clone()))
+			.service(payments_summary),
+	)
+	.await;
+
+	let req = test::TestRequest::get()
+		.uri("/payments-summary?latency=true")
+		.to_request();
+	let summary: PaymentsSummaryResponse =
+		test::read_body_json(test::call_service(&app, req).await).await;
+
+	assert_eq!(
+		summary.default.latency,
+		Some(ProcessingLatency {
+			count:  20,
+			avg_ms: 10.5,
+			p95_ms: 19.0,
+		})
+	);
+	assert_eq!(
+		summary.fallback.latency,
+		Some(ProcessingLatency {
+			count:  0,
+			avg_ms: 0.0,
+			p95_ms: 0.0,
+		})
+	);
+
+	let req = test::TestRequest::get()
+		.uri("/payments-summary")
+		.to_request();
+	let summary: PaymentsSummaryResponse =
+		test::read_body_json(test::call_service(&app, req).await).await;
+
+	assert_eq!(summary.default.latency, None);
+}
+
+#[actix_web::test]
+async fn test_payments_summary_scopes_totals_to_a_run_epoch() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let payment_repo = RedisPaymentRepository::new(redis_client.clone());
+
+	for (amount, epoch) in [(10.0, None), (20.0, Some(1)), (30.0, Some(2))] {
+		payment_repo
+			.save(Payment {
+				correlation_id: Uuid::new_v4(),
+				amount,
+				requested_at: Some(OffsetDateTime::now_utc()),
+				processed_at: Some(OffsetDateTime::now_utc()),
+				processed_by: Some("default".to_string()),
+				tag: None,
+				epoch,
+			})
+			.await
+			.unwrap();
+	}
+
+	let state = AppState::new(
+		Arc::new(PaymentQueue::new(redis_client.clone())),
+		Arc::new(payment_repo),
+	);
+	let app = test::init_service(
+		App::new()
+			.app_data(web::Data::new(state.clone()))
+			.service(payments_summary),
+	)
+	.await;
+
+	for (epoch, total_amount) in [(0, 10.0), (1, 20.0), (2, 30.0)] {
+		let req = test::TestRequest::get()
+			.uri(&format!("/payments-summary?epoch={epoch}"))
+			.to_request();
+		let summary: PaymentsSummaryResponse =
+			test::read_body_json(test::call_service(&app, req).await).await;
+
+		assert_eq!(summary.default.total_requests, 1);
+		assert_eq!(summary.default.total_amount, total_amount);
+	}
+
+	let req = test::TestRequest::get()
+		.uri("/payments-summary")
+		.to_request();
+	let summary: PaymentsSummaryResponse =
+		test::read_body_json(test::call_service(&app, req).await).await;
+
+	assert_eq!(summary.default.total_requests, 3);
+}
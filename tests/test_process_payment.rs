@@ -1,9 +1,13 @@
+use std::sync::Arc;
 use std::time::Duration;
 
 use circuitbreaker_rs::{CircuitBreaker, DefaultPolicy, State};
 use reqwest::Client;
 use rinha_de_backend::domain::payment::Payment;
+use rinha_de_backend::domain::payment_router::PaymentRouter;
+use rinha_de_backend::domain::repository::PaymentRepository;
 use rinha_de_backend::infrastructure::persistence::redis_payment_repository::RedisPaymentRepository;
+use rinha_de_backend::infrastructure::routing::in_memory_payment_router::InMemoryPaymentRouter;
 use rinha_de_backend::use_cases::process_payment::{
 	PaymentProcessingError, ProcessPaymentUseCase,
 };
@@ -34,6 +38,8 @@ async fn test_process_payment_success() {
 		requested_at:   None,
 		processed_at:   None,
 		processed_by:   None,
+		tag:            None,
+		epoch:          None,
 	};
 
 	let mut circuit_breaker: CircuitBreaker<DefaultPolicy, PaymentProcessingError> =
@@ -45,8 +51,9 @@ async fn test_process_payment_success() {
 	let result = process_payment_use_case
 		.execute(
 			payment,
-			default_url,
-			"default".to_string(),
+			None,
+			&[format!("{default_url}/payments").into()],
+			"default",
 			&mut circuit_breaker,
 		)
 		.await;
@@ -55,6 +62,53 @@ async fn test_process_payment_success() {
 	assert!(result.unwrap());
 }
 
+#[tokio::test]
+async fn test_process_payment_keeps_requested_at_of_earlier_attempt() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let payment_repo = RedisPaymentRepository::new(redis_client.clone());
+	let (default_processor_container, _) = setup_payment_processors().await;
+	let default_url = default_processor_container.url.clone();
+	let process_payment_use_case =
+		ProcessPaymentUseCase::new(payment_repo.clone(), Client::new());
+
+	let mut payment = Payment {
+		correlation_id: Uuid::new_v4(),
+		amount:         100.0,
+		requested_at:   None,
+		processed_at:   None,
+		processed_by:   None,
+		tag:            None,
+		epoch:          None,
+	};
+	process_payment_use_case.stamp_requested_at(&mut payment);
+	let first_requested_at = payment.requested_at.unwrap();
+
+	tokio::time::sleep(Duration::from_millis(10)).await;
+	process_payment_use_case.stamp_requested_at(&mut payment);
+	assert_eq!(payment.requested_at, Some(first_requested_at));
+
+	let mut circuit_breaker =
+		CircuitBreaker::<DefaultPolicy, PaymentProcessingError>::builder().build();
+	let processed = process_payment_use_case
+		.execute(
+			payment.clone(),
+			None,
+			&[format!("{default_url}/payments").into()],
+			"default",
+			&mut circuit_breaker,
+		)
+		.await
+		.unwrap();
+	assert!(processed);
+
+	let saved = payment_repo
+		.get_payment_summary("default", &payment.correlation_id.to_string())
+		.await
+		.unwrap();
+	assert_eq!(saved.requested_at, Some(first_requested_at));
+}
+
 #[tokio::test]
 async fn test_process_payment_duplicate_returns_false() {
 	let redis_container = get_test_redis_client().await;
@@ -75,6 +129,8 @@ async fn test_process_payment_duplicate_returns_false() {
 		requested_at:   None,
 		processed_at:   None,
 		processed_by:   None,
+		tag:            None,
+		epoch:          None,
 	};
 
 	let mut circuit_breaker: CircuitBreaker<DefaultPolicy, PaymentProcessingError> =
@@ -87,8 +143,9 @@ async fn test_process_payment_duplicate_returns_false() {
 	let result1 = process_payment_use_case
 		.execute(
 			payment.clone(),
-			default_url.clone(),
-			"default".to_string(),
+			None,
+			&[format!("{default_url}/payments").into()],
+			"default",
 			&mut circuit_breaker,
 		)
 		.await;
@@ -100,8 +157,9 @@ async fn test_process_payment_duplicate_returns_false() {
 	let result2 = process_payment_use_case
 		.execute(
 			payment,
-			default_url,
-			"default".to_string(),
+			None,
+			&[format!("{default_url}/payments").into()],
+			"default",
 			&mut circuit_breaker,
 		)
 		.await;
@@ -131,6 +189,8 @@ async fn test_process_payment_500_returns_false() {
 		requested_at:   None,
 		processed_at:   None,
 		processed_by:   None,
+		tag:            None,
+		epoch:          None,
 	};
 
 	let mut circuit_breaker: CircuitBreaker<DefaultPolicy, PaymentProcessingError> =
@@ -140,23 +200,18 @@ async fn test_process_payment_500_returns_false() {
 			.build();
 
 	// Configure the payment processor to return 500
-	let admin_url = format!("{default_url}/admin/configurations/failure");
-	let client = reqwest::Client::new();
-	client
-		.put(&admin_url)
-		.header("X-Rinha-Token", "123")
-		.json(&serde_json::json!({ "failure": true }))
-		.send()
+	default_processor_container
+		.admin_client()
+		.set_failure(true)
 		.await
-		.unwrap()
-		.error_for_status()
 		.unwrap();
 
 	let result = process_payment_use_case
 		.execute(
 			payment,
-			default_url,
-			"default".to_string(),
+			None,
+			&[format!("{default_url}/payments").into()],
+			"default",
 			&mut circuit_breaker,
 		)
 		.await;
@@ -184,6 +239,8 @@ async fn test_process_payment_circuit_breaker_open_returns_false() {
 		requested_at:   None,
 		processed_at:   None,
 		processed_by:   None,
+		tag:            None,
+		epoch:          None,
 	};
 
 	let mut circuit_breaker: CircuitBreaker<DefaultPolicy, PaymentProcessingError> =
@@ -198,8 +255,9 @@ async fn test_process_payment_circuit_breaker_open_returns_false() {
 	let result = process_payment_use_case
 		.execute(
 			payment,
-			default_url,
-			"default".to_string(),
+			None,
+			&[format!("{default_url}/payments").into()],
+			"default",
 			&mut circuit_breaker,
 		)
 		.await;
@@ -225,6 +283,8 @@ async fn test_process_payment_unreachable_service_opens_circuit_breaker() {
 		requested_at:   None,
 		processed_at:   None,
 		processed_by:   None,
+		tag:            None,
+		epoch:          None,
 	};
 
 	let mut circuit_breaker: CircuitBreaker<DefaultPolicy, PaymentProcessingError> =
@@ -240,8 +300,9 @@ async fn test_process_payment_unreachable_service_opens_circuit_breaker() {
 		let result = process_payment_use_case
 			.execute(
 				payment.clone(),
-				unreachable_url.clone(),
-				"default".to_string(),
+				None,
+				&[format!("{unreachable_url}/payments").into()],
+				"default",
 				&mut circuit_breaker,
 			)
 			.await;
@@ -253,3 +314,137 @@ async fn test_process_payment_unreachable_service_opens_circuit_breaker() {
 	// Verify that the circuit breaker is open
 	assert_eq!(circuit_breaker.current_state(), State::Open);
 }
+
+#[tokio::test]
+async fn test_process_payment_uses_processor_client() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let payment_repo = RedisPaymentRepository::new(redis_client.clone());
+	let (default_processor_container, _) = setup_payment_processors().await;
+	let default_url = default_processor_container.url.clone();
+	// Every request through the shared client times out immediately.
+	let shared_client = Client::builder()
+		.timeout(Duration::from_nanos(1))
+		.build()
+		.unwrap();
+	let default_client = Client::builder()
+		.timeout(Duration::from_secs(1))
+		.build()
+		.unwrap();
+	let process_payment_use_case =
+		ProcessPaymentUseCase::new(payment_repo.clone(), shared_client)
+			.with_processor_client("default", default_client);
+
+	let payment = Payment {
+		correlation_id: Uuid::new_v4(),
+		amount:         100.0,
+		requested_at:   None,
+		processed_at:   None,
+		processed_by:   None,
+		tag:            None,
+		epoch:          None,
+	};
+
+	let mut circuit_breaker: CircuitBreaker<DefaultPolicy, PaymentProcessingError> =
+		CircuitBreaker::<DefaultPolicy, PaymentProcessingError>::builder().build();
+
+	let result = process_payment_use_case
+		.execute(
+			payment,
+			None,
+			&[format!("{default_url}/payments").into()],
+			"default",
+			&mut circuit_breaker,
+		)
+		.await;
+
+	assert!(result.unwrap());
+}
+
+#[tokio::test]
+async fn test_process_payment_request_timeout_fails_slow_call() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let payment_repo = RedisPaymentRepository::new(redis_client.clone());
+	let (default_processor_container, _) = setup_payment_processors().await;
+	let default_url = default_processor_container.url.clone();
+	let http_client = Client::builder()
+		.timeout(Duration::from_secs(1))
+		.build()
+		.unwrap();
+	let process_payment_use_case =
+		ProcessPaymentUseCase::new(payment_repo.clone(), http_client)
+			.with_request_timeout(Duration::from_secs(1))
+			.with_processor_request_timeout("default", Duration::from_nanos(1));
+
+	let payment = Payment {
+		correlation_id: Uuid::new_v4(),
+		amount:         100.0,
+		requested_at:   None,
+		processed_at:   None,
+		processed_by:   None,
+		tag:            None,
+		epoch:          None,
+	};
+
+	let mut circuit_breaker: CircuitBreaker<DefaultPolicy, PaymentProcessingError> =
+		CircuitBreaker::<DefaultPolicy, PaymentProcessingError>::builder().build();
+
+	let result = process_payment_use_case
+		.execute(
+			payment,
+			None,
+			&[format!("{default_url}/payments").into()],
+			"default",
+			&mut circuit_breaker,
+		)
+		.await;
+
+	assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_process_payment_reports_failed_calls_to_router() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let payment_repo = RedisPaymentRepository::new(redis_client.clone());
+	let http_client = Client::builder()
+		.timeout(Duration::from_millis(100))
+		.build()
+		.unwrap();
+	let unreachable_url = "http://localhost:12345".to_string();
+	let router = InMemoryPaymentRouter::new();
+	router.record_health_check("default", &unreachable_url, true, 0);
+	let process_payment_use_case =
+		ProcessPaymentUseCase::new(payment_repo.clone(), http_client.clone())
+			.with_health_reporter(Arc::new(router.clone()));
+
+	let payment = Payment {
+		correlation_id: Uuid::new_v4(),
+		amount:         100.0,
+		requested_at:   None,
+		processed_at:   None,
+		processed_by:   None,
+		tag:            None,
+		epoch:          None,
+	};
+
+	let mut circuit_breaker: CircuitBreaker<DefaultPolicy, PaymentProcessingError> =
+		CircuitBreaker::<DefaultPolicy, PaymentProcessingError>::builder()
+			.failure_threshold(0.5)
+			.cooldown(Duration::from_secs(30))
+			.build();
+
+	let result = process_payment_use_case
+		.execute(
+			payment,
+			None,
+			&[format!("{unreachable_url}/payments").into()],
+			"default",
+			&mut circuit_breaker,
+		)
+		.await;
+
+	assert!(result.is_err());
+	assert!(router.get_processor("default").await.is_none());
+}
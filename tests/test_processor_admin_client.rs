@@ -0,0 +1,78 @@
+use std::time::{Duration, Instant};
+
+use reqwest::Client;
+use serde_json::json;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use uuid::Uuid;
+
+mod support;
+
+use crate::support::payment_processor_container::setup_payment_processors;
+
+async fn post_payment(client: &Client, url: &str) -> reqwest::Response {
+	client
+		.post(format!("{url}/payments"))
+		.json(&json!({
+			"correlationId": Uuid::new_v4(),
+			"amount": 10.0,
+			"requestedAt": OffsetDateTime::now_utc().format(&Rfc3339).unwrap(),
+		}))
+		.send()
+		.await
+		.unwrap()
+}
+
+#[tokio::test]
+async fn test_processor_admin_client_summary_and_purge() {
+	let (default_processor_container, _) = setup_payment_processors().await;
+	let admin_client = default_processor_container.admin_client();
+	let client = Client::new();
+
+	admin_client.purge_payments().await.unwrap();
+	assert!(
+		post_payment(&client, &default_processor_container.url)
+			.await
+			.status()
+			.is_success()
+	);
+
+	let summary = admin_client.get_payments_summary(None, None).await.unwrap();
+	assert_eq!(summary.total_requests, 1);
+	assert_eq!(summary.total_amount, 10.0);
+
+	admin_client.purge_payments().await.unwrap();
+
+	let summary = admin_client.get_payments_summary(None, None).await.unwrap();
+	assert_eq!(summary.total_requests, 0);
+}
+
+#[tokio::test]
+async fn test_processor_admin_client_failure_and_delay() {
+	let (default_processor_container, _) = setup_payment_processors().await;
+	let admin_client = default_processor_container.admin_client();
+	let client = Client::new();
+
+	admin_client.set_failure(true).await.unwrap();
+	assert!(
+		post_payment(&client, &default_processor_container.url)
+			.await
+			.status()
+			.is_server_error()
+	);
+
+	admin_client.set_failure(false).await.unwrap();
+	admin_client
+		.set_delay(Duration::from_millis(500))
+		.await
+		.unwrap();
+
+	let start = Instant::now();
+	assert!(
+		post_payment(&client, &default_processor_container.url)
+			.await
+			.status()
+			.is_success()
+	);
+	assert!(start.elapsed() >= Duration::from_millis(500));
+}
@@ -0,0 +1,27 @@
+#![cfg(feature = "contract")]
+
+use reqwest::Client;
+use rinha_de_backend::contract::ProcessorContract;
+
+mod support;
+
+use crate::support::payment_processor_container::setup_payment_processors;
+
+#[tokio::test]
+async fn test_processor_image_satisfies_contract() {
+	let (default_processor_container, fallback_processor_container) =
+		setup_payment_processors().await;
+
+	for container in [&default_processor_container, &fallback_processor_container] {
+		let contract = ProcessorContract::new(
+			Client::new(),
+			container.url.clone(),
+			container.admin_client(),
+		);
+
+		let report = contract.run().await;
+
+		let failures = report.failures().collect::<Vec<_>>();
+		assert!(report.is_satisfied(), "{failures:?}");
+	}
+}
@@ -1,12 +1,16 @@
 use reqwest::Client;
 use rinha_de_backend::domain::health_status::HealthStatus;
 use rinha_de_backend::domain::payment_processor::PaymentProcessor;
+use rinha_de_backend::infrastructure::alerting::processor_downtime_monitor::ProcessorDowntimeMonitor;
 use rinha_de_backend::infrastructure::routing::in_memory_payment_router::InMemoryPaymentRouter;
-use rinha_de_backend::infrastructure::workers::processor_health_monitor_worker::processor_health_monitor_worker;
+use rinha_de_backend::infrastructure::workers::processor_health_monitor_worker::{
+	HealthCheckSchedule, processor_health_monitor_worker,
+};
 use tokio::time::{Duration, sleep};
 
 mod support;
 
+use crate::support::config::test_config;
 use crate::support::payment_processor_container::setup_payment_processors;
 
 #[tokio::test]
@@ -19,14 +23,16 @@ async fn test_update_processor_health_when_processor_is_reachable() {
 		.timeout(Duration::from_secs(2))
 		.build()
 		.unwrap();
-	let router = InMemoryPaymentRouter::new();
+	let router = InMemoryPaymentRouter::new()
+		.with_processor_urls(&default_url, &fallback_url);
 
 	// Spawn the worker
 	let worker_handle = tokio::spawn(processor_health_monitor_worker(
 		router.clone(),
 		http_client.clone(),
-		default_url.clone(),
-		fallback_url.clone(),
+		ProcessorDowntimeMonitor::disabled(),
+		HealthCheckSchedule::default(),
+		HealthCheckSchedule::default(),
 	));
 
 	wait_for_workflow_to_run().await;
@@ -54,26 +60,28 @@ async fn test_marks_processor_as_failing_when_unreachable() {
 		.unwrap();
 	let default_url = "http://non-existent-default:8080".to_string();
 	let fallback_url = "http://non-existent-fallback:8080".to_string();
-	let router = InMemoryPaymentRouter::new();
-
-	router.update_processor_health(PaymentProcessor {
-		name:              "default".to_string(),
-		url:               default_url.clone(),
-		health:            HealthStatus::Healthy,
-		min_response_time: 0,
-	});
-	router.update_processor_health(PaymentProcessor {
-		name:              "fallback".to_string(),
-		url:               fallback_url.clone(),
-		health:            HealthStatus::Healthy,
-		min_response_time: 0,
-	});
+	let router = InMemoryPaymentRouter::new()
+		.with_processor_urls(&default_url, &fallback_url);
+
+	router.update_processor_health(PaymentProcessor::new(
+		"default",
+		&default_url,
+		HealthStatus::Healthy,
+		0,
+	));
+	router.update_processor_health(PaymentProcessor::new(
+		"fallback",
+		&fallback_url,
+		HealthStatus::Healthy,
+		0,
+	));
 
 	let worker_handle = tokio::spawn(processor_health_monitor_worker(
 		router.clone(),
 		http_client.clone(),
-		default_url.clone(),
-		fallback_url.clone(),
+		ProcessorDowntimeMonitor::disabled(),
+		HealthCheckSchedule::default(),
+		HealthCheckSchedule::default(),
 	));
 
 	wait_for_workflow_to_run().await;
@@ -99,31 +107,30 @@ async fn test_should_not_panic_an_error_occurs() {
 		.timeout(Duration::from_secs(2))
 		.build()
 		.unwrap();
-	let router = InMemoryPaymentRouter::new();
-
-	router.update_processor_health(PaymentProcessor {
-		name:              "default".to_string(),
-		url:               "http://another-non-existent-default:8080".to_string(),
-		health:            HealthStatus::Healthy,
-		min_response_time: 0,
-	});
-	router.update_processor_health(PaymentProcessor {
-		name:              "fallback".to_string(),
-		url:               "http://another-non-existent-fallback:8080".to_string(),
-		health:            HealthStatus::Healthy,
-		min_response_time: 0,
-	});
-
-	let default_non_existent_url =
-		"http://another-non-existent-default:8080".to_string();
-	let fallback_non_existent_url =
-		"http://another-non-existent-fallback:8080".to_string();
+	let default_non_existent_url = "http://another-non-existent-default:8080";
+	let fallback_non_existent_url = "http://another-non-existent-fallback:8080";
+	let router = InMemoryPaymentRouter::new()
+		.with_processor_urls(default_non_existent_url, fallback_non_existent_url);
+
+	router.update_processor_health(PaymentProcessor::new(
+		"default",
+		"http://another-non-existent-default:8080",
+		HealthStatus::Healthy,
+		0,
+	));
+	router.update_processor_health(PaymentProcessor::new(
+		"fallback",
+		"http://another-non-existent-fallback:8080",
+		HealthStatus::Healthy,
+		0,
+	));
 
 	let worker_handle = tokio::spawn(processor_health_monitor_worker(
 		router.clone(),
 		http_client.clone(),
-		default_non_existent_url.clone(),
-		fallback_non_existent_url.clone(),
+		ProcessorDowntimeMonitor::disabled(),
+		HealthCheckSchedule::default(),
+		HealthCheckSchedule::default(),
 	));
 
 	wait_for_workflow_to_run().await;
@@ -133,6 +140,30 @@ async fn test_should_not_panic_an_error_occurs() {
 	worker_handle.abort();
 }
 
+#[test]
+fn test_health_check_schedule_prefers_processor_overrides() {
+	let mut config = test_config("redis://localhost:6379");
+	config.health_check_interval_ms = 10_000;
+	config.health_check_timeout_ms = Some(1_000);
+	config.default_health_check_interval_ms = Some(6_000);
+	config.fallback_health_check_timeout_ms = Some(2_000);
+
+	assert_eq!(
+		HealthCheckSchedule::from_config(&config, "default"),
+		HealthCheckSchedule {
+			interval: Duration::from_millis(6_000),
+			timeout:  Some(Duration::from_millis(1_000)),
+		}
+	);
+	assert_eq!(
+		HealthCheckSchedule::from_config(&config, "fallback"),
+		HealthCheckSchedule {
+			interval: Duration::from_millis(10_000),
+			timeout:  Some(Duration::from_millis(2_000)),
+		}
+	);
+}
+
 async fn wait_for_workflow_to_run() {
 	sleep(Duration::from_secs(6)).await;
 }
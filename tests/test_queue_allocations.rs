@@ -0,0 +1,110 @@
+//! Counts the heap allocations made by calls through [`Queue`], the native
+//! async trait the workers call, against calls through its boxed
+//! [`DynQueue`] mirror, which the web state holds as a trait object. A boxed
+//! call costs what every call cost when the traits went through
+//! `async_trait`. [`DynPaymentRepository`] boxes its futures the same way.
+//!
+//! [`DynPaymentRepository`]: rinha_de_backend::domain::repository::DynPaymentRepository
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::Arc;
+
+use futures::executor::block_on;
+use rinha_de_backend::domain::payment::Payment;
+use rinha_de_backend::domain::queue::{DynQueue, Message, Queue};
+use time::OffsetDateTime;
+
+const CALLS: usize = 1000;
+
+/// Counts the allocations of the current thread only, so tests running in
+/// parallel do not add to each other's counts.
+struct CountingAllocator;
+
+thread_local! {
+	static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+		unsafe { System.alloc(layout) }
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		unsafe { System.dealloc(ptr, layout) }
+	}
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations_of(mut calls: impl FnMut()) -> usize {
+	// Warms up anything allocated once, such as the executor's waker.
+	calls();
+
+	let before = ALLOCATIONS.with(Cell::get);
+	calls();
+	ALLOCATIONS.with(Cell::get) - before
+}
+
+/// Always empty queue, so only the calls themselves can allocate.
+struct EmptyQueue;
+
+impl Queue<Payment> for EmptyQueue {
+	async fn pop(
+		&self,
+	) -> Result<Option<Message<Payment>>, Box<dyn std::error::Error + Send>> {
+		Ok(None)
+	}
+
+	async fn peek(
+		&self,
+		_offset: usize,
+		_limit: usize,
+	) -> Result<Vec<Message<Payment>>, Box<dyn std::error::Error + Send>> {
+		Ok(Vec::new())
+	}
+
+	async fn depth(&self) -> Result<usize, Box<dyn std::error::Error + Send>> {
+		Ok(0)
+	}
+
+	async fn oldest_enqueued_at(
+		&self,
+	) -> Result<Option<OffsetDateTime>, Box<dyn std::error::Error + Send>> {
+		Ok(None)
+	}
+
+	async fn push(
+		&self,
+		_message: Message<Payment>,
+	) -> Result<(), Box<dyn std::error::Error + Send>> {
+		Ok(())
+	}
+}
+
+/// Pops the way the workers do, generic over the queue.
+async fn pop_times<Q: Queue<Payment>>(queue: &Q, times: usize) {
+	for _ in 0..times {
+		assert!(queue.pop().await.unwrap().is_none());
+	}
+}
+
+#[test]
+fn test_native_async_queue_calls_do_not_allocate() {
+	let queue = EmptyQueue;
+
+	let allocations = allocations_of(|| block_on(pop_times(&queue, CALLS)));
+
+	assert_eq!(allocations, 0);
+}
+
+#[test]
+fn test_boxed_queue_calls_allocate_once_per_call() {
+	let queue: Arc<dyn DynQueue<Payment>> = Arc::new(EmptyQueue);
+
+	let allocations = allocations_of(|| block_on(pop_times(&queue, CALLS)));
+
+	assert_eq!(allocations, CALLS);
+}
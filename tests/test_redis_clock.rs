@@ -0,0 +1,19 @@
+use rinha_de_backend::domain::clock::Clock;
+use rinha_de_backend::infrastructure::persistence::redis_clock::RedisClock;
+use time::{Duration, OffsetDateTime};
+
+mod support;
+
+use crate::support::redis_container::get_test_redis_client;
+
+#[tokio::test]
+async fn test_redis_clock_follows_the_server_time() {
+	let redis_container = get_test_redis_client().await;
+	let clock = RedisClock::new(redis_container.client.clone());
+
+	let offset = clock.sync().await.unwrap();
+
+	// The container shares the host clock, so only the round trip is off.
+	assert!(offset.abs() < Duration::seconds(1));
+	assert!((clock.now() - OffsetDateTime::now_utc()).abs() < Duration::seconds(1));
+}
@@ -0,0 +1,32 @@
+use std::time::Duration;
+
+use rinha_de_backend::infrastructure::persistence::redis_connection::{
+	ConnectionHealth, SupervisedConnection,
+};
+
+mod support;
+
+use crate::support::redis_container::get_test_redis_client;
+
+#[tokio::test]
+async fn test_check_rebuilds_a_killed_connection() {
+	let redis_container = get_test_redis_client().await;
+	let connection = SupervisedConnection::new(redis_container.client.clone());
+	let timeout = Duration::from_secs(1);
+
+	assert_eq!(connection.check(timeout).await, ConnectionHealth::Healthy);
+
+	let mut admin = redis_container
+		.client
+		.get_multiplexed_async_connection()
+		.await
+		.unwrap();
+	let _: () = redis::cmd("CLIENT")
+		.arg(&["KILL", "TYPE", "normal", "SKIPME", "yes"])
+		.query_async(&mut admin)
+		.await
+		.unwrap();
+
+	assert_eq!(connection.check(timeout).await, ConnectionHealth::Rebuilt);
+	assert_eq!(connection.check(timeout).await, ConnectionHealth::Healthy);
+}
@@ -0,0 +1,57 @@
+use rinha_de_backend::domain::payment::Payment;
+use rinha_de_backend::domain::queue::{Message, Queue};
+use rinha_de_backend::domain::repository::PaymentRepository;
+use rinha_de_backend::infrastructure::config::redis::{
+	PAYMENTS_QUEUE_KEY, PROCESSED_PAYMENTS_SET_KEY,
+};
+use rinha_de_backend::infrastructure::persistence::redis_key_space::RedisKeySpace;
+use rinha_de_backend::infrastructure::persistence::redis_payment_repository::RedisPaymentRepository;
+use rinha_de_backend::infrastructure::queue::redis_payment_queue::PaymentQueue;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+mod support;
+
+use crate::support::redis_container::get_test_redis_client;
+
+fn payment(processed_by: Option<&str>) -> Payment {
+	Payment {
+		correlation_id: Uuid::new_v4(),
+		amount:         10.0,
+		requested_at:   Some(OffsetDateTime::now_utc()),
+		processed_at:   None,
+		processed_by:   processed_by.map(str::to_string),
+		tag:            None,
+		epoch:          None,
+	}
+}
+
+#[tokio::test]
+async fn test_reports_sizes_of_growing_keys() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client;
+	let payment_repo = RedisPaymentRepository::new(redis_client.clone());
+	let payment_queue = PaymentQueue::new(redis_client.clone());
+
+	for _ in 0..3 {
+		payment_repo.save(payment(Some("default"))).await.unwrap();
+	}
+	let queued = payment(None);
+	payment_queue
+		.push(Message::with(queued.correlation_id, queued))
+		.await
+		.unwrap();
+
+	let sizes = RedisKeySpace::new(redis_client).sizes().await.unwrap();
+	let size = |key| {
+		sizes
+			.iter()
+			.find(|(name, _)| *name == key)
+			.map(|(_, size)| *size)
+			.unwrap()
+	};
+
+	assert_eq!(size(PROCESSED_PAYMENTS_SET_KEY), 3);
+	assert_eq!(size(PAYMENTS_QUEUE_KEY), 1);
+	assert!(size("keys") >= 5);
+}
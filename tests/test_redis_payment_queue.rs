@@ -3,8 +3,14 @@ use std::time::Duration;
 
 use rinha_de_backend::domain::payment::Payment;
 use rinha_de_backend::domain::queue::{Message, Queue};
-use rinha_de_backend::infrastructure::config::redis::PAYMENTS_QUEUE_KEY;
+use rinha_de_backend::domain::repository::PaymentRepository;
+use rinha_de_backend::infrastructure::config::redis::{
+	DEAD_LETTERED_PAYMENTS_KEY_PREFIX, PAYMENTS_QUEUE_KEY,
+	QUARANTINED_MESSAGES_KEY_PREFIX,
+};
+use rinha_de_backend::infrastructure::persistence::redis_payment_repository::RedisPaymentRepository;
 use rinha_de_backend::infrastructure::queue::redis_payment_queue::PaymentQueue;
+use time::OffsetDateTime;
 use tokio::sync::mpsc;
 use uuid::Uuid;
 
@@ -24,6 +30,8 @@ async fn test_payment_queue_push_and_pop() {
 		requested_at:   None,
 		processed_at:   None,
 		processed_by:   None,
+		tag:            None,
+		epoch:          None,
 	};
 
 	let message = Message::with(Uuid::new_v4(), payment.clone());
@@ -48,6 +56,85 @@ async fn test_payment_queue_pop_empty() {
 	assert!(popped_message.is_none());
 }
 
+#[tokio::test]
+async fn test_payment_queue_pop_does_not_block_forever_on_zero_timeout() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client;
+	let payment_queue =
+		PaymentQueue::new(redis_client.clone()).with_pop_timeout(Duration::ZERO);
+
+	let popped_message =
+		tokio::time::timeout(Duration::from_secs(1), payment_queue.pop())
+			.await
+			.expect("pop should give up on an empty queue")
+			.unwrap();
+
+	assert!(popped_message.is_none());
+}
+
+#[tokio::test]
+async fn test_payment_queue_depth() {
+	let redis_container = get_test_redis_client().await;
+	let payment_queue = PaymentQueue::new(redis_container.client.clone());
+
+	assert_eq!(payment_queue.depth().await.unwrap(), 0);
+
+	for _ in 0..3 {
+		let payment = Payment {
+			correlation_id: Uuid::new_v4(),
+			amount:         1.0,
+			requested_at:   None,
+			processed_at:   None,
+			processed_by:   None,
+			tag:            None,
+			epoch:          None,
+		};
+		payment_queue
+			.push(Message::with(Uuid::new_v4(), payment))
+			.await
+			.unwrap();
+	}
+
+	assert_eq!(payment_queue.depth().await.unwrap(), 3);
+
+	payment_queue.pop().await.unwrap();
+
+	assert_eq!(payment_queue.depth().await.unwrap(), 2);
+}
+
+#[tokio::test]
+async fn test_payment_queue_reports_when_the_oldest_message_was_pushed() {
+	let redis_container = get_test_redis_client().await;
+	let payment_queue = PaymentQueue::new(redis_container.client.clone());
+
+	assert_eq!(payment_queue.oldest_enqueued_at().await.unwrap(), None);
+
+	let pushed_from = OffsetDateTime::now_utc();
+	for _ in 0..2 {
+		let payment = Payment {
+			correlation_id: Uuid::new_v4(),
+			amount:         1.0,
+			requested_at:   None,
+			processed_at:   None,
+			processed_by:   None,
+			tag:            None,
+			epoch:          None,
+		};
+		payment_queue
+			.push(Message::with(Uuid::new_v4(), payment))
+			.await
+			.unwrap();
+		tokio::time::sleep(Duration::from_millis(10)).await;
+	}
+
+	let oldest = payment_queue.oldest_enqueued_at().await.unwrap().unwrap();
+	let popped = payment_queue.pop().await.unwrap().unwrap();
+
+	assert!(oldest >= pushed_from);
+	assert_eq!(popped.enqueued_at, Some(oldest));
+	assert!(payment_queue.oldest_enqueued_at().await.unwrap().unwrap() > oldest);
+}
+
 #[tokio::test]
 async fn test_payment_queue_multiple_pushes_and_pops() {
 	let redis_container = get_test_redis_client().await;
@@ -60,6 +147,8 @@ async fn test_payment_queue_multiple_pushes_and_pops() {
 		requested_at:   None,
 		processed_at:   None,
 		processed_by:   None,
+		tag:            None,
+		epoch:          None,
 	};
 	let payment2 = Payment {
 		correlation_id: Uuid::new_v4(),
@@ -67,6 +156,8 @@ async fn test_payment_queue_multiple_pushes_and_pops() {
 		requested_at:   None,
 		processed_at:   None,
 		processed_by:   None,
+		tag:            None,
+		epoch:          None,
 	};
 
 	let message1 = Message::with(Uuid::new_v4(), payment1.clone());
@@ -128,6 +219,8 @@ async fn test_payment_queue_under_load() {
 			requested_at:   None,
 			processed_at:   None,
 			processed_by:   None,
+			tag:            None,
+			epoch:          None,
 		};
 		payment_queue
 			.push(Message::with(Uuid::new_v4(), payment))
@@ -165,3 +258,369 @@ async fn test_payment_queue_under_load() {
 		"All payments should be processed"
 	);
 }
+
+#[tokio::test]
+async fn test_payment_queue_drops_already_queued_payments() {
+	let redis_container = get_test_redis_client().await;
+	let payment_queue = PaymentQueue::new(redis_container.client.clone())
+		.with_dedup(Duration::from_secs(30));
+
+	let payment = Payment {
+		correlation_id: Uuid::new_v4(),
+		amount:         1.0,
+		requested_at:   None,
+		processed_at:   None,
+		processed_by:   None,
+		tag:            None,
+		epoch:          None,
+	};
+	let message = Message::with(payment.correlation_id, payment);
+
+	payment_queue.push(message.clone()).await.unwrap();
+	payment_queue.push(message.clone()).await.unwrap();
+
+	assert_eq!(payment_queue.depth().await.unwrap(), 1);
+	let (ingest_duplicates, _) =
+		RedisPaymentRepository::new(redis_container.client.clone())
+			.get_duplicates(OffsetDateTime::UNIX_EPOCH, OffsetDateTime::now_utc())
+			.await
+			.unwrap();
+	assert_eq!(ingest_duplicates, 1);
+
+	payment_queue.push(message.retried()).await.unwrap();
+
+	assert_eq!(payment_queue.depth().await.unwrap(), 2);
+}
+
+#[tokio::test]
+async fn test_payment_queue_accepts_payments_again_once_popped_or_purged() {
+	let redis_container = get_test_redis_client().await;
+	let payment_queue = PaymentQueue::new(redis_container.client.clone())
+		.with_dedup(Duration::from_secs(30));
+	let message = || {
+		let payment = Payment {
+			correlation_id: Uuid::new_v4(),
+			amount:         1.0,
+			requested_at:   None,
+			processed_at:   None,
+			processed_by:   None,
+			tag:            None,
+			epoch:          None,
+		};
+		Message::with(payment.correlation_id, payment)
+	};
+
+	let popped = message();
+	payment_queue.push(popped.clone()).await.unwrap();
+	payment_queue.pop().await.unwrap().unwrap();
+	payment_queue.push(popped.clone()).await.unwrap();
+	assert_eq!(payment_queue.depth().await.unwrap(), 1);
+	payment_queue.pop().await.unwrap().unwrap();
+
+	let purged = message();
+	payment_queue.push(purged.clone()).await.unwrap();
+	RedisPaymentRepository::new(redis_container.client.clone())
+		.clear()
+		.await
+		.unwrap();
+	payment_queue.push(purged).await.unwrap();
+	assert_eq!(payment_queue.depth().await.unwrap(), 2);
+}
+
+#[tokio::test]
+async fn test_payment_queue_round_trips_compressed_messages() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let payment_queue = PaymentQueue::new(redis_client.clone()).with_compression(1);
+
+	let message = Message::with(Uuid::new_v4(), Payment {
+		correlation_id: Uuid::new_v4(),
+		amount:         42.0,
+		requested_at:   None,
+		processed_at:   None,
+		processed_by:   None,
+		tag:            Some("staging".to_string()),
+		epoch:          None,
+	});
+	payment_queue.push(message.clone()).await.unwrap();
+
+	let mut conn = redis_client
+		.get_multiplexed_async_connection()
+		.await
+		.unwrap();
+	let stored: Vec<u8> = redis::cmd("LINDEX")
+		.arg(PAYMENTS_QUEUE_KEY)
+		.arg(0)
+		.query_async(&mut conn)
+		.await
+		.unwrap();
+	assert_ne!(stored.first(), Some(&b'{'));
+
+	let popped = PaymentQueue::new(redis_client.clone())
+		.pop()
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(popped.id, message.id);
+	assert_eq!(popped.body.tag.as_deref(), Some("staging"));
+}
+
+#[tokio::test]
+async fn test_payment_queue_quarantines_corrupted_messages() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let payment_queue = PaymentQueue::new(redis_client.clone()).with_checksums();
+
+	payment_queue
+		.push(Message::with(Uuid::new_v4(), Payment {
+			correlation_id: Uuid::new_v4(),
+			amount:         42.0,
+			requested_at:   None,
+			processed_at:   None,
+			processed_by:   None,
+			tag:            None,
+			epoch:          None,
+		}))
+		.await
+		.unwrap();
+
+	let mut conn = redis_client
+		.get_multiplexed_async_connection()
+		.await
+		.unwrap();
+	let mut stored: Vec<u8> = redis::cmd("RPOP")
+		.arg(PAYMENTS_QUEUE_KEY)
+		.query_async(&mut conn)
+		.await
+		.unwrap();
+	stored.truncate(stored.len() - 1);
+	redis::cmd("LPUSH")
+		.arg(PAYMENTS_QUEUE_KEY)
+		.arg(&stored)
+		.query_async::<()>(&mut conn)
+		.await
+		.unwrap();
+
+	assert!(payment_queue.pop().await.is_err());
+
+	let quarantined: Vec<Vec<u8>> = redis::cmd("LRANGE")
+		.arg(format!(
+			"{QUARANTINED_MESSAGES_KEY_PREFIX}:{PAYMENTS_QUEUE_KEY}"
+		))
+		.arg(0)
+		.arg(-1)
+		.query_async(&mut conn)
+		.await
+		.unwrap();
+	assert_eq!(quarantined, vec![stored]);
+}
+
+#[tokio::test]
+async fn test_payment_queue_dead_letters_payments_past_max_age() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client.clone();
+	let payment_queue =
+		PaymentQueue::new(redis_client.clone()).with_max_age(Duration::from_secs(5));
+
+	let payment = |requested_at| Payment {
+		correlation_id: Uuid::new_v4(),
+		amount: 1.0,
+		requested_at,
+		processed_at: None,
+		processed_by: None,
+		tag: None,
+		epoch: None,
+	};
+	let stale = payment(Some(OffsetDateTime::now_utc() - Duration::from_secs(60)));
+	let fresh = payment(None);
+	payment_queue
+		.push(Message::with(Uuid::new_v4(), stale.clone()))
+		.await
+		.unwrap();
+	payment_queue
+		.push(Message::with(Uuid::new_v4(), fresh.clone()))
+		.await
+		.unwrap();
+
+	let popped = payment_queue.pop().await.unwrap().unwrap();
+	assert_eq!(popped.body.correlation_id, fresh.correlation_id);
+
+	let mut conn = redis_client
+		.get_multiplexed_async_connection()
+		.await
+		.unwrap();
+	let dead_lettered: Vec<Vec<u8>> = redis::cmd("LRANGE")
+		.arg(format!(
+			"{DEAD_LETTERED_PAYMENTS_KEY_PREFIX}:{PAYMENTS_QUEUE_KEY}"
+		))
+		.arg(0)
+		.arg(-1)
+		.query_async(&mut conn)
+		.await
+		.unwrap();
+	assert_eq!(dead_lettered.len(), 1);
+	let dead_lettered: Message<Payment> =
+		serde_json::from_slice(&dead_lettered[0]).unwrap();
+	assert_eq!(dead_lettered.body.correlation_id, stale.correlation_id);
+
+	let dead_lettered = payment_queue.dead_lettered_payments(10).await.unwrap();
+	assert_eq!(dead_lettered.len(), 1);
+	assert_eq!(dead_lettered[0].correlation_id, stale.correlation_id);
+}
+
+#[tokio::test]
+async fn test_payment_queue_cutover_drains_previous_key_first() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client;
+	let message = |amount| {
+		Message::with(Uuid::new_v4(), Payment {
+			correlation_id: Uuid::new_v4(),
+			amount,
+			requested_at: None,
+			processed_at: None,
+			processed_by: None,
+			tag: None,
+			epoch: None,
+		})
+	};
+
+	let previous_queue = PaymentQueue::new(redis_client.clone());
+	let backlog = message(1.0);
+	previous_queue.push(backlog.clone()).await.unwrap();
+
+	let payment_queue = PaymentQueue::new(redis_client.clone())
+		.with_key("payments_queue:v2")
+		.with_cutover_from(PAYMENTS_QUEUE_KEY, None);
+	let new_payment = message(2.0);
+	payment_queue.push(new_payment.clone()).await.unwrap();
+
+	assert_eq!(previous_queue.depth().await.unwrap(), 1);
+	assert_eq!(payment_queue.depth().await.unwrap(), 2);
+	assert_eq!(payment_queue.drain_remaining().await.unwrap(), Some(1));
+
+	assert_eq!(payment_queue.pop().await.unwrap().unwrap().id, backlog.id);
+	assert_eq!(payment_queue.drain_remaining().await.unwrap(), Some(0));
+	assert!(!payment_queue.cutover_ended());
+	assert_eq!(
+		payment_queue.pop().await.unwrap().unwrap().id,
+		new_payment.id
+	);
+
+	// An instance not yet moved pushes to the previous key after it was
+	// found empty.
+	let late_payment = message(3.0);
+	previous_queue.push(late_payment.clone()).await.unwrap();
+
+	assert_eq!(payment_queue.depth().await.unwrap(), 1);
+	assert_eq!(payment_queue.peek(0, 10).await.unwrap().len(), 1);
+	assert_eq!(
+		payment_queue.pop().await.unwrap().unwrap().id,
+		late_payment.id
+	);
+	assert_eq!(
+		PaymentQueue::new(redis_client)
+			.drain_remaining()
+			.await
+			.unwrap(),
+		None
+	);
+}
+
+#[tokio::test]
+async fn test_payment_queue_peek_pages_in_pop_order_across_cutover() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client;
+	let message = |amount| {
+		Message::with(Uuid::new_v4(), Payment {
+			correlation_id: Uuid::new_v4(),
+			amount,
+			requested_at: None,
+			processed_at: None,
+			processed_by: None,
+			tag: None,
+			epoch: None,
+		})
+	};
+
+	let previous_queue = PaymentQueue::new(redis_client.clone());
+	let payment_queue = PaymentQueue::new(redis_client.clone())
+		.with_key("payments_queue:v2")
+		.with_cutover_from(PAYMENTS_QUEUE_KEY, None);
+
+	let mut pushed = Vec::new();
+	for amount in 0..3 {
+		let backlog = message(amount as f64);
+		previous_queue.push(backlog.clone()).await.unwrap();
+		pushed.push(backlog.id);
+	}
+	for amount in 3..5 {
+		let new_payment = message(amount as f64);
+		payment_queue.push(new_payment.clone()).await.unwrap();
+		pushed.push(new_payment.id);
+	}
+
+	let mut peeked = Vec::new();
+	for offset in (0..6).step_by(2) {
+		let page = payment_queue.peek(offset, 2).await.unwrap();
+		peeked.extend(page.into_iter().map(|message| message.id));
+	}
+
+	assert_eq!(peeked, pushed);
+	assert_eq!(payment_queue.depth().await.unwrap(), 5);
+}
+
+#[tokio::test]
+async fn test_payment_queue_cutover_ends_after_staying_empty_for_grace() {
+	let redis_container = get_test_redis_client().await;
+	let redis_client = redis_container.client;
+
+	let payment_queue = PaymentQueue::new(redis_client.clone())
+		.with_key("payments_queue:v2")
+		.with_cutover_from(PAYMENTS_QUEUE_KEY, Some(Duration::from_millis(200)));
+
+	assert_eq!(payment_queue.drain_remaining().await.unwrap(), Some(0));
+	assert!(!payment_queue.cutover_ended());
+
+	tokio::time::sleep(Duration::from_millis(300)).await;
+	assert_eq!(payment_queue.drain_remaining().await.unwrap(), Some(0));
+	assert!(payment_queue.cutover_ended());
+}
+
+#[tokio::test]
+async fn test_payment_queue_sequencing_counts_deliveries() {
+	let redis_container = get_test_redis_client().await;
+	let payment_queue =
+		PaymentQueue::new(redis_container.client.clone()).with_sequencing();
+	let message = || {
+		Message::with(Uuid::new_v4(), Payment {
+			correlation_id: Uuid::new_v4(),
+			amount:         1.0,
+			requested_at:   None,
+			processed_at:   None,
+			processed_by:   None,
+			tag:            None,
+			epoch:          None,
+		})
+	};
+
+	for _ in 0..3 {
+		payment_queue.push(message()).await.unwrap();
+	}
+	let first = payment_queue.pop().await.unwrap().unwrap();
+	assert_eq!(first.sequence, Some(1));
+
+	payment_queue.push(first.retried()).await.unwrap();
+	let report = payment_queue.sequence_report().await.unwrap().unwrap();
+	assert_eq!(report.issued, 3);
+	assert_eq!(report.delivered, 1);
+	assert_eq!(report.queued, 3);
+	assert_eq!(report.gaps(), 0);
+
+	assert!(
+		PaymentQueue::new(redis_container.client)
+			.sequence_report()
+			.await
+			.unwrap()
+			.is_none()
+	);
+}
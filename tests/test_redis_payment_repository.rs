@@ -0,0 +1,690 @@
+use std::ops::{Add, Sub};
+use std::time::Duration as StdDuration;
+
+use futures::TryStreamExt;
+use redis::AsyncCommands;
+use rinha_de_backend::domain::payment::Payment;
+use rinha_de_backend::domain::repository::{
+	DuplicateStage, PaymentFailure, PaymentRepository,
+};
+use rinha_de_backend::infrastructure::config::redis::{
+	IN_FLIGHT_PAYMENTS_KEY, LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY,
+	LEGACY_FALLBACK_PAYMENT_SUMMARY_KEY, LEGACY_TOTAL_AMOUNT_FIELD,
+	LEGACY_TOTAL_REQUESTS_FIELD, PROCESSED_PAYMENTS_SET_KEY,
+};
+use rinha_de_backend::infrastructure::persistence::redis_payment_repository::{
+	DedupStrategy, RedisPaymentRepository,
+};
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+mod support;
+
+use crate::support::redis_container::get_test_redis_client;
+
+fn processed_payment(group: &str, requested_at: OffsetDateTime) -> Payment {
+	Payment {
+		correlation_id: Uuid::new_v4(),
+		amount:         10.0,
+		requested_at:   Some(requested_at),
+		processed_at:   Some(requested_at),
+		processed_by:   Some(group.to_string()),
+		tag:            None,
+		epoch:          None,
+	}
+}
+
+#[tokio::test]
+async fn test_get_payments_stream_yields_all_payments_across_batches() {
+	let redis_container = get_test_redis_client().await;
+	let payment_repo = RedisPaymentRepository::new(redis_container.client.clone());
+	let now = OffsetDateTime::now_utc();
+
+	const NUM_PAYMENTS: usize = 1234;
+
+	for _ in 0..NUM_PAYMENTS {
+		payment_repo
+			.save(processed_payment("default", now))
+			.await
+			.unwrap();
+	}
+
+	let payments: Vec<Payment> = payment_repo
+		.get_payments_stream(
+			"default",
+			now.sub(Duration::minutes(1)),
+			now.add(Duration::minutes(1)),
+		)
+		.try_collect()
+		.await
+		.unwrap();
+
+	assert_eq!(payments.len(), NUM_PAYMENTS);
+}
+
+#[tokio::test]
+async fn test_get_payments_stream_filters_by_group_and_range() {
+	let redis_container = get_test_redis_client().await;
+	let payment_repo = RedisPaymentRepository::new(redis_container.client.clone());
+	let now = OffsetDateTime::now_utc();
+
+	let in_range = processed_payment("default", now);
+	let other_group = processed_payment("fallback", now);
+	let out_of_range = processed_payment("default", now.sub(Duration::hours(1)));
+
+	for payment in [&in_range, &other_group, &out_of_range] {
+		payment_repo.save(payment.clone()).await.unwrap();
+	}
+
+	let payments: Vec<Payment> = payment_repo
+		.get_payments_stream(
+			"default",
+			now.sub(Duration::minutes(1)),
+			now.add(Duration::minutes(1)),
+		)
+		.try_collect()
+		.await
+		.unwrap();
+
+	assert_eq!(payments.len(), 1);
+	assert_eq!(payments[0].correlation_id, in_range.correlation_id);
+}
+
+#[tokio::test]
+async fn test_get_payments_stream_empty() {
+	let redis_container = get_test_redis_client().await;
+	let payment_repo = RedisPaymentRepository::new(redis_container.client.clone());
+	let now = OffsetDateTime::now_utc();
+
+	let payments: Vec<Payment> = payment_repo
+		.get_payments_stream("default", now.sub(Duration::days(1)), now)
+		.try_collect()
+		.await
+		.unwrap();
+
+	assert!(payments.is_empty());
+}
+
+#[tokio::test]
+async fn test_trim_older_than_keeps_summary_totals() {
+	let redis_container = get_test_redis_client().await;
+	let payment_repo = RedisPaymentRepository::new(redis_container.client.clone());
+	let now = OffsetDateTime::now_utc();
+
+	let old_payment = processed_payment("default", now.sub(Duration::hours(2)));
+	let recent_payment = processed_payment("default", now);
+
+	payment_repo.save(old_payment.clone()).await.unwrap();
+	payment_repo.save(recent_payment.clone()).await.unwrap();
+
+	let trimmed = payment_repo
+		.trim_older_than(now.sub(Duration::hours(1)))
+		.await
+		.unwrap();
+
+	assert_eq!(trimmed, 1);
+	assert!(
+		!payment_repo
+			.is_already_processed(&old_payment.correlation_id.to_string())
+			.await
+			.unwrap()
+	);
+	assert!(
+		payment_repo
+			.is_already_processed(&recent_payment.correlation_id.to_string())
+			.await
+			.unwrap()
+	);
+
+	let (total_requests, total_amount) = payment_repo
+		.get_summary_by_group(
+			"default",
+			now.sub(Duration::days(1)),
+			now.add(Duration::minutes(1)),
+		)
+		.await
+		.unwrap();
+
+	assert_eq!(total_requests, 2);
+	assert_eq!(total_amount, 20.0);
+}
+
+#[tokio::test]
+async fn test_get_amount_buckets_by_group_counts_trimmed_payments() {
+	let redis_container = get_test_redis_client().await;
+	let payment_repo = RedisPaymentRepository::new(redis_container.client.clone());
+	let now = OffsetDateTime::now_utc();
+
+	let with_amount = |amount, requested_at| Payment {
+		amount,
+		..processed_payment("default", requested_at)
+	};
+	for payment in [
+		with_amount(5.0, now.sub(Duration::hours(2))),
+		with_amount(10.0, now.sub(Duration::hours(2))),
+		with_amount(99.9, now),
+		with_amount(250.0, now),
+	] {
+		payment_repo.save(payment).await.unwrap();
+	}
+
+	payment_repo
+		.trim_older_than(now.sub(Duration::hours(1)))
+		.await
+		.unwrap();
+
+	let counts = payment_repo
+		.get_amount_buckets_by_group(
+			"default",
+			now.sub(Duration::days(1)),
+			now.add(Duration::minutes(1)),
+		)
+		.await
+		.unwrap();
+
+	assert_eq!(counts, vec![1, 2, 1]);
+}
+
+#[tokio::test]
+async fn test_get_duplicates_counts_every_submission_within_range() {
+	let redis_container = get_test_redis_client().await;
+	let payment_repo = RedisPaymentRepository::new(redis_container.client.clone());
+	let from = OffsetDateTime::now_utc();
+	let payment_id = Uuid::new_v4().to_string();
+
+	payment_repo
+		.record_duplicate(&payment_id, DuplicateStage::Worker)
+		.await
+		.unwrap();
+	payment_repo
+		.record_duplicate(&payment_id, DuplicateStage::Worker)
+		.await
+		.unwrap();
+	payment_repo
+		.record_duplicate(&payment_id, DuplicateStage::Ingest)
+		.await
+		.unwrap();
+
+	let to = OffsetDateTime::now_utc();
+	assert_eq!(payment_repo.get_duplicates(from, to).await.unwrap(), (1, 2));
+	assert_eq!(
+		payment_repo
+			.get_duplicates(from.sub(Duration::hours(1)), from)
+			.await
+			.unwrap(),
+		(0, 0)
+	);
+
+	payment_repo
+		.trim_older_than(to.add(Duration::seconds(1)))
+		.await
+		.unwrap();
+	assert_eq!(payment_repo.get_duplicates(from, to).await.unwrap(), (0, 0));
+}
+
+#[tokio::test]
+async fn test_trim_older_than_nothing_to_trim() {
+	let redis_container = get_test_redis_client().await;
+	let payment_repo = RedisPaymentRepository::new(redis_container.client.clone());
+	let now = OffsetDateTime::now_utc();
+
+	payment_repo
+		.save(processed_payment("fallback", now))
+		.await
+		.unwrap();
+
+	let trimmed = payment_repo
+		.trim_older_than(now.sub(Duration::hours(1)))
+		.await
+		.unwrap();
+
+	assert_eq!(trimmed, 0);
+}
+
+#[tokio::test]
+async fn test_delete_removes_payments_from_summary() {
+	let redis_container = get_test_redis_client().await;
+	let payment_repo = RedisPaymentRepository::new(redis_container.client.clone());
+	let now = OffsetDateTime::now_utc();
+
+	let archived_payment = processed_payment("default", now);
+	let kept_payment = processed_payment("default", now);
+
+	payment_repo.save(archived_payment.clone()).await.unwrap();
+	payment_repo.save(kept_payment.clone()).await.unwrap();
+
+	payment_repo
+		.delete(std::slice::from_ref(&archived_payment))
+		.await
+		.unwrap();
+
+	let payments: Vec<Payment> = payment_repo
+		.get_payments_stream(
+			"default",
+			now.sub(Duration::minutes(1)),
+			now.add(Duration::minutes(1)),
+		)
+		.try_collect()
+		.await
+		.unwrap();
+
+	assert_eq!(payments.len(), 1);
+	assert_eq!(payments[0].correlation_id, kept_payment.correlation_id);
+}
+
+#[tokio::test]
+async fn test_clear_fences_off_payments_in_flight() {
+	let redis_container = get_test_redis_client().await;
+	let payment_repo = RedisPaymentRepository::new(redis_container.client.clone());
+	let now = OffsetDateTime::now_utc();
+	let in_flight_payment = processed_payment("default", now);
+	let stale_payment = processed_payment("default", now);
+	let later_payment = processed_payment("default", now);
+
+	let mut con = redis_container
+		.client
+		.get_multiplexed_async_connection()
+		.await
+		.unwrap();
+	let _: () = con
+		.zadd(
+			IN_FLIGHT_PAYMENTS_KEY,
+			format!("other-instance:{}", in_flight_payment.correlation_id),
+			now.unix_timestamp(),
+		)
+		.await
+		.unwrap();
+	let _: () = con
+		.zadd(
+			IN_FLIGHT_PAYMENTS_KEY,
+			format!("dead-instance:{}", stale_payment.correlation_id),
+			now.sub(Duration::hours(1)).unix_timestamp(),
+		)
+		.await
+		.unwrap();
+
+	payment_repo.clear().await.unwrap();
+	let in_flight: usize = con.zcard(IN_FLIGHT_PAYMENTS_KEY).await.unwrap();
+	assert_eq!(in_flight, 1);
+	payment_repo.save(in_flight_payment.clone()).await.unwrap();
+	payment_repo.save(stale_payment.clone()).await.unwrap();
+	payment_repo.save(later_payment.clone()).await.unwrap();
+
+	let payments: Vec<Payment> = payment_repo
+		.get_payments_stream(
+			"default",
+			now.sub(Duration::minutes(1)),
+			now.add(Duration::minutes(1)),
+		)
+		.try_collect()
+		.await
+		.unwrap();
+
+	assert_eq!(payments.len(), 2);
+	assert!(
+		payments.iter().all(
+			|payment| payment.correlation_id != in_flight_payment.correlation_id
+		)
+	);
+	assert!(
+		!payment_repo
+			.is_already_processed(&in_flight_payment.correlation_id.to_string())
+			.await
+			.unwrap()
+	);
+}
+
+#[tokio::test]
+async fn test_fenced_off_payment_writes_nothing() {
+	let redis_container = get_test_redis_client().await;
+	let payment_repo = RedisPaymentRepository::new(redis_container.client.clone())
+		.with_dedup(DedupStrategy::Expiring {
+			ttl: StdDuration::from_secs(60),
+		})
+		.with_legacy_summary();
+	let now = OffsetDateTime::now_utc();
+	let in_flight_payment = processed_payment("default", now);
+	let payment_id = in_flight_payment.correlation_id.to_string();
+
+	let mut con = redis_container
+		.client
+		.get_multiplexed_async_connection()
+		.await
+		.unwrap();
+	let _: () = con
+		.zadd(
+			IN_FLIGHT_PAYMENTS_KEY,
+			format!("other-instance:{payment_id}"),
+			now.unix_timestamp(),
+		)
+		.await
+		.unwrap();
+
+	payment_repo.clear().await.unwrap();
+	payment_repo.save(in_flight_payment).await.unwrap();
+
+	let payment_key_exists: bool = con
+		.exists(format!("payment_summary:default:{payment_id}"))
+		.await
+		.unwrap();
+	let dedup_key_exists: bool = con
+		.exists(format!("processed_payments:{payment_id}"))
+		.await
+		.unwrap();
+	let legacy_summary_exists: bool = con
+		.exists(LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY)
+		.await
+		.unwrap();
+	let processed: Option<f64> = con
+		.zscore(PROCESSED_PAYMENTS_SET_KEY, &payment_id)
+		.await
+		.unwrap();
+
+	assert!(!payment_key_exists);
+	assert!(!dedup_key_exists);
+	assert!(!legacy_summary_exists);
+	assert_eq!(processed, None);
+	assert!(
+		!payment_repo
+			.is_already_processed(&payment_id)
+			.await
+			.unwrap()
+	);
+}
+
+#[tokio::test]
+async fn test_legacy_summary_tracks_aggregate_totals() {
+	let redis_container = get_test_redis_client().await;
+	let payment_repo = RedisPaymentRepository::new(redis_container.client.clone())
+		.with_legacy_summary();
+	let now = OffsetDateTime::now_utc();
+
+	let deleted_payment = processed_payment("default", now);
+	payment_repo.save(deleted_payment.clone()).await.unwrap();
+	payment_repo
+		.save(processed_payment("default", now))
+		.await
+		.unwrap();
+	payment_repo
+		.save(processed_payment("fallback", now))
+		.await
+		.unwrap();
+
+	let mut con = redis_container
+		.client
+		.get_multiplexed_async_connection()
+		.await
+		.unwrap();
+	let totals = |key| {
+		let mut con = con.clone();
+		async move {
+			let requests: i64 =
+				con.hget(key, LEGACY_TOTAL_REQUESTS_FIELD).await.unwrap();
+			let amount: f64 =
+				con.hget(key, LEGACY_TOTAL_AMOUNT_FIELD).await.unwrap();
+			(requests, amount)
+		}
+	};
+
+	assert_eq!(totals(LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY).await, (2, 20.0));
+	assert_eq!(totals(LEGACY_FALLBACK_PAYMENT_SUMMARY_KEY).await, (1, 10.0));
+
+	payment_repo
+		.delete(std::slice::from_ref(&deleted_payment))
+		.await
+		.unwrap();
+	assert_eq!(totals(LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY).await, (1, 10.0));
+
+	// A payment that is already gone is not taken out twice.
+	payment_repo
+		.delete(std::slice::from_ref(&deleted_payment))
+		.await
+		.unwrap();
+	assert_eq!(totals(LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY).await, (1, 10.0));
+
+	let archived_payment = processed_payment("fallback", now);
+	payment_repo.save(archived_payment.clone()).await.unwrap();
+	payment_repo
+		.remove_archived(std::slice::from_ref(&archived_payment))
+		.await
+		.unwrap();
+	assert_eq!(totals(LEGACY_FALLBACK_PAYMENT_SUMMARY_KEY).await, (2, 20.0));
+
+	payment_repo.clear().await.unwrap();
+	let exists: bool = con
+		.exists(LEGACY_DEFAULT_PAYMENT_SUMMARY_KEY)
+		.await
+		.unwrap();
+	assert!(!exists);
+}
+
+#[tokio::test]
+async fn test_normalize_timestamp_scores_rescales_second_scores() {
+	let redis_container = get_test_redis_client().await;
+	let payment_repo = RedisPaymentRepository::new(redis_container.client.clone());
+	let now = OffsetDateTime::now_utc();
+	let payment = processed_payment("default", now);
+	payment_repo.save(payment.clone()).await.unwrap();
+
+	let mut con = redis_container
+		.client
+		.get_multiplexed_async_connection()
+		.await
+		.unwrap();
+	let _: () = con
+		.zadd(
+			PROCESSED_PAYMENTS_SET_KEY,
+			payment.correlation_id.to_string(),
+			now.unix_timestamp(),
+		)
+		.await
+		.unwrap();
+
+	let from = now.sub(Duration::minutes(1));
+	let to = now.add(Duration::minutes(1));
+	assert_eq!(
+		payment_repo
+			.get_summary_by_group("default", from, to)
+			.await
+			.unwrap()
+			.0,
+		0
+	);
+
+	assert_eq!(payment_repo.normalize_timestamp_scores().await.unwrap(), 1);
+	assert_eq!(
+		payment_repo
+			.get_summary_by_group("default", from, to)
+			.await
+			.unwrap()
+			.0,
+		1
+	);
+	assert_eq!(payment_repo.normalize_timestamp_scores().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_get_failures_by_group_counts_calls_within_range() {
+	let redis_container = get_test_redis_client().await;
+	let payment_repo = RedisPaymentRepository::new(redis_container.client.clone());
+	let now = OffsetDateTime::now_utc();
+	let payment = processed_payment("default", now);
+	let mut retried = payment.clone();
+	retried.requested_at = Some(now.add(Duration::seconds(1)));
+
+	for (payment, group, failure) in [
+		(&payment, "default", PaymentFailure::Failed),
+		(&retried, "default", PaymentFailure::Failed),
+		(&payment, "default", PaymentFailure::Rejected),
+		(&payment, "fallback", PaymentFailure::Failed),
+		(
+			&processed_payment("default", now.sub(Duration::hours(1))),
+			"default",
+			PaymentFailure::Failed,
+		),
+	] {
+		payment_repo
+			.record_failure(payment, group, failure)
+			.await
+			.unwrap();
+	}
+
+	let from = now.sub(Duration::minutes(1));
+	let to = now.add(Duration::minutes(1));
+	assert_eq!(
+		payment_repo
+			.get_failures_by_group("default", from, to)
+			.await
+			.unwrap(),
+		(2, 1)
+	);
+	assert_eq!(
+		payment_repo
+			.get_failures_by_group("fallback", from, to)
+			.await
+			.unwrap(),
+		(1, 0)
+	);
+
+	payment_repo.trim_older_than(from).await.unwrap();
+	assert_eq!(
+		payment_repo
+			.get_failures_by_group("default", OffsetDateTime::UNIX_EPOCH, to)
+			.await
+			.unwrap(),
+		(2, 1)
+	);
+}
+
+#[tokio::test]
+async fn test_record_failure_counts_every_attempt_of_a_payment() {
+	let redis_container = get_test_redis_client().await;
+	let payment_repo = RedisPaymentRepository::new(redis_container.client.clone());
+	let now = OffsetDateTime::now_utc();
+	let payment = processed_payment("default", now);
+
+	for _ in 0..2 {
+		payment_repo
+			.record_failure(&payment, "default", PaymentFailure::Failed)
+			.await
+			.unwrap();
+	}
+
+	assert_eq!(
+		payment_repo
+			.get_failures_by_group(
+				"default",
+				now.sub(Duration::minutes(1)),
+				now.add(Duration::minutes(1))
+			)
+			.await
+			.unwrap(),
+		(2, 0)
+	);
+
+	let failures: Vec<_> = payment_repo
+		.get_failures_stream("default", PaymentFailure::Failed)
+		.try_collect()
+		.await
+		.unwrap();
+	assert_eq!(failures.len(), 2);
+	for failure in &failures {
+		payment_repo.restore_failure(failure).await.unwrap();
+	}
+	assert_eq!(
+		payment_repo
+			.get_failures_stream("default", PaymentFailure::Failed)
+			.try_collect::<Vec<_>>()
+			.await
+			.unwrap(),
+		failures
+	);
+}
+
+async fn assert_dedup_strategy(dedup: DedupStrategy) {
+	let redis_container = get_test_redis_client().await;
+	let payment_repo = RedisPaymentRepository::new(redis_container.client.clone())
+		.with_dedup(dedup);
+	payment_repo.check_dedup_support().await.unwrap();
+	let payment = processed_payment("default", OffsetDateTime::now_utc());
+	let payment_id = payment.correlation_id.to_string();
+
+	assert!(
+		!payment_repo
+			.is_already_processed(&payment_id)
+			.await
+			.unwrap()
+	);
+
+	payment_repo.save(payment).await.unwrap();
+
+	assert!(
+		payment_repo
+			.is_already_processed(&payment_id)
+			.await
+			.unwrap()
+	);
+	assert!(
+		!payment_repo
+			.is_already_processed(&Uuid::new_v4().to_string())
+			.await
+			.unwrap()
+	);
+
+	payment_repo.clear().await.unwrap();
+
+	assert!(
+		!payment_repo
+			.is_already_processed(&payment_id)
+			.await
+			.unwrap()
+	);
+}
+
+#[tokio::test]
+async fn test_is_already_processed_with_bloom_dedup() {
+	assert_dedup_strategy(DedupStrategy::Bloom {
+		capacity:   1000,
+		error_rate: 0.001,
+	})
+	.await;
+}
+
+#[tokio::test]
+async fn test_is_already_processed_with_expiring_dedup() {
+	assert_dedup_strategy(DedupStrategy::Expiring {
+		ttl: StdDuration::from_secs(60),
+	})
+	.await;
+}
+
+#[tokio::test]
+async fn test_get_latencies_by_group_returns_stored_payments_lag() {
+	let redis_container = get_test_redis_client().await;
+	let payment_repo = RedisPaymentRepository::new(redis_container.client.clone());
+	let now = OffsetDateTime::now_utc();
+
+	let processed_after = |group, lag| Payment {
+		processed_at: Some(now.add(lag)),
+		..processed_payment(group, now)
+	};
+	for payment in [
+		processed_after("default", Duration::milliseconds(15)),
+		processed_after("default", Duration::microseconds(250)),
+		processed_after("fallback", Duration::seconds(1)),
+	] {
+		payment_repo.save(payment).await.unwrap();
+	}
+
+	let mut latencies = payment_repo
+		.get_latencies_by_group(
+			"default",
+			now.sub(Duration::minutes(1)),
+			now.add(Duration::minutes(1)),
+		)
+		.await
+		.unwrap();
+	latencies.sort();
+
+	assert_eq!(latencies, vec![250, 15_000]);
+}
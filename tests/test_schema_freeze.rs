@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+
+use rinha_de_backend::adapters::web::schema::{PaymentRequest, canonical_shapes};
+use rinha_de_backend::domain::payment::Payment;
+use rinha_de_backend::domain::queue::Message;
+use rinha_de_backend::use_cases::dto::PaymentsSummaryResponse;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Rewrites the golden files instead of checking them, for intended changes.
+const UPDATE_GOLDEN_ENV: &str = "UPDATE_GOLDEN";
+
+fn golden_path(name: &str) -> PathBuf {
+	PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+		.join("tests/golden")
+		.join(format!("{name}.json"))
+}
+
+fn read_golden(name: &str) -> serde_json::Value {
+	let path = golden_path(name);
+	let json = std::fs::read_to_string(&path)
+		.unwrap_or_else(|e| panic!("Missing golden file {}: {e}", path.display()));
+	serde_json::from_str(&json).unwrap()
+}
+
+/// Parses the golden file into `T` and serializes it back, so fields that
+/// are only renamed on one side are caught too.
+fn assert_round_trips<T: Serialize + DeserializeOwned>(name: &str) {
+	let golden = read_golden(name);
+	let parsed: T = serde_json::from_value(golden.clone())
+		.unwrap_or_else(|e| panic!("Golden {name} no longer parses: {e}"));
+
+	assert_eq!(serde_json::to_value(parsed).unwrap(), golden, "{name}");
+}
+
+#[test]
+fn test_canonical_shapes_match_golden_files() {
+	for (name, shape) in canonical_shapes() {
+		if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+			let json = serde_json::to_string_pretty(&shape).unwrap();
+			std::fs::write(golden_path(name), json + "\n").unwrap();
+			continue;
+		}
+
+		assert_eq!(
+			shape,
+			read_golden(name),
+			"{name} changed shape, rerun with {UPDATE_GOLDEN_ENV}=1 if intended"
+		);
+	}
+}
+
+#[test]
+fn test_golden_files_round_trip() {
+	assert_round_trips::<PaymentRequest>("payment_request");
+	assert_round_trips::<PaymentsSummaryResponse>("payments_summary");
+	assert_round_trips::<Message<Payment>>("queue_message");
+}